@@ -27,7 +27,11 @@ fn block_import() {
         data_dir,
         network,
         evm_engine,
-    ));
+        None,
+        false,
+        false,
+    ))
+    .unwrap();
 }
 
 pub fn criterion_benchmark(c: &mut Criterion) {
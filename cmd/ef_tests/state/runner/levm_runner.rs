@@ -188,6 +188,7 @@ pub fn prepare_vm_for_tx<'a>(
             tx_nonce: test_tx.nonce,
             block_gas_limit: test.env.current_gas_limit,
             transient_storage: HashMap::new(),
+            l1_fee_config: None,
         },
         db,
         &tx,
@@ -1,17 +1,23 @@
 use std::{
-    fs::{metadata, read_dir},
-    io::{self, Write},
-    path::Path,
+    fs::{metadata, read_dir, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
 };
 
 use clap::{ArgAction, Parser as ClapParser, Subcommand as ClapSubcommand};
-use ethrex_blockchain::fork_choice::apply_fork_choice;
-use ethrex_p2p::{sync::SyncMode, types::Node};
+use ethrex_blockchain::{fork_choice::apply_fork_choice, mempool::MempoolConfig};
+use ethrex_common::types::BlockNumber;
+use ethrex_p2p::{network::DiscoveryProtocol, sync::SyncMode, types::Node};
+use ethrex_rlp::encode::RLPEncode;
+use ethrex_rpc::LogQueryLimits;
+use ethrex_storage::Store;
 use ethrex_vm::EvmEngine;
+use eyre::{bail, eyre};
+use flate2::{write::GzEncoder, Compression};
 use tracing::{info, warn, Level};
 
 use crate::{
-    initializers::{init_blockchain, init_store},
+    initializers::{configure_store, get_state_retention, init_blockchain, init_store, open_store},
     utils::{self, set_datadir},
     DEFAULT_DATADIR,
 };
@@ -21,6 +27,19 @@ use crate::l2;
 
 pub const VERSION_STRING: &str = env!("CARGO_PKG_VERSION");
 
+/// Amount of blocks whose state is kept by default when pruning state
+pub const DEFAULT_STATE_RETENTION: u64 = 128;
+
+/// Defines which state is kept in the database
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GcMode {
+    /// Keep the state of every block
+    #[default]
+    Archive,
+    /// Only keep the state of the latest blocks, see `--state.retention`
+    Full,
+}
+
 #[allow(clippy::upper_case_acronyms)]
 #[derive(ClapParser)]
 #[command(name="ethrex", author = "Lambdaclass", version=VERSION_STRING, about, about = "ethrex Execution client")]
@@ -112,6 +131,106 @@ pub struct Options {
         long_help = "Possible values: info, debug, trace, warn, error",
         help_heading = "Node options")]
     pub log_level: Level,
+    #[arg(
+        long = "gcmode",
+        default_value = "archive",
+        value_name = "GC_MODE",
+        value_parser = utils::parse_gc_mode,
+        help = "Whether to keep the state of every block or prune old state.",
+        long_help = "Can be either \"archive\" or \"full\" with \"archive\" as default value. In \"full\" mode only the state of the latest `--state.retention` blocks is kept.",
+        help_heading = "Node options"
+    )]
+    pub gcmode: GcMode,
+    #[arg(
+        long = "state.retention",
+        default_value_t = DEFAULT_STATE_RETENTION,
+        value_name = "BLOCKS",
+        help = "Amount of recent blocks whose state is kept when running with `--gcmode full`.",
+        help_heading = "Node options"
+    )]
+    pub state_retention: u64,
+    #[arg(
+        long = "state.history",
+        action = ArgAction::SetTrue,
+        default_value = "false",
+        help = "Record the state changes of each block to query historical state without walking old tries.",
+        long_help = "The state changes of each block are recorded in a flat state history, so historical state can be queried without walking the tries of past blocks. It can only be enabled when running with `--gcmode archive`, on a database whose state was never pruned.",
+        help_heading = "Node options"
+    )]
+    pub state_history: bool,
+    #[arg(
+        long = "state.snapshot",
+        action = ArgAction::SetTrue,
+        default_value = "false",
+        help = "Serve the reads of the latest states from a flat snapshot instead of their tries.",
+        long_help = "Accounts and storage slots of the latest states are read from a flat snapshot, without walking their tries. If the snapshot doesn't cover the latest state it is generated on startup, which can take a while on a large database.",
+        help_heading = "Node options"
+    )]
+    pub state_snapshot: bool,
+    #[arg(
+        long = "txpool.journal",
+        value_name = "JOURNAL_PATH",
+        help = "File where the transactions submitted to this node are journaled so they survive restarts.",
+        long_help = "Transactions received through `eth_sendRawTransaction`, along with their blobs, are added back to the mempool on startup. The journal is periodically rewritten to drop the transactions that are no longer in the mempool.",
+        help_heading = "Node options"
+    )]
+    pub txpool_journal: Option<PathBuf>,
+    #[arg(
+        long = "txpool.accountslots",
+        default_value_t = MempoolConfig::default().account_slots,
+        value_name = "SLOTS",
+        help = "Maximum amount of executable transactions per sender in the mempool.",
+        help_heading = "Node options"
+    )]
+    pub txpool_account_slots: usize,
+    #[arg(
+        long = "txpool.accountqueue",
+        default_value_t = MempoolConfig::default().account_queue,
+        value_name = "SLOTS",
+        help = "Maximum amount of non-executable transactions per sender in the mempool.",
+        help_heading = "Node options"
+    )]
+    pub txpool_account_queue: usize,
+    #[arg(
+        long = "txpool.globalslots",
+        default_value_t = MempoolConfig::default().global_slots,
+        value_name = "SLOTS",
+        help = "Maximum amount of executable transactions in the mempool.",
+        help_heading = "Node options"
+    )]
+    pub txpool_global_slots: usize,
+    #[arg(
+        long = "txpool.globalqueue",
+        default_value_t = MempoolConfig::default().global_queue,
+        value_name = "SLOTS",
+        help = "Maximum amount of non-executable transactions in the mempool.",
+        help_heading = "Node options"
+    )]
+    pub txpool_global_queue: usize,
+    #[arg(
+        long = "txpool.pricebump",
+        default_value_t = MempoolConfig::default().price_bump,
+        value_name = "PERCENT",
+        help = "Minimum fee increase (in percent) required to replace a transaction in the mempool.",
+        help_heading = "Node options"
+    )]
+    pub txpool_price_bump: u64,
+    #[arg(
+        long = "blobpool.pricebump",
+        default_value_t = MempoolConfig::default().blob_price_bump,
+        value_name = "PERCENT",
+        help = "Minimum blob fee increase (in percent) required to replace a blob transaction in the mempool.",
+        help_heading = "Node options"
+    )]
+    pub blobpool_price_bump: u64,
+    #[arg(
+        long = "logs.bloom-index",
+        action = ArgAction::SetTrue,
+        help = "Index the logs blooms of the chain to speed up log queries over large block ranges.",
+        long_help = "Blocks are indexed in sections of 4096 blocks, once they are 256 blocks deep. Log queries use the index for the indexed sections and the block headers for the most recent blocks.",
+        help_heading = "Node options"
+    )]
+    pub logs_bloom_index: bool,
     #[arg(
         long = "http.addr",
         default_value = "localhost",
@@ -130,6 +249,31 @@ pub struct Options {
         env = "ETHREX_HTTP_PORT"
     )]
     pub http_port: String,
+    #[arg(
+        long = "ws.enabled",
+        action = ArgAction::SetTrue,
+        help = "Enable the websocket rpc server.",
+        help_heading = "RPC options"
+    )]
+    pub ws_enabled: bool,
+    #[arg(
+        long = "ws.addr",
+        default_value = "localhost",
+        value_name = "ADDRESS",
+        help = "Listening address for the websocket rpc server.",
+        help_heading = "RPC options",
+        env = "ETHREX_WS_ADDR"
+    )]
+    pub ws_addr: String,
+    #[arg(
+        long = "ws.port",
+        default_value = "8546",
+        value_name = "PORT",
+        help = "Listening port for the websocket rpc server.",
+        help_heading = "RPC options",
+        env = "ETHREX_WS_PORT"
+    )]
+    pub ws_port: String,
     #[arg(
         long = "authrpc.addr",
         default_value = "localhost",
@@ -154,6 +298,22 @@ pub struct Options {
         help_heading = "RPC options"
     )]
     pub authrpc_jwtsecret: String,
+    #[arg(
+        long = "logs.max-block-range",
+        default_value_t = LogQueryLimits::default().max_block_range,
+        value_name = "BLOCKS",
+        help = "Maximum amount of blocks that can be queried by a single eth_getLogs or filter request.",
+        help_heading = "RPC options"
+    )]
+    pub logs_max_block_range: u64,
+    #[arg(
+        long = "logs.max-results",
+        default_value_t = LogQueryLimits::default().max_results,
+        value_name = "LOGS",
+        help = "Maximum amount of logs that can be returned by a single eth_getLogs or filter request.",
+        help_heading = "RPC options"
+    )]
+    pub logs_max_results: usize,
     #[arg(long = "p2p.enabled", default_value = if cfg!(feature = "l2") { "false" } else { "true" }, value_name = "P2P_ENABLED", action = ArgAction::SetTrue, help_heading = "P2P options")]
     pub p2p_enabled: bool,
     #[arg(
@@ -186,6 +346,16 @@ pub struct Options {
         help_heading = "P2P options"
     )]
     pub discovery_port: String,
+    #[arg(
+        long = "discovery.protocol",
+        default_value = "v4",
+        value_name = "PROTOCOL",
+        value_parser = utils::parse_discovery_protocol,
+        help = "Protocol used for P2P discovery.",
+        long_help = "Can be either \"v4\" or \"v5\" with \"v4\" as default value.",
+        help_heading = "P2P options"
+    )]
+    pub discovery_protocol: DiscoveryProtocol,
 }
 
 impl Default for Options {
@@ -193,19 +363,37 @@ impl Default for Options {
         Self {
             http_addr: Default::default(),
             http_port: Default::default(),
+            ws_enabled: Default::default(),
+            ws_addr: Default::default(),
+            ws_port: Default::default(),
             log_level: Level::INFO,
             authrpc_addr: Default::default(),
             authrpc_port: Default::default(),
             authrpc_jwtsecret: Default::default(),
+            logs_max_block_range: LogQueryLimits::default().max_block_range,
+            logs_max_results: LogQueryLimits::default().max_results,
             p2p_enabled: Default::default(),
             p2p_addr: Default::default(),
             p2p_port: Default::default(),
             discovery_addr: Default::default(),
             discovery_port: Default::default(),
+            discovery_protocol: Default::default(),
             network: Default::default(),
             bootnodes: Default::default(),
             datadir: Default::default(),
             syncmode: Default::default(),
+            gcmode: Default::default(),
+            state_retention: DEFAULT_STATE_RETENTION,
+            state_history: false,
+            state_snapshot: false,
+            txpool_journal: None,
+            txpool_account_slots: MempoolConfig::default().account_slots,
+            txpool_account_queue: MempoolConfig::default().account_queue,
+            txpool_global_slots: MempoolConfig::default().global_slots,
+            txpool_global_queue: MempoolConfig::default().global_queue,
+            txpool_price_bump: MempoolConfig::default().price_bump,
+            blobpool_price_bump: MempoolConfig::default().blob_price_bump,
+            logs_bloom_index: false,
             metrics_addr: "0.0.0.0".to_owned(),
             metrics_port: Default::default(),
             metrics_enabled: Default::default(),
@@ -237,6 +425,41 @@ pub enum Subcommand {
         #[arg(long = "removedb", action = ArgAction::SetTrue)]
         removedb: bool,
     },
+    #[command(name = "export", about = "Export blocks in the database to a file")]
+    Export {
+        #[arg(
+            required = true,
+            value_name = "FILE_PATH",
+            help = "Path to the RLP chain file to write, gzip compressed if it ends with .gz"
+        )]
+        path: String,
+        #[arg(
+            long = "first",
+            value_name = "NUMBER",
+            help = "First block to export, the genesis block is not exported by default as importing it is not needed",
+            default_value_t = 1
+        )]
+        first: BlockNumber,
+        #[arg(
+            long = "last",
+            value_name = "NUMBER",
+            help = "Last block to export, defaults to the latest block"
+        )]
+        last: Option<BlockNumber>,
+    },
+    #[command(
+        name = "rewind",
+        about = "Rewind the canonical chain to a previous block"
+    )]
+    Rewind {
+        #[arg(
+            long = "to",
+            value_name = "NUMBER",
+            help = "Number of the block that becomes the latest block",
+            required = true
+        )]
+        to: BlockNumber,
+    },
     #[cfg(any(feature = "l2", feature = "based"))]
     #[command(subcommand)]
     L2(l2::Command),
@@ -266,7 +489,29 @@ impl Subcommand {
                     .as_ref()
                     .expect("--network is required and it was not provided");
 
-                import_blocks(&path, &opts.datadir, network, opts.evm).await;
+                import_blocks(
+                    &path,
+                    &opts.datadir,
+                    network,
+                    opts.evm,
+                    get_state_retention(opts),
+                    opts.state_history,
+                    opts.state_snapshot,
+                )
+                .await?;
+            }
+            Subcommand::Export { path, first, last } => {
+                export_blocks(&path, &opts.datadir, first, last).await?;
+            }
+            Subcommand::Rewind { to } => {
+                rewind_chain(
+                    &opts.datadir,
+                    to,
+                    get_state_retention(opts),
+                    opts.state_history,
+                    opts.state_snapshot,
+                )
+                .await?;
             }
             #[cfg(any(feature = "l2", feature = "based"))]
             Subcommand::L2(command) => command.run().await?,
@@ -302,12 +547,27 @@ pub fn remove_db(datadir: &str, force: bool) {
     }
 }
 
-pub async fn import_blocks(path: &str, data_dir: &str, network: &str, evm: EvmEngine) {
+pub async fn import_blocks(
+    path: &str,
+    data_dir: &str,
+    network: &str,
+    evm: EvmEngine,
+    state_retention: Option<u64>,
+    state_history: bool,
+    state_snapshot: bool,
+) -> eyre::Result<()> {
     let data_dir = set_datadir(data_dir);
 
-    let store = init_store(&data_dir, network).await;
+    let store = init_store(
+        &data_dir,
+        network,
+        state_retention,
+        state_history,
+        state_snapshot,
+    )
+    .await?;
 
-    let blockchain = init_blockchain(evm, store.clone());
+    let blockchain = init_blockchain(evm, store.clone(), MempoolConfig::default(), None);
 
     let path_metadata = metadata(path).expect("Failed to read path");
     let blocks = if path_metadata.is_dir() {
@@ -332,6 +592,12 @@ pub async fn import_blocks(path: &str, data_dir: &str, network: &str, evm: EvmEn
     for block in &blocks {
         let hash = block.hash();
 
+        // The genesis block is already stored by `init_store`, it has no parent to execute it on
+        if block.header.number == 0 {
+            info!("Skipping genesis block with hash {hash:#x}.");
+            continue;
+        }
+
         info!(
             "Adding block {} with hash {:#x}.",
             block.header.number, hash
@@ -342,7 +608,7 @@ pub async fn import_blocks(path: &str, data_dir: &str, network: &str, evm: EvmEn
                 "Failed to add block {} with hash {:#x}: {}.",
                 block.header.number, hash, error
             );
-            return;
+            return Ok(());
         }
     }
 
@@ -354,4 +620,172 @@ pub async fn import_blocks(path: &str, data_dir: &str, network: &str, evm: EvmEn
     }
 
     info!("Added {size} blocks to blockchain");
+    Ok(())
+}
+
+/// Writes the canonical blocks in `first..=last` to an RLP chain file that can be
+/// read back by `import_blocks` or other clients
+pub async fn export_blocks(
+    path: &str,
+    data_dir: &str,
+    first: BlockNumber,
+    last: Option<BlockNumber>,
+) -> eyre::Result<()> {
+    let data_dir = set_datadir(data_dir);
+    if !Path::new(&data_dir).exists() {
+        bail!("Data directory does not exist: {data_dir}");
+    }
+    let store = open_store(&data_dir);
+
+    let last = match last {
+        Some(last) => last,
+        None => store.get_latest_block_number().await?,
+    };
+    if first > last {
+        bail!("First block {first} is greater than last block {last}");
+    }
+
+    info!("Exporting blocks {first} to {last} to chain file: {path}");
+
+    let mut file = BufWriter::new(File::create(path)?);
+    if path.ends_with(".gz") {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        write_chain(&store, first, last, &mut encoder).await?;
+        encoder.finish()?.flush()?;
+    } else {
+        write_chain(&store, first, last, &mut file).await?;
+        file.flush()?;
+    }
+
+    info!("Exported {} blocks to {path}", last - first + 1);
+    Ok(())
+}
+
+/// Rewinds the canonical chain stored in the database to the given block
+pub async fn rewind_chain(
+    data_dir: &str,
+    block_number: BlockNumber,
+    state_retention: Option<u64>,
+    state_history: bool,
+    state_snapshot: bool,
+) -> eyre::Result<()> {
+    let data_dir = set_datadir(data_dir);
+    if !Path::new(&data_dir).exists() {
+        bail!("Data directory does not exist: {data_dir}");
+    }
+    // The store is configured as the node runs it, so the rewind keeps its state consistent
+    let store = configure_store(
+        open_store(&data_dir),
+        state_retention,
+        state_history,
+        state_snapshot,
+    )
+    .await?;
+    store.rewind(block_number).await?;
+    info!("Rewound chain to block {block_number}");
+    Ok(())
+}
+
+async fn write_chain(
+    store: &Store,
+    first: BlockNumber,
+    last: BlockNumber,
+    writer: &mut impl Write,
+) -> eyre::Result<()> {
+    for number in first..=last {
+        let block_hash = store
+            .get_canonical_block_hash(number)
+            .await?
+            .ok_or_else(|| eyre!("Block {number} is not in the canonical chain"))?;
+        let block = store
+            .get_block_by_hash(block_hash)
+            .await?
+            .ok_or_else(|| eyre!("Block {number} with hash {block_hash:#x} not found"))?;
+        writer.write_all(&block.encode_to_vec())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::read_chain_file;
+
+    const CHAIN_FILE: &str = "../../test_data/l2-loadtest.rlp";
+    const GENESIS_FILE: &str = "../../test_data/genesis-l2-ci.json";
+
+    #[tokio::test]
+    async fn export_import_roundtrip() {
+        let test_dir =
+            std::env::temp_dir().join(format!("ethrex-export-import-{:x}", rand::random::<u64>()));
+        let path = |name: &str| test_dir.join(name).to_str().unwrap().to_owned();
+        for name in ["original", "imported"] {
+            std::fs::create_dir_all(path(name)).unwrap();
+        }
+        let expected = read_chain_file(CHAIN_FILE);
+        let last_block = expected.last().unwrap();
+
+        import_blocks(
+            CHAIN_FILE,
+            &path("original"),
+            GENESIS_FILE,
+            EvmEngine::LEVM,
+            None,
+            false,
+            false,
+        )
+        .await
+        .unwrap();
+
+        // The default range starts after the genesis block
+        export_blocks(&path("default.rlp"), &path("original"), 1, None)
+            .await
+            .unwrap();
+        assert_eq!(read_chain_file(&path("default.rlp")), expected);
+
+        // A chain file including the genesis block can be imported too
+        export_blocks(&path("full.rlp.gz"), &path("original"), 0, None)
+            .await
+            .unwrap();
+        let exported = read_chain_file(&path("full.rlp.gz"));
+        assert_eq!(exported.first().unwrap().header.number, 0);
+        assert_eq!(&exported[1..], expected.as_slice());
+        import_blocks(
+            &path("full.rlp.gz"),
+            &path("imported"),
+            GENESIS_FILE,
+            EvmEngine::LEVM,
+            None,
+            false,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let store = open_store(&path("imported"));
+        assert_eq!(
+            store.get_latest_block_number().await.unwrap(),
+            last_block.header.number
+        );
+        assert_eq!(
+            store.get_latest_canonical_block_hash().await.unwrap(),
+            Some(last_block.hash())
+        );
+
+        std::fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn state_history_requires_archive_mode() {
+        let data_dir =
+            std::env::temp_dir().join(format!("ethrex-state-history-{:x}", rand::random::<u64>()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let data_dir = data_dir.to_str().unwrap();
+
+        assert!(init_store(data_dir, GENESIS_FILE, Some(128), true, false)
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(data_dir).unwrap();
+    }
 }
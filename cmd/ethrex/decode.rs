@@ -4,7 +4,7 @@ use ethrex_common::types::{Block, Genesis};
 use ethrex_rlp::decode::RLPDecode as _;
 use std::{
     fs::File,
-    io::{BufReader, Read as _},
+    io::{BufReader, Read},
 };
 pub fn jwtsecret_file(file: &mut File) -> Bytes {
     let mut contents = String::new();
@@ -18,8 +18,8 @@ pub fn jwtsecret_file(file: &mut File) -> Bytes {
         .expect("Secret should be hex encoded")
         .into()
 }
-pub fn chain_file(file: File) -> Result<Vec<Block>, Error> {
-    let mut chain_rlp_reader = BufReader::new(file);
+pub fn chain_file(reader: impl Read) -> Result<Vec<Block>, Error> {
+    let mut chain_rlp_reader = BufReader::new(reader);
     let mut buf = vec![];
     chain_rlp_reader.read_to_end(&mut buf)?;
     let mut buf = buf.as_slice();
@@ -41,7 +41,8 @@ pub fn genesis_file(file: File) -> Result<Genesis, serde_json::Error> {
 mod tests {
     use crate::decode::chain_file;
     use ethrex_common::H256;
-    use std::{fs::File, str::FromStr as _};
+    use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+    use std::{fs::File, io::Write as _, str::FromStr as _};
 
     #[test]
     fn decode_chain_file() {
@@ -74,4 +75,18 @@ mod tests {
             "Last block hash does not match"
         );
     }
+
+    #[test]
+    fn decode_gzipped_chain_file() {
+        let chain_rlp =
+            std::fs::read("../../test_data/chain.rlp").expect("Failed to read chain file");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&chain_rlp).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let blocks = chain_file(MultiGzDecoder::new(compressed.as_slice()))
+            .expect("Failed to decode gzipped chain file");
+        let expected = chain_file(chain_rlp.as_slice()).expect("Failed to decode chain file");
+        assert_eq!(expected, blocks);
+    }
 }
@@ -2,7 +2,8 @@ use clap::Parser;
 use ethrex::{
     cli::CLI,
     initializers::{
-        get_local_p2p_node, get_network, get_signer, init_blockchain, init_metrics, init_rpc_api,
+        get_local_p2p_node, get_mempool_config, get_network, get_signer, get_state_retention,
+        init_blockchain, init_bloom_indexer, init_mempool_journal, init_metrics, init_rpc_api,
         init_store, init_tracing,
     },
     utils::{set_datadir, store_known_peers},
@@ -29,9 +30,21 @@ async fn main() -> eyre::Result<()> {
 
     let network = get_network(&opts);
 
-    let store = init_store(&data_dir, &network).await;
+    let store = init_store(
+        &data_dir,
+        &network,
+        get_state_retention(&opts),
+        opts.state_history,
+        opts.state_snapshot,
+    )
+    .await?;
 
-    let blockchain = init_blockchain(opts.evm, store.clone());
+    let blockchain = init_blockchain(
+        opts.evm,
+        store.clone(),
+        get_mempool_config(&opts),
+        opts.txpool_journal.clone(),
+    );
 
     let signer = get_signer(&data_dir);
 
@@ -44,6 +57,14 @@ async fn main() -> eyre::Result<()> {
 
     let cancel_token = tokio_util::sync::CancellationToken::new();
 
+    if opts.logs_bloom_index {
+        init_bloom_indexer(store.clone(), cancel_token.clone(), tracker.clone());
+    }
+
+    if opts.txpool_journal.is_some() {
+        init_mempool_journal(blockchain.clone(), cancel_token.clone(), tracker.clone()).await;
+    }
+
     init_rpc_api(
         &opts,
         #[cfg(any(feature = "l2", feature = "based"))]
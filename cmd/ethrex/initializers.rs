@@ -1,15 +1,16 @@
 use crate::{
-    cli::Options,
+    cli::{GcMode, Options},
     networks,
     utils::{parse_socket_addr, read_genesis_file, read_jwtsecret_file, read_known_peers},
 };
-use ethrex_blockchain::Blockchain;
+use ethrex_blockchain::{mempool::MempoolConfig, mempool_journal::MempoolJournal, Blockchain};
 use ethrex_p2p::{
     kademlia::KademliaTable,
     network::node_id_from_signing_key,
     sync_manager::SyncManager,
     types::{Node, NodeRecord},
 };
+use ethrex_rpc::LogQueryLimits;
 use ethrex_storage::{EngineType, Store};
 use ethrex_vm::EvmEngine;
 use k256::ecdsa::SigningKey;
@@ -21,10 +22,11 @@ use std::{
     net::{Ipv4Addr, SocketAddr},
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use tokio::sync::Mutex;
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::{filter::Directive, EnvFilter, FmtSubscriber};
 
 #[cfg(feature = "l2")]
@@ -45,6 +47,12 @@ use ethrex_rpc::{EngineClient, EthClient};
 #[cfg(feature = "based")]
 use std::str::FromStr;
 
+/// How often the mempool journal is rewritten to drop the transactions that left the mempool
+const MEMPOOL_REJOURNAL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often the bloom indexer checks for new sections to index
+const BLOOM_INDEX_INTERVAL: Duration = Duration::from_secs(60);
+
 pub fn init_tracing(opts: &Options) {
     let log_filter = EnvFilter::builder()
         .with_default_directive(Directive::from(opts.log_level))
@@ -68,9 +76,58 @@ pub fn init_metrics(opts: &Options, tracker: TaskTracker) {
     tracker.spawn(metrics_api);
 }
 
-pub async fn init_store(data_dir: &str, network: &str) -> Store {
+pub async fn init_store(
+    data_dir: &str,
+    network: &str,
+    state_retention: Option<u64>,
+    state_history: bool,
+    state_snapshot: bool,
+) -> eyre::Result<Store> {
+    let store = open_store(data_dir);
+    let genesis = read_genesis_file(network);
+    store
+        .add_initial_state(genesis.clone())
+        .await
+        .expect("Failed to create genesis block");
+    configure_store(store, state_retention, state_history, state_snapshot).await
+}
+
+/// Enables the state retention, history and snapshot of the store as requested in the options
+pub async fn configure_store(
+    store: Store,
+    state_retention: Option<u64>,
+    state_history: bool,
+    state_snapshot: bool,
+) -> eyre::Result<Store> {
+    // The history can't be rebuilt for the blocks whose state was pruned
+    if state_history && state_retention.is_some() {
+        eyre::bail!("--state.history can only be enabled with --gcmode archive");
+    }
+    let store = match state_retention {
+        Some(retention) => store
+            .with_state_retention(retention)
+            .await
+            .expect("Failed to enable state pruning"),
+        None if state_history => store
+            .with_archive_mode()
+            .await
+            .expect("Failed to enable archive mode"),
+        None => store,
+    };
+    if state_snapshot {
+        Ok(store
+            .with_state_snapshot()
+            .await
+            .expect("Failed to enable state snapshot"))
+    } else {
+        Ok(store)
+    }
+}
+
+/// Opens the database in the given directory without initializing it
+pub fn open_store(data_dir: &str) -> Store {
     let path = PathBuf::from(data_dir);
-    let store = if path.ends_with("memory") {
+    if path.ends_with("memory") {
         Store::new(data_dir, EngineType::InMemory).expect("Failed to create Store")
     } else {
         cfg_if::cfg_if! {
@@ -85,17 +142,70 @@ pub async fn init_store(data_dir: &str, network: &str) -> Store {
             }
         }
         Store::new(data_dir, engine_type).expect("Failed to create Store")
-    };
-    let genesis = read_genesis_file(network);
-    store
-        .add_initial_state(genesis.clone())
-        .await
-        .expect("Failed to create genesis block");
-    store
+    }
 }
 
-pub fn init_blockchain(evm_engine: EvmEngine, store: Store) -> Arc<Blockchain> {
-    Blockchain::new(evm_engine, store).into()
+pub fn init_blockchain(
+    evm_engine: EvmEngine,
+    store: Store,
+    mempool_config: MempoolConfig,
+    mempool_journal: Option<PathBuf>,
+) -> Arc<Blockchain> {
+    let blockchain = Blockchain::new(evm_engine, store).with_mempool_config(mempool_config);
+    match mempool_journal {
+        Some(path) => blockchain.with_mempool_journal(
+            MempoolJournal::open(path).expect("Failed to open the mempool journal"),
+        ),
+        None => blockchain,
+    }
+    .into()
+}
+
+/// Adds the journaled transactions back to the mempool and keeps the journal
+/// in sync with the mempool until the node shuts down
+pub async fn init_mempool_journal(
+    blockchain: Arc<Blockchain>,
+    cancel_token: CancellationToken,
+    tracker: TaskTracker,
+) {
+    if let Err(error) = blockchain.replay_mempool_journal().await {
+        error!("Failed to restore the journaled transactions: {error}");
+    }
+    tracker.spawn(async move {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(MEMPOOL_REJOURNAL_INTERVAL) => {}
+                _ = cancel_token.cancelled() => break,
+            }
+            if let Err(error) = blockchain.rejournal_mempool() {
+                warn!("Failed to rewrite the mempool journal: {error}");
+            }
+        }
+        if let Err(error) = blockchain.rejournal_mempool() {
+            warn!("Failed to rewrite the mempool journal: {error}");
+        }
+    });
+}
+
+/// Indexes the logs blooms of the chain in the background as new sections become available
+pub fn init_bloom_indexer(store: Store, cancel_token: CancellationToken, tracker: TaskTracker) {
+    tracker.spawn(async move {
+        while !cancel_token.is_cancelled() {
+            match store.index_next_bloom_section().await {
+                // Keep indexing until there are no complete sections left
+                Ok(Some(section)) => {
+                    debug!("Indexed the logs blooms of section {section}");
+                    continue;
+                }
+                Ok(None) => {}
+                Err(error) => warn!("Failed to index the logs blooms: {error}"),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(BLOOM_INDEX_INTERVAL) => {}
+                _ = cancel_token.cancelled() => break,
+            }
+        }
+    });
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -129,6 +239,7 @@ pub async fn init_rpc_api(
 
     let rpc_api = ethrex_rpc::start_api(
         get_http_socket_addr(opts),
+        get_ws_socket_addr(opts),
         get_authrpc_socket_addr(opts),
         store,
         blockchain,
@@ -136,6 +247,7 @@ pub async fn init_rpc_api(
         local_p2p_node,
         local_node_record,
         syncer,
+        get_log_query_limits(opts),
         #[cfg(feature = "based")]
         get_gateway_http_client(&l2_opts.based_opts),
         #[cfg(feature = "based")]
@@ -206,6 +318,7 @@ pub async fn init_network(
         peer_table.clone(),
         store,
         blockchain,
+        opts.discovery_protocol,
     )
     .await
     .expect("Network starts");
@@ -269,6 +382,33 @@ pub fn get_network(opts: &Options) -> String {
     network
 }
 
+/// Returns the block range and result limits applied to the log queries served by the RPC
+pub fn get_log_query_limits(opts: &Options) -> LogQueryLimits {
+    LogQueryLimits {
+        max_block_range: opts.logs_max_block_range,
+        max_results: opts.logs_max_results,
+    }
+}
+
+pub fn get_mempool_config(opts: &Options) -> MempoolConfig {
+    MempoolConfig {
+        account_slots: opts.txpool_account_slots,
+        account_queue: opts.txpool_account_queue,
+        global_slots: opts.txpool_global_slots,
+        global_queue: opts.txpool_global_queue,
+        price_bump: opts.txpool_price_bump,
+        blob_price_bump: opts.blobpool_price_bump,
+    }
+}
+
+/// Returns the amount of blocks whose state should be kept, or `None` if the node runs in archive mode
+pub fn get_state_retention(opts: &Options) -> Option<u64> {
+    match opts.gcmode {
+        GcMode::Archive => None,
+        GcMode::Full => Some(opts.state_retention),
+    }
+}
+
 #[allow(dead_code)]
 pub fn get_bootnodes(opts: &Options, network: &str, data_dir: &str) -> Vec<Node> {
     let mut bootnodes: Vec<Node> = opts.bootnodes.clone();
@@ -372,6 +512,13 @@ pub fn get_http_socket_addr(opts: &Options) -> SocketAddr {
         .expect("Failed to parse http address and port")
 }
 
+pub fn get_ws_socket_addr(opts: &Options) -> Option<SocketAddr> {
+    opts.ws_enabled.then(|| {
+        parse_socket_addr(&opts.ws_addr, &opts.ws_port)
+            .expect("Failed to parse ws address and port")
+    })
+}
+
 #[cfg(feature = "l2")]
 pub fn get_valid_delegation_addresses(l2_opts: &L2Options) -> Vec<Address> {
     let Some(ref path) = l2_opts.sponsorable_addresses_file_path else {
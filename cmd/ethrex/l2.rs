@@ -1,7 +1,8 @@
 use crate::{
     cli::{self as ethrex_cli, Options},
     initializers::{
-        get_local_p2p_node, get_network, get_signer, init_blockchain, init_metrics, init_network,
+        get_local_p2p_node, get_mempool_config, get_network, get_signer, get_state_retention,
+        init_blockchain, init_bloom_indexer, init_mempool_journal, init_metrics, init_network,
         init_rpc_api, init_store,
     },
     utils::{self, set_datadir, store_known_peers},
@@ -14,6 +15,7 @@ use ethrex_rpc::{
     clients::{beacon::BeaconClient, eth::BlockByNumber},
     EthClient,
 };
+use ethrex_vm::EvmEngine;
 use eyre::OptionExt;
 use keccak_hash::keccak;
 use reqwest::Url;
@@ -124,9 +126,28 @@ impl Command {
 
                 let network = get_network(&opts.node_opts);
 
-                let store = init_store(&data_dir, &network).await;
+                let store = init_store(
+                    &data_dir,
+                    &network,
+                    get_state_retention(&opts.node_opts),
+                    opts.node_opts.state_history,
+                    opts.node_opts.state_snapshot,
+                )
+                .await?;
+
+                // Only LEVM charges the L1 fee, REVM would produce blocks the prover rejects
+                if opts.node_opts.evm == EvmEngine::REVM
+                    && store.get_chain_config()?.l1_fee_vault.is_some()
+                {
+                    eyre::bail!("The L1 fee vault is only supported with --evm levm");
+                }
 
-                let blockchain = init_blockchain(opts.node_opts.evm, store.clone());
+                let blockchain = init_blockchain(
+                    opts.node_opts.evm,
+                    store.clone(),
+                    get_mempool_config(&opts.node_opts),
+                    opts.node_opts.txpool_journal.clone(),
+                );
 
                 let signer = get_signer(&data_dir);
 
@@ -139,6 +160,15 @@ impl Command {
 
                 let cancel_token = tokio_util::sync::CancellationToken::new();
 
+                if opts.node_opts.logs_bloom_index {
+                    init_bloom_indexer(store.clone(), cancel_token.clone(), tracker.clone());
+                }
+
+                if opts.node_opts.txpool_journal.is_some() {
+                    init_mempool_journal(blockchain.clone(), cancel_token.clone(), tracker.clone())
+                        .await;
+                }
+
                 init_rpc_api(
                     &opts.node_opts,
                     &opts,
@@ -219,7 +249,7 @@ impl Command {
                     .checked_sub(U256::from(64))
                     .ok_or_eyre("Cannot get finalized block")?;
 
-                let event_signature = keccak("BlockCommitted(bytes32)");
+                let event_signature = keccak("BatchCommitted(uint256,bytes32)");
 
                 loop {
                     // Wait for a block
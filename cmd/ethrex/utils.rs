@@ -1,10 +1,13 @@
-use crate::decode;
+use crate::{cli::GcMode, decode};
 use bytes::Bytes;
 use directories::ProjectDirs;
 use ethrex_common::types::{Block, Genesis};
-use ethrex_p2p::{kademlia::KademliaTable, sync::SyncMode, types::Node};
+use ethrex_p2p::{
+    kademlia::KademliaTable, network::DiscoveryProtocol, sync::SyncMode, types::Node,
+};
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_vm::EvmEngine;
+use flate2::read::MultiGzDecoder;
 use hex::FromHexError;
 #[cfg(feature = "l2")]
 use secp256k1::SecretKey;
@@ -44,7 +47,12 @@ pub fn generate_jwt_secret() -> String {
 
 pub fn read_chain_file(chain_rlp_path: &str) -> Vec<Block> {
     let chain_file = std::fs::File::open(chain_rlp_path).expect("Failed to open chain rlp file");
-    decode::chain_file(chain_file).expect("Failed to decode chain rlp file")
+    if chain_rlp_path.ends_with(".gz") {
+        decode::chain_file(MultiGzDecoder::new(chain_file))
+    } else {
+        decode::chain_file(chain_file)
+    }
+    .expect("Failed to decode chain rlp file")
 }
 
 pub fn read_block_file(block_file_path: &str) -> Block {
@@ -73,6 +81,26 @@ pub fn parse_sync_mode(s: &str) -> eyre::Result<SyncMode> {
     }
 }
 
+pub fn parse_discovery_protocol(s: &str) -> eyre::Result<DiscoveryProtocol> {
+    match s {
+        "v4" => Ok(DiscoveryProtocol::V4),
+        "v5" => Ok(DiscoveryProtocol::V5),
+        other => Err(eyre::eyre!(
+            "Invalid discovery protocol {other:?} expected either v4 or v5",
+        )),
+    }
+}
+
+pub fn parse_gc_mode(s: &str) -> eyre::Result<GcMode> {
+    match s {
+        "archive" => Ok(GcMode::Archive),
+        "full" => Ok(GcMode::Full),
+        other => Err(eyre::eyre!(
+            "Invalid gcmode {other:?} expected either archive or full",
+        )),
+    }
+}
+
 pub fn parse_socket_addr(addr: &str, port: &str) -> io::Result<SocketAddr> {
     // NOTE: this blocks until hostname can be resolved
     format!("{addr}:{port}")
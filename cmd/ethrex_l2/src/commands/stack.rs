@@ -225,9 +225,11 @@ impl Command {
                         .await
                         .expect("Error applying account updates");
 
+                    // Each blob holds the state diff of a whole batch, so only the
+                    // last block of the batch can be reconstructed.
+                    let new_block_number = state_diff.header.number;
                     let new_block = BlockHeader {
                         coinbase,
-                        number: last_number + 1,
                         parent_hash: last_hash,
                         state_root: new_trie.hash().expect("Error committing state"),
                         ..state_diff.header
@@ -236,13 +238,13 @@ impl Command {
 
                     store.add_block_header(new_block_hash, new_block).await?;
                     store
-                        .add_block_number(new_block_hash, last_number + 1)
+                        .add_block_number(new_block_hash, new_block_number)
                         .await?;
                     store
-                        .set_canonical_block(last_number + 1, new_block_hash)
+                        .set_canonical_block(new_block_number, new_block_hash)
                         .await?;
 
-                    last_number += 1;
+                    last_number = new_block_number;
                     last_hash = new_block_hash;
                 }
 
@@ -3,18 +3,24 @@ use bytes::Bytes;
 use clap::Subcommand;
 use ethereum_types::{Address, H256, U256};
 use ethrex_l2_sdk::calldata::{encode_calldata, Value};
-use ethrex_l2_sdk::merkle_tree::merkle_proof;
-use ethrex_l2_sdk::{get_withdrawal_hash, COMMON_BRIDGE_L2_ADDRESS, L2_WITHDRAW_SIGNATURE};
+use ethrex_l2_sdk::{
+    get_erc20_withdrawal_amount, get_withdraw_merkle_proof, COMMON_BRIDGE_L2_ADDRESS,
+    L2_WITHDRAW_ERC20_SIGNATURE, L2_WITHDRAW_SIGNATURE,
+};
 use ethrex_rpc::clients::eth::BlockByNumber;
-use ethrex_rpc::clients::eth::{eth_sender::Overrides, EthClient};
-use ethrex_rpc::types::block::BlockBodyWrapper;
-use eyre::OptionExt;
+use ethrex_rpc::clients::eth::{errors::CalldataEncodeError, eth_sender::Overrides, EthClient};
 use hex::FromHexError;
-use itertools::Itertools;
 
 const CLAIM_WITHDRAWAL_SIGNATURE: &str =
     "claimWithdrawal(bytes32,uint256,uint256,uint256,bytes32[])";
 
+const CLAIM_ERC20_WITHDRAWAL_SIGNATURE: &str =
+    "claimERC20Withdrawal(address,bytes32,uint256,uint256,uint256,bytes32[])";
+
+const DEPOSIT_ERC20_SIGNATURE: &str = "depositERC20(address,address,uint256)";
+
+const APPROVE_SIGNATURE: &str = "approve(address,uint256)";
+
 #[derive(Subcommand)]
 pub(crate) enum Command {
     #[clap(about = "Get the balance of the wallet.")]
@@ -51,6 +57,11 @@ pub(crate) enum Command {
     #[clap(about = "Finalize a pending withdrawal.")]
     ClaimWithdraw {
         l2_withdrawal_tx_hash: H256,
+        #[clap(
+            long = "token",
+            help = "Specify the L1 token address if the withdrawal is of an ERC20 token."
+        )]
+        token_address: Option<Address>,
         #[arg(short = 'w', required = false)]
         wait_for_receipt: bool,
     },
@@ -204,48 +215,6 @@ fn decode_hex(s: &str) -> Result<Bytes, FromHexError> {
     }
 }
 
-async fn get_withdraw_merkle_proof(
-    client: &EthClient,
-    tx_hash: H256,
-) -> Result<(u64, Vec<H256>), eyre::Error> {
-    let tx_receipt = client
-        .get_transaction_receipt(tx_hash)
-        .await?
-        .ok_or_eyre("Transaction receipt not found")?;
-
-    let block = client
-        .get_block_by_hash(tx_receipt.block_info.block_hash)
-        .await?;
-
-    let transactions = match block.body {
-        BlockBodyWrapper::Full(body) => body.transactions,
-        BlockBodyWrapper::OnlyHashes(_) => unreachable!(),
-    };
-
-    let (index, tx_withdrawal_hash) = transactions
-        .iter()
-        .find_position(|tx| tx.hash == tx_hash)
-        .map(|(i, tx)| (i as u64, get_withdrawal_hash(&tx.tx).unwrap()))
-        .ok_or_eyre("Transaction is not a Withdrawal")?;
-
-    let path = merkle_proof(
-        transactions
-            .iter()
-            .filter_map(|tx| get_withdrawal_hash(&tx.tx))
-            .collect(),
-        tx_withdrawal_hash,
-    )
-    .map_err(|err| {
-        eyre::eyre!(
-            "Failed to generate merkle proof in get_withdraw_merkle_proof: {:?}",
-            err
-        )
-    })?
-    .ok_or_eyre("Transaction's WithdrawalData is not in block's WithdrawalDataMerkleRoot")?;
-
-    Ok((index, path))
-}
-
 impl Command {
     pub async fn run(self, cfg: EthrexL2Config) -> eyre::Result<()> {
         let eth_client = EthClient::new(&cfg.network.l1_rpc_url);
@@ -286,6 +255,65 @@ impl Command {
                 wait_for_receipt,
                 explorer_url: _,
             } => {
+                if let Some(token_address) = token_address {
+                    // The bridge pulls the tokens with transferFrom, so it must be
+                    // approved before the deposit.
+                    let approve_tx = eth_client
+                        .build_eip1559_transaction(
+                            token_address,
+                            cfg.wallet.address,
+                            encode_calldata(
+                                APPROVE_SIGNATURE,
+                                &[
+                                    Value::Address(cfg.contracts.common_bridge),
+                                    Value::Uint(amount),
+                                ],
+                            )?
+                            .into(),
+                            Overrides {
+                                chain_id: Some(cfg.network.l1_chain_id),
+                                from: Some(cfg.wallet.address),
+                                ..Default::default()
+                            },
+                        )
+                        .await?;
+                    let approve_tx_hash = eth_client
+                        .send_eip1559_transaction(&approve_tx, &cfg.wallet.private_key)
+                        .await?;
+                    println!("Approval sent: {approve_tx_hash:#x}");
+                    wait_for_transaction_receipt(&eth_client, approve_tx_hash).await?;
+
+                    let deposit_tx = eth_client
+                        .build_eip1559_transaction(
+                            cfg.contracts.common_bridge,
+                            cfg.wallet.address,
+                            encode_calldata(
+                                DEPOSIT_ERC20_SIGNATURE,
+                                &[
+                                    Value::Address(token_address),
+                                    Value::Address(to.unwrap_or(cfg.wallet.address)),
+                                    Value::Uint(amount),
+                                ],
+                            )?
+                            .into(),
+                            Overrides {
+                                chain_id: Some(cfg.network.l1_chain_id),
+                                from: Some(cfg.wallet.address),
+                                ..Default::default()
+                            },
+                        )
+                        .await?;
+                    let tx_hash = eth_client
+                        .send_eip1559_transaction(&deposit_tx, &cfg.wallet.private_key)
+                        .await?;
+
+                    println!("ERC20 deposit sent: {tx_hash:#x}");
+
+                    if wait_for_receipt {
+                        wait_for_transaction_receipt(&eth_client, tx_hash).await?;
+                    }
+                    return Ok(());
+                }
                 if to.is_some() {
                     // There are two ways of depositing funds into the L2:
                     // 1. Directly transferring funds to the bridge.
@@ -293,9 +321,6 @@ impl Command {
                     // The second method is not handled in the CLI yet.
                     todo!("Handle deposits through contract")
                 }
-                if token_address.is_some() {
-                    todo!("Handle ERC20 deposits")
-                }
                 Box::pin(async {
                     Self::Transfer {
                         amount,
@@ -313,39 +338,51 @@ impl Command {
             }
             Command::ClaimWithdraw {
                 l2_withdrawal_tx_hash,
+                token_address,
                 wait_for_receipt,
             } => {
-                let (withdrawal_l2_block_number, claimed_amount) = match rollup_client
-                    .get_transaction_by_hash(l2_withdrawal_tx_hash)
-                    .await?
-                {
-                    Some(l2_withdrawal_tx) => {
-                        (l2_withdrawal_tx.block_number, l2_withdrawal_tx.value)
-                    }
-                    None => {
-                        println!("Withdrawal transaction not found in L2");
-                        return Ok(());
-                    }
+                let claimed_amount = match token_address {
+                    // ERC20 withdrawals don't carry value, the amount is in the event
+                    Some(_) => match rollup_client
+                        .get_transaction_receipt(l2_withdrawal_tx_hash)
+                        .await?
+                        .as_ref()
+                        .and_then(get_erc20_withdrawal_amount)
+                    {
+                        Some(amount) => amount,
+                        None => {
+                            println!("ERC20 withdrawal transaction not found in L2");
+                            return Ok(());
+                        }
+                    },
+                    None => match rollup_client
+                        .get_transaction_by_hash(l2_withdrawal_tx_hash)
+                        .await?
+                    {
+                        Some(l2_withdrawal_tx) => l2_withdrawal_tx.value,
+                        None => {
+                            println!("Withdrawal transaction not found in L2");
+                            return Ok(());
+                        }
+                    },
                 };
 
-                let (index, proof) =
-                    get_withdraw_merkle_proof(&rollup_client, l2_withdrawal_tx_hash).await?;
-
-                let mut values = vec![
-                    Value::Uint(U256::from_big_endian(
-                        l2_withdrawal_tx_hash.as_fixed_bytes(),
-                    )),
-                    Value::Uint(claimed_amount),
-                    Value::Uint(withdrawal_l2_block_number),
-                    Value::Uint(U256::from(index)),
-                ];
-
-                for hash in proof {
-                    values.push(Value::Uint(U256::from_big_endian(hash.as_fixed_bytes())));
-                }
+                let (withdrawal_batch_number, index, proof) = get_withdraw_merkle_proof(
+                    &rollup_client,
+                    &eth_client,
+                    cfg.contracts.on_chain_proposer,
+                    l2_withdrawal_tx_hash,
+                )
+                .await?;
 
-                let claim_withdrawal_data =
-                    encode_calldata(CLAIM_WITHDRAWAL_SIGNATURE, &values).unwrap();
+                let claim_withdrawal_data = claim_withdrawal_calldata(
+                    token_address,
+                    l2_withdrawal_tx_hash,
+                    claimed_amount,
+                    withdrawal_batch_number,
+                    index,
+                    &proof,
+                )?;
                 println!(
                     "ClaimWithdrawalData: {}",
                     hex::encode(&claim_withdrawal_data)
@@ -425,10 +462,46 @@ impl Command {
                 amount,
                 to,
                 nonce,
-                token_address: _,
+                token_address,
                 wait_for_receipt,
                 explorer_url: _,
             } => {
+                if let Some(token_address) = token_address {
+                    // Unlike ETH withdrawals, these are regular calls to the L2 bridge,
+                    // which burns the bridged tokens of the sender.
+                    let withdraw_transaction = rollup_client
+                        .build_eip1559_transaction(
+                            COMMON_BRIDGE_L2_ADDRESS,
+                            cfg.wallet.address,
+                            encode_calldata(
+                                L2_WITHDRAW_ERC20_SIGNATURE,
+                                &[
+                                    Value::Address(token_address),
+                                    Value::Address(to.unwrap_or(cfg.wallet.address)),
+                                    Value::Uint(amount),
+                                ],
+                            )?
+                            .into(),
+                            Overrides {
+                                nonce,
+                                chain_id: Some(cfg.network.l2_chain_id),
+                                from: Some(cfg.wallet.address),
+                                ..Default::default()
+                            },
+                        )
+                        .await?;
+
+                    let tx_hash = rollup_client
+                        .send_eip1559_transaction(&withdraw_transaction, &cfg.wallet.private_key)
+                        .await?;
+
+                    println!("ERC20 withdrawal sent: {tx_hash:#x}");
+
+                    if wait_for_receipt {
+                        wait_for_transaction_receipt(&rollup_client, tx_hash).await?;
+                    }
+                    return Ok(());
+                }
                 let withdraw_transaction = rollup_client
                     .build_privileged_transaction(
                         to.unwrap_or(cfg.wallet.address),
@@ -460,7 +533,13 @@ impl Command {
                 }
             }
             Command::WithdrawalProof { tx_hash } => {
-                let (_index, path) = get_withdraw_merkle_proof(&rollup_client, tx_hash).await?;
+                let (_batch_number, _index, path) = get_withdraw_merkle_proof(
+                    &rollup_client,
+                    &eth_client,
+                    cfg.contracts.on_chain_proposer,
+                    tx_hash,
+                )
+                .await?;
                 println!("{path:?}");
             }
             Command::Address => {
@@ -597,6 +676,40 @@ impl Command {
     }
 }
 
+/// Encodes the call that claims a withdrawal on the L1 bridge, `claimERC20Withdrawal` if the
+/// withdrawal is of the given ERC20 token or `claimWithdrawal` if it is of ETH.
+fn claim_withdrawal_calldata(
+    token_address: Option<Address>,
+    l2_withdrawal_tx_hash: H256,
+    claimed_amount: U256,
+    withdrawal_batch_number: u64,
+    index: u64,
+    proof: &[H256],
+) -> Result<Vec<u8>, CalldataEncodeError> {
+    let mut values = vec![
+        Value::Uint(U256::from_big_endian(
+            l2_withdrawal_tx_hash.as_fixed_bytes(),
+        )),
+        Value::Uint(claimed_amount),
+        Value::Uint(U256::from(withdrawal_batch_number)),
+        Value::Uint(U256::from(index)),
+        Value::Array(
+            proof
+                .iter()
+                .map(|hash| Value::FixedBytes(hash.as_fixed_bytes().to_vec().into()))
+                .collect(),
+        ),
+    ];
+
+    match token_address {
+        Some(token_address) => {
+            values.insert(0, Value::Address(token_address));
+            encode_calldata(CLAIM_ERC20_WITHDRAWAL_SIGNATURE, &values)
+        }
+        None => encode_calldata(CLAIM_WITHDRAWAL_SIGNATURE, &values),
+    }
+}
+
 pub async fn wait_for_transaction_receipt(client: &EthClient, tx_hash: H256) -> eyre::Result<()> {
     println!("Waiting for transaction receipt...");
     while client.get_transaction_receipt(tx_hash).await?.is_none() {
@@ -667,3 +780,79 @@ fn test_balance_in_ether() {
     // test 0.0
     assert_eq!("0.000000000000000000", balance_in_wei(false, U256::zero()));
 }
+
+#[test]
+fn test_token_flag_selects_erc20_path() {
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(subcommand)]
+        command: Command,
+    }
+
+    let token = "0x000000000000000000000000000000000000c0de";
+    let token_address: Address = token.parse().unwrap();
+    let tx_hash = format!("{:#x}", H256::repeat_byte(0xab));
+
+    let Command::Deposit {
+        token_address: deposit_token,
+        ..
+    } = Cli::parse_from(["wallet", "deposit", "--amount", "100", "--token", token]).command
+    else {
+        panic!("expected a deposit command");
+    };
+    assert_eq!(deposit_token, Some(token_address));
+
+    let Command::Withdraw {
+        token_address: withdraw_token,
+        ..
+    } = Cli::parse_from(["wallet", "withdraw", "--amount", "100", "--token", token]).command
+    else {
+        panic!("expected a withdraw command");
+    };
+    assert_eq!(withdraw_token, Some(token_address));
+
+    let Command::ClaimWithdraw {
+        token_address: claim_token,
+        ..
+    } = Cli::parse_from(["wallet", "claim-withdraw", &tx_hash, "--token", token]).command
+    else {
+        panic!("expected a claim-withdraw command");
+    };
+    assert_eq!(claim_token, Some(token_address));
+
+    let Command::Withdraw {
+        token_address: None,
+        ..
+    } = Cli::parse_from(["wallet", "withdraw", "--amount", "100"]).command
+    else {
+        panic!("expected an ETH withdraw command");
+    };
+}
+
+#[test]
+fn test_claim_withdrawal_calldata() {
+    let token_address = Address::from_low_u64_be(0xc0de);
+    let tx_hash = H256::repeat_byte(0xab);
+    let amount = U256::from(100);
+    let proof = [H256::repeat_byte(1), H256::repeat_byte(2)];
+
+    let eth_claim = claim_withdrawal_calldata(None, tx_hash, amount, 3, 1, &proof).unwrap();
+    assert_eq!(
+        eth_claim[..4],
+        keccak_hash::keccak(CLAIM_WITHDRAWAL_SIGNATURE.as_bytes())[..4]
+    );
+    assert_eq!(eth_claim[4..36], tx_hash[..]);
+
+    // ERC20 claims take the L1 token as the first argument
+    let erc20_claim =
+        claim_withdrawal_calldata(Some(token_address), tx_hash, amount, 3, 1, &proof).unwrap();
+    assert_eq!(
+        erc20_claim[..4],
+        keccak_hash::keccak(CLAIM_ERC20_WITHDRAWAL_SIGNATURE.as_bytes())[..4]
+    );
+    assert_eq!(erc20_claim[4..36], H256::from(token_address)[..]);
+    assert_eq!(erc20_claim[36..68], tx_hash[..]);
+    assert_eq!(U256::from_big_endian(&erc20_claim[68..100]), amount);
+}
@@ -2,31 +2,39 @@ pub mod constants;
 pub mod error;
 pub mod fork_choice;
 pub mod mempool;
+pub mod mempool_journal;
 pub mod payload;
 mod smoke_test;
 
 use constants::MAX_INITCODE_SIZE;
 use error::MempoolError;
-use error::{ChainError, InvalidBlockError};
+use error::{ChainError, InvalidBlockError, InvalidForkChoice};
 use ethrex_common::constants::{GAS_PER_BLOB, MIN_BASE_FEE_PER_BLOB_GAS};
 use ethrex_common::types::requests::{compute_requests_hash, EncodedRequests, Requests};
 use ethrex_common::types::MempoolTransaction;
 use ethrex_common::types::{
     compute_receipts_root, validate_block_header, validate_cancun_header_fields,
     validate_prague_header_fields, validate_pre_cancun_header_fields, Block, BlockHash,
-    BlockHeader, BlockNumber, ChainConfig, EIP4844Transaction, Receipt, Transaction,
+    BlockHeader, BlockNumber, ChainConfig, EIP4844Transaction, P2PTransaction, Receipt,
+    Transaction,
 };
 use ethrex_common::types::{BlobsBundle, Fork};
 
 use ethrex_common::{Address, H256};
-use mempool::Mempool;
+use mempool::{Mempool, MempoolConfig};
+use mempool_journal::{journaled_transaction_hash, MempoolJournal};
 use std::collections::HashMap;
 use std::{ops::Div, time::Instant};
 
 use ethrex_storage::error::StoreError;
 use ethrex_storage::{AccountUpdate, Store};
 use ethrex_vm::{BlockExecutionResult, Evm, EvmEngine};
-use tracing::info;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, info, warn};
+
+/// Number of pending notifications kept for each chain event subscriber.
+/// Slower subscribers will miss the oldest notifications.
+const NOTIFICATION_CHANNEL_CAPACITY: usize = 256;
 
 //TODO: Implement a struct Chain or BlockChain to encapsulate
 //functionality and canonical chain state and config
@@ -36,6 +44,15 @@ pub struct Blockchain {
     pub evm_engine: EvmEngine,
     storage: Store,
     pub mempool: Mempool,
+    /// Journal of the transactions submitted to this node, if enabled
+    mempool_journal: Option<MempoolJournal>,
+    /// Notifies the headers that become the head of the canonical chain.
+    new_heads: broadcast::Sender<BlockHeader>,
+    /// Notifies the hashes of the transactions added to the mempool.
+    pending_transactions: broadcast::Sender<H256>,
+    /// Held while blocks are stored or the canonical chain is changed, so a chain rewind
+    /// doesn't interleave with them.
+    import_lock: Mutex<()>,
 }
 
 #[derive(Debug, Clone)]
@@ -50,15 +67,45 @@ impl Blockchain {
             evm_engine,
             storage: store,
             mempool: Mempool::new(),
+            mempool_journal: None,
+            new_heads: broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY).0,
+            pending_transactions: broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY).0,
+            import_lock: Mutex::new(()),
         }
     }
 
     pub fn default_with_store(store: Store) -> Self {
-        Self {
-            evm_engine: EvmEngine::default(),
-            storage: store,
-            mempool: Mempool::new(),
-        }
+        Self::new(EvmEngine::default(), store)
+    }
+
+    /// Enforces the given limits on the mempool, which must still be empty
+    pub fn with_mempool_config(mut self, config: MempoolConfig) -> Self {
+        self.mempool = Mempool::with_config(config);
+        self
+    }
+
+    /// Records the transactions added through [Blockchain::add_local_transaction_to_pool]
+    /// in the given journal, see [Blockchain::replay_mempool_journal].
+    pub fn with_mempool_journal(mut self, journal: MempoolJournal) -> Self {
+        self.mempool_journal = Some(journal);
+        self
+    }
+
+    /// Subscribes to the new heads of the canonical chain, see [Blockchain::notify_new_head].
+    pub fn subscribe_new_heads(&self) -> broadcast::Receiver<BlockHeader> {
+        self.new_heads.subscribe()
+    }
+
+    /// Notifies subscribers that `head` became the head of the canonical chain.
+    /// Must be called after a successful [fork_choice::apply_fork_choice].
+    pub fn notify_new_head(&self, head: BlockHeader) {
+        // Sending only fails when there are no subscribers
+        let _ = self.new_heads.send(head);
+    }
+
+    /// Subscribes to the hashes of the transactions added to the mempool.
+    pub fn subscribe_pending_transactions(&self) -> broadcast::Receiver<H256> {
+        self.pending_transactions.subscribe()
     }
 
     /// Executes a block withing a new vm instance and state
@@ -121,6 +168,7 @@ impl Blockchain {
         execution_result: BlockExecutionResult,
         account_updates: &[AccountUpdate],
     ) -> Result<(), ChainError> {
+        let _import_guard = self.import_lock.lock().await;
         // Apply the account updates over the last block's state and compute the new state root
         let new_state_root = self
             .storage
@@ -188,7 +236,9 @@ impl Blockchain {
     /// - The error type ([`ChainError`]).
     /// - [`BatchProcessingFailure`] (if the error was caused by block processing).
     ///
-    /// Note: only the last block's state trie is stored in the db
+    /// Note: only the last block's state trie is stored in the db. In archive mode the state
+    /// changes of the whole batch are recorded in the state history at the first block of the
+    /// batch whose state is the resulting one
     pub async fn add_blocks_in_batch(
         &self,
         blocks: Vec<Block>,
@@ -276,10 +326,24 @@ impl Blockchain {
             return Err((ChainError::Custom("Last block not found".into()), None));
         };
 
+        // The trailing blocks of the batch that didn't modify the state share its resulting state
+        let history_block_number = blocks
+            .iter()
+            .rev()
+            .take_while(|block| block.header.state_root == last_block.header.state_root)
+            .last()
+            .map_or(last_block.header.number, |block| block.header.number);
+
+        let _import_guard = self.import_lock.lock().await;
         // Apply the account updates over all blocks and compute the new state root
         let new_state_root = self
             .storage
-            .apply_account_updates(first_block_header.parent_hash, &account_updates)
+            .apply_account_updates_batch(
+                first_block_header.parent_hash,
+                history_block_number,
+                last_block.header.number,
+                &account_updates,
+            )
             .await
             .map_err(|e| (e.into(), None))?
             .ok_or((ChainError::ParentStateNotFound, None))?;
@@ -311,6 +375,25 @@ impl Blockchain {
         Ok(())
     }
 
+    /// Applies the fork choice as [fork_choice::apply_fork_choice], without interleaving
+    /// with a chain rewind.
+    pub async fn apply_fork_choice(
+        &self,
+        head_hash: H256,
+        safe_hash: H256,
+        finalized_hash: H256,
+    ) -> Result<BlockHeader, InvalidForkChoice> {
+        let _import_guard = self.import_lock.lock().await;
+        fork_choice::apply_fork_choice(&self.storage, head_hash, safe_hash, finalized_hash).await
+    }
+
+    /// Rewinds the canonical chain to the given block as [Store::rewind], waiting for the
+    /// blocks being stored and the fork choices being applied.
+    pub async fn rewind(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        let _import_guard = self.import_lock.lock().await;
+        self.storage.rewind(block_number).await
+    }
+
     /// Add a blob transaction and its blobs bundle to the mempool checking that the transaction is valid
     #[cfg(feature = "c-kzg")]
     pub async fn add_blob_transaction_to_pool(
@@ -330,9 +413,14 @@ impl Blockchain {
 
         // Add transaction and blobs bundle to storage
         let hash = transaction.compute_hash();
-        self.mempool
-            .add_transaction(hash, MempoolTransaction::new(transaction, sender))?;
+        let account_nonce = self.get_account_nonce(sender).await?;
+        self.mempool.insert_transaction(
+            hash,
+            MempoolTransaction::new(transaction, sender),
+            account_nonce,
+        )?;
         self.mempool.add_blobs_bundle(hash, blobs_bundle)?;
+        let _ = self.pending_transactions.send(hash);
         Ok(hash)
     }
 
@@ -352,17 +440,124 @@ impl Blockchain {
         let hash = transaction.compute_hash();
 
         // Add transaction to storage
-        self.mempool
-            .add_transaction(hash, MempoolTransaction::new(transaction, sender))?;
+        let transaction = MempoolTransaction::new(transaction, sender);
+        if matches!(*transaction, Transaction::PrivilegedL2Transaction(_)) {
+            // Privileged transactions are not subject to the pool limits
+            self.mempool.add_transaction(hash, transaction)?;
+        } else {
+            let account_nonce = self.get_account_nonce(sender).await?;
+            self.mempool
+                .insert_transaction(hash, transaction, account_nonce)?;
+        }
+        let _ = self.pending_transactions.send(hash);
 
         Ok(hash)
     }
 
-    /// Remove a transaction from the mempool
+    /// Add a transaction submitted to this node to the mempool checking that the transaction is valid.
+    /// Unlike transactions received from peers, it is recorded in the mempool journal if enabled.
+    pub async fn add_local_transaction_to_pool(
+        &self,
+        transaction: P2PTransaction,
+    ) -> Result<H256, MempoolError> {
+        let Some(journal) = &self.mempool_journal else {
+            return self.add_p2p_transaction_to_pool(transaction).await;
+        };
+        let hash = self
+            .add_p2p_transaction_to_pool(transaction.clone())
+            .await?;
+        // The transaction is already in the mempool, failing to journal it shouldn't reject it
+        if let Err(error) = journal.append(&transaction) {
+            warn!(%error, "Failed to journal transaction {hash:#x}");
+        }
+        Ok(hash)
+    }
+
+    async fn add_p2p_transaction_to_pool(
+        &self,
+        transaction: P2PTransaction,
+    ) -> Result<H256, MempoolError> {
+        match transaction {
+            #[cfg(feature = "c-kzg")]
+            P2PTransaction::EIP4844TransactionWithBlobs(wrapped) => {
+                self.add_blob_transaction_to_pool(wrapped.tx, wrapped.blobs_bundle)
+                    .await
+            }
+            transaction => {
+                let transaction = transaction
+                    .try_into()
+                    .map_err(MempoolError::InvalidTransaction)?;
+                self.add_transaction_to_pool(transaction).await
+            }
+        }
+    }
+
+    /// Adds the transactions recorded in the mempool journal back to the mempool,
+    /// validating them against the current state, and drops the invalid ones from the journal.
+    /// Meant to be called on startup.
+    pub async fn replay_mempool_journal(&self) -> Result<(), MempoolError> {
+        let Some(journal) = &self.mempool_journal else {
+            return Ok(());
+        };
+        let transactions = journal.load()?;
+        let total = transactions.len();
+        let mut added = 0;
+        for transaction in transactions {
+            let hash = journaled_transaction_hash(&transaction);
+            match self.add_p2p_transaction_to_pool(transaction).await {
+                Ok(_) => added += 1,
+                Err(error) => debug!(%error, "Dropping journaled transaction {hash:#x}"),
+            }
+        }
+        info!("Restored {added} of {total} journaled transactions to the mempool");
+        self.rejournal_mempool()
+    }
+
+    /// Rewrites the mempool journal dropping the transactions that are no longer in the mempool,
+    /// either because they were included in a block or became invalid.
+    pub fn rejournal_mempool(&self) -> Result<(), MempoolError> {
+        let Some(journal) = &self.mempool_journal else {
+            return Ok(());
+        };
+        let mut mempool_error = None;
+        let kept = journal.retain(|transaction| {
+            let hash = journaled_transaction_hash(transaction);
+            match self.mempool.get_transaction_by_hash(hash) {
+                Ok(found) => found.is_some(),
+                // Keep the entries if the mempool can't be read
+                Err(error) => {
+                    mempool_error.get_or_insert(error);
+                    true
+                }
+            }
+        })?;
+        if let Some(error) = mempool_error {
+            return Err(error.into());
+        }
+        debug!("Rewrote mempool journal with {kept} transactions");
+        Ok(())
+    }
+
+    /// Returns the nonce of the given account on the latest state
+    async fn get_account_nonce(&self, address: Address) -> Result<u64, MempoolError> {
+        let block_number = self.storage.get_latest_block_number().await?;
+        Ok(self
+            .storage
+            .get_nonce_by_account_address(block_number, address)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Remove a transaction that won't be executed from the mempool
     pub fn remove_transaction_from_pool(&self, hash: &H256) -> Result<(), StoreError> {
         self.mempool.remove_transaction(hash)
     }
 
+    /// Remove a transaction included in a block from the mempool
+    pub fn remove_included_transaction_from_pool(&self, hash: &H256) -> Result<(), StoreError> {
+        self.mempool.remove_included_transaction(hash)
+    }
+
     /*
 
     SOME VALIDATIONS THAT WE COULD INCLUDE
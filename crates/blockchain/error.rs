@@ -72,6 +72,20 @@ pub enum MempoolError {
     NotEnoughBalance,
     #[error("Transaction gas fields are invalid")]
     InvalidTxGasvalues,
+    #[error("Replacement transaction underpriced")]
+    ReplacementUnderpriced,
+    #[error("Transaction underpriced, the mempool is full of transactions paying higher fees")]
+    Underpriced,
+    #[error("Mempool is full")]
+    MempoolFull,
+    #[error("Sender exceeded the maximum amount of pending transactions")]
+    SenderSlotsExceeded,
+    #[error("Sender exceeded the maximum amount of queued transactions")]
+    SenderQueueExceeded,
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("Mempool journal error: {0}")]
+    JournalError(#[from] std::io::Error),
 }
 
 #[derive(Debug)]
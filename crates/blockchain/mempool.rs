@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
     sync::{Mutex, RwLock},
 };
 
@@ -12,21 +12,256 @@ use crate::{
     error::MempoolError,
 };
 use ethrex_common::{
-    types::{BlobsBundle, BlockHeader, ChainConfig, MempoolTransaction, Transaction, TxType},
+    types::{
+        Blob, BlobsBundle, BlockHeader, ChainConfig, MempoolTransaction, Proof, Transaction, TxType,
+    },
     Address, H256, U256,
 };
 use ethrex_storage::error::StoreError;
 
+/// Limits of the transaction pool, the defaults match the ones used by geth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MempoolConfig {
+    /// Maximum amount of executable transactions per sender
+    pub account_slots: usize,
+    /// Maximum amount of non-executable transactions per sender
+    pub account_queue: usize,
+    /// Maximum amount of executable transactions in the pool
+    pub global_slots: usize,
+    /// Maximum amount of non-executable transactions in the pool
+    pub global_queue: usize,
+    /// Minimum fee increase (in percent) required to replace a transaction with the same nonce
+    pub price_bump: u64,
+    /// Minimum blob fee increase (in percent) required to replace a blob transaction
+    pub blob_price_bump: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            account_slots: 16,
+            account_queue: 64,
+            global_slots: 5120,
+            global_queue: 1024,
+            price_bump: 10,
+            blob_price_bump: 100,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Mempool {
-    transaction_pool: RwLock<HashMap<H256, MempoolTransaction>>,
-    blobs_bundle_pool: Mutex<HashMap<H256, BlobsBundle>>,
+    transaction_pool: RwLock<TransactionPool>,
+    blobs_bundle_pool: Mutex<BlobsBundlePool>,
+    config: MempoolConfig,
+}
+
+#[derive(Debug, Default)]
+struct BlobsBundlePool {
+    /// Blobs bundles indexed by the hash of their blob transaction
+    bundles: HashMap<H256, BlobsBundle>,
+    /// Transaction hash and position within its bundle of each blob, indexed by versioned hash
+    blobs_by_versioned_hash: HashMap<H256, (H256, usize)>,
+}
+
+impl BlobsBundlePool {
+    fn insert(&mut self, tx_hash: H256, blobs_bundle: BlobsBundle) {
+        self.remove(&tx_hash);
+        for (index, versioned_hash) in blobs_bundle
+            .generate_versioned_hashes()
+            .into_iter()
+            .enumerate()
+        {
+            self.blobs_by_versioned_hash
+                .insert(versioned_hash, (tx_hash, index));
+        }
+        self.bundles.insert(tx_hash, blobs_bundle);
+    }
+
+    fn remove(&mut self, tx_hash: &H256) {
+        if let Some(blobs_bundle) = self.bundles.remove(tx_hash) {
+            for versioned_hash in blobs_bundle.generate_versioned_hashes() {
+                // The same blob may have been sent in another transaction that is still in the pool
+                if let Entry::Occupied(entry) = self.blobs_by_versioned_hash.entry(versioned_hash) {
+                    if entry.get().0 == *tx_hash {
+                        entry.remove();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TransactionPool {
+    transactions: HashMap<H256, MempoolTransaction>,
+    /// Transactions of each sender, privileged transactions are not included
+    senders: HashMap<Address, SenderTransactions>,
+    /// Amount of pending transactions among all senders
+    pending_count: usize,
+    /// Amount of queued transactions among all senders
+    queued_count: usize,
+}
+
+/// Hashes of the transactions of a sender indexed by nonce.
+/// The transactions with consecutive nonces starting from the sender's nonce are pending
+/// (executable), the ones after a nonce gap are queued.
+#[derive(Debug)]
+struct SenderTransactions {
+    /// Nonce of the sender's next transaction to be executed
+    nonce: u64,
+    transactions: BTreeMap<u64, H256>,
+}
+
+impl SenderTransactions {
+    fn new(nonce: u64) -> Self {
+        Self {
+            nonce,
+            transactions: BTreeMap::new(),
+        }
+    }
+
+    /// Nonce following the last pending transaction
+    fn next_nonce(&self) -> u64 {
+        let mut next_nonce = self.nonce;
+        for nonce in self
+            .transactions
+            .range(self.nonce..)
+            .map(|(nonce, _)| *nonce)
+        {
+            if nonce != next_nonce {
+                break;
+            }
+            next_nonce += 1;
+        }
+        next_nonce
+    }
+
+    fn pending_count(&self) -> usize {
+        (self.next_nonce() - self.nonce) as usize
+    }
+
+    fn queued_count(&self) -> usize {
+        self.transactions.len() - self.pending_count()
+    }
+
+    fn is_pending(&self, nonce: u64) -> bool {
+        nonce >= self.nonce && nonce < self.next_nonce()
+    }
+}
+
+impl TransactionPool {
+    fn insert(&mut self, hash: H256, transaction: MempoolTransaction) {
+        if !matches!(*transaction, Transaction::PrivilegedL2Transaction(_)) {
+            let nonce = transaction.nonce();
+            let address = transaction.sender();
+            self.senders
+                .entry(address)
+                .or_insert_with(|| SenderTransactions::new(nonce));
+            self.update_sender(address, |sender| {
+                sender.nonce = sender.nonce.min(nonce);
+                sender.transactions.insert(nonce, hash);
+            });
+        }
+        self.transactions.insert(hash, transaction);
+    }
+
+    /// Removes a transaction from the pool. Only the inclusion of a transaction advances
+    /// the nonce of its sender, the following transactions of a sender whose transaction
+    /// was evicted or discarded are queued until the nonce gap is filled.
+    fn remove(&mut self, hash: &H256, included: bool) -> Option<MempoolTransaction> {
+        let transaction = self.transactions.remove(hash)?;
+        let nonce = transaction.nonce();
+        self.update_sender(transaction.sender(), |sender| {
+            if sender.transactions.get(&nonce) == Some(hash) {
+                sender.transactions.remove(&nonce);
+                if included {
+                    sender.nonce = sender.nonce.max(nonce + 1);
+                }
+            }
+        });
+        Some(transaction)
+    }
+
+    /// Updates the nonce of a sender, returning its transactions with lower nonces
+    /// as they can no longer be executed
+    fn set_account_nonce(&mut self, address: Address, account_nonce: u64) -> Vec<H256> {
+        let stale = self
+            .update_sender(address, |sender| {
+                // The account nonce can be read from a state older than the included transactions
+                sender.nonce = sender.nonce.max(account_nonce);
+                let pending = sender.transactions.split_off(&sender.nonce);
+                std::mem::replace(&mut sender.transactions, pending)
+            })
+            .unwrap_or_default();
+        let stale: Vec<H256> = stale.into_values().collect();
+        for hash in stale.iter() {
+            self.transactions.remove(hash);
+        }
+        stale
+    }
+
+    /// Applies `update` to the transactions of a sender keeping the pending and queued
+    /// counts of the pool in sync. The sender is dropped once it has no transactions left.
+    fn update_sender<T>(
+        &mut self,
+        address: Address,
+        update: impl FnOnce(&mut SenderTransactions) -> T,
+    ) -> Option<T> {
+        let Entry::Occupied(mut entry) = self.senders.entry(address) else {
+            return None;
+        };
+        let sender = entry.get_mut();
+        self.pending_count -= sender.pending_count();
+        self.queued_count -= sender.queued_count();
+        let result = update(sender);
+        self.pending_count += sender.pending_count();
+        self.queued_count += sender.queued_count();
+        if sender.transactions.is_empty() {
+            entry.remove();
+        }
+        Some(result)
+    }
+
+    /// Returns the cheapest transaction that can be evicted without creating a nonce gap
+    /// among the pending (or queued) transactions of the senders other than `address`
+    fn eviction_candidate(&self, address: Address, pending: bool) -> Option<H256> {
+        self.senders
+            .iter()
+            .filter(|(sender, _)| **sender != address)
+            .filter_map(|(_, sender)| {
+                let next_nonce = sender.next_nonce();
+                if pending {
+                    next_nonce
+                        .checked_sub(1)
+                        .filter(|nonce| *nonce >= sender.nonce)
+                        .and_then(|nonce| sender.transactions.get(&nonce))
+                } else {
+                    sender
+                        .transactions
+                        .last_key_value()
+                        .filter(|(nonce, _)| **nonce > next_nonce)
+                        .map(|(_, hash)| hash)
+                }
+            })
+            .filter_map(|hash| self.transactions.get(hash).map(|tx| (hash, tx)))
+            .min_by_key(|(_, tx)| (tx.gas_fee_cap(), tx.gas_tip_cap()))
+            .map(|(hash, _)| *hash)
+    }
 }
+
 impl Mempool {
     pub fn new() -> Self {
         Self::default()
     }
 
+    pub fn with_config(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
     /// Add transaction to the pool without doing validity checks
     pub fn add_transaction(
         &self,
@@ -42,6 +277,125 @@ impl Mempool {
         Ok(())
     }
 
+    /// Add a transaction to the pool enforcing the pool limits and the replacement rules.
+    /// `account_nonce` is the nonce of the sender on the latest state.
+    /// Returns the hashes of the transactions removed from the pool to make room for it.
+    pub fn insert_transaction(
+        &self,
+        hash: H256,
+        transaction: MempoolTransaction,
+        account_nonce: u64,
+    ) -> Result<Vec<H256>, MempoolError> {
+        let mut tx_pool = self
+            .transaction_pool
+            .write()
+            .map_err(|error| StoreError::MempoolWriteLock(error.to_string()))?;
+        if tx_pool.transactions.contains_key(&hash) {
+            return Ok(vec![]);
+        }
+        let address = transaction.sender();
+        let nonce = transaction.nonce();
+
+        // Transactions with nonces lower than the sender's can't be executed anymore
+        let mut removed = tx_pool.set_account_nonce(address, account_nonce);
+
+        // Replace the transaction with the same nonce if the new one pays enough
+        let sender = tx_pool.senders.get(&address);
+        if let Some(old_hash) = sender.and_then(|s| s.transactions.get(&nonce)).copied() {
+            let old_transaction = tx_pool
+                .transactions
+                .get(&old_hash)
+                .ok_or(MempoolError::ReplacementUnderpriced)?;
+            if !self.is_valid_replacement(old_transaction, &transaction) {
+                return Err(MempoolError::ReplacementUnderpriced);
+            }
+            self.remove_from_pool(&mut tx_pool, &old_hash, false)?;
+            removed.push(old_hash);
+            tx_pool.insert(hash, transaction);
+            return Ok(removed);
+        }
+
+        let (pending_count, queued_count, next_nonce) = sender
+            .map(|s| (s.pending_count(), s.queued_count(), s.next_nonce()))
+            .unwrap_or((0, 0, account_nonce));
+        let is_pending = nonce == next_nonce;
+        if is_pending && pending_count >= self.config.account_slots {
+            return Err(MempoolError::SenderSlotsExceeded);
+        }
+        if !is_pending && queued_count >= self.config.account_queue {
+            return Err(MempoolError::SenderQueueExceeded);
+        }
+
+        // Evict the cheapest transaction if the sub-pool is full
+        let is_full = if is_pending {
+            tx_pool.pending_count >= self.config.global_slots
+        } else {
+            tx_pool.queued_count >= self.config.global_queue
+        };
+        if is_full {
+            let evicted = tx_pool
+                .eviction_candidate(address, is_pending)
+                .ok_or(MempoolError::MempoolFull)?;
+            let cheapest = tx_pool
+                .transactions
+                .get(&evicted)
+                .ok_or(MempoolError::MempoolFull)?;
+            if (transaction.gas_fee_cap(), transaction.gas_tip_cap())
+                <= (cheapest.gas_fee_cap(), cheapest.gas_tip_cap())
+            {
+                return Err(MempoolError::Underpriced);
+            }
+            self.remove_from_pool(&mut tx_pool, &evicted, false)?;
+            removed.push(evicted);
+        }
+
+        tx_pool.insert(hash, transaction);
+        Ok(removed)
+    }
+
+    /// Checks that a transaction pays enough to replace one with the same nonce.
+    /// Both the fee cap and the tip cap must be increased by at least `price_bump` percent,
+    /// and the blob fee cap by `blob_price_bump` percent.
+    fn is_valid_replacement(&self, old: &Transaction, new: &Transaction) -> bool {
+        let bumped = |old: u64, bump: u64| u128::from(old) * u128::from(100 + bump) / 100;
+        let bumps_fee = |old: u64, new: u64| {
+            new > old && u128::from(new) >= bumped(old, self.config.price_bump)
+        };
+        if !bumps_fee(old.gas_fee_cap(), new.gas_fee_cap())
+            || !bumps_fee(old.gas_tip_cap(), new.gas_tip_cap())
+        {
+            return false;
+        }
+        match (old.max_fee_per_blob_gas(), new.max_fee_per_blob_gas()) {
+            (Some(old_fee), Some(new_fee)) => {
+                new_fee > old_fee
+                    && new_fee.full_mul(100.into())
+                        >= old_fee.full_mul((100 + self.config.blob_price_bump).into())
+            }
+            (None, None) => true,
+            // Blob transactions can only be replaced by blob transactions and vice versa
+            _ => false,
+        }
+    }
+
+    /// Removes a transaction from the pool along with its blobs bundle
+    fn remove_from_pool(
+        &self,
+        tx_pool: &mut TransactionPool,
+        hash: &H256,
+        included: bool,
+    ) -> Result<(), StoreError> {
+        if let Some(tx) = tx_pool.remove(hash, included) {
+            if matches!(tx.tx_type(), TxType::EIP4844) {
+                self.blobs_bundle_pool
+                    .lock()
+                    .map_err(|error| StoreError::Custom(error.to_string()))?
+                    .remove(hash);
+            }
+        }
+        Ok(())
+    }
+
     /// Add a blobs bundle to the pool by its blob transaction hash
     pub fn add_blobs_bundle(
         &self,
@@ -61,28 +415,49 @@ impl Mempool {
             .blobs_bundle_pool
             .lock()
             .map_err(|error| StoreError::Custom(error.to_string()))?
+            .bundles
             .get(&tx_hash)
             .cloned())
     }
 
-    /// Remove a transaction from the pool
+    /// Get a blob and its KZG proof given the blob's versioned hash
+    pub fn get_blob_and_proof(
+        &self,
+        versioned_hash: &H256,
+    ) -> Result<Option<(Blob, Proof)>, StoreError> {
+        let Some((tx_hash, index)) = self
+            .blobs_bundle_pool
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .blobs_by_versioned_hash
+            .get(versioned_hash)
+            .copied()
+        else {
+            return Ok(None);
+        };
+        Ok(self
+            .get_blobs_bundle(tx_hash)?
+            .and_then(|bundle| Some((*bundle.blobs.get(index)?, *bundle.proofs.get(index)?))))
+    }
+
+    /// Remove a transaction that won't be executed from the pool.
+    /// The following transactions of its sender are queued until the nonce gap is filled.
     pub fn remove_transaction(&self, hash: &H256) -> Result<(), StoreError> {
         let mut tx_pool = self
             .transaction_pool
             .write()
             .map_err(|error| StoreError::MempoolWriteLock(error.to_string()))?;
-        if let Some(tx) = tx_pool.get(hash) {
-            if matches!(tx.tx_type(), TxType::EIP4844) {
-                self.blobs_bundle_pool
-                    .lock()
-                    .map_err(|error| StoreError::Custom(error.to_string()))?
-                    .remove(&tx.compute_hash());
-            }
-
-            tx_pool.remove(hash);
-        };
+        self.remove_from_pool(&mut tx_pool, hash, false)
+    }
 
-        Ok(())
+    /// Remove a transaction included in a block from the pool.
+    /// The following transactions of its sender become executable.
+    pub fn remove_included_transaction(&self, hash: &H256) -> Result<(), StoreError> {
+        let mut tx_pool = self
+            .transaction_pool
+            .write()
+            .map_err(|error| StoreError::MempoolWriteLock(error.to_string()))?;
+        self.remove_from_pool(&mut tx_pool, hash, true)
     }
 
     /// Applies the filter and returns a set of suitable transactions from the mempool.
@@ -124,7 +499,7 @@ impl Mempool {
         self.filter_transactions_with_filter_fn(&filter_tx)
     }
 
-    /// Applies the filter and returns a set of suitable transactions from the mempool.
+    /// Applies the filter and returns a set of suitable pending transactions from the mempool.
     /// These transactions will be grouped by sender and sorted by nonce
     pub fn filter_transactions_with_filter_fn(
         &self,
@@ -136,8 +511,16 @@ impl Mempool {
             .read()
             .map_err(|error| StoreError::MempoolReadLock(error.to_string()))?;
 
-        for (_, tx) in tx_pool.iter() {
-            if filter(tx) {
+        for (_, tx) in tx_pool.transactions.iter() {
+            // Queued transactions can't be executed until their nonce gap is filled
+            let is_pending = match **tx {
+                Transaction::PrivilegedL2Transaction(_) => true,
+                _ => tx_pool
+                    .senders
+                    .get(&tx.sender())
+                    .is_some_and(|sender| sender.is_pending(tx.nonce())),
+            };
+            if is_pending && filter(tx) {
                 txs_by_sender
                     .entry(tx.sender())
                     .or_default()
@@ -149,6 +532,31 @@ impl Mempool {
         Ok(txs_by_sender)
     }
 
+    /// Returns the queued transactions from the mempool, which can't be executed until
+    /// their nonce gap is filled. These transactions will be grouped by sender and sorted by nonce
+    pub fn queued_transactions(
+        &self,
+    ) -> Result<HashMap<Address, Vec<MempoolTransaction>>, StoreError> {
+        let tx_pool = self
+            .transaction_pool
+            .read()
+            .map_err(|error| StoreError::MempoolReadLock(error.to_string()))?;
+
+        let mut txs_by_sender = HashMap::new();
+        for (address, sender) in tx_pool.senders.iter() {
+            let next_nonce = sender.next_nonce();
+            let queued: Vec<MempoolTransaction> = sender
+                .transactions
+                .range(next_nonce..)
+                .filter_map(|(_, hash)| tx_pool.transactions.get(hash).cloned())
+                .collect();
+            if !queued.is_empty() {
+                txs_by_sender.insert(*address, queued);
+            }
+        }
+        Ok(txs_by_sender)
+    }
+
     /// Gets hashes from possible_hashes that are not already known in the mempool.
     pub fn filter_unknown_transactions(
         &self,
@@ -159,7 +567,7 @@ impl Mempool {
             .read()
             .map_err(|error| StoreError::MempoolReadLock(error.to_string()))?;
 
-        let tx_set: HashSet<_> = tx_pool.iter().map(|(hash, _)| hash).collect();
+        let tx_set: HashSet<_> = tx_pool.transactions.keys().collect();
         Ok(possible_hashes
             .iter()
             .filter(|hash| !tx_set.contains(hash))
@@ -175,26 +583,25 @@ impl Mempool {
             .transaction_pool
             .read()
             .map_err(|error| StoreError::MempoolReadLock(error.to_string()))?
+            .transactions
             .get(&transaction_hash)
             .map(|e| e.clone().into());
 
         Ok(tx)
     }
 
+    /// Returns the nonce following the last pending transaction of the given sender,
+    /// or `None` if the sender has no pending transactions
     pub fn get_nonce(&self, address: &Address) -> Result<Option<u64>, MempoolError> {
-        let pending_filter = PendingTxFilter {
-            min_tip: None,
-            base_fee: None,
-            blob_fee: None,
-            only_plain_txs: false,
-            only_blob_txs: false,
-        };
-
-        let pending_txs = self.filter_transactions(&pending_filter)?;
-        let nonce = match pending_txs.get(address) {
-            Some(txs) => txs.last().map(|tx| tx.nonce() + 1),
-            None => None,
-        };
+        let tx_pool = self
+            .transaction_pool
+            .read()
+            .map_err(|error| StoreError::MempoolReadLock(error.to_string()))?;
+        let nonce = tx_pool
+            .senders
+            .get(address)
+            .filter(|sender| sender.pending_count() > 0)
+            .map(|sender| sender.next_nonce());
 
         Ok(nonce)
     }
@@ -274,9 +681,9 @@ mod tests {
     use crate::constants::MAX_INITCODE_SIZE;
     use crate::error::MempoolError;
     use crate::mempool::{
-        Mempool, TX_ACCESS_LIST_ADDRESS_GAS, TX_ACCESS_LIST_STORAGE_KEY_GAS, TX_CREATE_GAS_COST,
-        TX_DATA_NON_ZERO_GAS, TX_DATA_NON_ZERO_GAS_EIP2028, TX_DATA_ZERO_GAS_COST, TX_GAS_COST,
-        TX_INIT_CODE_WORD_GAS_COST,
+        Mempool, MempoolConfig, TX_ACCESS_LIST_ADDRESS_GAS, TX_ACCESS_LIST_STORAGE_KEY_GAS,
+        TX_CREATE_GAS_COST, TX_DATA_NON_ZERO_GAS, TX_DATA_NON_ZERO_GAS_EIP2028,
+        TX_DATA_ZERO_GAS_COST, TX_GAS_COST, TX_INIT_CODE_WORD_GAS_COST,
     };
     use crate::Blockchain;
     use std::collections::HashMap;
@@ -672,4 +1079,263 @@ mod tests {
             mempool.add_blobs_bundle(H256::random(), bundle).unwrap();
         }
     }
+
+    #[test]
+    fn blob_and_proof_by_versioned_hash() {
+        let mempool = Mempool::new();
+        let bundle = BlobsBundle {
+            blobs: vec![[1; BYTES_PER_BLOB], [2; BYTES_PER_BLOB]],
+            commitments: vec![[1; 48], [2; 48]],
+            proofs: vec![[3; 48], [4; 48]],
+        };
+        let versioned_hashes = bundle.generate_versioned_hashes();
+        let blob_tx = MempoolTransaction::new(
+            Transaction::EIP4844Transaction(EIP4844Transaction {
+                blob_versioned_hashes: versioned_hashes.clone(),
+                ..Default::default()
+            }),
+            Address::random(),
+        );
+        let tx_hash = blob_tx.compute_hash();
+        mempool.add_transaction(tx_hash, blob_tx).unwrap();
+        mempool.add_blobs_bundle(tx_hash, bundle).unwrap();
+
+        assert_eq!(
+            mempool.get_blob_and_proof(&versioned_hashes[1]).unwrap(),
+            Some(([2; BYTES_PER_BLOB], [4; 48]))
+        );
+        assert_eq!(mempool.get_blob_and_proof(&H256::random()).unwrap(), None);
+
+        mempool.remove_transaction(&tx_hash).unwrap();
+        assert_eq!(
+            mempool.get_blob_and_proof(&versioned_hashes[0]).unwrap(),
+            None
+        );
+    }
+
+    fn mempool_transaction(
+        sender: Address,
+        nonce: u64,
+        max_fee_per_gas: u64,
+        max_priority_fee_per_gas: u64,
+    ) -> (H256, MempoolTransaction) {
+        let tx = Transaction::EIP1559Transaction(EIP1559Transaction {
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit: 21_000,
+            to: TxKind::Call(sender),
+            ..Default::default()
+        });
+        (tx.compute_hash(), MempoolTransaction::new(tx, sender))
+    }
+
+    #[test]
+    fn replacement_requires_price_bump() {
+        let mempool = Mempool::new();
+        let sender = Address::from_low_u64_be(1);
+        let (hash, tx) = mempool_transaction(sender, 0, 100, 10);
+        mempool.insert_transaction(hash, tx, 0).unwrap();
+
+        let (underpriced_hash, underpriced_tx) = mempool_transaction(sender, 0, 105, 11);
+        assert!(matches!(
+            mempool.insert_transaction(underpriced_hash, underpriced_tx, 0),
+            Err(MempoolError::ReplacementUnderpriced)
+        ));
+
+        let (replacement_hash, replacement_tx) = mempool_transaction(sender, 0, 110, 11);
+        let removed = mempool
+            .insert_transaction(replacement_hash, replacement_tx, 0)
+            .unwrap();
+        assert_eq!(removed, vec![hash]);
+        assert!(mempool.get_transaction_by_hash(hash).unwrap().is_none());
+        assert!(mempool
+            .get_transaction_by_hash(replacement_hash)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn queued_transactions_are_not_pending() {
+        let mempool = Mempool::new();
+        let sender = Address::from_low_u64_be(1);
+        let (hash_0, tx_0) = mempool_transaction(sender, 0, 100, 10);
+        let (hash_1, tx_1) = mempool_transaction(sender, 1, 100, 10);
+        let (hash_2, tx_2) = mempool_transaction(sender, 2, 100, 10);
+        mempool.insert_transaction(hash_0, tx_0.clone(), 0).unwrap();
+        mempool.insert_transaction(hash_2, tx_2.clone(), 0).unwrap();
+
+        let pending = mempool
+            .filter_transactions_with_filter_fn(&|_| true)
+            .unwrap();
+        assert_eq!(pending, HashMap::from([(sender, vec![tx_0.clone()])]));
+        let queued = mempool.queued_transactions().unwrap();
+        assert_eq!(queued, HashMap::from([(sender, vec![tx_2.clone()])]));
+        assert_eq!(mempool.get_nonce(&sender).unwrap(), Some(1));
+
+        // Filling the nonce gap promotes the queued transaction
+        mempool.insert_transaction(hash_1, tx_1.clone(), 0).unwrap();
+        let pending = mempool
+            .filter_transactions_with_filter_fn(&|_| true)
+            .unwrap();
+        assert_eq!(pending, HashMap::from([(sender, vec![tx_0, tx_1, tx_2])]));
+        assert!(mempool.queued_transactions().unwrap().is_empty());
+        assert_eq!(mempool.get_nonce(&sender).unwrap(), Some(3));
+
+        // Executed transactions are dropped once the sender's nonce advances
+        mempool.remove_included_transaction(&hash_0).unwrap();
+        assert_eq!(mempool.get_nonce(&sender).unwrap(), Some(3));
+        let (hash_3, tx_3) = mempool_transaction(sender, 3, 100, 10);
+        assert_eq!(
+            mempool.insert_transaction(hash_3, tx_3, 2).unwrap(),
+            vec![hash_1]
+        );
+    }
+
+    #[test]
+    fn stale_account_nonces_dont_lower_the_nonce() {
+        let mempool = Mempool::new();
+        let sender = Address::from_low_u64_be(1);
+        let (hash_0, tx_0) = mempool_transaction(sender, 0, 100, 10);
+        let (hash_1, tx_1) = mempool_transaction(sender, 1, 100, 10);
+        mempool.insert_transaction(hash_0, tx_0, 0).unwrap();
+        mempool.insert_transaction(hash_1, tx_1, 0).unwrap();
+        mempool.remove_included_transaction(&hash_0).unwrap();
+
+        // A transaction validated against the state before the inclusion keeps the others pending
+        let (hash_2, tx_2) = mempool_transaction(sender, 2, 100, 10);
+        assert!(mempool
+            .insert_transaction(hash_2, tx_2, 0)
+            .unwrap()
+            .is_empty());
+        assert_eq!(mempool.get_nonce(&sender).unwrap(), Some(3));
+        assert!(mempool.queued_transactions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn discarded_transactions_dont_advance_the_nonce() {
+        let mempool = Mempool::new();
+        let sender = Address::from_low_u64_be(1);
+        let mut hashes = vec![];
+        for nonce in 0..3 {
+            let (hash, tx) = mempool_transaction(sender, nonce, 100, 10);
+            mempool.insert_transaction(hash, tx, 0).unwrap();
+            hashes.push(hash);
+        }
+
+        // Replacing a transaction keeps the following ones pending
+        let (replacement_hash, replacement_tx) = mempool_transaction(sender, 0, 110, 11);
+        mempool
+            .insert_transaction(replacement_hash, replacement_tx, 0)
+            .unwrap();
+        assert_eq!(mempool.get_nonce(&sender).unwrap(), Some(3));
+
+        // Discarding a transaction queues the following ones until the gap is filled
+        mempool.remove_transaction(&hashes[1]).unwrap();
+        assert_eq!(mempool.get_nonce(&sender).unwrap(), Some(1));
+        let queued = mempool.queued_transactions().unwrap();
+        assert_eq!(queued[&sender].len(), 1);
+
+        // Including the first one doesn't make the next ones executable either
+        mempool
+            .remove_included_transaction(&replacement_hash)
+            .unwrap();
+        assert_eq!(mempool.get_nonce(&sender).unwrap(), None);
+        assert!(mempool
+            .filter_transactions_with_filter_fn(&|_| true)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn pool_counts_match_the_senders() {
+        let mempool = Mempool::with_config(MempoolConfig {
+            global_slots: 4,
+            ..Default::default()
+        });
+        let mut hashes = HashMap::new();
+        for (sender, nonce, fee) in [(1, 0, 100), (1, 1, 100), (1, 3, 100), (2, 0, 100)] {
+            let (hash, tx) = mempool_transaction(Address::from_low_u64_be(sender), nonce, fee, 10);
+            mempool.insert_transaction(hash, tx, 0).unwrap();
+            hashes.insert((sender, nonce), hash);
+        }
+        for (sender, nonce, fee) in [(2, 2, 100), (3, 0, 50), (2, 1, 200)] {
+            let (hash, tx) = mempool_transaction(Address::from_low_u64_be(sender), nonce, fee, 10);
+            mempool.insert_transaction(hash, tx, 0).unwrap();
+            hashes.insert((sender, nonce), hash);
+        }
+        // Filling the nonce gap of the second sender evicted the cheapest pending transaction
+        assert!(mempool
+            .get_transaction_by_hash(hashes[&(3, 0)])
+            .unwrap()
+            .is_none());
+        mempool.remove_transaction(&hashes[&(1, 0)]).unwrap();
+        mempool
+            .remove_included_transaction(&hashes[&(2, 0)])
+            .unwrap();
+
+        let tx_pool = mempool.transaction_pool.read().unwrap();
+        let pending: usize = tx_pool.senders.values().map(|s| s.pending_count()).sum();
+        let queued: usize = tx_pool.senders.values().map(|s| s.queued_count()).sum();
+        assert_eq!(
+            (tx_pool.pending_count, tx_pool.queued_count),
+            (pending, queued)
+        );
+        assert_eq!((pending, queued), (2, 2));
+    }
+
+    #[test]
+    fn sender_limits_are_enforced() {
+        let mempool = Mempool::with_config(MempoolConfig {
+            account_slots: 2,
+            account_queue: 1,
+            ..Default::default()
+        });
+        let sender = Address::from_low_u64_be(1);
+        for nonce in 0..2 {
+            let (hash, tx) = mempool_transaction(sender, nonce, 100, 10);
+            mempool.insert_transaction(hash, tx, 0).unwrap();
+        }
+        let (hash, tx) = mempool_transaction(sender, 2, 100, 10);
+        assert!(matches!(
+            mempool.insert_transaction(hash, tx, 0),
+            Err(MempoolError::SenderSlotsExceeded)
+        ));
+
+        let (hash, tx) = mempool_transaction(sender, 5, 100, 10);
+        mempool.insert_transaction(hash, tx, 0).unwrap();
+        let (hash, tx) = mempool_transaction(sender, 6, 100, 10);
+        assert!(matches!(
+            mempool.insert_transaction(hash, tx, 0),
+            Err(MempoolError::SenderQueueExceeded)
+        ));
+    }
+
+    #[test]
+    fn cheapest_transaction_is_evicted_when_full() {
+        let mempool = Mempool::with_config(MempoolConfig {
+            global_slots: 2,
+            ..Default::default()
+        });
+        let (cheap_hash, cheap_tx) = mempool_transaction(Address::from_low_u64_be(1), 0, 10, 1);
+        let (hash, tx) = mempool_transaction(Address::from_low_u64_be(2), 0, 20, 1);
+        mempool.insert_transaction(cheap_hash, cheap_tx, 0).unwrap();
+        mempool.insert_transaction(hash, tx, 0).unwrap();
+
+        let (hash, tx) = mempool_transaction(Address::from_low_u64_be(3), 0, 10, 1);
+        assert!(matches!(
+            mempool.insert_transaction(hash, tx, 0),
+            Err(MempoolError::Underpriced)
+        ));
+
+        let (hash, tx) = mempool_transaction(Address::from_low_u64_be(3), 0, 30, 1);
+        assert_eq!(
+            mempool.insert_transaction(hash, tx, 0).unwrap(),
+            vec![cheap_hash]
+        );
+        assert!(mempool
+            .get_transaction_by_hash(cheap_hash)
+            .unwrap()
+            .is_none());
+    }
 }
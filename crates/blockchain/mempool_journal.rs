@@ -0,0 +1,233 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+use ethrex_common::{
+    types::{P2PTransaction, Transaction},
+    H256,
+};
+use ethrex_rlp::{
+    decode::{get_item_with_prefix, RLPDecode},
+    encode::RLPEncode,
+};
+use tracing::warn;
+
+use crate::error::MempoolError;
+
+/// On-disk journal of the transactions submitted to this node, so they can be added back
+/// to the mempool after a restart.
+/// Entries are RLP encoded [P2PTransaction]s, which keep the blobs bundle of blob transactions,
+/// appended to the file as transactions are submitted.
+#[derive(Debug)]
+pub struct MempoolJournal {
+    path: PathBuf,
+    /// Append handle to the journal file, also held while the journal is being rewritten
+    file: Mutex<File>,
+}
+
+impl MempoolJournal {
+    /// Opens the journal stored at `path`, creating it if it doesn't exist
+    pub fn open(path: PathBuf) -> Result<Self, MempoolError> {
+        let file = open_for_append(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Returns the transactions recorded in the journal, in the order they were submitted
+    pub fn load(&self) -> Result<Vec<P2PTransaction>, MempoolError> {
+        let _file = self.lock()?;
+        self.read_entries()
+    }
+
+    /// Records a transaction at the end of the journal
+    pub fn append(&self, transaction: &P2PTransaction) -> Result<(), MempoolError> {
+        let mut file = self.lock()?;
+        file.write_all(&transaction.encode_to_vec())?;
+        Ok(())
+    }
+
+    /// Rewrites the journal keeping only the transactions for which `keep` returns true.
+    /// Returns the amount of transactions kept.
+    pub fn retain(
+        &self,
+        mut keep: impl FnMut(&P2PTransaction) -> bool,
+    ) -> Result<usize, MempoolError> {
+        let mut file = self.lock()?;
+        let mut entries = self.read_entries()?;
+        entries.retain(|transaction| keep(transaction));
+
+        // Write the new journal aside and swap it in, so a crash never leaves it half written
+        let mut new_path = self.path.clone().into_os_string();
+        new_path.push(".new");
+        let new_path = PathBuf::from(new_path);
+        let mut writer = BufWriter::new(File::create(&new_path)?);
+        for transaction in &entries {
+            writer.write_all(&transaction.encode_to_vec())?;
+        }
+        writer
+            .into_inner()
+            .map_err(|error| error.into_error())?
+            .sync_all()?;
+        fs::rename(&new_path, &self.path)?;
+
+        *file = open_for_append(&self.path)?;
+        Ok(entries.len())
+    }
+
+    fn read_entries(&self) -> Result<Vec<P2PTransaction>, MempoolError> {
+        let data = fs::read(&self.path)?;
+        let mut entries = Vec::new();
+        let mut rest = data.as_slice();
+        while !rest.is_empty() {
+            let entry = get_item_with_prefix(rest)
+                .and_then(|(item, remaining)| Ok((P2PTransaction::decode(item)?, remaining)));
+            match entry {
+                Ok((transaction, remaining)) => {
+                    entries.push(transaction);
+                    rest = remaining;
+                }
+                // The last entry may have been cut short if the node stopped while writing it
+                Err(error) => {
+                    let ignored = rest.len();
+                    warn!(%error, "Ignoring the last {ignored} bytes of the mempool journal");
+                    break;
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, File>, MempoolError> {
+        self.file
+            .lock()
+            .map_err(|error| MempoolError::JournalError(io::Error::other(error.to_string())))
+    }
+}
+
+fn open_for_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Returns the hash of a journaled transaction
+pub fn journaled_transaction_hash(transaction: &P2PTransaction) -> H256 {
+    match transaction {
+        P2PTransaction::LegacyTransaction(tx) => {
+            Transaction::LegacyTransaction(tx.clone()).compute_hash()
+        }
+        P2PTransaction::EIP2930Transaction(tx) => {
+            Transaction::EIP2930Transaction(tx.clone()).compute_hash()
+        }
+        P2PTransaction::EIP1559Transaction(tx) => {
+            Transaction::EIP1559Transaction(tx.clone()).compute_hash()
+        }
+        P2PTransaction::EIP4844TransactionWithBlobs(wrapped) => {
+            Transaction::EIP4844Transaction(wrapped.tx.clone()).compute_hash()
+        }
+        P2PTransaction::EIP7702Transaction(tx) => {
+            Transaction::EIP7702Transaction(tx.clone()).compute_hash()
+        }
+        P2PTransaction::PrivilegedL2Transaction(tx) => {
+            Transaction::PrivilegedL2Transaction(tx.clone()).compute_hash()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use ethrex_common::types::{
+        BlobsBundle, EIP1559Transaction, EIP4844Transaction, WrappedEIP4844Transaction,
+    };
+
+    fn journal_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "ethrex-mempool-journal-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn transaction(nonce: u64) -> P2PTransaction {
+        P2PTransaction::EIP1559Transaction(EIP1559Transaction {
+            nonce,
+            data: Bytes::from(vec![0xaa; 100]),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn journaled_transactions_are_loaded_in_order() {
+        let path = journal_path("load");
+        let blob_transaction =
+            P2PTransaction::EIP4844TransactionWithBlobs(WrappedEIP4844Transaction {
+                tx: EIP4844Transaction {
+                    nonce: 2,
+                    data: Bytes::from(vec![0xaa; 100]),
+                    ..Default::default()
+                },
+                blobs_bundle: BlobsBundle::default(),
+            });
+
+        let journal = MempoolJournal::open(path.clone()).unwrap();
+        journal.append(&transaction(0)).unwrap();
+        journal.append(&transaction(1)).unwrap();
+        journal.append(&blob_transaction).unwrap();
+        drop(journal);
+
+        let journal = MempoolJournal::open(path.clone()).unwrap();
+        assert_eq!(
+            journal.load().unwrap(),
+            vec![transaction(0), transaction(1), blob_transaction]
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn truncated_entries_are_ignored() {
+        let path = journal_path("truncated");
+        let journal = MempoolJournal::open(path.clone()).unwrap();
+        journal.append(&transaction(0)).unwrap();
+        let encoded = transaction(1).encode_to_vec();
+        journal
+            .lock()
+            .unwrap()
+            .write_all(&encoded[..encoded.len() / 2])
+            .unwrap();
+
+        assert_eq!(journal.load().unwrap(), vec![transaction(0)]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn retain_rewrites_the_journal() {
+        let path = journal_path("retain");
+        let journal = MempoolJournal::open(path.clone()).unwrap();
+        for nonce in 0..4 {
+            journal.append(&transaction(nonce)).unwrap();
+        }
+        let kept_hashes = [
+            journaled_transaction_hash(&transaction(1)),
+            journaled_transaction_hash(&transaction(3)),
+        ];
+
+        let kept = journal
+            .retain(|tx| kept_hashes.contains(&journaled_transaction_hash(tx)))
+            .unwrap();
+        assert_eq!(kept, 2);
+
+        // New entries are appended after the retained ones
+        journal.append(&transaction(4)).unwrap();
+        assert_eq!(
+            journal.load().unwrap(),
+            vec![transaction(1), transaction(3), transaction(4)]
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
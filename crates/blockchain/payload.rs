@@ -12,7 +12,7 @@ use ethrex_common::{
         compute_receipts_root, compute_transactions_root, compute_withdrawals_root,
         requests::{compute_requests_hash, EncodedRequests},
         BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig,
-        MempoolTransaction, Receipt, Transaction, Withdrawal, DEFAULT_OMMERS_HASH,
+        MempoolTransaction, Receipt, Transaction, TxType, Withdrawal, DEFAULT_OMMERS_HASH,
         DEFAULT_REQUESTS_HASH,
     },
     Address, Bloom, Bytes, H256, U256,
@@ -415,7 +415,7 @@ impl Blockchain {
                 Ok(receipt) => {
                     txs.shift()?;
                     // Pull transaction from the mempool
-                    self.remove_transaction_from_pool(&head_tx.tx.compute_hash())?;
+                    self.remove_included_transaction_from_pool(&head_tx.tx.compute_hash())?;
 
                     metrics!(METRICS_TX.inc_tx_with_status_and_type(
                         MetricsTxStatus::Succeeded,
@@ -662,11 +662,18 @@ impl TransactionQueue {
 }
 
 // Orders transactions by highest tip, if tip is equal, orders by lowest timestamp
+// L2 privileged transactions (deposits and forced transactions) go first, in the order they
+// were sent to the L1 bridge, which is their nonce
 impl Ord for HeadTransaction {
     fn cmp(&self, other: &Self) -> Ordering {
-        match other.tip.cmp(&self.tip) {
-            Ordering::Equal => self.tx.time().cmp(&other.tx.time()),
-            ordering => ordering,
+        match (self.tx.tx_type(), other.tx.tx_type()) {
+            (TxType::Privileged, TxType::Privileged) => self.tx.nonce().cmp(&other.tx.nonce()),
+            (TxType::Privileged, _) => Ordering::Less,
+            (_, TxType::Privileged) => Ordering::Greater,
+            _ => match other.tip.cmp(&self.tip) {
+                Ordering::Equal => self.tx.time().cmp(&other.tx.time()),
+                ordering => ordering,
+            },
         }
     }
 }
@@ -676,3 +683,64 @@ impl PartialOrd for HeadTransaction {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::types::{EIP1559Transaction, PrivilegedL2Transaction, TxKind};
+
+    fn privileged_tx(nonce: u64) -> Transaction {
+        Transaction::PrivilegedL2Transaction(PrivilegedL2Transaction {
+            nonce,
+            max_fee_per_gas: 1,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::zero()),
+            ..Default::default()
+        })
+    }
+
+    fn eip1559_tx(tip: u64) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            max_priority_fee_per_gas: tip,
+            max_fee_per_gas: 1 + tip,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::zero()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn privileged_transactions_go_first_by_nonce() {
+        // Each transaction comes from a different sender, so all of them are heads
+        let txs = [
+            eip1559_tx(5),
+            privileged_tx(8),
+            eip1559_tx(10),
+            privileged_tx(7),
+        ];
+        let mempool_txs = txs
+            .iter()
+            .enumerate()
+            .map(|(i, tx)| {
+                let sender = Address::from_low_u64_be(i as u64 + 1);
+                (sender, vec![MempoolTransaction::new(tx.clone(), sender)])
+            })
+            .collect();
+        let mut queue = TransactionQueue::new(mempool_txs, Some(1)).unwrap();
+
+        let mut ordered = Vec::new();
+        while let Some(head) = queue.peek() {
+            ordered.push((head.tx_type(), head.nonce(), head.tip));
+            queue.pop();
+        }
+        assert_eq!(
+            ordered,
+            vec![
+                (TxType::Privileged, 7, 0),
+                (TxType::Privileged, 8, 0),
+                (TxType::EIP1559, 0, 10),
+                (TxType::EIP1559, 0, 5),
+            ]
+        );
+    }
+}
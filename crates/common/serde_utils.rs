@@ -254,6 +254,33 @@ pub mod bytes {
             serialize_vec_of_hex_encodables(value, serializer)
         }
     }
+
+    pub mod opt {
+        use super::*;
+
+        pub fn deserialize<'de, D>(d: D) -> Result<Option<Bytes>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Option::<String>::deserialize(d)?
+                .map(|value| {
+                    hex::decode(value.trim_start_matches("0x"))
+                        .map(Bytes::from)
+                        .map_err(|e| D::Error::custom(e.to_string()))
+                })
+                .transpose()
+        }
+
+        pub fn serialize<S>(value: &Option<Bytes>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match value {
+                Some(value) => serializer.serialize_str(&format!("0x{:x}", value)),
+                None => serializer.serialize_none(),
+            }
+        }
+    }
 }
 
 /// Serializes to and deserializes from 0x prefixed hex string
@@ -281,6 +308,25 @@ pub mod bool {
 pub mod bytes48 {
     use super::*;
 
+    pub fn serialize<S>(value: &[u8; 48], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{}", hex::encode(value)))
+    }
+
+    pub fn deserialize<'de, D>(d: D) -> Result<[u8; 48], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(d)?;
+        let bytes = hex::decode(value.trim_start_matches("0x"))
+            .map_err(|e| D::Error::custom(e.to_string()))?;
+        bytes.try_into().map_err(|bytes: Vec<u8>| {
+            D::Error::custom(format!("Expected 48 bytes, got {}", bytes.len()))
+        })
+    }
+
     pub mod vec {
         use super::*;
 
@@ -317,10 +363,32 @@ pub mod bytes48 {
 
 pub mod blob {
     use super::*;
+    use crate::types::BYTES_PER_BLOB;
 
-    pub mod vec {
-        use crate::types::BYTES_PER_BLOB;
+    pub fn serialize<S>(value: &[u8; BYTES_PER_BLOB], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{}", hex::encode(value)))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<[u8; BYTES_PER_BLOB], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        let bytes = hex::decode(value.trim_start_matches("0x"))
+            .map_err(|e| D::Error::custom(e.to_string()))?;
+        bytes.try_into().map_err(|bytes: Vec<u8>| {
+            D::Error::custom(format!(
+                "Expected {} bytes, got {}",
+                BYTES_PER_BLOB,
+                bytes.len()
+            ))
+        })
+    }
 
+    pub mod vec {
         use super::*;
 
         pub fn serialize<S>(
@@ -9,9 +9,10 @@ pub trait TrieDB: Send + Sync {
     fn put(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), TrieError>;
     // fn put_batch(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), TrieError>;
     fn put_batch(&self, key_values: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), TrieError>;
+    fn remove_batch(&self, keys: Vec<Vec<u8>>) -> Result<(), TrieError>;
 }
 
-/// InMemory implementation for the TrieDB trait, with get and put operations.
+/// InMemory implementation for the TrieDB trait, with get, put and remove operations.
 pub struct InMemoryTrieDB {
     inner: Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>,
 }
@@ -54,4 +55,14 @@ impl TrieDB for InMemoryTrieDB {
 
         Ok(())
     }
+
+    fn remove_batch(&self, keys: Vec<Vec<u8>>) -> Result<(), TrieError> {
+        let mut db = self.inner.lock().map_err(|_| TrieError::LockError)?;
+
+        for key in keys {
+            db.remove(&key);
+        }
+
+        Ok(())
+    }
 }
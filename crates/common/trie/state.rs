@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use crate::error::TrieError;
+use ethereum_types::H256;
 use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 
 use super::db::TrieDB;
@@ -49,26 +50,40 @@ impl TrieState {
     /// Commits cache changes to DB and clears it
     /// Only writes nodes that follow the root's canonical trie
     pub fn commit(&mut self, root: &NodeHash) -> Result<(), TrieError> {
-        self.commit_node(root)?;
-        self.cache.clear();
+        self.commit_tracked(root)?;
         Ok(())
     }
 
+    /// Commits cache changes to DB and clears it, returning the nodes that were written
+    pub(crate) fn commit_tracked(
+        &mut self,
+        root: &NodeHash,
+    ) -> Result<Vec<(NodeHash, Node)>, TrieError> {
+        let committed = self.commit_node(root)?;
+        self.cache.clear();
+        Ok(committed)
+    }
+
     // Writes a node and its children into the DB
-    fn commit_node(&mut self, node_hash: &NodeHash) -> Result<(), TrieError> {
+    fn commit_node(&mut self, node_hash: &NodeHash) -> Result<Vec<(NodeHash, Node)>, TrieError> {
         let mut to_commit = vec![];
         self.commit_node_tail_recursive(node_hash, &mut to_commit)?;
 
-        self.db.put_batch(to_commit)?;
+        self.db.put_batch(
+            to_commit
+                .iter()
+                .map(|(hash, node)| (hash.into(), node.encode_to_vec()))
+                .collect(),
+        )?;
 
-        Ok(())
+        Ok(to_commit)
     }
 
     // Writes a node and its children into the DB
     fn commit_node_tail_recursive(
         &mut self,
         node_hash: &NodeHash,
-        acc: &mut Vec<(Vec<u8>, Vec<u8>)>,
+        acc: &mut Vec<(NodeHash, Node)>,
     ) -> Result<(), TrieError> {
         let Some(node) = self.cache.remove(node_hash) else {
             // If the node is not in the cache then it means it is already stored in the DB
@@ -87,7 +102,7 @@ impl TrieState {
             Node::Leaf(_) => {}
         }
         // Commit self
-        acc.push((node_hash.into(), node.encode_to_vec()));
+        acc.push((*node_hash, node));
 
         Ok(())
     }
@@ -114,4 +129,11 @@ impl TrieState {
         self.db.put_batch(key_values)?;
         Ok(())
     }
+
+    /// Removes a batch of nodes directly from the DB bypassing the cache
+    pub fn remove_node_batch(&mut self, hashes: &[H256]) -> Result<(), TrieError> {
+        let keys = hashes.iter().map(|hash| hash.as_bytes().to_vec()).collect();
+        self.db.remove_batch(keys)?;
+        Ok(())
+    }
 }
@@ -42,6 +42,15 @@ pub type ValueRLP = Vec<u8>;
 /// RLP-encoded trie node
 pub type NodeRLP = Vec<u8>;
 
+/// Hashes of the stored nodes written and made unreachable by a trie commit
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrieDiff {
+    /// Nodes written to the DB by the commit
+    pub inserted: Vec<H256>,
+    /// Nodes of the previous trie that are not part of the new one
+    pub removed: Vec<H256>,
+}
+
 /// Libmdx-based Ethereum Compatible Merkle Patricia Trie
 pub struct Trie {
     /// Hash of the current node
@@ -148,6 +157,65 @@ impl Trie {
         Ok(())
     }
 
+    /// Return the hash of the trie's root node and commits changes to the DB, like [Trie::hash].
+    /// Also returns the nodes written by the commit and the nodes of the trie rooted at `prev_root`
+    /// that are no longer reachable from the new root, so they can be pruned later on.
+    pub fn hash_with_diff(&mut self, prev_root: H256) -> Result<(H256, TrieDiff), TrieError> {
+        let committed = match self.root {
+            Some(ref root) => self.state.commit_tracked(root)?,
+            None => vec![],
+        };
+
+        // Nodes of the new trie that may also be part of the previous one: the ones written by
+        // this commit and the unchanged subtries they point to.
+        let mut reachable: HashSet<NodeHash> = self.root.into_iter().collect();
+        for (hash, node) in committed.iter() {
+            reachable.insert(*hash);
+            match node {
+                Node::Branch(n) => reachable.extend(n.choices.iter().filter(|c| c.is_valid())),
+                Node::Extension(n) => {
+                    reachable.insert(n.child);
+                }
+                Node::Leaf(_) => {}
+            }
+        }
+
+        // Walk the previous trie, stopping at the nodes that are still reachable
+        let mut removed = vec![];
+        let mut pending = vec![];
+        if prev_root != *EMPTY_TRIE_HASH {
+            pending.push(NodeHash::Hashed(prev_root));
+        }
+        while let Some(hash) = pending.pop() {
+            // Inlined nodes are never stored on their own
+            let NodeHash::Hashed(stored_hash) = hash else {
+                continue;
+            };
+            if reachable.contains(&hash) {
+                continue;
+            }
+            let Some(node) = self.state.get_node(hash)? else {
+                continue;
+            };
+            removed.push(stored_hash);
+            match node {
+                Node::Branch(n) => pending.extend(n.choices.iter().filter(|c| c.is_valid())),
+                Node::Extension(n) => pending.push(n.child),
+                Node::Leaf(_) => {}
+            }
+        }
+
+        let inserted = committed
+            .into_iter()
+            .filter_map(|(hash, _)| match hash {
+                NodeHash::Hashed(hash) => Some(hash),
+                NodeHash::Inline(_) => None,
+            })
+            .collect();
+
+        Ok((self.hash_no_commit(), TrieDiff { inserted, removed }))
+    }
+
     /// Obtain a merkle proof for the given path.
     /// The proof will contain all the encoded nodes traversed until reaching the node where the path is stored (including this last node).
     /// The proof will still be constructed even if the path is not stored in the trie, proving its absence.
@@ -252,6 +320,10 @@ impl Trie {
             fn put_batch(&self, _key_values: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), TrieError> {
                 Ok(())
             }
+
+            fn remove_batch(&self, _keys: Vec<Vec<u8>>) -> Result<(), TrieError> {
+                Ok(())
+            }
         }
 
         Trie::new(Box::new(NullTrieDB))
@@ -997,4 +1069,48 @@ mod test {
         let trie_proof = trie.get_proof(&a).unwrap();
         assert_eq!(cita_proof, trie_proof);
     }
+
+    #[test]
+    fn hash_with_diff_removes_unreachable_nodes() {
+        let map = Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let mut trie = Trie::new(Box::new(InMemoryTrieDB::new(map.clone())));
+        let key = |i: u8| Keccak256::digest([i]).to_vec();
+        for i in 0..100 {
+            trie.insert(key(i), vec![i; 40]).unwrap();
+        }
+        let prev_root = trie.hash().unwrap();
+
+        // Update, remove and re-insert some of the values
+        for i in 0..10 {
+            trie.insert(key(i), vec![i + 1; 40]).unwrap();
+        }
+        for i in 10..15 {
+            trie.remove(key(i)).unwrap();
+        }
+        for i in 15..20 {
+            trie.insert(key(i), vec![i; 40]).unwrap();
+        }
+        let (root, diff) = trie.hash_with_diff(prev_root).unwrap();
+        assert!(!diff.inserted.is_empty());
+        assert!(!diff.removed.is_empty());
+
+        let mut trie = Trie::open(Box::new(InMemoryTrieDB::new(map.clone())), root);
+        trie.state_mut().remove_node_batch(&diff.removed).unwrap();
+
+        // Only the nodes of the new trie are left in the DB
+        for i in 0..100 {
+            let expected = match i {
+                0..10 => Some(vec![i + 1; 40]),
+                10..15 => None,
+                _ => Some(vec![i; 40]),
+            };
+            assert_eq!(trie.get(&key(i)).unwrap(), expected);
+        }
+        let stored_nodes = map.lock().unwrap().len();
+        let reachable_nodes = trie
+            .into_iter()
+            .filter(|(_, node)| node.encode_raw().len() >= 32)
+            .count();
+        assert_eq!(stored_nodes, reachable_nodes);
+    }
 }
@@ -136,6 +136,11 @@ pub struct ChainConfig {
     pub blob_schedule: BlobSchedule,
     // Deposits system contract address
     pub deposit_contract_address: Address,
+
+    /// L2 only. Address credited with the L1 fees charged to the transactions.
+    /// L1 fees are only charged if it is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_fee_vault: Option<Address>,
 }
 
 #[repr(u8)]
@@ -0,0 +1,114 @@
+use bytes::Bytes;
+use ethereum_types::U256;
+
+use super::Transaction;
+
+/// Amount of L1 gas charged per L2 transaction, its share of the commit and verify transactions.
+pub const L1_FEE_GAS_PER_TX: u64 = 1_000;
+
+/// L1 prices used to charge L2 transactions for the L1 resources they consume.
+/// The sequencer tracks them and sets them in the `extra_data` of every L2 block header, so any
+/// node re-executing the block (including the prover) charges the same fees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct L1FeeParams {
+    pub l1_base_fee_per_gas: u64,
+    pub l1_blob_base_fee_per_gas: u64,
+}
+
+impl L1FeeParams {
+    /// Decodes the params from the `extra_data` of a block header, as two big endian u64.
+    /// Missing bytes are taken as zero, so blocks without params don't charge any L1 fee.
+    pub fn from_extra_data(extra_data: &[u8]) -> Self {
+        let read_u64 = |offset: usize| {
+            let mut bytes = [0u8; 8];
+            if let Some(src) = extra_data.get(offset..offset + 8) {
+                bytes.copy_from_slice(src);
+            }
+            u64::from_be_bytes(bytes)
+        };
+        Self {
+            l1_base_fee_per_gas: read_u64(0),
+            l1_blob_base_fee_per_gas: read_u64(8),
+        }
+    }
+
+    pub fn encode_extra_data(&self) -> Bytes {
+        [
+            self.l1_base_fee_per_gas.to_be_bytes(),
+            self.l1_blob_base_fee_per_gas.to_be_bytes(),
+        ]
+        .concat()
+        .into()
+    }
+
+    /// Computes the L1 fee of a transaction.
+    /// The transaction pays for its compressed size in the blob, one blob gas per byte, plus its
+    /// share of the L1 gas of the batch commitment.
+    pub fn l1_fee(&self, transaction: &Transaction) -> U256 {
+        U256::from(compressed_size(transaction)) * U256::from(self.l1_blob_base_fee_per_gas)
+            + U256::from(L1_FEE_GAS_PER_TX) * U256::from(self.l1_base_fee_per_gas)
+    }
+}
+
+/// Size of the snappy compressed canonical encoding of the transaction, which is how much the
+/// changes it makes to the state can be expected to take in the blob.
+fn compressed_size(transaction: &Transaction) -> usize {
+    let encoded = transaction.encode_canonical_to_vec();
+    // Compression only fails for inputs too big to be a transaction
+    snap::raw::Encoder::new()
+        .compress_vec(&encoded)
+        .map_or(encoded.len(), |compressed| compressed.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EIP1559Transaction;
+
+    #[test]
+    fn extra_data_roundtrip() {
+        let params = L1FeeParams {
+            l1_base_fee_per_gas: 7_000_000_000,
+            l1_blob_base_fee_per_gas: 3,
+        };
+        assert_eq!(
+            L1FeeParams::from_extra_data(&params.encode_extra_data()),
+            params
+        );
+        assert_eq!(L1FeeParams::from_extra_data(&[]), L1FeeParams::default());
+    }
+
+    fn transaction_with_data(data: Bytes) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            data,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn l1_fee_charges_blob_bytes_and_commitment_gas() {
+        let params = L1FeeParams {
+            l1_base_fee_per_gas: 10,
+            l1_blob_base_fee_per_gas: 2,
+        };
+        let transaction = transaction_with_data(Bytes::from(vec![0xff; 4]));
+        let fee = params.l1_fee(&transaction);
+        assert_eq!(
+            fee,
+            U256::from(compressed_size(&transaction) as u64 * 2 + L1_FEE_GAS_PER_TX * 10)
+        );
+        assert_eq!(L1FeeParams::default().l1_fee(&transaction), U256::zero());
+    }
+
+    #[test]
+    fn l1_fee_charges_the_compressed_size() {
+        let params = L1FeeParams {
+            l1_base_fee_per_gas: 0,
+            l1_blob_base_fee_per_gas: 1,
+        };
+        let repetitive = transaction_with_data(Bytes::from(vec![0; 1024]));
+        let random = transaction_with_data((0..1024u32).map(|i| (i * 7919 % 251) as u8).collect());
+        assert!(params.l1_fee(&repetitive) < U256::from(256));
+        assert!(params.l1_fee(&repetitive) < params.l1_fee(&random));
+    }
+}
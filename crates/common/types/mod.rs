@@ -4,6 +4,7 @@ mod block;
 mod constants;
 mod fork_id;
 mod genesis;
+mod l1_fee;
 pub mod payload;
 mod receipt;
 pub mod requests;
@@ -16,6 +17,7 @@ pub use block::*;
 pub use constants::*;
 pub use fork_id::*;
 pub use genesis::*;
+pub use l1_fee::*;
 pub use receipt::*;
 pub use transaction::*;
 pub use tx_fields::*;
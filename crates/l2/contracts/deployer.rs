@@ -42,6 +42,7 @@ struct SetupResult {
     sp1_deploy_verifier_on_l1: bool,
     pico_contract_verifier_address: Address,
     pico_deploy_verifier_on_l1: bool,
+    genesis_state_root: H256,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -79,7 +80,7 @@ lazy_static::lazy_static! {
 }
 
 const INITIALIZE_ON_CHAIN_PROPOSER_SIGNATURE: &str =
-    "initialize(address,address,address,address,address[])";
+    "initialize(address,address,address,address,address[],bytes32)";
 
 const BRIDGE_INITIALIZER_SIGNATURE: &str = "initialize(address)";
 
@@ -121,6 +122,7 @@ async fn main() -> Result<(), DeployError> {
         setup_result.risc0_contract_verifier_address,
         sp1_contract_verifier_address,
         pico_contract_verifier_address,
+        setup_result.genesis_state_root,
         &setup_result.eth_client,
     )
     .await?;
@@ -242,6 +244,11 @@ fn setup() -> Result<SetupResult, DeployError> {
     };
     let pico_contract_verifier_address = parse_env_var("DEPLOYER_PICO_CONTRACT_VERIFIER")?;
 
+    // The OnChainProposer checks the first batch proof against the L2 genesis state
+    let genesis_l2_path =
+        std::env::var("GENESIS_L2_PATH").unwrap_or("../../test_data/genesis-l2.json".to_string());
+    let genesis_state_root = read_genesis_file(&genesis_l2_path).compute_state_root();
+
     Ok(SetupResult {
         deployer_address,
         deployer_private_key,
@@ -254,6 +261,7 @@ fn setup() -> Result<SetupResult, DeployError> {
         sp1_contract_verifier_address,
         pico_deploy_verifier_on_l1,
         pico_contract_verifier_address,
+        genesis_state_root,
     })
 }
 
@@ -610,6 +618,7 @@ async fn initialize_contracts(
     risc0_verifier_address: Address,
     sp1_verifier_address: Address,
     pico_verifier_address: Address,
+    genesis_state_root: H256,
     eth_client: &EthClient,
 ) -> Result<(), DeployError> {
     let initialize_frames = spinner!(["🪄❱❱", "❱🪄❱", "❱❱🪄"], 200);
@@ -630,6 +639,7 @@ async fn initialize_contracts(
         deployer_private_key,
         committer,
         verifier,
+        genesis_state_root,
         eth_client,
     )
     .await
@@ -673,6 +683,7 @@ async fn initialize_on_chain_proposer(
     deployer_private_key: SecretKey,
     committer: Address,
     verifier: Address,
+    genesis_state_root: H256,
     eth_client: &EthClient,
 ) -> Result<H256, DeployError> {
     let calldata_values = vec![
@@ -681,6 +692,7 @@ async fn initialize_on_chain_proposer(
         Value::Address(sp1_verifier_address),
         Value::Address(pico_verifier_address),
         Value::Array(vec![Value::Address(committer), Value::Address(verifier)]),
+        Value::FixedBytes(genesis_state_root.0.to_vec().into()),
     ];
 
     let on_chain_proposer_initialization_calldata =
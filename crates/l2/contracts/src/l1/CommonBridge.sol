@@ -3,12 +3,18 @@ pragma solidity =0.8.29;
 
 import "../../lib/openzeppelin-contracts/contracts/access/Ownable.sol";
 import "../../lib/openzeppelin-contracts/contracts/utils/ReentrancyGuard.sol";
+import "../../lib/openzeppelin-contracts/contracts/token/ERC20/IERC20.sol";
+import "../../lib/openzeppelin-contracts/contracts/token/ERC20/extensions/IERC20Metadata.sol";
+import "../../lib/openzeppelin-contracts/contracts/token/ERC20/utils/SafeERC20.sol";
 import "./interfaces/ICommonBridge.sol";
 import "./interfaces/IOnChainProposer.sol";
+import {ICommonBridgeL2} from "../l2/interfaces/ICommonBridgeL2.sol";
 
 /// @title CommonBridge contract.
 /// @author LambdaClass
 contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
+    using SafeERC20 for IERC20;
+
     /// @notice Mapping of unclaimed withdrawals. A withdrawal is claimed if
     /// there is a non-zero value in the mapping (a merkle root) for the hash
     /// of the L2 transaction that requested the withdrawal.
@@ -18,15 +24,19 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
     mapping(bytes32 => bool) public claimedWithdrawals;
 
     /// @notice Mapping of merkle roots to the L2 withdrawal transaction logs.
-    /// @dev The key is the L2 block number where the logs were emitted.
+    /// @dev The key is the number of the L2 batch whose blocks emitted the logs.
     /// @dev The value is the merkle root of the logs.
-    /// @dev If there exist a merkle root for a given block number it means
-    /// that the logs were published on L1, and that that block was committed.
-    mapping(uint256 => bytes32) public blockWithdrawalLogsMerkleRoots;
+    /// @dev If there exist a merkle root for a given batch number it means
+    /// that the logs were published on L1, and that that batch was committed.
+    mapping(uint256 => bytes32) public batchWithdrawalLogsMerkleRoots;
 
     /// @notice Array of hashed pending deposit logs.
     bytes32[] public pendingDepositLogs;
 
+    /// @notice Timestamps at which each of the pending deposit logs was queued.
+    /// @dev It is kept aligned with pendingDepositLogs.
+    uint256[] public pendingDepositLogsTimestamps;
+
     address public ON_CHAIN_PROPOSER;
 
     /// @notice Block in which the CommonBridge was initialized.
@@ -37,6 +47,43 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
     /// @dev It is used as the nonce of the mint transaction created by the L1Watcher.
     uint256 public depositId;
 
+    /// @notice Amount of each ERC20 token locked in the bridge.
+    /// @dev The key is the address of the token on L1.
+    /// @dev Withdrawals of a token can't claim more than what was deposited.
+    mapping(address => uint256) public depositedERC20;
+
+    /// @notice Address of the CommonBridgeL2 contract.
+    /// @dev ERC20 deposits are sent to it, with itself as the sender, so it
+    /// can tell them apart from any other transaction.
+    address public constant L2_BRIDGE_ADDRESS = address(0xffff);
+
+    /// @notice Gas limit of the L2 transaction of an ERC20 deposit.
+    /// @dev It has to cover the deployment of the bridged token on the first
+    /// deposit of an L1 token.
+    uint256 public constant ERC20_DEPOSIT_GAS_LIMIT = 2_000_000;
+
+    /// @notice Time after which a pending deposit log must be included in
+    /// the next committed batch.
+    /// @dev This applies to both deposits and forced transactions, so the
+    /// sequencer can't censor any of them for longer than this.
+    uint256 public constant FORCED_INCLUSION_DEADLINE = 1 days;
+
+    /// @notice Maximum gas limit of a forced transaction.
+    /// @dev It must fit in an L2 block, or it could never be included.
+    uint256 public constant FORCED_TRANSACTION_MAX_GAS_LIMIT = 10_000_000;
+
+    /// @notice Maximum gas that the forced transactions queued in a single
+    /// L1 block can add up to.
+    /// @dev It keeps the queue growing slower than the L2 can process it, so
+    /// filling it can't stall the commits through the inclusion deadline.
+    uint256 public constant FORCED_TRANSACTIONS_MAX_GAS_PER_BLOCK = 10_000_000;
+
+    /// @notice L1 block in which forcedTransactionsGas was accumulated.
+    uint256 public forcedTransactionsBlock;
+
+    /// @notice Gas limit of the forced transactions queued in forcedTransactionsBlock.
+    uint256 public forcedTransactionsGas;
+
     modifier onlyOnChainProposer() {
         require(
             msg.sender == ON_CHAIN_PROPOSER,
@@ -72,15 +119,17 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
         return pendingDepositLogs;
     }
 
-    function _deposit(DepositValues memory depositValues) private {
-        require(msg.value > 0, "CommonBridge: amount to deposit is zero");
-
+    function _deposit(
+        address from,
+        uint256 value,
+        DepositValues memory depositValues
+    ) private {
         bytes32 l2MintTxHash = keccak256(
             abi.encodePacked(
-                msg.sender,
+                from,
                 depositValues.to,
                 depositValues.recipient,
-                msg.value,
+                value,
                 depositValues.gasLimit,
                 depositId,
                 depositValues.data
@@ -91,21 +140,22 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
             keccak256(
                 bytes.concat(
                     bytes20(depositValues.to),
-                    bytes32(msg.value),
+                    bytes32(value),
                     bytes32(depositId),
                     bytes20(depositValues.recipient),
-                    bytes20(msg.sender),
+                    bytes20(from),
                     bytes32(depositValues.gasLimit),
                     bytes32(keccak256(depositValues.data))
                 )
             )
         );
+        pendingDepositLogsTimestamps.push(block.timestamp);
         emit DepositInitiated(
-            msg.value,
+            value,
             depositValues.to,
             depositId,
             depositValues.recipient,
-            msg.sender,
+            from,
             depositValues.gasLimit,
             depositValues.data,
             l2MintTxHash
@@ -115,17 +165,119 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
 
     /// @inheritdoc ICommonBridge
     function deposit(DepositValues calldata depositValues) public payable {
-        _deposit(depositValues);
+        require(msg.value > 0, "CommonBridge: amount to deposit is zero");
+        _deposit(msg.sender, msg.value, depositValues);
+    }
+
+    /// @inheritdoc ICommonBridge
+    function sendForcedTransaction(
+        DepositValues calldata depositValues
+    ) public payable {
+        require(
+            msg.sender == tx.origin,
+            "CommonBridge: forced transactions can only be sent by EOAs"
+        );
+        require(
+            depositValues.gasLimit >= 21000 + 16 * depositValues.data.length,
+            "CommonBridge: gas limit is below the intrinsic gas"
+        );
+        require(
+            depositValues.gasLimit <= FORCED_TRANSACTION_MAX_GAS_LIMIT,
+            "CommonBridge: gas limit is too high"
+        );
+
+        if (forcedTransactionsBlock != block.number) {
+            forcedTransactionsBlock = block.number;
+            forcedTransactionsGas = 0;
+        }
+        forcedTransactionsGas += depositValues.gasLimit;
+        require(
+            forcedTransactionsGas <= FORCED_TRANSACTIONS_MAX_GAS_PER_BLOCK,
+            "CommonBridge: forced transactions gas limit reached for this block"
+        );
+
+        // The L2 gas is paid at the L1 base fee. It isn't minted on L2, so it
+        // stays locked in the bridge.
+        uint256 fee = depositValues.gasLimit * block.basefee;
+        require(
+            msg.value >= fee,
+            "CommonBridge: value doesn't cover the L2 gas fee"
+        );
+        _deposit(msg.sender, msg.value - fee, depositValues);
+    }
+
+    /// @inheritdoc ICommonBridge
+    function hasExpiredDepositLogs(
+        uint256 includedLogs
+    ) public view returns (bool) {
+        return
+            includedLogs < pendingDepositLogs.length &&
+            pendingDepositLogsTimestamps[includedLogs] +
+                FORCED_INCLUSION_DEADLINE <
+            block.timestamp;
     }
 
     receive() external payable {
+        require(msg.value > 0, "CommonBridge: amount to deposit is zero");
         DepositValues memory depositValues = DepositValues({
             to: msg.sender,
             recipient: msg.sender,
             gasLimit: 21000 * 5,
             data: bytes("")
         });
-        _deposit(depositValues);
+        _deposit(msg.sender, msg.value, depositValues);
+    }
+
+    /// @inheritdoc ICommonBridge
+    function depositERC20(
+        address tokenL1,
+        address recipient,
+        uint256 amount
+    ) public nonReentrant {
+        require(amount > 0, "CommonBridge: amount to deposit is zero");
+
+        IERC20(tokenL1).safeTransferFrom(msg.sender, address(this), amount);
+        depositedERC20[tokenL1] += amount;
+
+        (
+            string memory name,
+            string memory symbol,
+            uint8 decimals
+        ) = _tokenMetadata(tokenL1);
+        DepositValues memory depositValues = DepositValues({
+            to: L2_BRIDGE_ADDRESS,
+            recipient: L2_BRIDGE_ADDRESS,
+            gasLimit: ERC20_DEPOSIT_GAS_LIMIT,
+            data: abi.encodeCall(
+                ICommonBridgeL2.mintERC20,
+                (tokenL1, recipient, amount, name, symbol, decimals)
+            )
+        });
+        emit ERC20DepositInitiated(tokenL1, msg.sender, recipient, amount);
+        _deposit(L2_BRIDGE_ADDRESS, 0, depositValues);
+    }
+
+    /// @dev The metadata is optional in ERC20, tokens that don't implement
+    /// it are bridged with an empty name and symbol and 18 decimals.
+    function _tokenMetadata(
+        address token
+    )
+        private
+        view
+        returns (string memory name, string memory symbol, uint8 decimals)
+    {
+        try IERC20Metadata(token).name() returns (string memory tokenName) {
+            name = tokenName;
+        } catch {}
+        try IERC20Metadata(token).symbol() returns (
+            string memory tokenSymbol
+        ) {
+            symbol = tokenSymbol;
+        } catch {}
+        decimals = 18;
+        try IERC20Metadata(token).decimals() returns (uint8 tokenDecimals) {
+            decimals = tokenDecimals;
+        } catch {}
     }
 
     /// @inheritdoc ICommonBridge
@@ -159,35 +311,39 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
 
         for (uint i = 0; i < pendingDepositLogs.length - number; i++) {
             pendingDepositLogs[i] = pendingDepositLogs[i + number];
+            pendingDepositLogsTimestamps[i] = pendingDepositLogsTimestamps[
+                i + number
+            ];
         }
 
         for (uint _i = 0; _i < number; _i++) {
             pendingDepositLogs.pop();
+            pendingDepositLogsTimestamps.pop();
         }
     }
 
     /// @inheritdoc ICommonBridge
     function getWithdrawalLogsMerkleRoot(
-        uint256 blockNumber
+        uint256 batchNumber
     ) public view returns (bytes32) {
-        return blockWithdrawalLogsMerkleRoots[blockNumber];
+        return batchWithdrawalLogsMerkleRoots[batchNumber];
     }
 
     /// @inheritdoc ICommonBridge
     function publishWithdrawals(
-        uint256 withdrawalLogsBlockNumber,
+        uint256 withdrawalLogsBatchNumber,
         bytes32 withdrawalsLogsMerkleRoot
     ) public onlyOnChainProposer {
         require(
-            blockWithdrawalLogsMerkleRoots[withdrawalLogsBlockNumber] ==
+            batchWithdrawalLogsMerkleRoots[withdrawalLogsBatchNumber] ==
                 bytes32(0),
             "CommonBridge: withdrawal logs already published"
         );
-        blockWithdrawalLogsMerkleRoots[
-            withdrawalLogsBlockNumber
+        batchWithdrawalLogsMerkleRoots[
+            withdrawalLogsBatchNumber
         ] = withdrawalsLogsMerkleRoot;
         emit WithdrawalsPublished(
-            withdrawalLogsBlockNumber,
+            withdrawalLogsBatchNumber,
             withdrawalsLogsMerkleRoot
         );
     }
@@ -196,18 +352,84 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
     function claimWithdrawal(
         bytes32 l2WithdrawalTxHash,
         uint256 claimedAmount,
-        uint256 withdrawalBlockNumber,
+        uint256 withdrawalBatchNumber,
         uint256 withdrawalLogIndex,
         bytes32[] calldata withdrawalProof
     ) public nonReentrant {
+        bytes32 withdrawalLeaf = keccak256(
+            abi.encodePacked(msg.sender, claimedAmount, l2WithdrawalTxHash)
+        );
+        _claimWithdrawal(
+            withdrawalLeaf,
+            l2WithdrawalTxHash,
+            withdrawalBatchNumber,
+            withdrawalLogIndex,
+            withdrawalProof
+        );
+
+        (bool success, ) = payable(msg.sender).call{value: claimedAmount}("");
+
+        require(success, "CommonBridge: failed to send the claimed amount");
+
+        emit WithdrawalClaimed(l2WithdrawalTxHash, msg.sender, claimedAmount);
+    }
+
+    /// @inheritdoc ICommonBridge
+    function claimERC20Withdrawal(
+        address tokenL1,
+        bytes32 l2WithdrawalTxHash,
+        uint256 claimedAmount,
+        uint256 withdrawalBatchNumber,
+        uint256 withdrawalLogIndex,
+        bytes32[] calldata withdrawalProof
+    ) public nonReentrant {
+        bytes32 withdrawalLeaf = keccak256(
+            abi.encodePacked(
+                tokenL1,
+                msg.sender,
+                claimedAmount,
+                l2WithdrawalTxHash
+            )
+        );
+        _claimWithdrawal(
+            withdrawalLeaf,
+            l2WithdrawalTxHash,
+            withdrawalBatchNumber,
+            withdrawalLogIndex,
+            withdrawalProof
+        );
+
+        require(
+            depositedERC20[tokenL1] >= claimedAmount,
+            "CommonBridge: claimed amount exceeds the deposited tokens"
+        );
+        depositedERC20[tokenL1] -= claimedAmount;
+        IERC20(tokenL1).safeTransfer(msg.sender, claimedAmount);
+
+        emit ERC20WithdrawalClaimed(
+            tokenL1,
+            l2WithdrawalTxHash,
+            msg.sender,
+            claimedAmount
+        );
+    }
+
+    /// @dev Checks that the withdrawal can be claimed and marks it as claimed.
+    function _claimWithdrawal(
+        bytes32 withdrawalLeaf,
+        bytes32 l2WithdrawalTxHash,
+        uint256 withdrawalBatchNumber,
+        uint256 withdrawalLogIndex,
+        bytes32[] calldata withdrawalProof
+    ) private {
         require(
-            blockWithdrawalLogsMerkleRoots[withdrawalBlockNumber] != bytes32(0),
-            "CommonBridge: the block that emitted the withdrawal logs was not committed"
+            batchWithdrawalLogsMerkleRoots[withdrawalBatchNumber] != bytes32(0),
+            "CommonBridge: the batch that emitted the withdrawal logs was not committed"
         );
         require(
-            withdrawalBlockNumber <=
-                IOnChainProposer(ON_CHAIN_PROPOSER).lastVerifiedBlock(),
-            "CommonBridge: the block that emitted the withdrawal logs was not verified"
+            withdrawalBatchNumber <=
+                IOnChainProposer(ON_CHAIN_PROPOSER).lastVerifiedBatch(),
+            "CommonBridge: the batch that emitted the withdrawal logs was not verified"
         );
         require(
             claimedWithdrawals[l2WithdrawalTxHash] == false,
@@ -215,34 +437,23 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
         );
         require(
             _verifyWithdrawProof(
-                l2WithdrawalTxHash,
-                claimedAmount,
-                withdrawalBlockNumber,
+                withdrawalLeaf,
+                withdrawalBatchNumber,
                 withdrawalLogIndex,
                 withdrawalProof
             ),
             "CommonBridge: invalid withdrawal proof"
         );
 
-        (bool success, ) = payable(msg.sender).call{value: claimedAmount}("");
-
-        require(success, "CommonBridge: failed to send the claimed amount");
-
         claimedWithdrawals[l2WithdrawalTxHash] = true;
-
-        emit WithdrawalClaimed(l2WithdrawalTxHash, msg.sender, claimedAmount);
     }
 
     function _verifyWithdrawProof(
-        bytes32 l2WithdrawalTxHash,
-        uint256 claimedAmount,
-        uint256 withdrawalBlockNumber,
+        bytes32 withdrawalLeaf,
+        uint256 withdrawalBatchNumber,
         uint256 withdrawalLogIndex,
         bytes32[] calldata withdrawalProof
     ) internal view returns (bool) {
-        bytes32 withdrawalLeaf = keccak256(
-            abi.encodePacked(msg.sender, claimedAmount, l2WithdrawalTxHash)
-        );
         for (uint256 i = 0; i < withdrawalProof.length; i++) {
             if (withdrawalLogIndex % 2 == 0) {
                 withdrawalLeaf = keccak256(
@@ -257,6 +468,6 @@ contract CommonBridge is ICommonBridge, Ownable, ReentrancyGuard {
         }
         return
             withdrawalLeaf ==
-            blockWithdrawalLogsMerkleRoots[withdrawalBlockNumber];
+            batchWithdrawalLogsMerkleRoots[withdrawalBatchNumber];
     }
 }
@@ -13,38 +13,62 @@ import {IPicoVerifier} from "./interfaces/IPicoVerifier.sol";
 /// @title OnChainProposer contract.
 /// @author LambdaClass
 contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
-    /// @notice Committed blocks data.
-    /// @dev This struct holds the information about the committed blocks.
+    /// @notice Committed batches data.
+    /// @dev This struct holds the information about the committed batches.
+    /// @dev A batch is a range of consecutive L2 blocks, from `firstBlockNumber`
+    /// to `lastBlockNumber` (both inclusive), committed in a single transaction.
     /// @dev processedDepositLogsRollingHash is the Merkle root of the logs of the
-    /// deposits that were processed in the block being committed. The amount of
+    /// deposits that were processed in the batch being committed. The amount of
     /// logs that is encoded in this root are to be removed from the
     /// pendingDepositLogs queue of the CommonBridge contract.
-    struct BlockCommitmentInfo {
+    /// @dev withdrawalsLogsMerkleRoot is the Merkle root of the withdrawals of
+    /// the batch, checked against the proof public inputs when verifying.
+    struct BatchCommitmentInfo {
+        uint256 firstBlockNumber;
+        uint256 lastBlockNumber;
         bytes32 newStateRoot;
         bytes32 stateDiffKZGVersionedHash;
         bytes32 processedDepositLogsRollingHash;
+        bytes32 withdrawalsLogsMerkleRoot;
     }
 
-    /// @notice The commitments of the committed blocks.
-    /// @dev If a block is committed, the commitment is stored here.
-    /// @dev If a block was not committed yet, it won't be here.
-    /// @dev It is used by other contracts to verify if a block was committed.
-    mapping(uint256 => BlockCommitmentInfo) public blockCommitments;
+    /// @notice The commitments of the committed batches.
+    /// @dev If a batch is committed, the commitment is stored here.
+    /// @dev If a batch was not committed yet, it won't be here.
+    /// @dev It is used by other contracts to verify if a batch was committed,
+    /// and by clients to find the batch a given L2 block belongs to.
+    mapping(uint256 => BatchCommitmentInfo) public batchCommitments;
 
-    /// @notice The latest verified block number.
-    /// @dev This variable holds the block number of the most recently verified block.
+    /// @notice The latest verified batch number.
+    /// @dev This variable holds the batch number of the most recently verified batch.
+    /// @dev All batches with a batch number less than or equal to `lastVerifiedBatch` are considered verified.
+    /// @dev Batches with a batch number greater than `lastVerifiedBatch` have not been verified yet.
+    /// @dev This is crucial for ensuring that only valid and confirmed batches are processed in the contract.
+    uint256 public lastVerifiedBatch;
+
+    /// @notice The latest committed batch number.
+    /// @dev This variable holds the batch number of the most recently committed batch.
+    /// @dev All batches with a batch number less than or equal to `lastCommittedBatch` are considered committed.
+    /// @dev Batches with a batch number greater than `lastCommittedBatch` have not been committed yet.
+    /// @dev This is crucial for ensuring that only subsequents batches are committed in the contract.
+    uint256 public lastCommittedBatch;
+
+    /// @notice The last block of the latest verified batch.
     /// @dev All blocks with a block number less than or equal to `lastVerifiedBlock` are considered verified.
-    /// @dev Blocks with a block number greater than `lastVerifiedBlock` have not been verified yet.
-    /// @dev This is crucial for ensuring that only valid and confirmed blocks are processed in the contract.
     uint256 public lastVerifiedBlock;
 
-    /// @notice The latest committed block number.
-    /// @dev This variable holds the block number of the most recently committed block.
+    /// @notice The last block of the latest committed batch.
     /// @dev All blocks with a block number less than or equal to `lastCommittedBlock` are considered committed.
-    /// @dev Blocks with a block number greater than `lastCommittedBlock` have not been committed yet.
-    /// @dev This is crucial for ensuring that only subsequents blocks are committed in the contract.
+    /// @dev The next batch must start at `lastCommittedBlock + 1`.
     uint256 public lastCommittedBlock;
 
+    /// @notice Number of pending deposit logs included in the batches that
+    /// were committed but not verified yet.
+    /// @dev Verified batches already removed theirs from the bridge queue.
+    /// @dev It is kept as a running count so that committing doesn't have to
+    /// go through every unverified batch.
+    uint256 public committedDepositLogs;
+
     /// @dev The sequencer addresses that are authorized to commit and verify blocks.
     mapping(address _authorizedAddress => bool)
         public authorizedSequencerAddresses;
@@ -84,7 +108,8 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
         address r0verifier,
         address sp1verifier,
         address picoverifier,
-        address[] calldata sequencerAddresses
+        address[] calldata sequencerAddresses,
+        bytes32 genesisStateRoot
     ) public nonReentrant {
         // Set the CommonBridge address
         require(
@@ -149,11 +174,20 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
         for (uint256 i = 0; i < sequencerAddresses.length; i++) {
             authorizedSequencerAddresses[sequencerAddresses[i]] = true;
         }
+
+        // The genesis block is batch 0, the first batch is proven on top of it
+        require(
+            genesisStateRoot != bytes32(0),
+            "OnChainProposer: genesisStateRoot is zero"
+        );
+        batchCommitments[0].newStateRoot = genesisStateRoot;
     }
 
     /// @inheritdoc IOnChainProposer
     function commit(
-        uint256 blockNumber,
+        uint256 batchNumber,
+        uint256 firstBlockNumber,
+        uint256 lastBlockNumber,
         bytes32 newStateRoot,
         bytes32 stateDiffKZGVersionedHash,
         bytes32 withdrawalsLogsMerkleRoot,
@@ -161,12 +195,20 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
     ) external override onlySequencer {
         // TODO: Refactor validation
         require(
-            blockNumber == lastCommittedBlock + 1,
-            "OnChainProposer: blockNumber is not the immediate successor of lastCommittedBlock"
+            batchNumber == lastCommittedBatch + 1,
+            "OnChainProposer: batchNumber is not the immediate successor of lastCommittedBatch"
         );
         require(
-            blockCommitments[blockNumber].newStateRoot == bytes32(0),
-            "OnChainProposer: tried to commit an already committed block"
+            batchCommitments[batchNumber].newStateRoot == bytes32(0),
+            "OnChainProposer: tried to commit an already committed batch"
+        );
+        require(
+            firstBlockNumber == lastCommittedBlock + 1,
+            "OnChainProposer: firstBlockNumber is not the immediate successor of lastCommittedBlock"
+        );
+        require(
+            lastBlockNumber >= firstBlockNumber,
+            "OnChainProposer: lastBlockNumber is lower than firstBlockNumber"
         );
 
         // Check if commitment is equivalent to blob's KZG commitment.
@@ -181,35 +223,46 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
                 "OnChainProposer: invalid deposit logs"
             );
         }
+        // Deposits and forced transactions queued for longer than the
+        // deadline must be included before any new batch is committed.
+        require(
+            !ICommonBridge(BRIDGE).hasExpiredDepositLogs(
+                committedDepositLogs +
+                    uint16(bytes2(processedDepositLogsRollingHash))
+            ),
+            "OnChainProposer: batch skips expired forced transactions"
+        );
         if (withdrawalsLogsMerkleRoot != bytes32(0)) {
             ICommonBridge(BRIDGE).publishWithdrawals(
-                blockNumber,
+                batchNumber,
                 withdrawalsLogsMerkleRoot
             );
         }
 
-        blockCommitments[blockNumber] = BlockCommitmentInfo(
+        batchCommitments[batchNumber] = BatchCommitmentInfo(
+            firstBlockNumber,
+            lastBlockNumber,
             newStateRoot,
             stateDiffKZGVersionedHash,
-            processedDepositLogsRollingHash
+            processedDepositLogsRollingHash,
+            withdrawalsLogsMerkleRoot
         );
-        emit BlockCommitted(newStateRoot);
+        emit BatchCommitted(batchNumber, newStateRoot);
 
-        lastCommittedBlock = blockNumber;
+        lastCommittedBatch = batchNumber;
+        lastCommittedBlock = lastBlockNumber;
+        committedDepositLogs += uint16(bytes2(processedDepositLogsRollingHash));
     }
 
     /// @inheritdoc IOnChainProposer
-    /// @notice The first `require` checks that the block number is the subsequent block.
-    /// @notice The second `require` checks if the block has been committed.
-    /// @notice The order of these `require` statements is important.
-    /// Ordering Reason: After the verification process, we delete the `blockCommitments` for `blockNumber - 1`. This means that when checking the block,
-    /// we might get an error indicating that the block hasn’t been committed, even though it was committed but deleted. Therefore, it has already been verified.
+    /// @notice The first `require` checks that the batch number is the subsequent batch.
+    /// @notice The second `require` checks if the batch has been committed.
     function verify(
-        uint256 blockNumber,
+        uint256 batchNumber,
         //risc0
         bytes calldata risc0BlockProof,
         bytes32 risc0ImageId,
-        bytes32 risc0JournalDigest,
+        bytes calldata risc0Journal,
         //sp1
         bytes32 sp1ProgramVKey,
         bytes calldata sp1PublicValues,
@@ -223,12 +276,12 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
         // TODO: imageid, programvkey and riscvvkey should be constants
         // TODO: organize each zkvm proof arguments in their own structs
         require(
-            blockNumber == lastVerifiedBlock + 1,
-            "OnChainProposer: block already verified"
+            batchNumber == lastVerifiedBatch + 1,
+            "OnChainProposer: batch already verified"
         );
         require(
-            blockCommitments[blockNumber].newStateRoot != bytes32(0),
-            "OnChainProposer: cannot verify an uncommitted block"
+            batchCommitments[batchNumber].newStateRoot != bytes32(0),
+            "OnChainProposer: cannot verify an uncommitted batch"
         );
 
         if (PICOVERIFIER != DEV_MODE) {
@@ -238,6 +291,7 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
                 picoPublicValues,
                 picoProof
             );
+            _verifyPublicInputs(batchNumber, picoPublicValues);
         }
 
         if (R0VERIFIER != DEV_MODE) {
@@ -245,8 +299,9 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
             IRiscZeroVerifier(R0VERIFIER).verify(
                 risc0BlockProof,
                 risc0ImageId,
-                risc0JournalDigest
+                sha256(risc0Journal)
             );
+            _verifyPublicInputs(batchNumber, risc0Journal);
         }
 
         if (SP1VERIFIER != DEV_MODE) {
@@ -256,22 +311,58 @@ contract OnChainProposer is IOnChainProposer, ReentrancyGuard {
                 sp1PublicValues,
                 sp1ProofBytes
             );
+            _verifyPublicInputs(batchNumber, sp1PublicValues);
         }
 
-        lastVerifiedBlock = blockNumber;
+        lastVerifiedBatch = batchNumber;
+        lastVerifiedBlock = batchCommitments[batchNumber].lastBlockNumber;
 
         // The first 2 bytes are the number of deposits.
         uint16 deposits_amount = uint16(
             bytes2(
-                blockCommitments[blockNumber].processedDepositLogsRollingHash
+                batchCommitments[batchNumber].processedDepositLogsRollingHash
             )
         );
         if (deposits_amount > 0) {
             ICommonBridge(BRIDGE).removePendingDepositLogs(deposits_amount);
+            committedDepositLogs -= deposits_amount;
         }
-        // Remove previous block commitment as it is no longer needed.
-        delete blockCommitments[blockNumber - 1];
 
-        emit BlockVerified(blockNumber);
+        emit BatchVerified(batchNumber);
+    }
+
+    /// @notice Checks that the proven state transition, withdrawals and
+    /// deposits match the ones committed for the batch.
+    /// @dev The public inputs start with the encoded ProgramOutput of the
+    /// zkVM program: initial state root, final state root, withdrawals
+    /// Merkle root and deposit logs hash, 32 bytes each.
+    function _verifyPublicInputs(
+        uint256 batchNumber,
+        bytes calldata publicInputs
+    ) internal view {
+        require(
+            publicInputs.length >= 128,
+            "OnChainProposer: invalid public inputs length"
+        );
+        BatchCommitmentInfo storage commitment = batchCommitments[batchNumber];
+        require(
+            bytes32(publicInputs[0:32]) ==
+                batchCommitments[batchNumber - 1].newStateRoot,
+            "OnChainProposer: initial state root public input does not match with previous batch state root"
+        );
+        require(
+            bytes32(publicInputs[32:64]) == commitment.newStateRoot,
+            "OnChainProposer: final state root public input does not match with committed state root"
+        );
+        require(
+            bytes32(publicInputs[64:96]) ==
+                commitment.withdrawalsLogsMerkleRoot,
+            "OnChainProposer: withdrawals public input does not match with committed withdrawals"
+        );
+        require(
+            bytes32(publicInputs[96:128]) ==
+                commitment.processedDepositLogsRollingHash,
+            "OnChainProposer: deposits public input does not match with committed deposits"
+        );
     }
 }
@@ -30,13 +30,27 @@ interface ICommonBridge {
         bytes32 l2MintTxHash
     );
 
+    /// @notice A deposit of ERC20 tokens to L2 has initiated.
+    /// @dev Event emitted along the DepositInitiated of the L2 transaction
+    /// that mints the bridged tokens.
+    /// @param tokenL1 the address of the token on L1.
+    /// @param from the address that initiated the deposit.
+    /// @param recipient the address that will receive the tokens on L2.
+    /// @param amount the amount of tokens being deposited.
+    event ERC20DepositInitiated(
+        address indexed tokenL1,
+        address indexed from,
+        address indexed recipient,
+        uint256 amount
+    );
+
     /// @notice L2 withdrawals have been published on L1.
     /// @dev Event emitted when the L2 withdrawals are published on L1.
-    /// @param withdrawalLogsBlockNumber the block number in L2 where the
-    /// withdrawal logs were emitted.
+    /// @param withdrawalLogsBatchNumber the number of the L2 batch whose
+    /// blocks emitted the withdrawal logs.
     /// @param withdrawalsLogsMerkleRoot the merkle root of the withdrawal logs.
     event WithdrawalsPublished(
-        uint256 indexed withdrawalLogsBlockNumber,
+        uint256 indexed withdrawalLogsBatchNumber,
         bytes32 indexed withdrawalsLogsMerkleRoot
     );
 
@@ -51,6 +65,19 @@ interface ICommonBridge {
         uint256 indexed claimedAmount
     );
 
+    /// @notice An ERC20 withdrawal has been claimed.
+    /// @dev Event emitted when an ERC20 withdrawal is claimed.
+    /// @param tokenL1 the address of the token on L1.
+    /// @param l2WithdrawalTxHash the hash of the L2 withdrawal transaction.
+    /// @param claimee the address that claimed the withdrawal.
+    /// @param claimedAmount the amount of tokens that was claimed.
+    event ERC20WithdrawalClaimed(
+        address indexed tokenL1,
+        bytes32 indexed l2WithdrawalTxHash,
+        address indexed claimee,
+        uint256 claimedAmount
+    );
+
     struct DepositValues {
         address to;
         address recipient;
@@ -76,6 +103,44 @@ interface ICommonBridge {
     /// @param depositValues the values needed to create the deposit.
     function deposit(DepositValues calldata depositValues) external payable;
 
+    /// @notice Method that queues an arbitrary L2 transaction from the sender.
+    /// @dev The transaction goes through the same queue as deposits, so the
+    /// sequencer must include it before FORCED_INCLUSION_DEADLINE expires or
+    /// it won't be able to commit new batches. Only EOAs can send them, as
+    /// the address of an L1 contract isn't controlled by the same party on L2.
+    /// @dev The sent value must cover gasLimit times the L1 base fee, the
+    /// rest is minted to the recipient. The gas of the forced transactions
+    /// queued in a single L1 block is capped.
+    /// @param depositValues the values of the L2 transaction.
+    function sendForcedTransaction(
+        DepositValues calldata depositValues
+    ) external payable;
+
+    /// @notice Checks if the pending deposit logs queue has an expired entry
+    /// after the first `includedLogs`.
+    /// @dev This method is used by the L2 OnChainOperator at the commit stage,
+    /// to reject batches that skip expired deposits or forced transactions.
+    /// As the queue is ordered, only the first non-included log is checked.
+    /// @param includedLogs number of pending deposit logs included in the
+    /// committed batches.
+    function hasExpiredDepositLogs(
+        uint256 includedLogs
+    ) external view returns (bool);
+
+    /// @notice Method that starts an L2 ERC20 deposit process.
+    /// @dev The tokens are locked in the bridge and a deposit to the
+    /// CommonBridgeL2 is initiated, which mints them on its canonical
+    /// BridgedERC20. The sender must have approved the bridge to transfer
+    /// the amount.
+    /// @param tokenL1 the address of the token on L1.
+    /// @param recipient the address that will receive the tokens on L2.
+    /// @param amount the amount of tokens to deposit.
+    function depositERC20(
+        address tokenL1,
+        address recipient,
+        uint256 amount
+    ) external;
+
     /// @notice Method to retrieve the versioned hash of the first `number`
     /// pending deposit logs.
     /// @param number of pending deposit logs to retrieve the versioned hash.
@@ -92,31 +157,31 @@ interface ICommonBridge {
     function removePendingDepositLogs(uint16 number) external;
 
     /// @notice Method to retrieve the merkle root of the withdrawal logs of a
-    /// given block.
+    /// given batch.
     /// @dev This method is used by the L2 OnChainOperator at the verify stage.
-    /// @param blockNumber the block number in L2 where the withdrawal logs were
-    /// emitted.
-    /// @return the merkle root of the withdrawal logs of the given block.
+    /// @param batchNumber the number of the L2 batch whose blocks emitted the
+    /// withdrawal logs.
+    /// @return the merkle root of the withdrawal logs of the given batch.
     function getWithdrawalLogsMerkleRoot(
-        uint256 blockNumber
+        uint256 batchNumber
     ) external view returns (bytes32);
 
     /// @notice Publishes the L2 withdrawals on L1.
     /// @dev This method is used by the L2 OnChainOperator to publish the L2
-    /// withdrawals when an L2 block is committed.
-    /// @param withdrawalLogsBlockNumber the block number in L2 where the
-    /// withdrawal logs were emitted.
+    /// withdrawals when an L2 batch is committed.
+    /// @param withdrawalLogsBatchNumber the number of the L2 batch whose
+    /// blocks emitted the withdrawal logs.
     /// @param withdrawalsLogsMerkleRoot the merkle root of the withdrawal logs.
     function publishWithdrawals(
-        uint256 withdrawalLogsBlockNumber,
+        uint256 withdrawalLogsBatchNumber,
         bytes32 withdrawalsLogsMerkleRoot
     ) external;
 
     /// @notice Method that claims an L2 withdrawal.
     /// @dev For a user to claim a withdrawal, this method verifies:
-    /// - The l2WithdrawalBlockNumber was committed. If the given block was not
+    /// - The l2WithdrawalBatchNumber was committed. If the given batch was not
     /// committed, this means that the withdrawal was not published on L1.
-    /// - The l2WithdrawalBlockNumber was verified. If the given block was not
+    /// - The l2WithdrawalBatchNumber was verified. If the given batch was not
     /// verified, this means that the withdrawal claim was not enabled.
     /// - The withdrawal was not claimed yet. This is to avoid double claims.
     /// - The withdrawal proof is valid. This is, there exists a merkle path
@@ -128,16 +193,35 @@ interface ICommonBridge {
     /// @param l2WithdrawalTxHash the hash of the L2 withdrawal transaction.
     /// @param claimedAmount the amount that will be claimed.
     /// @param withdrawalProof the merkle path to the withdrawal log.
-    /// @param withdrawalLogIndex the index of the withdrawal log in the block.
-    /// This is the index of the withdraw transaction relative to the block's
+    /// @param withdrawalLogIndex the index of the withdrawal log in the batch.
+    /// This is the index of the withdraw transaction relative to the batch's
     /// withdrawal transctions.
-    /// A pseudocode would be [tx if tx is withdrawx for tx in block.txs()].index(leaf_tx).
-    /// @param l2WithdrawalBlockNumber the block number where the withdrawal log
-    /// was emitted.
+    /// A pseudocode would be [tx if tx is withdrawx for block in batch for tx in block.txs()].index(leaf_tx).
+    /// @param l2WithdrawalBatchNumber the number of the batch that contains
+    /// the block where the withdrawal log was emitted.
     function claimWithdrawal(
         bytes32 l2WithdrawalTxHash,
         uint256 claimedAmount,
-        uint256 l2WithdrawalBlockNumber,
+        uint256 l2WithdrawalBatchNumber,
+        uint256 withdrawalLogIndex,
+        bytes32[] calldata withdrawalProof
+    ) external;
+
+    /// @notice Method that claims an L2 ERC20 withdrawal.
+    /// @dev It makes the same checks as `claimWithdrawal`, with the token
+    /// address being part of the withdrawal log.
+    /// @param tokenL1 the address of the token on L1.
+    /// @param l2WithdrawalTxHash the hash of the L2 withdrawal transaction.
+    /// @param claimedAmount the amount of tokens that will be claimed.
+    /// @param l2WithdrawalBatchNumber the number of the batch that contains
+    /// the block where the withdrawal log was emitted.
+    /// @param withdrawalLogIndex the index of the withdrawal log in the batch.
+    /// @param withdrawalProof the merkle path to the withdrawal log.
+    function claimERC20Withdrawal(
+        address tokenL1,
+        bytes32 l2WithdrawalTxHash,
+        uint256 claimedAmount,
+        uint256 l2WithdrawalBatchNumber,
         uint256 withdrawalLogIndex,
         bytes32[] calldata withdrawalProof
     ) external;
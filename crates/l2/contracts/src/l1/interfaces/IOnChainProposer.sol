@@ -4,24 +4,36 @@ pragma solidity =0.8.29;
 /// @title Interface for the OnChainProposer contract.
 /// @author LambdaClass
 /// @notice A OnChainProposer contract ensures the advancement of the L2. It is used
-/// by the proposer to commit blocks and verify block proofs.
+/// by the proposer to commit batches of blocks and verify batch proofs.
 interface IOnChainProposer {
-    /// @notice The latest committed block number.
+    /// @notice The latest committed batch number.
+    /// @return The latest committed batch number as a uint256.
+    function lastCommittedBatch() external view returns (uint256);
+
+    /// @notice The latest verified batch number.
+    /// @return The latest verified batch number as a uint256.
+    function lastVerifiedBatch() external view returns (uint256);
+
+    /// @notice The last block of the latest committed batch.
     /// @return The latest committed block number as a uint256.
     function lastCommittedBlock() external view returns (uint256);
 
-    /// @notice The latest verified block number.
+    /// @notice The last block of the latest verified batch.
     /// @return The latest verified block number as a uint256.
     function lastVerifiedBlock() external view returns (uint256);
 
-    /// @notice A block has been committed.
-    /// @dev Event emitted when a block is committed.
-    /// @param newStateRoot The new state root of the block that was committed.
-    event BlockCommitted(bytes32 indexed newStateRoot);
+    /// @notice A batch has been committed.
+    /// @dev Event emitted when a batch is committed.
+    /// @param batchNumber The number of the batch that was committed.
+    /// @param newStateRoot The new state root of the batch that was committed.
+    event BatchCommitted(
+        uint256 indexed batchNumber,
+        bytes32 indexed newStateRoot
+    );
 
-    /// @notice A block has been verified.
-    /// @dev Event emitted when a block is verified.
-    event BlockVerified(uint256 indexed blockNumber);
+    /// @notice A batch has been verified.
+    /// @dev Event emitted when a batch is verified.
+    event BatchVerified(uint256 indexed batchNumber);
 
     /// @notice Initializes the contract.
     /// @dev This method is called only once after the contract is deployed.
@@ -29,40 +41,49 @@ interface IOnChainProposer {
     /// @param bridge the address of the bridge contract.
     /// @param r0verifier the address of the risc0 groth16 verifier.
     /// @param sp1verifier the address of the sp1 groth16 verifier.
+    /// @param genesisStateRoot the state root of the L2 genesis block.
     function initialize(
         address bridge,
         address r0verifier,
         address sp1verifier,
         address picoverifier,
-        address[] calldata sequencerAddress
+        address[] calldata sequencerAddress,
+        bytes32 genesisStateRoot
     ) external;
 
-    /// @notice Commits to an L2 block.
-    /// @dev Committing to an L2 block means to store the block's commitment
+    /// @notice Commits to a batch of L2 blocks.
+    /// @dev Committing to a batch means to store the batch's commitment
     /// and to publish withdrawals if any.
-    /// @param blockNumber the number of the block to be committed.
-    /// @param newStateRoot the new state root of the block to be committed.
-    /// @param stateDiffKZGVersionedHash of the block to be committed.
+    /// @param batchNumber the number of the batch to be committed.
+    /// @param firstBlockNumber the number of the first block of the batch.
+    /// @param lastBlockNumber the number of the last block of the batch.
+    /// @param newStateRoot the state root after the last block of the batch.
+    /// @param stateDiffKZGVersionedHash of the batch to be committed.
     /// @param withdrawalsLogsMerkleRoot the merkle root of the withdrawal logs
-    /// of the block to be committed.
+    /// of all the blocks of the batch.
     /// @param processedDepositLogsRollingHash the rolling hash of the processed
-    /// deposits logs of the block to be committed.
+    /// deposits logs of all the blocks of the batch.
     function commit(
-        uint256 blockNumber,
+        uint256 batchNumber,
+        uint256 firstBlockNumber,
+        uint256 lastBlockNumber,
         bytes32 newStateRoot,
         bytes32 stateDiffKZGVersionedHash,
         bytes32 withdrawalsLogsMerkleRoot,
         bytes32 processedDepositLogsRollingHash
     ) external;
 
-    /// @notice Method used to verify an L2 block proof.
-    /// @dev This method is used by the operator when a block is ready to be
+    /// @notice Method used to verify an L2 batch proof.
+    /// @dev This method is used by the operator when a batch is ready to be
     /// verified (this is after proved).
-    /// @param blockNumber is the number of the block to be verified.
+    /// @dev The withdrawals Merkle root and deposit logs hash in the public
+    /// inputs of each proof must match the ones committed for the batch.
+    /// @param batchNumber is the number of the batch to be verified.
     /// ----------------------------------------------------------------------
-    /// @param risc0BlockProof is the proof of the block to be verified.
+    /// @param risc0BlockProof is the proof of the batch to be verified.
     /// @param risc0ImageId Digest of the zkVM imageid.
-    /// @param risc0JournalDigest Digest of the public_inputs aka journal
+    /// @param risc0Journal public_inputs aka journal, its digest is checked by
+    /// the verifier
     /// ----------------------------------------------------------------------
     /// @param sp1ProgramVKey Public verifying key
     /// @param sp1PublicValues Values used to perform the execution
@@ -72,11 +93,11 @@ interface IOnChainProposer {
     /// @param picoPublicValues Values used to perform the execution
     /// @param picoProof Groth16 proof
     function verify(
-        uint256 blockNumber,
+        uint256 batchNumber,
         //risc0
         bytes calldata risc0BlockProof,
         bytes32 risc0ImageId,
-        bytes32 risc0JournalDigest,
+        bytes calldata risc0Journal,
         //sp1
         bytes32 sp1ProgramVKey,
         bytes calldata sp1PublicValues,
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MIT
+pragma solidity =0.8.29;
+
+import "../../lib/openzeppelin-contracts/contracts/token/ERC20/ERC20.sol";
+
+/// @title BridgedERC20 contract.
+/// @author LambdaClass
+/// @notice Canonical L2 representation of an L1 ERC20 token.
+/// @dev It is deployed by the CommonBridgeL2 on the first deposit of the L1
+/// token, which is the only one allowed to mint and burn it.
+contract BridgedERC20 is ERC20 {
+    /// @notice Address of the token on L1.
+    address public immutable L1_TOKEN;
+
+    /// @notice Address of the CommonBridgeL2 that deployed the token.
+    address public immutable BRIDGE;
+
+    uint8 private immutable DECIMALS;
+
+    modifier onlyBridge() {
+        require(msg.sender == BRIDGE, "BridgedERC20: caller is not the bridge");
+        _;
+    }
+
+    constructor(
+        address l1Token,
+        string memory tokenName,
+        string memory tokenSymbol,
+        uint8 tokenDecimals
+    ) ERC20(tokenName, tokenSymbol) {
+        L1_TOKEN = l1Token;
+        BRIDGE = msg.sender;
+        DECIMALS = tokenDecimals;
+    }
+
+    /// @notice Same decimals as the L1 token.
+    function decimals() public view override returns (uint8) {
+        return DECIMALS;
+    }
+
+    /// @notice Mints the tokens of a finalized deposit.
+    function mint(address to, uint256 amount) external onlyBridge {
+        _mint(to, amount);
+    }
+
+    /// @notice Burns the tokens of an initiated withdrawal.
+    function burn(address from, uint256 amount) external onlyBridge {
+        _burn(from, amount);
+    }
+}
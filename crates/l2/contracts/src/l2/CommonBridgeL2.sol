@@ -4,6 +4,7 @@ pragma solidity =0.8.29;
 import "../../lib/openzeppelin-contracts/contracts/access/Ownable.sol";
 import "../../lib/openzeppelin-contracts/contracts/utils/ReentrancyGuard.sol";
 import "./interfaces/ICommonBridgeL2.sol";
+import {BridgedERC20} from "./BridgedERC20.sol";
 
 /// @title CommonBridge L2 contract.
 /// @author LambdaClass
@@ -11,6 +12,21 @@ contract CommonBridgeL2 is ICommonBridgeL2 {
     address public constant BURN_ADDRESS =
         0x0000000000000000000000000000000000000000;
 
+    /// @notice Canonical L2 token of each bridged L1 token.
+    /// @dev The key is the address of the token on L1.
+    /// @dev The value is the BridgedERC20 deployed on its first deposit.
+    mapping(address => address) public bridgedTokens;
+
+    /// @dev ERC20 deposits are privileged transactions sent by the L1
+    /// CommonBridge with this contract as sender, which no one else can use.
+    modifier onlySelf() {
+        require(
+            msg.sender == address(this),
+            "CommonBridgeL2: caller is not the bridge"
+        );
+        _;
+    }
+
     function withdraw(address _receiverOnL1) external payable {
             require(msg.value > 0, "Withdrawal amount must be positive");
 
@@ -21,4 +37,54 @@ contract CommonBridgeL2 is ICommonBridgeL2 {
             // them on every block.
             emit WithdrawalInitiated(msg.sender, _receiverOnL1, msg.value);
     }
+
+    /// @inheritdoc ICommonBridgeL2
+    function mintERC20(
+        address tokenL1,
+        address recipient,
+        uint256 amount,
+        string calldata name,
+        string calldata symbol,
+        uint8 decimals
+    ) external onlySelf {
+        address tokenL2 = bridgedTokens[tokenL1];
+        if (tokenL2 == address(0)) {
+            // The salt makes the token address depend only on the L1 token
+            tokenL2 = address(
+                new BridgedERC20{salt: bytes32(uint256(uint160(tokenL1)))}(
+                    tokenL1,
+                    name,
+                    symbol,
+                    decimals
+                )
+            );
+            bridgedTokens[tokenL1] = tokenL2;
+            emit BridgedTokenDeployed(tokenL1, tokenL2);
+        }
+        BridgedERC20(tokenL2).mint(recipient, amount);
+    }
+
+    /// @inheritdoc ICommonBridgeL2
+    function withdrawERC20(
+        address tokenL1,
+        address receiverOnL1,
+        uint256 amount
+    ) external {
+        require(amount > 0, "Withdrawal amount must be positive");
+        address tokenL2 = bridgedTokens[tokenL1];
+        require(
+            tokenL2 != address(0),
+            "CommonBridgeL2: token was never bridged"
+        );
+
+        BridgedERC20(tokenL2).burn(msg.sender, amount);
+
+        // Like ETH withdrawals, this event gets pushed to L1.
+        emit ERC20WithdrawalInitiated(
+            tokenL1,
+            msg.sender,
+            receiverOnL1,
+            amount
+        );
+    }
 }
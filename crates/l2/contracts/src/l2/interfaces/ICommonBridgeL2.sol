@@ -17,10 +17,62 @@ interface ICommonBridgeL2 {
         uint256 indexed amount
     );
 
+    /// @notice A withdrawal of ERC20 tokens to L1 has initiated.
+    /// @dev Event emitted when an ERC20 withdrawal is initiated.
+    /// @param tokenL1 the address of the token on L1.
+    /// @param senderOnL2 the sender of the transaction on L2.
+    /// @param receiverOnL1 the address on L1 that will receive the tokens back.
+    /// @param amount the amount of tokens being withdrawn.
+    event ERC20WithdrawalInitiated(
+        address indexed tokenL1,
+        address indexed senderOnL2,
+        address indexed receiverOnL1,
+        uint256 amount
+    );
+
+    /// @notice The canonical L2 token of an L1 token has been deployed.
+    /// @param tokenL1 the address of the token on L1.
+    /// @param tokenL2 the address of its BridgedERC20 on L2.
+    event BridgedTokenDeployed(
+        address indexed tokenL1,
+        address indexed tokenL2
+    );
+
     /// @notice Initiates the withdrawal of funds to the L1.
     /// @dev This is the first step in the two step process of a user withdrawal.
     /// @dev It burns funds on L2 and sends a message to the L1 so users
     /// @dev can claim those funds on L1.
     /// @param _receiverOnL1 the address that can claim the funds on L1.
     function withdraw(address _receiverOnL1) external payable;
+
+    /// @notice Finalizes an ERC20 deposit made on the L1 CommonBridge.
+    /// @dev Only callable by the deposit transaction, whose sender is this
+    /// contract. The canonical token is deployed on the first deposit of
+    /// the L1 token, using the given metadata.
+    /// @param tokenL1 the address of the token on L1.
+    /// @param recipient the address that receives the tokens on L2.
+    /// @param amount the amount of tokens deposited.
+    /// @param name the name of the L1 token.
+    /// @param symbol the symbol of the L1 token.
+    /// @param decimals the decimals of the L1 token.
+    function mintERC20(
+        address tokenL1,
+        address recipient,
+        uint256 amount,
+        string calldata name,
+        string calldata symbol,
+        uint8 decimals
+    ) external;
+
+    /// @notice Initiates the withdrawal of ERC20 tokens to the L1.
+    /// @dev It burns the bridged tokens on L2 and sends a message to the L1
+    /// so they can be claimed there.
+    /// @param tokenL1 the address of the token on L1.
+    /// @param receiverOnL1 the address that can claim the tokens on L1.
+    /// @param amount the amount of tokens to withdraw.
+    function withdrawERC20(
+        address tokenL1,
+        address receiverOnL1,
+        uint256 amount
+    ) external;
 }
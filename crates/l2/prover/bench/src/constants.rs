@@ -39,7 +39,8 @@ pub static ref CANCUN_CONFIG: ChainConfig = ChainConfig {
     verkle_time: None,
     blob_schedule: BlobSchedule::default(),
     // Mainnet address
-    deposit_contract_address: H160::from_str("0x00000000219ab540356cbb839cbe05303d7705fa").expect("Invalid deposit contract address")
+    deposit_contract_address: H160::from_str("0x00000000219ab540356cbb839cbe05303d7705fa").expect("Invalid deposit contract address"),
+    l1_fee_vault: None,
 };
 }
 pub const MAINNET_CHAIN_ID: u64 = 0x1;
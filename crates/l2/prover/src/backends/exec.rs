@@ -5,9 +5,16 @@ use ethrex_vm::Evm;
 use tracing::warn;
 use zkvm_interface::{
     io::{ProgramInput, ProgramOutput},
+    messages::{
+        compute_deposit_logs_hash, compute_withdrawals_merkle_root, get_block_deposit_hashes,
+        get_block_withdrawal_hashes,
+    },
     trie::{update_tries, verify_db},
 };
 
+/// Proving system used by this backend
+pub const PROVER_TYPE: ProverType = ProverType::Exec;
+
 pub struct ProveOutput(pub ProgramOutput);
 
 pub fn execute(input: ProgramInput) -> Result<(), Box<dyn std::error::Error>> {
@@ -29,19 +36,20 @@ pub fn verify(_proof: &ProveOutput) -> Result<(), Box<dyn std::error::Error>> {
 pub fn to_calldata(proof: ProveOutput) -> Result<ProofCalldata, Box<dyn std::error::Error>> {
     let public_inputs = proof.0.encode();
     Ok(ProofCalldata {
-        prover_type: ProverType::Exec,
+        prover_type: PROVER_TYPE,
         calldata: vec![Value::Bytes(public_inputs.into())],
     })
 }
 
 fn execution_program(input: ProgramInput) -> Result<ProgramOutput, Box<dyn std::error::Error>> {
     let ProgramInput {
-        block,
+        blocks,
         parent_block_header,
         db,
     } = input;
-    // Validate the block
-    validate_block(&block, &parent_block_header, &db.chain_config)?;
+    let (Some(first_block), Some(last_block)) = (blocks.first(), blocks.last()) else {
+        return Err("no blocks to execute".to_string().into());
+    };
 
     // Tries used for validating initial and final state root
     let (mut state_trie, mut storage_tries) = db.get_tries()?;
@@ -54,25 +62,43 @@ fn execution_program(input: ProgramInput) -> Result<ProgramOutput, Box<dyn std::
     if !verify_db(&db, &state_trie, &storage_tries)? {
         return Err("invalid database".to_string().into());
     };
-    let fork = db.chain_config.fork(block.header.timestamp);
+    let fork = db.chain_config.fork(first_block.header.timestamp);
 
+    // Execute every block of the batch on top of the previous one
     let mut vm = Evm::from_execution_db(db.clone());
-    let result = vm.execute_block(&block)?;
-    let receipts = result.receipts;
+    let mut parent_header = &parent_block_header;
+    let mut withdrawal_hashes = Vec::new();
+    let mut deposit_hashes = Vec::new();
+    for block in &blocks {
+        validate_block(block, parent_header, &db.chain_config)?;
+        let result = vm.execute_block(block)?;
+        validate_gas_used(&result.receipts, &block.header)?;
+        withdrawal_hashes.extend(get_block_withdrawal_hashes(
+            &block.body.transactions,
+            &result.receipts,
+        )?);
+        deposit_hashes.extend(get_block_deposit_hashes(&block.body.transactions));
+        parent_header = &block.header;
+    }
     let account_updates = vm.get_state_transitions(fork)?;
-    validate_gas_used(&receipts, &block.header)?;
 
     // Update state trie
     update_tries(&mut state_trie, &mut storage_tries, &account_updates)?;
 
     // Calculate final state root hash and check
     let final_state_hash = state_trie.hash_no_commit();
-    if final_state_hash != block.header.state_root {
+    if final_state_hash != last_block.header.state_root {
         return Err("invalid final state trie".to_string().into());
     }
 
+    // Calculate the withdrawals merkle root and deposits hash committed to the L1
+    let withdrawals_merkle_root = compute_withdrawals_merkle_root(&withdrawal_hashes);
+    let deposit_logs_hash = compute_deposit_logs_hash(&deposit_hashes)?;
+
     Ok(ProgramOutput {
         initial_state_hash,
         final_state_hash,
+        withdrawals_merkle_root,
+        deposit_logs_hash,
     })
 }
@@ -9,6 +9,9 @@ use thiserror::Error;
 use tracing::{info, warn};
 use zkvm_interface::{io::ProgramInput, methods::ZKVM_PICO_PROGRAM_ELF};
 
+/// Proving system used by this backend
+pub const PROVER_TYPE: ProverType = ProverType::Pico;
+
 #[derive(Debug, Error)]
 pub enum PicoBackendError {
     #[error("proof byte count ({0}) isn't the expected (256)")]
@@ -90,7 +93,7 @@ pub fn to_calldata(output: ProveOutput) -> Result<ProofCalldata, Box<dyn std::er
     let calldata = vec![Value::Bytes(public_values.into()), Value::FixedArray(proof)];
 
     Ok(ProofCalldata {
-        prover_type: ProverType::Pico,
+        prover_type: PROVER_TYPE,
         calldata,
     })
 }
@@ -1,15 +1,16 @@
 use ethrex_l2::utils::prover::proving_systems::{ProofCalldata, ProverType};
 use ethrex_l2_sdk::calldata::Value;
 use risc0_ethereum_contracts::encode_seal;
-use risc0_zkvm::{
-    default_executor, default_prover, sha::Digestible, ExecutorEnv, ProverOpts, Receipt,
-};
+use risc0_zkvm::{default_executor, default_prover, ExecutorEnv, ProverOpts, Receipt};
 use tracing::info;
 use zkvm_interface::{
     io::ProgramInput,
     methods::{ZKVM_RISC0_PROGRAM_ELF, ZKVM_RISC0_PROGRAM_ID},
 };
 
+/// Proving system used by this backend
+pub const PROVER_TYPE: ProverType = ProverType::RISC0;
+
 pub fn execute(input: ProgramInput) -> Result<(), Box<dyn std::error::Error>> {
     let env = ExecutorEnv::builder().write(&input)?.build()?;
 
@@ -46,7 +47,6 @@ pub fn verify(receipt: &Receipt) -> Result<(), Box<dyn std::error::Error>> {
 pub fn to_calldata(receipt: Receipt) -> Result<ProofCalldata, Box<dyn std::error::Error>> {
     let seal = encode_seal(&receipt)?;
     let image_id = ZKVM_RISC0_PROGRAM_ID;
-    let journal_digest = receipt.journal.digest().as_bytes().to_vec();
 
     // convert image_id into bytes
     let image_id = {
@@ -59,15 +59,15 @@ pub fn to_calldata(receipt: Receipt) -> Result<ProofCalldata, Box<dyn std::error
 
     // bytes calldata seal,
     // bytes32 imageId,
-    // bytes32 journalDigest
+    // bytes calldata journal
     let calldata = vec![
         Value::Bytes(seal.into()),
         Value::FixedBytes(image_id.into()),
-        Value::FixedBytes(journal_digest.into()),
+        Value::Bytes(receipt.journal.bytes.into()),
     ];
 
     Ok(ProofCalldata {
-        prover_type: ProverType::RISC0,
+        prover_type: PROVER_TYPE,
         calldata,
     })
 }
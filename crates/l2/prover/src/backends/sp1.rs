@@ -9,6 +9,9 @@ use sp1_sdk::{
 use tracing::info;
 use zkvm_interface::io::ProgramInput;
 
+/// Proving system used by this backend
+pub const PROVER_TYPE: ProverType = ProverType::SP1;
+
 static PROGRAM_ELF: &[u8] =
     include_bytes!("../../zkvm/interface/sp1/elf/riscv32im-succinct-zkvm-elf");
 
@@ -91,7 +94,7 @@ pub fn to_calldata(proof: ProveOutput) -> Result<ProofCalldata, Box<dyn std::err
     ];
 
     Ok(ProofCalldata {
-        prover_type: ProverType::SP1,
+        prover_type: PROVER_TYPE,
         calldata,
     })
 }
@@ -1,30 +1,39 @@
-use crate::{prove, to_calldata};
+use crate::{prove, to_calldata, PROVER_TYPE};
 use ethrex_l2::{
     sequencer::proof_coordinator::ProofData,
-    utils::{config::prover::ProverConfig, prover::proving_systems::ProofCalldata},
+    utils::{
+        config::prover::ProverConfig,
+        prover::{
+            protocol::{
+                read_challenge, read_message, write_message, SignedMessage, MAX_RESPONSE_SIZE,
+            },
+            proving_systems::ProofCalldata,
+        },
+    },
 };
+use secp256k1::SecretKey;
 use std::time::Duration;
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
-    time::sleep,
-};
+use tokio::{net::TcpStream, task::JoinHandle, time::sleep};
 use tracing::{debug, error, info, warn};
 use zkvm_interface::io::ProgramInput;
 
 pub async fn start_prover(config: ProverConfig) {
-    let prover_worker = Prover::new(config);
+    let mut prover_worker = Prover::new(config);
     prover_worker.start().await;
 }
 
 struct ProverData {
-    block_number: u64,
+    batch_number: u64,
     input: ProgramInput,
 }
 
 struct Prover {
     prover_server_endpoint: String,
     proving_time_ms: u64,
+    signer: SecretKey,
+    /// Interval at which heartbeats are sent while proving,
+    /// `None` until the prover is registered with the ProofCoordinator
+    heartbeat_interval: Option<Duration>,
 }
 
 impl Prover {
@@ -32,27 +41,46 @@ impl Prover {
         Self {
             prover_server_endpoint: config.prover_server_endpoint,
             proving_time_ms: config.proving_time_ms,
+            signer: config.prover_private_key,
+            heartbeat_interval: None,
         }
     }
 
-    pub async fn start(&self) {
+    pub async fn start(&mut self) {
         // Build the prover depending on the prover_type passed as argument.
         loop {
+            if self.heartbeat_interval.is_none() {
+                if let Err(e) = self.register().await {
+                    warn!("Failed to register: {e}");
+                    sleep(Duration::from_millis(self.proving_time_ms)).await;
+                    continue;
+                }
+            }
             match self.request_new_input().await {
                 // If we get the input
                 Ok(prover_data) => {
-                    // Generate the Proof
-                    match prove(prover_data.input).and_then(to_calldata) {
-                        Ok(proving_output) => {
+                    // Keep the lease of the batch while generating the Proof
+                    let heartbeats = self.send_heartbeats(prover_data.batch_number);
+                    let proving_output = tokio::task::spawn_blocking(move || {
+                        prove(prover_data.input)
+                            .and_then(to_calldata)
+                            .map_err(|e| e.to_string())
+                    })
+                    .await;
+                    heartbeats.abort();
+
+                    match proving_output {
+                        Ok(Ok(proving_output)) => {
                             if let Err(e) = self
-                                .submit_proof(prover_data.block_number, proving_output)
+                                .submit_proof(prover_data.batch_number, proving_output)
                                 .await
                             {
                                 // TODO: Retry?
                                 warn!("Failed to submit proof: {e}");
                             }
                         }
-                        Err(e) => error!(e),
+                        Ok(Err(e)) => error!("Failed to generate proof: {e}"),
+                        Err(e) => error!("Proving task failed: {e}"),
                     };
                 }
                 Err(e) => {
@@ -64,24 +92,83 @@ impl Prover {
         }
     }
 
-    async fn request_new_input(&self) -> Result<ProverData, String> {
-        // Request the input with the correct block_number
-        let request = ProofData::block_request();
-        let response = connect_to_prover_server_wr(&self.prover_server_endpoint, &request)
-            .await
-            .map_err(|e| format!("Failed to get Response: {e}"))?;
+    async fn register(&mut self) -> Result<(), String> {
+        let request = ProofData::prover_register(PROVER_TYPE);
+        match self.send_request(request).await? {
+            ProofData::ProverRegisterACK {
+                heartbeat_interval_ms,
+            } => {
+                info!("Registered as {PROVER_TYPE} prover");
+                self.heartbeat_interval = Some(Duration::from_millis(heartbeat_interval_ms));
+                Ok(())
+            }
+            _ => Err("Expecting ProofData::ProverRegisterACK".to_owned()),
+        }
+    }
+
+    /// Signs the request and sends it to the ProofCoordinator. If the request is rejected
+    /// the prover registers again before its next request, as the ProofCoordinator may
+    /// have been restarted.
+    async fn send_request(&mut self, data: ProofData) -> Result<ProofData, String> {
+        let response =
+            connect_to_prover_server_wr(&self.prover_server_endpoint, data, &self.signer)
+                .await
+                .map_err(|e| format!("Failed to get Response: {e}"))?;
+
+        if let ProofData::Rejected { reason } = response {
+            self.heartbeat_interval = None;
+            return Err(format!("Request rejected: {reason}"));
+        }
+        Ok(response)
+    }
+
+    /// Spawns a task that renews the lease of the batch until it is aborted
+    fn send_heartbeats(&self, batch_number: u64) -> JoinHandle<()> {
+        let endpoint = self.prover_server_endpoint.clone();
+        let signer = self.signer;
+        let interval = self
+            .heartbeat_interval
+            .unwrap_or(Duration::from_millis(self.proving_time_ms));
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                let response = connect_to_prover_server_wr(
+                    &endpoint,
+                    ProofData::heartbeat(batch_number),
+                    &signer,
+                )
+                .await
+                .map_err(|e| e.to_string());
+                match response {
+                    Ok(ProofData::HeartbeatACK { renewed: true, .. }) => {
+                        debug!("Renewed lease of batch {batch_number}")
+                    }
+                    Ok(ProofData::HeartbeatACK { renewed: false, .. }) => {
+                        warn!("Lost the lease of batch {batch_number}, it may be proven by another prover")
+                    }
+                    Ok(_) => warn!("Expecting ProofData::HeartbeatACK"),
+                    Err(e) => warn!("Failed to send heartbeat: {e}"),
+                }
+            }
+        })
+    }
+
+    async fn request_new_input(&mut self) -> Result<ProverData, String> {
+        // Request the input with the correct batch_number
+        let request = ProofData::batch_request();
+        let response = self.send_request(request).await?;
 
         match response {
-            ProofData::BlockResponse {
-                block_number,
+            ProofData::BatchResponse {
+                batch_number,
                 input,
-            } => match (block_number, input) {
-                (Some(block_number), Some(input)) => {
-                    info!("Received Response for block_number: {block_number}");
+            } => match (batch_number, input) {
+                (Some(batch_number), Some(input)) => {
+                    info!("Received Response for batch_number: {batch_number}");
                     let prover_data = ProverData{
-                        block_number,
+                        batch_number,
                         input:  ProgramInput {
-                            block: input.block,
+                            blocks: input.blocks,
                             parent_block_header: input.parent_block_header,
                             db: input.db
                         }
@@ -89,7 +176,7 @@ impl Prover {
                     Ok(prover_data)
                 }
                 _ => Err(
-                    "Received Empty Response, meaning that the ProverServer doesn't have blocks to prove.\nThe Prover may be advancing faster than the Proposer."
+                    "Received Empty Response, meaning that the ProverServer doesn't have batches to prove.\nThe Prover may be advancing faster than the Proposer."
                         .to_owned(),
                 ),
             },
@@ -98,19 +185,20 @@ impl Prover {
     }
 
     async fn submit_proof(
-        &self,
-        block_number: u64,
+        &mut self,
+        batch_number: u64,
         proving_output: ProofCalldata,
     ) -> Result<(), String> {
-        let submit = ProofData::proof_submit(block_number, proving_output);
+        let submit = ProofData::proof_submit(batch_number, proving_output);
 
-        let submit_ack = connect_to_prover_server_wr(&self.prover_server_endpoint, &submit)
+        let submit_ack = self
+            .send_request(submit)
             .await
             .map_err(|e| format!("Failed to get SubmitAck: {e}"))?;
 
         match submit_ack {
-            ProofData::ProofSubmitACK { block_number } => {
-                info!("Received submit ack for block_number: {}", block_number);
+            ProofData::ProofSubmitACK { batch_number } => {
+                info!("Received submit ack for batch_number: {}", batch_number);
                 Ok(())
             }
             _ => Err("Expecting ProofData::SubmitAck".to_owned()),
@@ -118,21 +206,20 @@ impl Prover {
     }
 }
 
+/// Sends the request signed with the challenge of a new connection to the ProofCoordinator
+/// and reads its response
 async fn connect_to_prover_server_wr(
     addr: &str,
-    write: &ProofData,
+    data: ProofData,
+    signer: &SecretKey,
 ) -> Result<ProofData, Box<dyn std::error::Error>> {
     debug!("Connecting with {addr}");
     let mut stream = TcpStream::connect(addr).await?;
     debug!("Connection established!");
 
-    stream.write_all(&serde_json::to_vec(&write)?).await?;
-    stream.shutdown().await?;
-
-    let mut buffer = Vec::new();
-    stream.read_to_end(&mut buffer).await?;
-    debug!("Got response {}", hex::encode(&buffer));
-
-    let response: Result<ProofData, _> = serde_json::from_slice(&buffer);
-    Ok(response?)
+    let challenge = read_challenge(&mut stream).await?;
+    let request = SignedMessage::sign(data, challenge, signer)?;
+    write_message(&mut stream, &request).await?;
+    let response = read_message(&mut stream, MAX_RESPONSE_SIZE).await?;
+    Ok(response)
 }
@@ -65,12 +65,12 @@ async fn setup() -> (ProgramInput, Block) {
         .unwrap()
         .unwrap();
 
-    let db = Evm::to_execution_db(&store.clone(), block_to_prove)
+    let db = Evm::to_execution_db(&store.clone(), &[block_to_prove.clone()])
         .await
         .unwrap();
 
     let input = ProgramInput {
-        block: block_to_prove.clone(),
+        blocks: vec![block_to_prove.clone()],
         parent_block_header,
         db,
     };
@@ -1,11 +1,15 @@
 #![no_main]
 
-use pico_sdk::io::{commit, read_as};
+use pico_sdk::io::{commit_bytes, read_as};
 
 use ethrex_blockchain::{validate_block, validate_gas_used};
-use ethrex_vm::backends::revm::{REVM, db::EvmState};
+use ethrex_vm::Evm;
 use zkvm_interface::{
     io::{ProgramInput, ProgramOutput},
+    messages::{
+        compute_deposit_logs_hash, compute_withdrawals_merkle_root, get_block_deposit_hashes,
+        get_block_withdrawal_hashes,
+    },
     trie::{update_tries, verify_db},
 };
 
@@ -13,17 +17,14 @@ pico_sdk::entrypoint!(main);
 
 pub fn main() {
     let ProgramInput {
-        block,
+        blocks,
         parent_block_header,
         db,
     } = read_as();
-    let mut state = EvmState::from(db.clone());
-    let chain_config = state
-        .chain_config()
-        .expect("Failed to get chain config from state");
-
-    // Validate the block
-    validate_block(&block, &parent_block_header, &chain_config).expect("invalid block");
+    let (first_block, last_block) = match (blocks.first(), blocks.last()) {
+        (Some(first_block), Some(last_block)) => (first_block, last_block),
+        _ => panic!("no blocks to execute"),
+    };
 
     // Tries used for validating initial and final state root
     let (mut state_trie, mut storage_tries) = db
@@ -39,10 +40,27 @@ pub fn main() {
         panic!("invalid database")
     };
 
-    let result = REVM::execute_block(&block, &mut state).expect("failed to execute block");
-    let receipts = result.receipts;
-    let account_updates = REVM::get_state_transitions(&mut state);
-    validate_gas_used(&receipts, &block.header).expect("invalid gas used");
+    let fork = db.chain_config.fork(first_block.header.timestamp);
+
+    // Execute every block of the batch on top of the previous one
+    let mut evm = Evm::from_execution_db(db.clone());
+    let mut parent_header = &parent_block_header;
+    let mut withdrawal_hashes = Vec::new();
+    let mut deposit_hashes = Vec::new();
+    for block in &blocks {
+        validate_block(block, parent_header, &db.chain_config).expect("invalid block");
+        let result = evm.execute_block(block).expect("failed to execute block");
+        validate_gas_used(&result.receipts, &block.header).expect("invalid gas used");
+        withdrawal_hashes.extend(
+            get_block_withdrawal_hashes(&block.body.transactions, &result.receipts)
+                .expect("invalid withdrawal"),
+        );
+        deposit_hashes.extend(get_block_deposit_hashes(&block.body.transactions));
+        parent_header = &block.header;
+    }
+    let account_updates = evm
+        .get_state_transitions(fork)
+        .expect("failed to get state transitions");
 
     // Output gas for measurement purposes
     // let cumulative_gas_used = receipts
@@ -57,12 +75,21 @@ pub fn main() {
 
     // Calculate final state root hash and check
     let final_state_hash = state_trie.hash_no_commit();
-    if final_state_hash != block.header.state_root {
+    if final_state_hash != last_block.header.state_root {
         panic!("invalid final state trie");
     }
 
-    commit(&ProgramOutput {
+    // Calculate the withdrawals merkle root and deposits hash committed to the L1
+    let withdrawals_merkle_root = compute_withdrawals_merkle_root(&withdrawal_hashes);
+    let deposit_logs_hash =
+        compute_deposit_logs_hash(&deposit_hashes).expect("failed to hash deposits");
+
+    let output = ProgramOutput {
         initial_state_hash,
         final_state_hash,
-    });
+        withdrawals_merkle_root,
+        deposit_logs_hash,
+    };
+    // The public values hold the raw encoding so the L1 contract can read it
+    commit_bytes(&output.encode());
 }
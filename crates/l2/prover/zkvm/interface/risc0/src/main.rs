@@ -5,17 +5,23 @@ use ethrex_vm::Evm;
 
 use zkvm_interface::{
     io::{ProgramInput, ProgramOutput},
+    messages::{
+        compute_deposit_logs_hash, compute_withdrawals_merkle_root, get_block_deposit_hashes,
+        get_block_withdrawal_hashes,
+    },
     trie::{update_tries, verify_db},
 };
 
 fn main() {
     let ProgramInput {
-        block,
+        blocks,
         parent_block_header,
         db,
     } = env::read();
-    // Validate the block
-    validate_block(&block, &parent_block_header, &db.chain_config).expect("invalid block");
+    let (first_block, last_block) = match (blocks.first(), blocks.last()) {
+        (Some(first_block), Some(last_block)) => (first_block, last_block),
+        _ => panic!("no blocks to execute"),
+    };
 
     // Tries used for validating initial and final state root
     let (mut state_trie, mut storage_tries) = db
@@ -31,18 +37,33 @@ fn main() {
         panic!("invalid database")
     };
 
-    let fork = db.chain_config.fork(block.header.timestamp);
+    let fork = db.chain_config.fork(first_block.header.timestamp);
+
+    // Execute every block of the batch on top of the previous one
     let mut evm = Evm::from_execution_db(db.clone());
-    let result = evm.execute_block(&block).expect("failed to execute block");
-    let receipts = result.receipts;
+    let mut parent_header = &parent_block_header;
+    let mut cumulative_gas_used = 0;
+    let mut withdrawal_hashes = Vec::new();
+    let mut deposit_hashes = Vec::new();
+    for block in &blocks {
+        validate_block(block, parent_header, &db.chain_config).expect("invalid block");
+        let result = evm.execute_block(block).expect("failed to execute block");
+        validate_gas_used(&result.receipts, &block.header).expect("invalid gas used");
+        withdrawal_hashes.extend(
+            get_block_withdrawal_hashes(&block.body.transactions, &result.receipts)
+                .expect("invalid withdrawal"),
+        );
+        deposit_hashes.extend(get_block_deposit_hashes(&block.body.transactions));
+        cumulative_gas_used += result
+            .receipts
+            .last()
+            .map(|last_receipt| last_receipt.cumulative_gas_used)
+            .unwrap_or_default();
+        parent_header = &block.header;
+    }
     let account_updates = evm.get_state_transitions(fork).expect("failed to get state transitions");
-    validate_gas_used(&receipts, &block.header).expect("invalid gas used");
 
     // Output gas for measurement purposes
-    let cumulative_gas_used = receipts
-        .last()
-        .map(|last_receipt| last_receipt.cumulative_gas_used)
-        .unwrap_or_default();
     env::write(&cumulative_gas_used);
 
     // Update state trie
@@ -51,12 +72,21 @@ fn main() {
 
     // Calculate final state root hash and check
     let final_state_hash = state_trie.hash_no_commit();
-    if final_state_hash != block.header.state_root {
+    if final_state_hash != last_block.header.state_root {
         panic!("invalid final state trie");
     }
 
-    env::commit(&ProgramOutput {
+    // Calculate the withdrawals merkle root and deposits hash committed to the L1
+    let withdrawals_merkle_root = compute_withdrawals_merkle_root(&withdrawal_hashes);
+    let deposit_logs_hash =
+        compute_deposit_logs_hash(&deposit_hashes).expect("failed to hash deposits");
+
+    let output = ProgramOutput {
         initial_state_hash,
         final_state_hash,
-    });
+        withdrawals_merkle_root,
+        deposit_logs_hash,
+    };
+    // The journal holds the raw encoding so the L1 contract can read it
+    env::commit_slice(&output.encode());
 }
@@ -32,10 +32,10 @@ pub mod io {
     #[serde_as]
     #[derive(Serialize, Deserialize)]
     pub struct ProgramInput {
-        /// block to execute
+        /// blocks of the batch to execute, in order
         #[serde_as(as = "SerdeJSON")]
-        pub block: Block,
-        /// header of the previous block
+        pub blocks: Vec<Block>,
+        /// header of the block previous to the first one of the batch
         #[serde_as(as = "SerdeJSON")]
         pub parent_block_header: BlockHeader,
         /// database containing only the data necessary to execute
@@ -50,13 +50,21 @@ pub mod io {
         pub initial_state_hash: H256,
         /// final state trie root hash
         pub final_state_hash: H256,
+        /// merkle root of the withdrawals of the batch, zero if there are none
+        pub withdrawals_merkle_root: H256,
+        /// hash of the deposits processed in the batch, zero if there are none
+        pub deposit_logs_hash: H256,
     }
 
     impl ProgramOutput {
+        /// Encodes the output as the concatenation of its fields, this is the layout the
+        /// `OnChainProposer` contract expects for the public values of a proof.
         pub fn encode(&self) -> Vec<u8> {
             [
                 self.initial_state_hash.to_fixed_bytes(),
                 self.final_state_hash.to_fixed_bytes(),
+                self.withdrawals_merkle_root.to_fixed_bytes(),
+                self.deposit_logs_hash.to_fixed_bytes(),
             ]
             .concat()
         }
@@ -88,6 +96,175 @@ pub mod io {
     }
 }
 
+pub mod messages {
+    use ethrex_common::{
+        types::{Log, Receipt, Transaction, TxKind},
+        Address, H160, H256,
+    };
+    use keccak_hash::keccak;
+    use thiserror::Error;
+
+    /// Address of the `CommonBridge` contract on the L2
+    pub const COMMON_BRIDGE_L2_ADDRESS: Address = H160([
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0xff, 0xff,
+    ]);
+
+    /// keccak256("WithdrawalInitiated(address,address,uint256)")
+    const WITHDRAWAL_EVENT_SELECTOR: [u8; 32] = [
+        0xbb, 0x26, 0x89, 0xff, 0x87, 0x6f, 0x7e, 0xf4, 0x53, 0xcf, 0x88, 0x65, 0xdd, 0xe5, 0xab,
+        0x10, 0x34, 0x9d, 0x22, 0x2e, 0x2e, 0x13, 0x83, 0xc5, 0x15, 0x2f, 0xbd, 0xb0, 0x83, 0xf0,
+        0x2d, 0xa2,
+    ];
+
+    /// keccak256("ERC20WithdrawalInitiated(address,address,address,uint256)")
+    const ERC20_WITHDRAWAL_EVENT_SELECTOR: [u8; 32] = [
+        0x54, 0x53, 0x8b, 0x93, 0xc6, 0xe9, 0xb3, 0xf5, 0x18, 0x07, 0x6d, 0xb2, 0xd8, 0x96, 0x12,
+        0x2f, 0x65, 0x3f, 0xac, 0x2b, 0xb3, 0x2f, 0xa0, 0xb6, 0xbc, 0x75, 0x09, 0x7b, 0x9f, 0x33,
+        0x2e, 0x75,
+    ];
+
+    #[derive(Debug, Error)]
+    pub enum Error {
+        #[error("Withdrawal transaction {0:#x} has an invalid calldata")]
+        InvalidWithdrawalTransaction(H256),
+        #[error("ERC20 withdrawal transaction {0:#x} has an invalid log")]
+        InvalidERC20WithdrawalLog(H256),
+        #[error("Too many deposits in the batch: {0}")]
+        TooManyDeposits(usize),
+    }
+
+    /// Returns whether the transaction is a withdrawal, that is, a call to the L2 bridge that
+    /// emitted a `WithdrawalInitiated` or an `ERC20WithdrawalInitiated` event
+    pub fn is_withdrawal(tx: &Transaction, receipt: &Receipt) -> bool {
+        match tx.to() {
+            TxKind::Call(to) if to == COMMON_BRIDGE_L2_ADDRESS => receipt.logs.iter().any(|log| {
+                is_erc20_withdrawal_log(log)
+                    || log
+                        .topics
+                        .iter()
+                        .any(|topic| topic.0 == WITHDRAWAL_EVENT_SELECTOR)
+            }),
+            _ => false,
+        }
+    }
+
+    fn is_erc20_withdrawal_log(log: &Log) -> bool {
+        log.address == COMMON_BRIDGE_L2_ADDRESS
+            && log
+                .topics
+                .first()
+                .is_some_and(|topic| topic.0 == ERC20_WITHDRAWAL_EVENT_SELECTOR)
+    }
+
+    /// Returns the hashes of the withdrawals of a block, given its transactions and their
+    /// receipts in order
+    pub fn get_block_withdrawal_hashes(
+        transactions: &[Transaction],
+        receipts: &[Receipt],
+    ) -> Result<Vec<H256>, Error> {
+        transactions
+            .iter()
+            .zip(receipts)
+            .filter(|(tx, receipt)| is_withdrawal(tx, receipt))
+            .map(|(tx, receipt)| {
+                match receipt.logs.iter().find(|log| is_erc20_withdrawal_log(log)) {
+                    Some(log) => get_erc20_withdrawal_hash(tx.compute_hash(), log),
+                    None => get_withdrawal_hash(tx),
+                }
+            })
+            .collect()
+    }
+
+    /// Hashes an ETH withdrawal into a leaf of the withdrawals merkle tree
+    pub fn get_withdrawal_hash(tx: &Transaction) -> Result<H256, Error> {
+        let tx_hash = tx.compute_hash();
+        let to = tx
+            .data()
+            .get(16..36)
+            .ok_or(Error::InvalidWithdrawalTransaction(tx_hash))?;
+        Ok(keccak(
+            [to, &tx.value().to_big_endian(), tx_hash.as_bytes()].concat(),
+        ))
+    }
+
+    /// Hashes an ERC20 withdrawal into a leaf of the withdrawals merkle tree, from its
+    /// `ERC20WithdrawalInitiated` log. The leaf is keccak(tokenL1 || receiverOnL1 || amount ||
+    /// tx_hash), as the L1 `CommonBridge` computes it when claiming.
+    pub fn get_erc20_withdrawal_hash(tx_hash: H256, log: &Log) -> Result<H256, Error> {
+        let (Some(token), Some(receiver), Some(amount)) =
+            (log.topics.get(1), log.topics.get(3), log.data.get(..32))
+        else {
+            return Err(Error::InvalidERC20WithdrawalLog(tx_hash));
+        };
+        Ok(keccak(
+            [
+                &token.as_bytes()[12..],
+                &receiver.as_bytes()[12..],
+                amount,
+                tx_hash.as_bytes(),
+            ]
+            .concat(),
+        ))
+    }
+
+    /// Returns the hashes of the deposits (privileged transactions) of a block
+    pub fn get_block_deposit_hashes(transactions: &[Transaction]) -> Vec<H256> {
+        transactions
+            .iter()
+            .filter_map(|tx| match tx {
+                Transaction::PrivilegedL2Transaction(tx) => tx.get_deposit_hash(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Computes the merkle root of the withdrawal hashes, zero if there are none
+    pub fn compute_withdrawals_merkle_root(withdrawal_hashes: &[H256]) -> H256 {
+        if withdrawal_hashes.is_empty() {
+            return H256::zero();
+        }
+        let mut level = withdrawal_hashes.to_vec();
+        // A single leaf is still hashed with itself, so the loop runs at least once
+        loop {
+            level = level
+                .chunks(2)
+                .map(|pair| {
+                    let left = pair.first().copied().unwrap_or_default();
+                    let right = pair.get(1).copied().unwrap_or(left);
+                    keccak([left.as_bytes(), right.as_bytes()].concat())
+                })
+                .collect();
+            if let [root] = level.as_slice() {
+                return *root;
+            }
+        }
+    }
+
+    /// Computes the hash of the deposits processed in a batch, zero if there are none.
+    /// The first 2 bytes hold the amount of deposits and the rest are the last 30 bytes of the
+    /// keccak of the concatenated deposit hashes.
+    pub fn compute_deposit_logs_hash(deposit_hashes: &[H256]) -> Result<H256, Error> {
+        if deposit_hashes.is_empty() {
+            return Ok(H256::zero());
+        }
+        let deposits_amount: u16 = deposit_hashes
+            .len()
+            .try_into()
+            .map_err(|_| Error::TooManyDeposits(deposit_hashes.len()))?;
+        let hash = keccak(
+            deposit_hashes
+                .iter()
+                .map(H256::as_bytes)
+                .collect::<Vec<_>>()
+                .concat(),
+        );
+        let mut deposit_logs_hash = hash.0;
+        deposit_logs_hash[..2].copy_from_slice(&deposits_amount.to_be_bytes());
+        Ok(H256(deposit_logs_hash))
+    }
+}
+
 pub mod trie {
     use std::collections::HashMap;
 
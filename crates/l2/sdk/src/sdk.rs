@@ -5,14 +5,18 @@ use ethrex_common::types::{GenericTransaction, Transaction, TxKind};
 use ethrex_rpc::clients::eth::{
     errors::{EthClientError, GetTransactionReceiptError},
     eth_sender::Overrides,
-    EthClient,
+    BlockByNumber, EthClient,
+};
+use ethrex_rpc::types::{
+    block::BlockBodyWrapper,
+    receipt::{RpcLogInfo, RpcReceipt},
 };
-use ethrex_rpc::types::{block::BlockBodyWrapper, receipt::RpcReceipt};
 use itertools::Itertools;
 use keccak_hash::keccak;
 use merkle_tree::merkle_proof;
 use secp256k1::SecretKey;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::info;
 pub mod calldata;
 pub mod merkle_tree;
 
@@ -29,6 +33,13 @@ pub const COMMON_BRIDGE_L2_ADDRESS: Address = H160([
 
 pub const L2_WITHDRAW_SIGNATURE: &str = "withdraw(address)";
 
+pub const L2_WITHDRAW_ERC20_SIGNATURE: &str = "withdrawERC20(address,address,uint256)";
+
+pub const WITHDRAWAL_EVENT_SIGNATURE: &str = "WithdrawalInitiated(address,address,uint256)";
+
+pub const ERC20_WITHDRAWAL_EVENT_SIGNATURE: &str =
+    "ERC20WithdrawalInitiated(address,address,address,uint256)";
+
 #[derive(Debug, thiserror::Error)]
 pub enum SdkError {
     #[error("Failed to parse address from hex")]
@@ -160,6 +171,82 @@ pub async fn withdraw(
         .await
 }
 
+pub async fn deposit_erc20(
+    token_l1: Address,
+    amount: U256,
+    recipient: Address,
+    from: Address,
+    from_pk: SecretKey,
+    eth_client: &EthClient,
+) -> Result<H256, EthClientError> {
+    info!("Depositing {amount} of token {token_l1:#x} from {from:#x} to bridge");
+    let bridge_address = bridge_address().map_err(|err| EthClientError::Custom(err.to_string()))?;
+
+    // The bridge pulls the tokens with transferFrom, so it must be approved first
+    let approve_tx = eth_client
+        .build_eip1559_transaction(
+            token_l1,
+            from,
+            Bytes::from(encode_calldata(
+                "approve(address,uint256)",
+                &[Value::Address(bridge_address), Value::Uint(amount)],
+            )?),
+            Overrides::default(),
+        )
+        .await?;
+    let approve_tx_hash = eth_client
+        .send_eip1559_transaction(&approve_tx, &from_pk)
+        .await?;
+    wait_for_transaction_receipt(approve_tx_hash, eth_client, 100).await?;
+
+    let deposit_tx = eth_client
+        .build_eip1559_transaction(
+            bridge_address,
+            from,
+            Bytes::from(encode_calldata(
+                "depositERC20(address,address,uint256)",
+                &[
+                    Value::Address(token_l1),
+                    Value::Address(recipient),
+                    Value::Uint(amount),
+                ],
+            )?),
+            Overrides::default(),
+        )
+        .await?;
+    eth_client
+        .send_eip1559_transaction(&deposit_tx, &from_pk)
+        .await
+}
+
+pub async fn withdraw_erc20(
+    token_l1: Address,
+    amount: U256,
+    from: Address,
+    from_pk: SecretKey,
+    proposer_client: &EthClient,
+) -> Result<H256, EthClientError> {
+    let withdraw_transaction = proposer_client
+        .build_eip1559_transaction(
+            COMMON_BRIDGE_L2_ADDRESS,
+            from,
+            Bytes::from(encode_calldata(
+                L2_WITHDRAW_ERC20_SIGNATURE,
+                &[
+                    Value::Address(token_l1),
+                    Value::Address(from),
+                    Value::Uint(amount),
+                ],
+            )?),
+            Overrides::default(),
+        )
+        .await?;
+
+    proposer_client
+        .send_eip1559_transaction(&withdraw_transaction, &from_pk)
+        .await
+}
+
 pub async fn claim_withdraw(
     l2_withdrawal_tx_hash: H256,
     amount: U256,
@@ -173,11 +260,11 @@ pub async fn claim_withdraw(
     const CLAIM_WITHDRAWAL_SIGNATURE: &str =
         "claimWithdrawal(bytes32,uint256,uint256,uint256,bytes32[])";
 
-    let (withdrawal_l2_block_number, claimed_amount) = match proposer_client
+    let claimed_amount = match proposer_client
         .get_transaction_by_hash(l2_withdrawal_tx_hash)
         .await?
     {
-        Some(l2_withdrawal_tx) => (l2_withdrawal_tx.block_number, l2_withdrawal_tx.value),
+        Some(l2_withdrawal_tx) => l2_withdrawal_tx.value,
         None => {
             println!("Withdrawal transaction not found in L2");
             return Err(EthClientError::GetTransactionReceiptError(
@@ -188,14 +275,24 @@ pub async fn claim_withdraw(
         }
     };
 
-    let (index, proof) = get_withdraw_merkle_proof(proposer_client, l2_withdrawal_tx_hash).await?;
+    let bridge_address = bridge_address().map_err(|err| EthClientError::Custom(err.to_string()))?;
+    let on_chain_proposer_address =
+        EthClient::get_on_chain_proposer_address(eth_client, bridge_address).await?;
+
+    let (withdrawal_batch_number, index, proof) = get_withdraw_merkle_proof(
+        proposer_client,
+        eth_client,
+        on_chain_proposer_address,
+        l2_withdrawal_tx_hash,
+    )
+    .await?;
 
     let calldata_values = vec![
         Value::Uint(U256::from_big_endian(
             l2_withdrawal_tx_hash.as_fixed_bytes(),
         )),
         Value::Uint(claimed_amount),
-        Value::Uint(withdrawal_l2_block_number),
+        Value::Uint(U256::from(withdrawal_batch_number)),
         Value::Uint(U256::from(index)),
         Value::Array(
             proof
@@ -214,7 +311,80 @@ pub async fn claim_withdraw(
 
     let claim_tx = eth_client
         .build_eip1559_transaction(
-            bridge_address().map_err(|err| EthClientError::Custom(err.to_string()))?,
+            bridge_address,
+            from,
+            claim_withdrawal_data.into(),
+            Overrides {
+                from: Some(from),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    eth_client
+        .send_eip1559_transaction(&claim_tx, &from_pk)
+        .await
+}
+
+pub async fn claim_erc20_withdraw(
+    token_l1: Address,
+    l2_withdrawal_tx_hash: H256,
+    from: Address,
+    from_pk: SecretKey,
+    proposer_client: &EthClient,
+    eth_client: &EthClient,
+) -> Result<H256, EthClientError> {
+    const CLAIM_ERC20_WITHDRAWAL_SIGNATURE: &str =
+        "claimERC20Withdrawal(address,bytes32,uint256,uint256,uint256,bytes32[])";
+
+    let receipt = proposer_client
+        .get_transaction_receipt(l2_withdrawal_tx_hash)
+        .await?
+        .ok_or(EthClientError::GetTransactionReceiptError(
+            GetTransactionReceiptError::RPCError(
+                "Withdrawal transaction not found in L2".to_owned(),
+            ),
+        ))?;
+    let claimed_amount = get_erc20_withdrawal_amount(&receipt).ok_or(EthClientError::Custom(
+        "Transaction is not an ERC20 withdrawal".to_owned(),
+    ))?;
+
+    info!("Claiming {claimed_amount} of token {token_l1:#x} from bridge to {from:#x}");
+
+    let bridge_address = bridge_address().map_err(|err| EthClientError::Custom(err.to_string()))?;
+    let on_chain_proposer_address =
+        EthClient::get_on_chain_proposer_address(eth_client, bridge_address).await?;
+
+    let (withdrawal_batch_number, index, proof) = get_withdraw_merkle_proof(
+        proposer_client,
+        eth_client,
+        on_chain_proposer_address,
+        l2_withdrawal_tx_hash,
+    )
+    .await?;
+
+    let calldata_values = vec![
+        Value::Address(token_l1),
+        Value::Uint(U256::from_big_endian(
+            l2_withdrawal_tx_hash.as_fixed_bytes(),
+        )),
+        Value::Uint(claimed_amount),
+        Value::Uint(U256::from(withdrawal_batch_number)),
+        Value::Uint(U256::from(index)),
+        Value::Array(
+            proof
+                .iter()
+                .map(|hash| Value::FixedBytes(hash.as_fixed_bytes().to_vec().into()))
+                .collect(),
+        ),
+    ];
+
+    let claim_withdrawal_data =
+        encode_calldata(CLAIM_ERC20_WITHDRAWAL_SIGNATURE, &calldata_values)?;
+
+    let claim_tx = eth_client
+        .build_eip1559_transaction(
+            bridge_address,
             from,
             claim_withdrawal_data.into(),
             Overrides {
@@ -246,10 +416,43 @@ pub fn get_withdrawal_hash(tx: &Transaction) -> Option<H256> {
     ))
 }
 
+/// Returns the formated hash of an ERC20 withdrawal from its `ERC20WithdrawalInitiated` log,
+/// or None if the log is malformed.
+/// The hash is computed as keccak256(token_l1 || receiver_on_l1 || amount || tx_hash)
+pub fn get_erc20_withdrawal_hash(tx_hash: H256, log: &RpcLogInfo) -> Option<H256> {
+    let token = log.topics.get(1)?.as_bytes().get(12..)?;
+    let receiver = log.topics.get(3)?.as_bytes().get(12..)?;
+    let amount = log.data.get(..32)?;
+
+    Some(keccak_hash::keccak(
+        [token, receiver, amount, tx_hash.as_bytes()].concat(),
+    ))
+}
+
+/// Returns the amount of an ERC20 withdrawal from the `ERC20WithdrawalInitiated` log of its
+/// receipt, or None if the transaction is not an ERC20 withdrawal.
+pub fn get_erc20_withdrawal_amount(receipt: &RpcReceipt) -> Option<U256> {
+    receipt
+        .logs
+        .iter()
+        .find(|log| is_erc20_withdrawal_log(&log.log))
+        .and_then(|log| log.log.data.get(..32))
+        .map(U256::from_big_endian)
+}
+
+fn is_erc20_withdrawal_log(log: &RpcLogInfo) -> bool {
+    log.address == COMMON_BRIDGE_L2_ADDRESS
+        && log.topics.first() == Some(&keccak(ERC20_WITHDRAWAL_EVENT_SIGNATURE.as_bytes()))
+}
+
+/// Returns the number of the batch that includes the withdrawal, the index of the withdrawal
+/// among all the withdrawals of that batch and its merkle proof.
 pub async fn get_withdraw_merkle_proof(
     client: &EthClient,
+    eth_client: &EthClient,
+    on_chain_proposer_address: Address,
     tx_hash: H256,
-) -> Result<(u64, Vec<H256>), EthClientError> {
+) -> Result<(u64, u64, Vec<H256>), EthClientError> {
     let tx_receipt =
         client
             .get_transaction_receipt(tx_hash)
@@ -258,22 +461,67 @@ pub async fn get_withdraw_merkle_proof(
                 "Failed to get transaction receipt".to_string(),
             ))?;
 
-    let block = client
-        .get_block_by_hash(tx_receipt.block_info.block_hash)
-        .await?;
+    let batch_number = EthClient::get_batch_number_by_block(
+        eth_client,
+        on_chain_proposer_address,
+        tx_receipt.block_info.block_number,
+    )
+    .await?
+    .ok_or(EthClientError::Custom(
+        "Failed to get withdrawal batch, the block was not committed yet".to_string(),
+    ))?;
 
-    let transactions = match block.body {
-        BlockBodyWrapper::Full(body) => body.transactions,
-        BlockBodyWrapper::OnlyHashes(_) => unreachable!(),
-    };
-    let Some(Some((index, tx_withdrawal_hash))) = transactions
+    let (first_block_number, last_block_number) =
+        EthClient::get_batch_block_range(eth_client, on_chain_proposer_address, batch_number)
+            .await?;
+
+    // The withdrawals merkle tree is built with the withdrawals of every block in the batch
+    let mut withdrawals = Vec::new();
+    for block_number in first_block_number..=last_block_number {
+        let block_hash = client
+            .get_block_by_number(BlockByNumber::Number(block_number))
+            .await?
+            .header
+            .compute_block_hash();
+        let block = client.get_block_by_hash(block_hash).await?;
+
+        let transactions = match block.body {
+            BlockBodyWrapper::Full(body) => body.transactions,
+            BlockBodyWrapper::OnlyHashes(_) => unreachable!(),
+        };
+        for tx in transactions {
+            if tx.tx.to() != TxKind::Call(COMMON_BRIDGE_L2_ADDRESS) {
+                continue;
+            }
+            // Only the calls that emitted a withdrawal event are in the tree, as in the committer
+            let Some(receipt) = client.get_transaction_receipt(tx.hash).await? else {
+                continue;
+            };
+            let withdrawal_event_selector = keccak(WITHDRAWAL_EVENT_SIGNATURE.as_bytes());
+            let withdrawal_hash = match receipt
+                .logs
+                .iter()
+                .find(|log| is_erc20_withdrawal_log(&log.log))
+            {
+                Some(log) => get_erc20_withdrawal_hash(tx.hash, &log.log),
+                None if receipt
+                    .logs
+                    .iter()
+                    .any(|log| log.log.topics.contains(&withdrawal_event_selector)) =>
+                {
+                    get_withdrawal_hash(&tx.tx)
+                }
+                None => None,
+            };
+            if let Some(withdrawal_hash) = withdrawal_hash {
+                withdrawals.push((tx.hash, withdrawal_hash));
+            }
+        }
+    }
+
+    let Some((index, (_, tx_withdrawal_hash))) = withdrawals
         .iter()
-        .filter(|tx| match &tx.tx.to() {
-            ethrex_common::types::TxKind::Call(to) => *to == COMMON_BRIDGE_L2_ADDRESS,
-            ethrex_common::types::TxKind::Create => false,
-        })
-        .find_position(|tx| tx.hash == tx_hash)
-        .map(|(i, tx)| get_withdrawal_hash(&tx.tx).map(|withdrawal_hash| (i, (withdrawal_hash))))
+        .find_position(|(hash, _)| *hash == tx_hash)
     else {
         return Err(EthClientError::Custom(
             "Failed to get widthdrawal hash, transaction is not a withdrawal".to_string(),
@@ -281,14 +529,11 @@ pub async fn get_withdraw_merkle_proof(
     };
 
     let path = merkle_proof(
-        transactions
+        withdrawals
             .iter()
-            .filter_map(|tx| match tx.tx.to() {
-                TxKind::Call(to) if to == COMMON_BRIDGE_L2_ADDRESS => get_withdrawal_hash(&tx.tx),
-                _ => None,
-            })
+            .map(|(_, withdrawal_hash)| *withdrawal_hash)
             .collect(),
-        tx_withdrawal_hash,
+        *tx_withdrawal_hash,
     )
     .map_err(|err| EthClientError::Custom(format!("Failed to generate merkle proof: {err}")))?
     .ok_or(EthClientError::Custom(
@@ -296,6 +541,7 @@ pub async fn get_withdraw_merkle_proof(
     ))?;
 
     Ok((
+        batch_number,
         index
             .try_into()
             .map_err(|err| EthClientError::Custom(format!("index does not fit in u64: {}", err)))?,
@@ -5,25 +5,30 @@ use std::{
 };
 
 use ethrex_blockchain::{
-    fork_choice::apply_fork_choice,
     payload::{create_payload, BuildPayloadArgs},
     validate_block, Blockchain,
 };
-use ethrex_common::Address;
+use ethrex_common::{types::L1FeeParams, Address};
+use ethrex_rpc::clients::eth::{BlockByNumber, EthClient};
 use ethrex_storage::Store;
 use ethrex_vm::BlockExecutionResult;
 use keccak_hash::H256;
 use payload_builder::build_payload;
 use tokio::time::sleep;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use crate::utils::config::{block_producer::BlockProducerConfig, errors::ConfigError};
+use crate::utils::config::{
+    block_producer::BlockProducerConfig, errors::ConfigError, eth::EthConfig,
+};
 
 use super::{errors::BlockProducerError, execution_cache::ExecutionCache};
 
 pub struct BlockProducer {
     block_time_ms: u64,
     coinbase_address: Address,
+    eth_client: EthClient,
+    /// Last L1 fee params fetched, used when the L1 can't be reached
+    last_l1_fee_params: Option<L1FeeParams>,
 }
 
 pub async fn start_block_producer(
@@ -32,7 +37,9 @@ pub async fn start_block_producer(
     execution_cache: Arc<ExecutionCache>,
 ) -> Result<(), ConfigError> {
     let proposer_config = BlockProducerConfig::from_env()?;
-    let proposer = BlockProducer::new_from_config(proposer_config).map_err(ConfigError::from)?;
+    let eth_config = EthConfig::from_env()?;
+    let mut proposer =
+        BlockProducer::new_from_config(proposer_config, eth_config).map_err(ConfigError::from)?;
 
     proposer
         .run(store.clone(), blockchain, execution_cache)
@@ -41,7 +48,10 @@ pub async fn start_block_producer(
 }
 
 impl BlockProducer {
-    pub fn new_from_config(config: BlockProducerConfig) -> Result<Self, BlockProducerError> {
+    pub fn new_from_config(
+        config: BlockProducerConfig,
+        eth_config: EthConfig,
+    ) -> Result<Self, BlockProducerError> {
         let BlockProducerConfig {
             block_time_ms,
             coinbase_address,
@@ -49,11 +59,13 @@ impl BlockProducer {
         Ok(Self {
             block_time_ms,
             coinbase_address,
+            eth_client: EthClient::new(&eth_config.rpc_url),
+            last_l1_fee_params: None,
         })
     }
 
     pub async fn run(
-        &self,
+        &mut self,
         store: Store,
         blockchain: Arc<Blockchain>,
         execution_cache: Arc<ExecutionCache>,
@@ -71,7 +83,7 @@ impl BlockProducer {
     }
 
     pub async fn main_logic(
-        &self,
+        &mut self,
         store: Store,
         blockchain: Arc<Blockchain>,
         execution_cache: Arc<ExecutionCache>,
@@ -102,7 +114,13 @@ impl BlockProducer {
             beacon_root: Some(head_beacon_block_root),
             version,
         };
-        let payload = create_payload(&args, &store)?;
+        let mut payload = create_payload(&args, &store)?;
+
+        // The L1 prices used to charge the L1 fee are set in the block, so that every node
+        // executing it charges the same fees
+        if store.get_chain_config()?.l1_fee_vault.is_some() {
+            payload.header.extra_data = self.fetch_l1_fee_params().await?.encode_extra_data();
+        }
 
         // Blockchain builds the payload from mempool txs and executes them
         let payload_build_result = build_payload(blockchain.clone(), payload, &store).await?;
@@ -133,8 +151,86 @@ impl BlockProducer {
         execution_cache.push(block.hash(), account_updates)?;
 
         // Make the new head be part of the canonical chain
-        apply_fork_choice(&store, block.hash(), block.hash(), block.hash()).await?;
+        let head = blockchain
+            .apply_fork_choice(block.hash(), block.hash(), block.hash())
+            .await?;
+        blockchain.notify_new_head(head);
 
         Ok(())
     }
+
+    /// Fetches the current L1 base fee and blob base fee.
+    /// If the L1 can't be reached, the last known params are used instead, so that block
+    /// production doesn't stop on transient L1 RPC errors.
+    async fn fetch_l1_fee_params(&mut self) -> Result<L1FeeParams, BlockProducerError> {
+        match self.fetch_current_l1_fee_params().await {
+            Ok(params) => {
+                self.last_l1_fee_params = Some(params);
+                Ok(params)
+            }
+            Err(err) => match self.last_l1_fee_params {
+                Some(params) => {
+                    warn!("Failed to fetch L1 fee params, using the last known ones: {err}");
+                    Ok(params)
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    async fn fetch_current_l1_fee_params(&self) -> Result<L1FeeParams, BlockProducerError> {
+        let l1_base_fee_per_gas = self
+            .eth_client
+            .get_block_by_number(BlockByNumber::Latest)
+            .await?
+            .header
+            .base_fee_per_gas
+            .unwrap_or_default();
+        let l1_blob_base_fee_per_gas = self
+            .eth_client
+            .get_blob_base_fee()
+            .await?
+            .try_into()
+            .map_err(|_| {
+                BlockProducerError::Custom("L1 blob base fee does not fit in u64".to_owned())
+            })?;
+        debug!(
+            "L1 fee params: base fee {l1_base_fee_per_gas}, blob base fee {l1_blob_base_fee_per_gas}"
+        );
+        Ok(L1FeeParams {
+            l1_base_fee_per_gas,
+            l1_blob_base_fee_per_gas,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn l1_fee_params_fall_back_to_the_last_known_ones() {
+        // Nothing listens on this port, so every L1 request fails
+        let mut block_producer = BlockProducer {
+            block_time_ms: 1000,
+            coinbase_address: Address::zero(),
+            eth_client: EthClient::new("http://127.0.0.1:1"),
+            last_l1_fee_params: None,
+        };
+        assert!(block_producer.fetch_l1_fee_params().await.is_err());
+
+        let last_params = L1FeeParams {
+            l1_base_fee_per_gas: 7,
+            l1_blob_base_fee_per_gas: 3,
+        };
+        block_producer.last_l1_fee_params = Some(last_params);
+        assert_eq!(
+            block_producer
+                .fetch_l1_fee_params()
+                .await
+                .expect("fallback params"),
+            last_params
+        );
+    }
 }
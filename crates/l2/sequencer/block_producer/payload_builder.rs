@@ -24,9 +24,9 @@ use crate::{
     utils::helpers::{is_deposit_l2, is_withdrawal_l2},
 };
 
-// transactions_root(H256) + receipts_root(H256) + gas_limit(u64) + gas_used(u64) + timestamp(u64) + base_fee_per_gas(u64).
-// 32bytes + 32bytes + 8bytes + 8bytes + 8bytes + 8bytes
-const HEADER_FIELDS_SIZE: usize = 96;
+// number(u64) + transactions_root(H256) + receipts_root(H256) + gas_limit(u64) + gas_used(u64) + timestamp(u64) + base_fee_per_gas(u64).
+// 8bytes + 32bytes + 32bytes + 8bytes + 8bytes + 8bytes + 8bytes
+const HEADER_FIELDS_SIZE: usize = 104;
 
 // address(H160) + amount(U256) + tx_hash(H256).
 // 20bytes + 32bytes + 32bytes.
@@ -188,7 +188,7 @@ pub async fn fill_transactions(
                 }
                 txs.shift()?;
                 // Pull transaction from the mempool
-                blockchain.remove_transaction_from_pool(&head_tx.tx.compute_hash())?;
+                blockchain.remove_included_transaction_from_pool(&head_tx.tx.compute_hash())?;
 
                 metrics!(METRICS_TX.inc_tx_with_status_and_type(
                     MetricsTxStatus::Succeeded,
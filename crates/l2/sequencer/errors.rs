@@ -1,17 +1,17 @@
 use crate::utils::config::errors::ConfigError;
 use crate::utils::error::UtilsError;
-use crate::utils::prover::errors::SaveStateError;
+use crate::utils::prover::errors::{ProtocolError, SaveStateError};
 use crate::utils::prover::proving_systems::ProverType;
 use ethereum_types::FromStrRadixErr;
-use ethrex_blockchain::error::{ChainError, InvalidForkChoice};
+use ethrex_blockchain::error::{ChainError, InvalidForkChoice, MempoolError};
 use ethrex_common::types::{BlobsBundleError, FakeExponentialError};
-use ethrex_l2_sdk::merkle_tree::MerkleError;
 use ethrex_rpc::clients::eth::errors::{CalldataEncodeError, EthClientError};
 use ethrex_rpc::clients::EngineClientError;
 use ethrex_storage::error::StoreError;
 use ethrex_trie::TrieError;
 use ethrex_vm::EvmError;
 use tokio::task::JoinError;
+use zkvm_interface::messages::Error as MessagesError;
 
 #[derive(Debug, thiserror::Error)]
 pub enum L1WatcherError {
@@ -27,6 +27,8 @@ pub enum L1WatcherError {
     FailedToGetConfig(#[from] ConfigError),
     #[error("L1Watcher failed to access Store: {0}")]
     FailedAccessingStore(#[from] StoreError),
+    #[error("L1Watcher failed to add a deposit to the mempool: {0}")]
+    FailedToAddDepositToMempool(#[from] MempoolError),
     #[error("{0}")]
     Custom(String),
 }
@@ -61,6 +63,12 @@ pub enum ProverServerError {
     InternalError(String),
     #[error("ProverServer failed when (de)serializing JSON: {0}")]
     JsonError(#[from] serde_json::Error),
+    #[error("ProverServer failed to communicate with a prover: {0}")]
+    ProtocolError(#[from] ProtocolError),
+    #[error("ProverServer connection timed out")]
+    ConnectionTimeout,
+    #[error("ProverServer failed to lock the prover registry: {0}")]
+    RegistryLock(String),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -81,6 +89,8 @@ pub enum ProofSenderError {
 pub enum BlockProducerError {
     #[error("Block Producer failed because of an EngineClient error: {0}")]
     EngineClientError(#[from] EngineClientError),
+    #[error("Block Producer failed because of an EthClient error: {0}")]
+    EthClientError(#[from] EthClientError),
     #[error("Block Producer failed because of a ChainError error: {0}")]
     ChainError(#[from] ChainError),
     #[error("Block Producer failed because of a EvmError error: {0}")]
@@ -133,12 +143,8 @@ pub enum CommitterError {
     FailedToReExecuteBlock(#[from] EvmError),
     #[error("Committer failed to send transaction: {0}")]
     FailedToSendCommitment(String),
-    #[error("Committer failed to decode deposit hash")]
-    FailedToDecodeDepositHash,
-    #[error("Committer failed to merkelize: {0}")]
-    FailedToMerkelize(#[from] MerkleError),
-    #[error("Withdrawal transaction was invalid")]
-    InvalidWithdrawalTransaction,
+    #[error("Committer failed to hash the withdrawals or deposits: {0}")]
+    FailedToHashMessages(#[from] MessagesError),
     #[error("Blob estimation failed: {0}")]
     BlobEstimationError(#[from] BlobEstimationError),
     #[error("length does not fit in u16")]
@@ -187,6 +193,8 @@ pub enum StateDiffError {
     StoreError(#[from] StoreError),
     #[error("New nonce is lower than the previous one")]
     FailedToCalculateNonce,
+    #[error("The accumulated nonce diff is too big to fit in u16")]
+    NonceDiffOverflow,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -11,27 +11,41 @@ use crate::{
 
 use ethrex_common::{
     types::{
-        blobs_bundle, fake_exponential_checked, BlobsBundle, BlobsBundleError, Block,
-        PrivilegedL2Transaction, Receipt, Transaction, TxKind, BLOB_BASE_FEE_UPDATE_FRACTION,
-        MIN_BASE_FEE_PER_BLOB_GAS,
+        blobs_bundle, fake_exponential_checked, BlobsBundle, BlobsBundleError, Block, BlockHash,
+        BlockNumber, PrivilegedL2Transaction, Receipt, Transaction, TxKind,
+        BLOB_BASE_FEE_UPDATE_FRACTION, MIN_BASE_FEE_PER_BLOB_GAS, SAFE_BYTES_PER_BLOB,
     },
     Address, H256, U256,
 };
 use ethrex_l2_sdk::calldata::{encode_calldata, Value};
-use ethrex_l2_sdk::{get_withdrawal_hash, merkle_tree::merkelize};
 use ethrex_rpc::clients::eth::{
     eth_sender::Overrides, BlockByNumber, EthClient, WrappedTransaction,
 };
 use ethrex_storage::{AccountUpdate, Store};
 use ethrex_vm::Evm;
-use keccak_hash::keccak;
 use secp256k1::SecretKey;
 use std::{collections::HashMap, sync::Arc};
 use tracing::{debug, error, info, warn};
+use zkvm_interface::messages::{
+    compute_deposit_logs_hash, compute_withdrawals_merkle_root, get_block_withdrawal_hashes,
+};
 
 use super::{errors::BlobEstimationError, execution_cache::ExecutionCache, utils::sleep_random};
 
-const COMMIT_FUNCTION_SIGNATURE: &str = "commit(uint256,bytes32,bytes32,bytes32,bytes32)";
+const COMMIT_FUNCTION_SIGNATURE: &str =
+    "commit(uint256,uint256,uint256,bytes32,bytes32,bytes32,bytes32)";
+
+/// Consecutive blocks committed together in a single L1 transaction.
+#[derive(Default)]
+struct Batch {
+    first_block_number: BlockNumber,
+    last_block_number: BlockNumber,
+    last_block_hash: BlockHash,
+    withdrawal_hashes: Vec<H256>,
+    deposit_hashes: Vec<H256>,
+    /// Merged state diff of all the blocks, `None` in validium mode.
+    state_diff: Option<StateDiff>,
+}
 
 pub struct Committer {
     eth_client: EthClient,
@@ -40,6 +54,7 @@ pub struct Committer {
     l1_address: Address,
     l1_private_key: SecretKey,
     commit_time_ms: u64,
+    max_blocks_per_batch: u64,
     arbitrary_base_blob_gas_price: u64,
     execution_cache: Arc<ExecutionCache>,
     validium: bool,
@@ -72,6 +87,7 @@ impl Committer {
             l1_address: committer_config.l1_address,
             l1_private_key: committer_config.l1_private_key,
             commit_time_ms: committer_config.commit_time_ms,
+            max_blocks_per_batch: committer_config.max_blocks_per_batch,
             arbitrary_base_blob_gas_price: committer_config.arbitrary_base_blob_gas_price,
             execution_cache,
             validium: committer_config.validium,
@@ -89,106 +105,44 @@ impl Committer {
     }
 
     async fn main_logic(&mut self) -> Result<(), CommitterError> {
-        let block_number = 1 + EthClient::get_last_committed_block(
+        let batch_number = 1 + EthClient::get_last_committed_batch(
+            &self.eth_client,
+            self.on_chain_proposer_address,
+        )
+        .await?;
+        let first_block_number = 1 + EthClient::get_last_committed_block(
             &self.eth_client,
             self.on_chain_proposer_address,
         )
         .await?;
 
-        let Some(block_to_commit_body) = self
-            .store
-            .get_block_body(block_number)
-            .await
-            .map_err(CommitterError::from)?
-        else {
+        let Some(batch) = self.prepare_batch(first_block_number).await? else {
             debug!("No new block to commit, skipping..");
             return Ok(());
         };
 
-        let block_to_commit_header = self
-            .store
-            .get_block_header(block_number)
-            .map_err(CommitterError::from)?
-            .ok_or(CommitterError::FailedToGetInformationFromStorage(
-                "Failed to get_block_header() after get_block_body()".to_owned(),
-            ))?;
-
-        let mut txs_and_receipts = vec![];
-        for (index, tx) in block_to_commit_body.transactions.iter().enumerate() {
-            let receipt = self
-                .store
-                .get_receipt(block_number, index.try_into()?)
-                .await?
-                .ok_or(CommitterError::InternalError(
-                    "Transactions in a block should have a receipt".to_owned(),
-                ))?;
-            txs_and_receipts.push((tx.clone(), receipt));
-        }
-
-        let block_to_commit = Block::new(block_to_commit_header, block_to_commit_body);
-
-        let withdrawals = self.get_block_withdrawals(&txs_and_receipts)?;
-        let deposits = self.get_block_deposits(&block_to_commit);
-
-        let mut withdrawal_hashes = vec![];
-
-        for (_, tx) in &withdrawals {
-            let hash =
-                get_withdrawal_hash(tx).ok_or(CommitterError::InvalidWithdrawalTransaction)?;
-            withdrawal_hashes.push(hash);
-        }
-
-        let withdrawal_logs_merkle_root = self.get_withdrawals_merkle_root(withdrawal_hashes)?;
-        let deposit_logs_hash = self.get_deposit_hash(
-            deposits
-                .iter()
-                .filter_map(|tx| tx.get_deposit_hash())
-                .collect(),
-        )?;
-
-        let account_updates = match self.execution_cache.get(block_to_commit.hash())? {
-            Some(account_updates) => account_updates,
-            None => {
-                warn!(
-                            "Could not find execution cache result for block {block_number}, falling back to re-execution"
-                        );
-                let mut vm = Evm::default(self.store.clone(), block_to_commit.header.parent_hash);
-                vm.execute_block(&block_to_commit)?;
-                let fork = self
-                    .store
-                    .get_chain_config()?
-                    .fork(block_to_commit.header.timestamp);
-                vm.get_state_transitions(fork)?
-            }
-        };
+        // Computed the same way as in the prover, which proves these values
+        let withdrawal_logs_merkle_root = compute_withdrawals_merkle_root(&batch.withdrawal_hashes);
+        let deposit_logs_hash = compute_deposit_logs_hash(&batch.deposit_hashes)?;
 
         let new_state_root = self
             .store
-            .state_trie(block_to_commit.hash())?
+            .state_trie(batch.last_block_hash)?
             .ok_or(CommitterError::FailedToGetInformationFromStorage(
                 "Failed to get state root from storage".to_owned(),
             ))?
             .hash_no_commit();
 
-        let blobs_bundle = if !self.validium {
-            let state_diff = self
-                .prepare_state_diff(
-                    &block_to_commit,
-                    self.store.clone(),
-                    withdrawals,
-                    deposits,
-                    &account_updates,
-                )
-                .await?;
-            self.generate_blobs_bundle(&state_diff)?
-        } else {
-            BlobsBundle::default()
+        let blobs_bundle = match &batch.state_diff {
+            Some(state_diff) => self.generate_blobs_bundle(state_diff)?,
+            None => BlobsBundle::default(),
         };
 
-        let head_block_hash = block_to_commit.hash();
         match self
             .send_commitment(
-                block_to_commit.header.number,
+                batch_number,
+                batch.first_block_number,
+                batch.last_block_number,
                 new_state_root,
                 withdrawal_logs_merkle_root,
                 deposit_logs_hash,
@@ -197,15 +151,138 @@ impl Committer {
             .await
         {
             Ok(commit_tx_hash) => {
-                info!("Sent commitment to block {head_block_hash:#x}, with transaction hash {commit_tx_hash:#x}");
+                info!(
+                    "Sent commitment to batch {batch_number} (blocks {} to {}), with transaction hash {commit_tx_hash:#x}",
+                    batch.first_block_number, batch.last_block_number
+                );
                 Ok(())
             }
             Err(error) => Err(CommitterError::FailedToSendCommitment(format!(
-                "Failed to send commitment to block {head_block_hash:#x}: {error}"
+                "Failed to send commitment to batch {batch_number}: {error}"
             ))),
         }
     }
 
+    /// Accumulates the consecutive blocks that follow the last committed one,
+    /// until `max_blocks_per_batch` is reached, there are no more blocks, or
+    /// the merged state diff would no longer fit in a blob.
+    /// Returns `None` if there are no blocks to commit.
+    async fn prepare_batch(
+        &self,
+        first_block_number: BlockNumber,
+    ) -> Result<Option<Batch>, CommitterError> {
+        let mut batch: Option<Batch> = None;
+
+        let last_block_number = first_block_number + self.max_blocks_per_batch.saturating_sub(1);
+        for block_number in first_block_number..=last_block_number {
+            let Some(block_body) = self
+                .store
+                .get_block_body(block_number)
+                .await
+                .map_err(CommitterError::from)?
+            else {
+                break;
+            };
+
+            let block_header = self
+                .store
+                .get_block_header(block_number)
+                .map_err(CommitterError::from)?
+                .ok_or(CommitterError::FailedToGetInformationFromStorage(
+                    "Failed to get_block_header() after get_block_body()".to_owned(),
+                ))?;
+
+            let mut txs_and_receipts = vec![];
+            for (index, tx) in block_body.transactions.iter().enumerate() {
+                let receipt = self
+                    .store
+                    .get_receipt(block_number, index.try_into()?)
+                    .await?
+                    .ok_or(CommitterError::InternalError(
+                        "Transactions in a block should have a receipt".to_owned(),
+                    ))?;
+                txs_and_receipts.push((tx.clone(), receipt));
+            }
+
+            let block = Block::new(block_header, block_body);
+
+            let withdrawals = self.get_block_withdrawals(&txs_and_receipts)?;
+            let deposits = self.get_block_deposits(&block);
+
+            let receipts: Vec<Receipt> = txs_and_receipts
+                .iter()
+                .map(|(_, receipt)| receipt.clone())
+                .collect();
+            let withdrawal_hashes =
+                get_block_withdrawal_hashes(&block.body.transactions, &receipts)?;
+            let deposit_hashes = deposits
+                .iter()
+                .filter_map(|tx| tx.get_deposit_hash())
+                .collect::<Vec<_>>();
+
+            let state_diff = if !self.validium {
+                let account_updates = self.get_block_account_updates(&block)?;
+                let block_state_diff = self
+                    .prepare_state_diff(
+                        &block,
+                        self.store.clone(),
+                        withdrawals,
+                        deposits,
+                        &account_updates,
+                    )
+                    .await?;
+                let state_diff = match batch.as_ref().and_then(|batch| batch.state_diff.clone()) {
+                    Some(mut state_diff) => {
+                        state_diff.merge(block_state_diff)?;
+                        state_diff
+                    }
+                    None => block_state_diff,
+                };
+                // The first block always fits, as the block producer limits its state diff size.
+                if batch.is_some() && state_diff.encode()?.len() > SAFE_BYTES_PER_BLOB {
+                    debug!(
+                        "Batch state diff is full, closing the batch before block {block_number}"
+                    );
+                    break;
+                }
+                Some(state_diff)
+            } else {
+                None
+            };
+
+            let batch = batch.get_or_insert_with(|| Batch {
+                first_block_number,
+                ..Default::default()
+            });
+            batch.last_block_number = block_number;
+            batch.last_block_hash = block.hash();
+            batch.withdrawal_hashes.extend(withdrawal_hashes);
+            batch.deposit_hashes.extend(deposit_hashes);
+            batch.state_diff = state_diff;
+        }
+
+        Ok(batch)
+    }
+
+    fn get_block_account_updates(
+        &self,
+        block: &Block,
+    ) -> Result<Vec<AccountUpdate>, CommitterError> {
+        match self.execution_cache.get(block.hash())? {
+            Some(account_updates) => Ok(account_updates),
+            None => {
+                warn!(
+                    "Could not find execution cache result for block {}, falling back to re-execution",
+                    block.header.number
+                );
+                let mut vm = Evm::default(self.store.clone(), block.header.parent_hash);
+                vm.execute_block(block)?;
+                let fork = self.store.get_chain_config()?.fork(block.header.timestamp);
+                Ok(vm.get_state_transitions(fork)?)
+            }
+        }
+    }
+
     fn get_block_withdrawals(
         &self,
         txs_and_receipts: &[(Transaction, Receipt)],
@@ -220,17 +297,6 @@ impl Committer {
         Ok(ret)
     }
 
-    fn get_withdrawals_merkle_root(
-        &self,
-        withdrawals_hashes: Vec<H256>,
-    ) -> Result<H256, CommitterError> {
-        if !withdrawals_hashes.is_empty() {
-            merkelize(withdrawals_hashes).map_err(CommitterError::FailedToMerkelize)
-        } else {
-            Ok(H256::zero())
-        }
-    }
-
     fn get_block_deposits(&self, block: &Block) -> Vec<PrivilegedL2Transaction> {
         let deposits = block
             .body
@@ -245,35 +311,8 @@ impl Committer {
         deposits
     }
 
-    fn get_deposit_hash(&self, deposit_hashes: Vec<H256>) -> Result<H256, CommitterError> {
-        if !deposit_hashes.is_empty() {
-            let deposit_hashes_len: u16 = deposit_hashes
-                .len()
-                .try_into()
-                .map_err(CommitterError::from)?;
-            Ok(H256::from_slice(
-                [
-                    &deposit_hashes_len.to_be_bytes(),
-                    keccak(
-                        deposit_hashes
-                            .iter()
-                            .map(H256::as_bytes)
-                            .collect::<Vec<&[u8]>>()
-                            .concat(),
-                    )
-                    .as_bytes()
-                    .get(2..32)
-                    .ok_or(CommitterError::FailedToDecodeDepositHash)?,
-                ]
-                .concat()
-                .as_slice(),
-            ))
-        } else {
-            Ok(H256::zero())
-        }
-    }
-
     /// Prepare the state diff for the block.
+    /// The state diffs of the blocks of a batch are merged with [StateDiff::merge].
     async fn prepare_state_diff(
         &self,
         block: &Block,
@@ -345,15 +384,18 @@ impl Committer {
         BlobsBundle::create_from_blobs(&vec![blob]).map_err(CommitterError::from)
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn send_commitment(
         &self,
-        block_number: u64,
+        batch_number: u64,
+        first_block_number: BlockNumber,
+        last_block_number: BlockNumber,
         new_state_root: H256,
         withdrawal_logs_merkle_root: H256,
         deposit_logs_hash: H256,
         blobs_bundle: BlobsBundle,
     ) -> Result<H256, CommitterError> {
-        info!("Sending commitment for block {block_number}");
+        info!("Sending commitment for batch {batch_number}");
 
         let state_diff_kzg_versioned_hash = if !self.validium {
             let blob_versioned_hashes = blobs_bundle.generate_versioned_hashes();
@@ -367,7 +409,9 @@ impl Committer {
         };
 
         let calldata_values = vec![
-            Value::Uint(U256::from(block_number)),
+            Value::Uint(U256::from(batch_number)),
+            Value::Uint(U256::from(first_block_number)),
+            Value::Uint(U256::from(last_block_number)),
             Value::FixedBytes(new_state_root.0.to_vec().into()),
             Value::FixedBytes(state_diff_kzg_versioned_hash.to_vec().into()),
             Value::FixedBytes(withdrawal_logs_merkle_root.0.to_vec().into()),
@@ -514,3 +558,315 @@ async fn estimate_blob_gas(
 
     Ok(blob_gas)
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use ethrex_l2_sdk::merkle_tree::merkelize;
+
+    #[test]
+    fn withdrawals_merkle_root_matches_sdk() {
+        // Users claim their withdrawals with proofs built by the sdk, so the root proven by
+        // the prover and committed here has to be the same the sdk computes
+        for amount in 1..=5u64 {
+            let hashes: Vec<H256> = (0..amount).map(H256::from_low_u64_be).collect();
+            let root = compute_withdrawals_merkle_root(&hashes);
+            assert_eq!(root, merkelize(hashes.clone()).expect("merkelized"));
+        }
+        assert_eq!(compute_withdrawals_merkle_root(&[]), H256::zero());
+    }
+
+    /// Stores a chain with a block for each of the given account updates,
+    /// and a committer that finds those updates in its execution cache.
+    /// `seed` keeps the block hashes of different tests apart, as the cache is shared.
+    async fn committer_with_blocks(
+        seed: u64,
+        blocks_updates: Vec<Vec<AccountUpdate>>,
+        max_blocks_per_batch: u64,
+        validium: bool,
+    ) -> Committer {
+        use ethrex_common::types::{BlockBody, BlockHeader, Genesis};
+        use ethrex_storage::EngineType;
+
+        let store = Store::new("", EngineType::InMemory).expect("store");
+        let genesis = Genesis::default();
+        let mut parent = genesis.get_block().header;
+        store
+            .add_initial_state(genesis)
+            .await
+            .expect("initial state");
+
+        let execution_cache = Arc::new(ExecutionCache::default());
+        let mut blocks = vec![];
+        for (number, account_updates) in (1..).zip(blocks_updates) {
+            let header = BlockHeader {
+                number,
+                parent_hash: parent.compute_block_hash(),
+                state_root: parent.state_root,
+                timestamp: seed * 1_000 + number,
+                ..Default::default()
+            };
+            let block = Block::new(header, BlockBody::default());
+            execution_cache
+                .push(block.hash(), account_updates)
+                .expect("cached updates");
+            parent = block.header.clone();
+            blocks.push(block);
+        }
+        store.add_blocks(blocks.clone()).await.expect("blocks");
+        store
+            .mark_chain_as_canonical(&blocks)
+            .await
+            .expect("canonical chain");
+
+        Committer {
+            eth_client: EthClient::new("http://127.0.0.1:1"),
+            on_chain_proposer_address: Address::zero(),
+            store,
+            l1_address: Address::zero(),
+            l1_private_key: SecretKey::from_slice(&[1; 32]).expect("private key"),
+            commit_time_ms: 0,
+            max_blocks_per_batch,
+            arbitrary_base_blob_gas_price: 0,
+            execution_cache,
+            validium,
+        }
+    }
+
+    /// Updates that write `slots` storage slots of their own account, so that the
+    /// state diff of each block grows by the same amount
+    fn storage_updates(account: u64, slots: u64) -> Vec<AccountUpdate> {
+        let mut update = AccountUpdate::new(Address::from_low_u64_be(account));
+        update.added_storage = (0..slots)
+            .map(|slot| (H256::from_low_u64_be(slot), U256::one()))
+            .collect();
+        vec![update]
+    }
+
+    async fn batch_blocks(committer: &Committer, first_block_number: BlockNumber) -> (u64, u64) {
+        let batch = committer
+            .prepare_batch(first_block_number)
+            .await
+            .expect("batch prepared")
+            .expect("batch with blocks");
+        (batch.first_block_number, batch.last_block_number)
+    }
+
+    // Each block writes 600 slots (~38KB), so only three of them fit in a blob
+    const SLOTS_PER_BLOCK: u64 = 600;
+
+    #[tokio::test]
+    async fn batch_is_closed_when_the_blob_is_full() {
+        let blocks_updates = (1..=4)
+            .map(|account| storage_updates(account, SLOTS_PER_BLOCK))
+            .collect();
+        let committer = committer_with_blocks(1, blocks_updates, 10, false).await;
+
+        let batch = committer
+            .prepare_batch(1)
+            .await
+            .expect("batch prepared")
+            .expect("batch with blocks");
+        assert_eq!((batch.first_block_number, batch.last_block_number), (1, 3));
+        let state_diff = batch.state_diff.expect("rollup batches have a state diff");
+        assert!(state_diff.encode().expect("encoded").len() <= SAFE_BYTES_PER_BLOB);
+        assert_eq!(state_diff.header.number, 3);
+
+        // The block that didn't fit opens the next batch
+        assert_eq!(batch_blocks(&committer, 4).await, (4, 4));
+        assert!(committer
+            .prepare_batch(5)
+            .await
+            .expect("batch prepared")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn overlapping_writes_dont_fill_the_blob() {
+        // Every block writes the same slots, so the merged state diff doesn't grow
+        let blocks_updates = (1..=4)
+            .map(|_| storage_updates(1, SLOTS_PER_BLOCK))
+            .collect();
+        let committer = committer_with_blocks(2, blocks_updates, 10, false).await;
+
+        assert_eq!(batch_blocks(&committer, 1).await, (1, 4));
+    }
+
+    #[tokio::test]
+    async fn batch_is_closed_at_the_max_blocks_per_batch() {
+        let blocks_updates = (1..=4).map(|account| storage_updates(account, 1)).collect();
+        let committer = committer_with_blocks(3, blocks_updates, 2, false).await;
+
+        assert_eq!(batch_blocks(&committer, 1).await, (1, 2));
+        assert_eq!(batch_blocks(&committer, 3).await, (3, 4));
+    }
+
+    #[tokio::test]
+    async fn validium_batches_ignore_the_blob_size() {
+        let blocks_updates = (1..=4)
+            .map(|account| storage_updates(account, SLOTS_PER_BLOCK))
+            .collect();
+        let committer = committer_with_blocks(4, blocks_updates, 10, true).await;
+
+        let batch = committer
+            .prepare_batch(1)
+            .await
+            .expect("batch prepared")
+            .expect("batch with blocks");
+        assert_eq!((batch.first_block_number, batch.last_block_number), (1, 4));
+        assert!(batch.state_diff.is_none());
+    }
+
+    #[test]
+    fn eth_withdrawal_leaves_match_sdk() {
+        use ethrex_common::types::{EIP1559Transaction, LegacyTransaction, Log};
+        use keccak_hash::keccak;
+        use zkvm_interface::messages::COMMON_BRIDGE_L2_ADDRESS;
+
+        assert_eq!(
+            COMMON_BRIDGE_L2_ADDRESS,
+            ethrex_l2_sdk::COMMON_BRIDGE_L2_ADDRESS
+        );
+
+        let withdraw_calldata = |receiver: u64| -> bytes::Bytes {
+            encode_calldata(
+                ethrex_l2_sdk::L2_WITHDRAW_SIGNATURE,
+                &[Value::Address(Address::from_low_u64_be(receiver))],
+            )
+            .expect("encoded calldata")
+            .into()
+        };
+        let transactions = vec![
+            Transaction::EIP1559Transaction(EIP1559Transaction {
+                to: TxKind::Call(COMMON_BRIDGE_L2_ADDRESS),
+                value: U256::from(1_000u64),
+                data: withdraw_calldata(0x4ece),
+                ..Default::default()
+            }),
+            Transaction::EIP1559Transaction(EIP1559Transaction {
+                nonce: 1,
+                to: TxKind::Call(COMMON_BRIDGE_L2_ADDRESS),
+                value: U256::MAX,
+                data: withdraw_calldata(0xbeef),
+                ..Default::default()
+            }),
+            Transaction::LegacyTransaction(LegacyTransaction {
+                to: TxKind::Call(COMMON_BRIDGE_L2_ADDRESS),
+                value: U256::one(),
+                data: withdraw_calldata(0x4ece),
+                ..Default::default()
+            }),
+        ];
+        let withdrawal_log = Log {
+            address: COMMON_BRIDGE_L2_ADDRESS,
+            topics: vec![keccak(ethrex_l2_sdk::WITHDRAWAL_EVENT_SIGNATURE.as_bytes())],
+            data: Default::default(),
+        };
+        let receipts: Vec<Receipt> = transactions
+            .iter()
+            .map(|tx| Receipt::new(tx.tx_type(), true, 21_000, vec![withdrawal_log.clone()]))
+            .collect();
+
+        // The leaves the prover commits to have to be the ones the sdk proves when claiming
+        let prover_leaves = get_block_withdrawal_hashes(&transactions, &receipts).expect("leaves");
+        let sdk_leaves: Vec<H256> = transactions
+            .iter()
+            .map(|tx| ethrex_l2_sdk::get_withdrawal_hash(tx).expect("sdk leaf"))
+            .collect();
+        assert_eq!(prover_leaves, sdk_leaves);
+
+        // Calls to the bridge that didn't emit a withdrawal event are left out by both
+        let failed_receipts: Vec<Receipt> = transactions
+            .iter()
+            .map(|tx| Receipt::new(tx.tx_type(), false, 21_000, vec![]))
+            .collect();
+        assert!(get_block_withdrawal_hashes(&transactions, &failed_receipts)
+            .expect("leaves")
+            .is_empty());
+    }
+
+    #[test]
+    fn erc20_withdrawal_leaf_matches_sdk() {
+        use ethrex_common::types::{EIP1559Transaction, Log, TxType};
+        use ethrex_rpc::types::receipt::{RpcReceipt, RpcReceiptBlockInfo, RpcReceiptTxInfo};
+        use keccak_hash::keccak;
+        use zkvm_interface::messages::COMMON_BRIDGE_L2_ADDRESS;
+
+        let token = Address::from_low_u64_be(0x70c3);
+        let sender = Address::from_low_u64_be(0x5e4d);
+        let receiver = Address::from_low_u64_be(0x4ece);
+        let amount = U256::from(1_000_000u64);
+
+        let tx = Transaction::EIP1559Transaction(EIP1559Transaction {
+            to: TxKind::Call(COMMON_BRIDGE_L2_ADDRESS),
+            data: encode_calldata(
+                ethrex_l2_sdk::L2_WITHDRAW_ERC20_SIGNATURE,
+                &[
+                    Value::Address(token),
+                    Value::Address(receiver),
+                    Value::Uint(amount),
+                ],
+            )
+            .expect("encoded calldata")
+            .into(),
+            ..Default::default()
+        });
+        let tx_hash = tx.compute_hash();
+        let log = Log {
+            address: COMMON_BRIDGE_L2_ADDRESS,
+            topics: vec![
+                keccak(ethrex_l2_sdk::ERC20_WITHDRAWAL_EVENT_SIGNATURE.as_bytes()),
+                H256::from(token),
+                H256::from(sender),
+                H256::from(receiver),
+            ],
+            data: amount.to_big_endian().to_vec().into(),
+        };
+        let receipt = Receipt::new(TxType::EIP1559, true, 21_000, vec![log.clone()]);
+
+        // The leaf the prover commits to and the one the sdk proves when claiming have to be
+        // the same, and both the one the L1 bridge rebuilds from the claim arguments
+        let expected = keccak(
+            [
+                token.as_bytes(),
+                receiver.as_bytes(),
+                &amount.to_big_endian(),
+                tx_hash.as_bytes(),
+            ]
+            .concat(),
+        );
+        let prover_leaves =
+            get_block_withdrawal_hashes(&[tx.clone()], &[receipt.clone()]).expect("leaves");
+        assert_eq!(prover_leaves, vec![expected]);
+        assert_eq!(
+            ethrex_l2_sdk::get_erc20_withdrawal_hash(tx_hash, &log.into()),
+            Some(expected)
+        );
+
+        let rpc_receipt = RpcReceipt::new(
+            receipt,
+            RpcReceiptTxInfo {
+                transaction_hash: tx_hash,
+                transaction_index: 0,
+                from: sender,
+                to: Some(COMMON_BRIDGE_L2_ADDRESS),
+                contract_address: None,
+                gas_used: 21_000,
+                effective_gas_price: 0,
+                blob_gas_price: None,
+                blob_gas_used: None,
+                l1_fee: None,
+            },
+            RpcReceiptBlockInfo {
+                block_hash: H256::zero(),
+                block_number: 1,
+            },
+            0,
+        );
+        assert_eq!(
+            ethrex_l2_sdk::get_erc20_withdrawal_amount(&rpc_receipt),
+            Some(amount)
+        );
+    }
+}
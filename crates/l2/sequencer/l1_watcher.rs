@@ -9,18 +9,22 @@ use bytes::Bytes;
 use ethereum_types::{Address, H256, U256};
 use ethrex_blockchain::Blockchain;
 use ethrex_common::{types::Transaction, H160};
+use ethrex_l2_sdk::COMMON_BRIDGE_L2_ADDRESS;
 use ethrex_rpc::types::receipt::RpcLog;
 use ethrex_rpc::{
-    clients::eth::{errors::EthClientError, eth_sender::Overrides, EthClient},
+    clients::eth::{errors::EthClientError, eth_sender::Overrides, BlockByNumber, EthClient},
     types::receipt::RpcLogInfo,
 };
 use ethrex_storage::Store;
 use keccak_hash::keccak;
-use std::{cmp::min, sync::Arc};
+use std::{cmp::min, collections::VecDeque, future::Future, sync::Arc};
 use tracing::{debug, error, info, warn};
 
 use super::utils::sleep_random;
 
+/// Amount of fetched L1 blocks whose hashes are kept to detect reorgs
+const MAX_TRACKED_L1_BLOCKS: usize = 128;
+
 pub async fn start_l1_watcher(
     store: Store,
     blockchain: Arc<Blockchain>,
@@ -37,10 +41,24 @@ pub struct L1Watcher {
     l2_client: EthClient,
     address: Address,
     max_block_step: U256,
+    confirmations: U256,
     last_block_fetched: U256,
+    /// Number and hash of the last block of each fetched range, used to detect L1 reorgs
+    fetched_blocks: VecDeque<(U256, H256)>,
+    /// Deposits added to the mempool that were not included in an L2 block yet
+    pending_deposits: Vec<PendingDeposit>,
     check_interval: u64,
 }
 
+/// Deposit added to the mempool, along with the L1 block and log it comes from
+#[derive(Debug, Clone)]
+struct PendingDeposit {
+    l1_block_number: U256,
+    l1_block_hash: H256,
+    log_index: u64,
+    tx_hash: H256,
+}
+
 impl L1Watcher {
     pub async fn new_from_config(
         watcher_config: L1WatcherConfig,
@@ -55,7 +73,10 @@ impl L1Watcher {
             l2_client,
             address: watcher_config.bridge_address,
             max_block_step: watcher_config.max_block_step,
+            confirmations: watcher_config.confirmations.into(),
             last_block_fetched,
+            fetched_blocks: VecDeque::new(),
+            pending_deposits: Vec::new(),
             check_interval: watcher_config.check_interval_ms,
         })
     }
@@ -76,18 +97,32 @@ impl L1Watcher {
         loop {
             sleep_random(self.check_interval).await;
 
-            let logs = self.get_logs().await?;
+            self.forget_sequenced_deposits(store).await?;
+            self.handle_reorgs(store, blockchain).await?;
 
-            // We may not have a deposit nor a withdrawal, that means no events -> no logs.
-            if logs.is_empty() {
+            let Some((last_block, last_block_hash, logs)) = self.get_logs().await? else {
                 continue;
+            };
+
+            // We may not have a deposit nor a withdrawal, that means no events -> no logs.
+            if !logs.is_empty() {
+                let _deposit_txs = self.process_logs(logs, store, blockchain).await?;
             }
 
-            let _deposit_txs = self.process_logs(logs, store, blockchain).await?;
+            // The range is only marked as fetched once its logs were processed, so it is
+            // fetched again if anything failed
+            self.last_block_fetched = last_block;
+            self.fetched_blocks.push_back((last_block, last_block_hash));
+            if self.fetched_blocks.len() > MAX_TRACKED_L1_BLOCKS {
+                self.fetched_blocks.pop_front();
+            }
         }
     }
 
-    pub async fn get_logs(&mut self) -> Result<Vec<RpcLog>, L1WatcherError> {
+    /// Fetches the deposit logs of the next range of L1 blocks with enough confirmations.
+    /// Returns the number and hash of the last block of the range along with its logs,
+    /// or `None` if there are no new confirmed blocks.
+    pub async fn get_logs(&mut self) -> Result<Option<(U256, H256, Vec<RpcLog>)>, L1WatcherError> {
         if self.last_block_fetched.is_zero() {
             self.last_block_fetched =
                 EthClient::get_last_fetched_l1_block(&self.eth_client, self.address)
@@ -102,18 +137,30 @@ impl L1Watcher {
             current_block, current_block
         );
 
-        let new_last_block = min(self.last_block_fetched + self.max_block_step, current_block);
+        let Some(new_last_block) = confirmed_range_end(
+            self.last_block_fetched,
+            current_block,
+            self.confirmations,
+            self.max_block_step,
+        ) else {
+            return Ok(None);
+        };
+
+        // The hash is read before the logs so a reorg in between is detected afterwards
+        let new_last_block_hash = self.get_block_hash(new_last_block).await?;
 
         debug!(
             "Looking logs from block {:#x} to {:#x}",
-            self.last_block_fetched, new_last_block
+            self.last_block_fetched + 1,
+            new_last_block
         );
 
         // Matches the event DepositInitiated from ICommonBridge.sol
         let topic = keccak(
             b"DepositInitiated(uint256,address,uint256,address,address,uint256,bytes,bytes32)",
         );
-        let logs = match self
+        // On failure the range is not marked as fetched, so it is retried on the next iteration
+        let logs = self
             .eth_client
             .get_logs(
                 self.last_block_fetched + 1,
@@ -121,28 +168,115 @@ impl L1Watcher {
                 self.address,
                 topic,
             )
-            .await
-        {
-            Ok(logs) => logs,
-            Err(error) => {
-                // We may get an error if the RPC doesn't has the logs for the requested
-                // block interval. For example, Light Nodes.
-                warn!("Error when getting logs from L1: {}", error);
-                vec![]
-            }
-        };
+            .await?;
 
         debug!("Logs: {:#?}", logs);
 
-        // If we have an error adding the tx to the mempool we may assign it to the next
-        // block to fetch, but we may lose a deposit tx.
-        self.last_block_fetched = new_last_block;
+        Ok(Some((new_last_block, new_last_block_hash, logs)))
+    }
 
-        Ok(logs)
+    /// Returns the hash of the L1 block with the given number
+    async fn get_block_hash(&self, block_number: U256) -> Result<H256, L1WatcherError> {
+        let block_number = block_number
+            .try_into()
+            .map_err(|_| L1WatcherError::Custom("L1 block number out of range".to_owned()))?;
+        Ok(self
+            .eth_client
+            .get_block_by_number(BlockByNumber::Number(block_number))
+            .await?
+            .hash)
     }
 
-    pub async fn process_logs(
+    /// Returns true if the L1 block with the given number and hash is part of the chain
+    /// whose head has the given number
+    async fn is_canonical(
         &self,
+        block_number: U256,
+        block_hash: H256,
+        head: U256,
+    ) -> Result<bool, L1WatcherError> {
+        Ok(block_number <= head && self.get_block_hash(block_number).await? == block_hash)
+    }
+
+    /// Detects whether the last fetched L1 block is no longer part of the chain. If so,
+    /// logs are fetched again from the latest tracked block that is still part of it, and
+    /// the deposits of the orphaned blocks that were not included in the L2 yet are removed
+    /// from the mempool.
+    async fn handle_reorgs(
+        &mut self,
+        store: &Store,
+        blockchain: &Blockchain,
+    ) -> Result<(), L1WatcherError> {
+        let Some((block_number, block_hash)) = self.fetched_blocks.back().copied() else {
+            return Ok(());
+        };
+        let head = self.eth_client.get_block_number().await?;
+        if self.is_canonical(block_number, block_hash, head).await? {
+            return Ok(());
+        }
+
+        // The latest tracked block that is still part of the chain
+        let fork_point = find_fork_point(&self.fetched_blocks, |number, hash| {
+            self.is_canonical(number, hash, head)
+        })
+        .await?;
+        let (tracked_blocks, fork_block) = match fork_point {
+            Some(fork_point) => fork_point,
+            None => {
+                // The reorg is deeper than the tracked blocks, so logs are fetched again from
+                // the last fetched L1 block stored in the bridge, as no deposit can be older
+                // than it. Deposits already included in the L2 or in the mempool are skipped.
+                let last_fetched_block: U256 =
+                    EthClient::get_last_fetched_l1_block(&self.eth_client, self.address)
+                        .await?
+                        .into();
+                error!("L1 reorg deeper than the tracked blocks, fetching logs again from the last fetched L1 block {last_fetched_block}");
+                (0, last_fetched_block)
+            }
+        };
+        warn!("L1 reorg detected, fetching logs again after block {fork_block}");
+
+        // Orphaned deposits are removed before rewinding, so they're not missed if this fails
+        let orphaned =
+            find_orphaned_deposits(&self.pending_deposits, fork_block, |number, hash| {
+                self.is_canonical(number, hash, head)
+            })
+            .await?;
+        for tx_hash in orphaned {
+            if store.get_transaction_by_hash(tx_hash).await?.is_some() {
+                error!("Deposit {tx_hash:#x} was included in the L2 but its L1 block was reorged");
+            } else {
+                warn!("Removing deposit {tx_hash:#x} from the mempool, its L1 block was reorged");
+                blockchain.remove_transaction_from_pool(&tx_hash)?;
+            }
+            self.pending_deposits
+                .retain(|deposit| deposit.tx_hash != tx_hash);
+        }
+
+        self.fetched_blocks.truncate(tracked_blocks);
+        self.last_block_fetched = fork_block;
+        Ok(())
+    }
+
+    /// Stops tracking the deposits that were already included in an L2 block
+    async fn forget_sequenced_deposits(&mut self, store: &Store) -> Result<(), L1WatcherError> {
+        let mut sequenced = Vec::new();
+        for deposit in &self.pending_deposits {
+            if store
+                .get_transaction_by_hash(deposit.tx_hash)
+                .await?
+                .is_some()
+            {
+                sequenced.push(deposit.tx_hash);
+            }
+        }
+        self.pending_deposits
+            .retain(|deposit| !sequenced.contains(&deposit.tx_hash));
+        Ok(())
+    }
+
+    pub async fn process_logs(
+        &mut self,
         logs: Vec<RpcLog>,
         store: &Store,
         blockchain: &Blockchain,
@@ -150,6 +284,14 @@ impl L1Watcher {
         let mut deposit_txs = Vec::new();
 
         for log in logs {
+            // Logs are fetched again after a reorg or a failure, deposits already in the
+            // mempool are skipped
+            if is_pending_deposit(&self.pending_deposits, log.block_hash, log.log_index) {
+                continue;
+            }
+            let (l1_block_number, l1_block_hash, log_index) =
+                (log.block_number.into(), log.block_hash, log.log_index);
+
             let (mint_value, to_address, deposit_id, recipient, from, gas_limit, calldata) =
                 parse_values_log(log.log)?;
 
@@ -180,7 +322,15 @@ impl L1Watcher {
                 continue;
             }
 
-            info!("Initiating mint transaction for {recipient:#x} with value {mint_value:#x} and depositId: {deposit_id:#}",);
+            if from == COMMON_BRIDGE_L2_ADDRESS {
+                // ERC20 deposits call the L2 bridge with the token data, which mints the tokens
+                info!("Initiating ERC20 deposit transaction with depositId: {deposit_id:#}");
+            } else if mint_value.is_zero() {
+                // Forced transactions are queued by their sender through the L1 bridge
+                info!("Initiating forced transaction from {from:#x} to {to_address:#x} with depositId: {deposit_id:#}");
+            } else {
+                info!("Initiating mint transaction for {recipient:#x} with value {mint_value:#x} and depositId: {deposit_id:#}",);
+            }
 
             let gas_price = self.l2_client.get_gas_price().await?;
             // Avoid panicking when using as_u64()
@@ -220,26 +370,89 @@ impl L1Watcher {
                 )
                 .await?;
 
-            match blockchain
+            // On failure the range is fetched again, the deposits of this range that were
+            // already added are tracked as pending so they're not added twice
+            let hash = blockchain
                 .add_transaction_to_pool(Transaction::PrivilegedL2Transaction(mint_transaction))
-                .await
-            {
-                Ok(hash) => {
-                    info!("Mint transaction added to mempool {hash:#x}",);
-                    deposit_txs.push(hash);
-                }
-                Err(e) => {
-                    warn!("Failed to add mint transaction to the mempool: {e:#?}");
-                    // TODO: Figure out if we want to continue or not
-                    continue;
-                }
-            }
+                .await?;
+            info!("Mint transaction added to mempool {hash:#x}",);
+            self.pending_deposits.push(PendingDeposit {
+                l1_block_number,
+                l1_block_hash,
+                log_index,
+                tx_hash: hash,
+            });
+            deposit_txs.push(hash);
         }
 
         Ok(deposit_txs)
     }
 }
 
+/// Returns the last block of the next range of L1 blocks to fetch, which must have at least
+/// `confirmations` blocks on top of it, or `None` if there are no new confirmed blocks.
+fn confirmed_range_end(
+    last_block_fetched: U256,
+    current_block: U256,
+    confirmations: U256,
+    max_block_step: U256,
+) -> Option<U256> {
+    let confirmed_block = current_block.checked_sub(confirmations)?;
+    let new_last_block = min(last_block_fetched + max_block_step, confirmed_block);
+    (new_last_block > last_block_fetched).then_some(new_last_block)
+}
+
+/// Returns the amount of tracked blocks up to the latest one that is still canonical,
+/// along with its number, or `None` if none of them is canonical anymore.
+async fn find_fork_point<F, Fut>(
+    fetched_blocks: &VecDeque<(U256, H256)>,
+    mut is_canonical: F,
+) -> Result<Option<(usize, U256)>, L1WatcherError>
+where
+    F: FnMut(U256, H256) -> Fut,
+    Fut: Future<Output = Result<bool, L1WatcherError>>,
+{
+    for (index, (block_number, block_hash)) in fetched_blocks.iter().enumerate().rev() {
+        if is_canonical(*block_number, *block_hash).await? {
+            return Ok(Some((index + 1, *block_number)));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the hashes of the pending deposits whose L1 block is after the fork block and
+/// is not canonical anymore.
+async fn find_orphaned_deposits<F, Fut>(
+    pending_deposits: &[PendingDeposit],
+    fork_block: U256,
+    mut is_canonical: F,
+) -> Result<Vec<H256>, L1WatcherError>
+where
+    F: FnMut(U256, H256) -> Fut,
+    Fut: Future<Output = Result<bool, L1WatcherError>>,
+{
+    let mut orphaned = Vec::new();
+    for deposit in pending_deposits {
+        if deposit.l1_block_number > fork_block
+            && !is_canonical(deposit.l1_block_number, deposit.l1_block_hash).await?
+        {
+            orphaned.push(deposit.tx_hash);
+        }
+    }
+    Ok(orphaned)
+}
+
+/// Returns true if the deposit emitted by the given log was already added to the mempool
+fn is_pending_deposit(
+    pending_deposits: &[PendingDeposit],
+    block_hash: H256,
+    log_index: u64,
+) -> bool {
+    pending_deposits
+        .iter()
+        .any(|deposit| deposit.l1_block_hash == block_hash && deposit.log_index == log_index)
+}
+
 #[allow(clippy::type_complexity)]
 fn parse_values_log(
     log: RpcLogInfo,
@@ -335,3 +548,110 @@ fn parse_values_log(
         calldata.to_vec(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u64) -> (U256, H256) {
+        (U256::from(number), H256::from_low_u64_be(number))
+    }
+
+    fn deposit(l1_block: u64, log_index: u64) -> PendingDeposit {
+        let (l1_block_number, l1_block_hash) = block(l1_block);
+        PendingDeposit {
+            l1_block_number,
+            l1_block_hash,
+            log_index,
+            tx_hash: H256::from_low_u64_be(l1_block * 100 + log_index),
+        }
+    }
+
+    /// Blocks up to `fork_block` keep their hash, the following ones were reorged
+    fn canonical_until(
+        fork_block: u64,
+    ) -> impl FnMut(U256, H256) -> std::future::Ready<Result<bool, L1WatcherError>> {
+        move |number, hash| {
+            std::future::ready(Ok(
+                number <= U256::from(fork_block) && block(number.as_u64()).1 == hash
+            ))
+        }
+    }
+
+    #[test]
+    fn range_end_waits_for_confirmations() {
+        let (last, step, confirmations) = (U256::from(100), U256::from(50), U256::from(12));
+        // Not enough confirmations for any new block
+        assert_eq!(
+            confirmed_range_end(last, U256::from(112), confirmations, step),
+            None
+        );
+        assert_eq!(
+            confirmed_range_end(last, U256::from(113), confirmations, step),
+            Some(U256::from(101))
+        );
+        // The range is capped by the max block step
+        assert_eq!(
+            confirmed_range_end(last, U256::from(1000), confirmations, step),
+            Some(U256::from(150))
+        );
+        // The chain is shorter than the confirmation depth
+        assert_eq!(
+            confirmed_range_end(U256::zero(), U256::from(5), confirmations, step),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn fork_point_is_latest_canonical_tracked_block() {
+        let fetched_blocks: VecDeque<_> = [10, 20, 30, 40].into_iter().map(block).collect();
+
+        let fork_point = find_fork_point(&fetched_blocks, canonical_until(40))
+            .await
+            .unwrap();
+        assert_eq!(fork_point, Some((4, U256::from(40))));
+
+        let fork_point = find_fork_point(&fetched_blocks, canonical_until(25))
+            .await
+            .unwrap();
+        assert_eq!(fork_point, Some((2, U256::from(20))));
+
+        // The reorg is deeper than the tracked blocks
+        let fork_point = find_fork_point(&fetched_blocks, canonical_until(5))
+            .await
+            .unwrap();
+        assert_eq!(fork_point, None);
+    }
+
+    #[tokio::test]
+    async fn reorg_removes_only_orphaned_deposits() {
+        let pending_deposits = vec![deposit(15, 0), deposit(25, 0), deposit(25, 1)];
+        let orphaned =
+            find_orphaned_deposits(&pending_deposits, U256::from(20), canonical_until(20))
+                .await
+                .unwrap();
+        assert_eq!(
+            orphaned,
+            vec![deposit(25, 0).tx_hash, deposit(25, 1).tx_hash]
+        );
+
+        let orphaned =
+            find_orphaned_deposits(&pending_deposits, U256::from(30), canonical_until(30))
+                .await
+                .unwrap();
+        assert!(orphaned.is_empty());
+    }
+
+    #[test]
+    fn fetched_again_logs_are_deduplicated() {
+        let pending_deposits = vec![deposit(15, 0), deposit(25, 1)];
+        assert!(is_pending_deposit(&pending_deposits, block(25).1, 1));
+        // Same log index in a different (reorged) block
+        assert!(!is_pending_deposit(
+            &pending_deposits,
+            H256::repeat_byte(0xaa),
+            1
+        ));
+        assert!(!is_pending_deposit(&pending_deposits, block(25).1, 0));
+    }
+}
@@ -7,29 +7,47 @@ use crate::utils::{
         committer::CommitterConfig, errors::ConfigError, eth::EthConfig,
         proof_coordinator::ProofCoordinatorConfig,
     },
-    prover::proving_systems::ProofCalldata,
+    prover::{
+        protocol::{read_message, send_challenge, write_message, SignedMessage, MAX_REQUEST_SIZE},
+        proving_systems::{ProofCalldata, ProverType},
+    },
 };
 use ethrex_common::{
     types::{Block, BlockHeader},
-    Address,
+    Address, H256,
 };
 use ethrex_rpc::clients::eth::EthClient;
 use ethrex_storage::Store;
 use ethrex_vm::{Evm, EvmError, ExecutionDB};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Debug, net::IpAddr};
+use serde_with::serde_as;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    net::IpAddr,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
     net::{TcpListener, TcpStream},
     sync::TryAcquireError,
+    time::timeout,
 };
 use tracing::{debug, error, info, warn};
+use zkvm_interface::io::SerdeJSON;
 
 use super::utils::sleep_random;
 
+/// Maximum time a prover can take to send its request once connected
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[serde_as]
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ProverInputData {
-    pub block: Block,
+    #[serde_as(as = "SerdeJSON")]
+    pub blocks: Vec<Block>,
+    #[serde_as(as = "SerdeJSON")]
     pub parent_block_header: BlockHeader,
     pub db: ExecutionDB,
 }
@@ -41,70 +59,238 @@ struct ProofCoordinator {
     store: Store,
     eth_client: EthClient,
     on_chain_proposer_address: Address,
+    prover_whitelist: Vec<Address>,
+    lease_timeout: Duration,
+    registry: Arc<Mutex<ProverRegistry>>,
 }
 
 /// Enum for the ProverServer <--> ProverClient Communication Protocol.
+/// The messages sent by the Client are signed with its key, see [SignedMessage].
 #[derive(Serialize, Deserialize)]
 pub enum ProofData {
     /// 1.
-    /// The Client initiates the connection with a BlockRequest.
-    /// Asking for the ProverInputData the prover_server considers/needs.
-    BlockRequest,
+    /// The Client registers itself with the proving system it uses.
+    /// Only Clients whose address is in the Server's whitelist can register.
+    ProverRegister { prover_type: ProverType },
 
     /// 2.
-    /// The Server responds with a BlockResponse containing the ProverInputData.
-    /// If the BlockResponse is ProofData::BlockResponse{None, None},
-    /// the Client knows the BlockRequest couldn't be performed.
-    BlockResponse {
-        block_number: Option<u64>,
+    /// The Server acknowledges the registration with the interval
+    /// at which the Client has to send heartbeats while proving.
+    ProverRegisterACK { heartbeat_interval_ms: u64 },
+
+    /// 3.
+    /// The Client asks for a batch to prove with a BatchRequest.
+    /// Asking for the ProverInputData the prover_server considers/needs.
+    BatchRequest,
+
+    /// 4.
+    /// The Server responds with a BatchResponse containing the ProverInputData,
+    /// and leases the batch to the Client.
+    /// If the BatchResponse is ProofData::BatchResponse{None, None},
+    /// the Client knows the BatchRequest couldn't be performed.
+    BatchResponse {
+        batch_number: Option<u64>,
         input: Option<ProverInputData>,
     },
 
-    /// 3.
+    /// 5.
+    /// While proving, the Client periodically sends a Heartbeat to renew its lease.
+    /// If the lease expires, the batch can be assigned to another Client.
+    Heartbeat { batch_number: u64 },
+
+    /// 6.
+    /// The Server responds whether the Client still holds the lease of the batch.
+    HeartbeatACK { batch_number: u64, renewed: bool },
+
+    /// 7.
     /// The Client submits the zk Proof generated by the prover
-    /// for the specified block, as calldata for the verifier contract.
+    /// for the specified batch, as calldata for the verifier contract.
     ProofSubmit {
-        block_number: u64,
+        batch_number: u64,
         calldata: ProofCalldata,
     },
 
-    /// 4.
+    /// 8.
     /// The Server acknowledges the receipt of the proof and updates its state,
-    ProofSubmitACK { block_number: u64 },
+    ProofSubmitACK { batch_number: u64 },
+
+    /// The Server couldn't authenticate the request or the Client isn't registered.
+    Rejected { reason: String },
 }
 
 impl ProofData {
-    /// Builder function for creating a BlockRequest
-    pub fn block_request() -> Self {
-        ProofData::BlockRequest
+    /// Builder function for creating a ProverRegister
+    pub fn prover_register(prover_type: ProverType) -> Self {
+        ProofData::ProverRegister { prover_type }
     }
 
-    /// Builder function for creating a BlockResponse
-    pub fn block_response(block_number: u64, input: ProverInputData) -> Self {
-        ProofData::BlockResponse {
-            block_number: Some(block_number),
+    /// Builder function for creating a ProverRegisterACK
+    pub fn prover_register_ack(heartbeat_interval: Duration) -> Self {
+        ProofData::ProverRegisterACK {
+            heartbeat_interval_ms: u64::try_from(heartbeat_interval.as_millis())
+                .unwrap_or(u64::MAX),
+        }
+    }
+
+    /// Builder function for creating a BatchRequest
+    pub fn batch_request() -> Self {
+        ProofData::BatchRequest
+    }
+
+    /// Builder function for creating a BatchResponse
+    pub fn batch_response(batch_number: u64, input: ProverInputData) -> Self {
+        ProofData::BatchResponse {
+            batch_number: Some(batch_number),
             input: Some(input),
         }
     }
 
-    pub fn empty_block_response() -> Self {
-        ProofData::BlockResponse {
-            block_number: None,
+    pub fn empty_batch_response() -> Self {
+        ProofData::BatchResponse {
+            batch_number: None,
             input: None,
         }
     }
 
     /// Builder function for creating a ProofSubmit
-    pub fn proof_submit(block_number: u64, calldata: ProofCalldata) -> Self {
+    pub fn proof_submit(batch_number: u64, calldata: ProofCalldata) -> Self {
         ProofData::ProofSubmit {
-            block_number,
+            batch_number,
             calldata,
         }
     }
 
     /// Builder function for creating a ProofSubmitAck
-    pub fn proof_submit_ack(block_number: u64) -> Self {
-        ProofData::ProofSubmitACK { block_number }
+    pub fn proof_submit_ack(batch_number: u64) -> Self {
+        ProofData::ProofSubmitACK { batch_number }
+    }
+
+    /// Builder function for creating a Heartbeat
+    pub fn heartbeat(batch_number: u64) -> Self {
+        ProofData::Heartbeat { batch_number }
+    }
+
+    /// Builder function for creating a HeartbeatACK
+    pub fn heartbeat_ack(batch_number: u64, renewed: bool) -> Self {
+        ProofData::HeartbeatACK {
+            batch_number,
+            renewed,
+        }
+    }
+
+    /// Builder function for creating a Rejected response
+    pub fn rejected(reason: impl Into<String>) -> Self {
+        ProofData::Rejected {
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Assignment of a batch to a prover
+#[derive(Debug)]
+struct Lease {
+    prover: Address,
+    expires_at: Instant,
+}
+
+/// Registered provers and the batches they are proving
+#[derive(Debug, Default)]
+struct ProverRegistry {
+    /// Proving system used by each registered prover
+    provers: HashMap<Address, ProverType>,
+    /// Leases indexed by batch number and proving system,
+    /// as a batch has to be proven once by each proving system
+    leases: HashMap<(u64, ProverType), Lease>,
+}
+
+impl ProverRegistry {
+    /// Leases the batch to the prover, unless another prover holds a lease that hasn't
+    /// expired yet. Returns whether the prover holds the lease.
+    fn acquire_lease(
+        &mut self,
+        batch_number: u64,
+        prover_type: ProverType,
+        prover: Address,
+        lease_timeout: Duration,
+    ) -> bool {
+        let now = Instant::now();
+        match self.leases.get(&(batch_number, prover_type)) {
+            Some(lease) if lease.prover != prover && lease.expires_at > now => false,
+            _ => {
+                self.leases.insert(
+                    (batch_number, prover_type),
+                    Lease {
+                        prover,
+                        expires_at: now + lease_timeout,
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    /// Extends the lease of the batch if the prover holds it. Returns whether it was renewed.
+    fn renew_lease(
+        &mut self,
+        batch_number: u64,
+        prover_type: ProverType,
+        prover: Address,
+        lease_timeout: Duration,
+    ) -> bool {
+        match self.leases.get_mut(&(batch_number, prover_type)) {
+            Some(lease) if lease.prover == prover => {
+                lease.expires_at = Instant::now() + lease_timeout;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the batch is leased to the prover. An expired lease is still held until the
+    /// batch is assigned to another prover.
+    fn holds_lease(&self, batch_number: u64, prover_type: ProverType, prover: Address) -> bool {
+        self.leases
+            .get(&(batch_number, prover_type))
+            .is_some_and(|lease| lease.prover == prover)
+    }
+
+    fn release_lease(&mut self, batch_number: u64, prover_type: ProverType, prover: Address) {
+        if self
+            .leases
+            .get(&(batch_number, prover_type))
+            .is_some_and(|lease| lease.prover == prover)
+        {
+            self.leases.remove(&(batch_number, prover_type));
+        }
+    }
+
+    /// Leases to the prover the first batch of the range that isn't proven by its proving
+    /// system yet nor leased to another prover. Any lease previously held by the prover
+    /// is released, as provers work on a single batch at a time.
+    fn assign_batch<E>(
+        &mut self,
+        batches: RangeInclusive<u64>,
+        prover_type: ProverType,
+        prover: Address,
+        lease_timeout: Duration,
+        is_proven: impl Fn(u64) -> Result<bool, E>,
+    ) -> Result<Option<u64>, E> {
+        self.leases.retain(|_, lease| lease.prover != prover);
+        for batch_number in batches {
+            if is_proven(batch_number)? {
+                continue;
+            }
+            if self.acquire_lease(batch_number, prover_type, prover, lease_timeout) {
+                return Ok(Some(batch_number));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Forgets the leases of the batches that were already verified
+    fn remove_verified_leases(&mut self, last_verified_batch: u64) {
+        self.leases
+            .retain(|(batch_number, _), _| *batch_number > last_verified_batch);
     }
 }
 
@@ -140,6 +326,9 @@ impl ProofCoordinator {
             store,
             eth_client,
             on_chain_proposer_address,
+            prover_whitelist: config.prover_whitelist,
+            lease_timeout: Duration::from_millis(config.lease_timeout_ms),
+            registry: Arc::new(Mutex::new(ProverRegistry::default())),
         })
     }
 
@@ -202,125 +391,340 @@ impl ProofCoordinator {
     }
 
     async fn handle_connection(&self, mut stream: TcpStream) -> Result<(), ProverServerError> {
-        let mut buffer = Vec::new();
-        stream.read_to_end(&mut buffer).await?;
+        let challenge = send_challenge(&mut stream).await?;
+        let request: SignedMessage<ProofData> = timeout(
+            CONNECTION_TIMEOUT,
+            read_message(&mut stream, MAX_REQUEST_SIZE),
+        )
+        .await
+        .map_err(|_| ProverServerError::ConnectionTimeout)??;
+
+        let response = match self.authenticate(&request, challenge) {
+            Ok(prover) => self.handle_request(prover, request.data).await?,
+            Err(reason) => {
+                warn!("Rejected request: {reason}");
+                ProofData::rejected(reason)
+            }
+        };
+        write_message(&mut stream, &response).await?;
+
+        debug!("Connection closed");
+        Ok(())
+    }
+
+    /// Returns the address of the prover that signed the request with the challenge
+    /// of its connection, or the reason why it was rejected
+    fn authenticate(
+        &self,
+        request: &SignedMessage<ProofData>,
+        challenge: H256,
+    ) -> Result<Address, String> {
+        let prover = request
+            .recover_signer(challenge)
+            .map_err(|e| format!("Failed to authenticate prover: {e}"))?;
+        if !self.prover_whitelist.contains(&prover) {
+            return Err(format!("Prover {prover:#x} is not whitelisted"));
+        }
+        Ok(prover)
+    }
+
+    async fn handle_request(
+        &self,
+        prover: Address,
+        data: ProofData,
+    ) -> Result<ProofData, ProverServerError> {
+        if let ProofData::ProverRegister { prover_type } = data {
+            return self.handle_register(prover, prover_type);
+        }
 
-        let data: Result<ProofData, _> = serde_json::from_slice(&buffer);
+        let Some(prover_type) = self.registry()?.provers.get(&prover).copied() else {
+            warn!("Request from unregistered prover {prover:#x}");
+            return Ok(ProofData::rejected(format!(
+                "Prover {prover:#x} is not registered"
+            )));
+        };
         match data {
-            Ok(ProofData::BlockRequest) => {
-                if let Err(e) = self.handle_request(&mut stream).await {
-                    error!("Failed to handle BlockRequest: {e}");
-                }
+            ProofData::BatchRequest => self.handle_batch_request(prover, prover_type).await,
+            ProofData::Heartbeat { batch_number } => {
+                self.handle_heartbeat(prover, prover_type, batch_number)
             }
-            Ok(ProofData::ProofSubmit {
-                block_number,
+            ProofData::ProofSubmit {
+                batch_number,
                 calldata,
-            }) => {
-                if let Err(e) = self
-                    .handle_submit(&mut stream, block_number, calldata)
-                    .await
-                {
-                    error!("Failed to handle ProofSubmit: {e}");
-                }
-            }
-            Err(e) => {
-                warn!("Failed to parse request: {e}");
-            }
+            } => self.handle_submit(prover, prover_type, batch_number, calldata),
             _ => {
                 warn!("Invalid request");
+                Ok(ProofData::rejected("Invalid request"))
             }
         }
-
-        debug!("Connection closed");
-        Ok(())
     }
 
-    async fn handle_request(&self, stream: &mut TcpStream) -> Result<(), ProverServerError> {
-        info!("BlockRequest received");
+    fn registry(&self) -> Result<std::sync::MutexGuard<'_, ProverRegistry>, ProverServerError> {
+        self.registry
+            .lock()
+            .map_err(|e| ProverServerError::RegistryLock(e.to_string()))
+    }
 
-        let block_to_verify = 1 + EthClient::get_last_verified_block(
-            &self.eth_client,
-            self.on_chain_proposer_address,
-        )
-        .await?;
+    fn handle_register(
+        &self,
+        prover: Address,
+        prover_type: ProverType,
+    ) -> Result<ProofData, ProverServerError> {
+        info!("ProverRegister received from {prover:#x} using {prover_type}");
+        self.registry()?.provers.insert(prover, prover_type);
+        // Leave room for a couple of heartbeats to be lost before the lease expires
+        Ok(ProofData::prover_register_ack(self.lease_timeout / 3))
+    }
 
-        let latest_block_number = self.store.get_latest_block_number().await?;
+    async fn handle_batch_request(
+        &self,
+        prover: Address,
+        prover_type: ProverType,
+    ) -> Result<ProofData, ProverServerError> {
+        info!("BatchRequest received from {prover:#x}");
+
+        let last_verified_batch =
+            EthClient::get_last_verified_batch(&self.eth_client, self.on_chain_proposer_address)
+                .await?;
+
+        let last_committed_batch =
+            EthClient::get_last_committed_batch(&self.eth_client, self.on_chain_proposer_address)
+                .await?;
+
+        // Every committed batch can be proven in parallel, the proofs are sent to the L1
+        // in order by the L1ProofSender
+        let (batch_to_prove, in_flight) = {
+            let mut registry = self.registry()?;
+            registry.remove_verified_leases(last_verified_batch);
+            let batch_to_prove = registry.assign_batch(
+                (last_verified_batch + 1)..=last_committed_batch,
+                prover_type,
+                prover,
+                self.lease_timeout,
+                |batch_number| {
+                    block_number_has_state_file(StateFileType::Proof(prover_type), batch_number)
+                },
+            )?;
+            (batch_to_prove, registry.leases.len())
+        };
+        let Some(batch_to_prove) = batch_to_prove else {
+            debug!("No batches left to assign, sending empty BatchResponse");
+            return Ok(ProofData::empty_batch_response());
+        };
+        debug!("Batch {batch_to_prove} assigned to {prover:#x}, {in_flight} batches being proven");
 
-        let response = if block_to_verify > latest_block_number {
-            let response = ProofData::empty_block_response();
-            debug!("Sending empty BlockResponse");
-            response
-        } else {
-            let input = self.create_prover_input(block_to_verify).await?;
-            let response = ProofData::block_response(block_to_verify, input);
-            debug!("Sending BlockResponse for block_number: {block_to_verify}");
-            response
+        let input = match self.create_prover_input(batch_to_prove).await {
+            Ok(input) => input,
+            Err(e) => {
+                self.registry()?
+                    .release_lease(batch_to_prove, prover_type, prover);
+                return Err(e);
+            }
         };
+        info!("Sending BatchResponse for batch number: {batch_to_prove}");
+        Ok(ProofData::batch_response(batch_to_prove, input))
+    }
 
-        let buffer = serde_json::to_vec(&response)?;
-        stream
-            .write_all(&buffer)
-            .await
-            .map_err(ProverServerError::ConnectionError)
-            .map(|_| info!("BlockResponse sent for block number: {block_to_verify}"))
+    fn handle_heartbeat(
+        &self,
+        prover: Address,
+        prover_type: ProverType,
+        batch_number: u64,
+    ) -> Result<ProofData, ProverServerError> {
+        debug!("Heartbeat received from {prover:#x} for batch number: {batch_number}");
+        let renewed =
+            self.registry()?
+                .renew_lease(batch_number, prover_type, prover, self.lease_timeout);
+        if !renewed {
+            warn!("Prover {prover:#x} lost the lease of batch {batch_number}");
+        }
+        Ok(ProofData::heartbeat_ack(batch_number, renewed))
     }
 
-    async fn handle_submit(
+    fn handle_submit(
         &self,
-        stream: &mut TcpStream,
-        block_number: u64,
+        prover: Address,
+        prover_type: ProverType,
+        batch_number: u64,
         calldata: ProofCalldata,
-    ) -> Result<(), ProverServerError> {
-        info!("ProofSubmit received for block number: {block_number}");
+    ) -> Result<ProofData, ProverServerError> {
+        info!("ProofSubmit received from {prover:#x} for batch number: {batch_number}");
+
+        if calldata.prover_type != prover_type {
+            warn!(
+                "Prover {prover:#x} registered as {prover_type} submitted a {} proof",
+                calldata.prover_type
+            );
+            return Ok(ProofData::rejected(format!(
+                "Prover is registered as {prover_type}"
+            )));
+        }
+
+        // Only the prover the batch was assigned to can submit its proof
+        if !self
+            .registry()?
+            .holds_lease(batch_number, prover_type, prover)
+        {
+            warn!("Prover {prover:#x} submitted a proof for batch {batch_number} without holding its lease");
+            return Ok(ProofData::rejected(format!(
+                "Prover doesn't hold the lease of batch {batch_number}"
+            )));
+        }
 
         // Check if we have the proof for that ProverType
-        if block_number_has_state_file(StateFileType::Proof(calldata.prover_type), block_number)? {
+        if block_number_has_state_file(StateFileType::Proof(calldata.prover_type), batch_number)? {
             debug!("Already known proof. Skipping");
         } else {
-            write_state(block_number, &StateType::Proof(calldata))?;
+            write_state(batch_number, &StateType::Proof(calldata))?;
         }
+        self.registry()?
+            .release_lease(batch_number, prover_type, prover);
 
-        let response = ProofData::proof_submit_ack(block_number);
-
-        let buffer = serde_json::to_vec(&response)?;
-        stream
-            .write_all(&buffer)
-            .await
-            .map_err(ProverServerError::ConnectionError)
-            .map(|_| info!("ProofSubmit ACK sent"))
+        info!("Sending ProofSubmit ACK for batch number: {batch_number}");
+        Ok(ProofData::proof_submit_ack(batch_number))
     }
 
     async fn create_prover_input(
         &self,
-        block_number: u64,
+        batch_number: u64,
     ) -> Result<ProverInputData, ProverServerError> {
-        let header = self
-            .store
-            .get_block_header(block_number)?
-            .ok_or(ProverServerError::StorageDataIsNone)?;
-        let body = self
-            .store
-            .get_block_body(block_number)
-            .await?
-            .ok_or(ProverServerError::StorageDataIsNone)?;
+        let (first_block_number, last_block_number) = EthClient::get_batch_block_range(
+            &self.eth_client,
+            self.on_chain_proposer_address,
+            batch_number,
+        )
+        .await?;
 
-        let block = Block::new(header, body);
+        let mut blocks = Vec::new();
+        for block_number in first_block_number..=last_block_number {
+            let header = self
+                .store
+                .get_block_header(block_number)?
+                .ok_or(ProverServerError::StorageDataIsNone)?;
+            let body = self
+                .store
+                .get_block_body(block_number)
+                .await?
+                .ok_or(ProverServerError::StorageDataIsNone)?;
+            blocks.push(Block::new(header, body));
+        }
 
-        let db = Evm::to_execution_db(&self.store.clone(), &block)
+        let db = Evm::to_execution_db(&self.store.clone(), &blocks)
             .await
             .map_err(EvmError::ExecutionDB)?;
 
+        let parent_hash = blocks
+            .first()
+            .ok_or(ProverServerError::StorageDataIsNone)?
+            .header
+            .parent_hash;
         let parent_block_header = self
             .store
-            .get_block_header_by_hash(block.header.parent_hash)?
+            .get_block_header_by_hash(parent_hash)?
             .ok_or(ProverServerError::StorageDataIsNone)?;
 
-        debug!("Created prover input for block {block_number}");
+        debug!(
+            "Created prover input for batch {batch_number} (blocks {first_block_number} to {last_block_number})"
+        );
 
         Ok(ProverInputData {
             db,
-            block,
+            blocks,
             parent_block_header,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEASE_TIMEOUT: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn leases_are_exclusive_until_they_expire() {
+        let mut registry = ProverRegistry::default();
+        let first = Address::from_low_u64_be(1);
+        let second = Address::from_low_u64_be(2);
+
+        assert!(registry.acquire_lease(1, ProverType::Exec, first, LEASE_TIMEOUT));
+        assert!(!registry.acquire_lease(1, ProverType::Exec, second, LEASE_TIMEOUT));
+        // Heartbeats renew the lease of its holder only
+        assert!(registry.renew_lease(1, ProverType::Exec, first, LEASE_TIMEOUT));
+        assert!(!registry.renew_lease(1, ProverType::Exec, second, LEASE_TIMEOUT));
+        assert!(registry.holds_lease(1, ProverType::Exec, first));
+        assert!(!registry.holds_lease(1, ProverType::Exec, second));
+        // Each proving system proves the batch independently
+        assert!(registry.acquire_lease(1, ProverType::SP1, second, LEASE_TIMEOUT));
+
+        // Submitting the proof releases the lease
+        registry.release_lease(1, ProverType::Exec, first);
+        assert!(!registry.holds_lease(1, ProverType::Exec, first));
+        assert!(registry.acquire_lease(1, ProverType::Exec, second, LEASE_TIMEOUT));
+    }
+
+    #[test]
+    fn heartbeats_dont_lease_unassigned_batches() {
+        let mut registry = ProverRegistry::default();
+        let prover = Address::from_low_u64_be(1);
+
+        assert!(!registry.renew_lease(1, ProverType::Exec, prover, LEASE_TIMEOUT));
+        assert!(!registry.holds_lease(1, ProverType::Exec, prover));
+        assert!(registry.leases.is_empty());
+    }
+
+    #[test]
+    fn expired_lease_is_reassigned() {
+        let mut registry = ProverRegistry::default();
+        let crashed = Address::from_low_u64_be(1);
+        let other = Address::from_low_u64_be(2);
+
+        assert!(registry.acquire_lease(1, ProverType::Exec, crashed, Duration::ZERO));
+        assert!(registry.acquire_lease(1, ProverType::Exec, other, LEASE_TIMEOUT));
+        // A late heartbeat from the previous holder doesn't take the batch back
+        assert!(!registry.renew_lease(1, ProverType::Exec, crashed, LEASE_TIMEOUT));
+        assert!(!registry.holds_lease(1, ProverType::Exec, crashed));
+
+        registry.remove_verified_leases(1);
+        assert!(registry.leases.is_empty());
+    }
+
+    #[test]
+    fn provers_are_assigned_distinct_batches() {
+        let mut registry = ProverRegistry::default();
+        let provers: Vec<Address> = (1..=3).map(Address::from_low_u64_be).collect();
+        // Batch 2 was already proven and its proof is waiting for batch 1's
+        let is_proven = |batch_number| Ok::<_, ()>(batch_number == 2);
+
+        let assigned: Vec<Option<u64>> = provers
+            .iter()
+            .map(|prover| {
+                registry
+                    .assign_batch(1..=4, ProverType::Exec, *prover, LEASE_TIMEOUT, is_proven)
+                    .ok()
+                    .flatten()
+            })
+            .collect();
+        assert_eq!(assigned, vec![Some(1), Some(3), Some(4)]);
+
+        // There's nothing left to prove for a fourth prover
+        let fourth = Address::from_low_u64_be(4);
+        assert_eq!(
+            registry.assign_batch(1..=4, ProverType::Exec, fourth, LEASE_TIMEOUT, is_proven),
+            Ok(None)
+        );
+
+        // Asking for a new batch releases the previous one
+        let first = Address::from_low_u64_be(1);
+        assert_eq!(
+            registry.assign_batch(1..=4, ProverType::Exec, first, LEASE_TIMEOUT, |n| {
+                Ok::<_, ()>(n <= 2)
+            }),
+            Ok(None)
+        );
+        assert_eq!(
+            registry.assign_batch(1..=4, ProverType::Exec, fourth, LEASE_TIMEOUT, is_proven),
+            Ok(Some(1))
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap};
 
 use bytes::Bytes;
 use ethereum_types::{Address, H256, U256};
@@ -106,6 +106,7 @@ impl StateDiff {
         encoded.push(self.version);
 
         // Header fields
+        encoded.extend(self.header.number.to_be_bytes());
         encoded.extend(self.header.transactions_root.0);
         encoded.extend(self.header.receipts_root.0);
         encoded.extend(self.header.gas_limit.to_be_bytes());
@@ -154,6 +155,7 @@ impl StateDiff {
         }
 
         // Header fields
+        let number = decoder.get_u64()?;
         let transactions_root = decoder.get_h256()?;
         let receipts_root = decoder.get_h256()?;
         let gas_limit = decoder.get_u64()?;
@@ -205,6 +207,7 @@ impl StateDiff {
 
         Ok(Self {
             header: BlockHeader {
+                number,
                 transactions_root,
                 receipts_root,
                 gas_limit,
@@ -220,6 +223,25 @@ impl StateDiff {
         })
     }
 
+    /// Merges the diff of the block that follows the ones covered by `self`,
+    /// so that the result describes the state changes of all of them.
+    /// The header is replaced by the one of the latest block.
+    pub fn merge(&mut self, next: StateDiff) -> Result<(), StateDiffError> {
+        for (address, diff) in next.modified_accounts {
+            match self.modified_accounts.entry(address) {
+                Entry::Occupied(mut entry) => entry.get_mut().merge(diff)?,
+                Entry::Vacant(entry) => {
+                    entry.insert(diff);
+                }
+            }
+        }
+        self.header = next.header;
+        self.withdrawal_logs.extend(next.withdrawal_logs);
+        self.deposit_logs.extend(next.deposit_logs);
+
+        Ok(())
+    }
+
     pub fn to_account_updates(
         &self,
         prev_state: &Trie,
@@ -326,6 +348,26 @@ impl AccountStateDiff {
         Ok((r#type, Bytes::from(encoded)))
     }
 
+    /// Merges the diff of the same account in a later block.
+    /// Balances, storage values and code are absolute so the latest ones are kept,
+    /// while nonce diffs are accumulated.
+    fn merge(&mut self, next: AccountStateDiff) -> Result<(), StateDiffError> {
+        if next.new_balance.is_some() {
+            self.new_balance = next.new_balance;
+        }
+        self.nonce_diff = self
+            .nonce_diff
+            .checked_add(next.nonce_diff)
+            .ok_or(StateDiffError::NonceDiffOverflow)?;
+        self.storage.extend(next.storage);
+        if next.bytecode.is_some() || next.bytecode_hash.is_some() {
+            self.bytecode = next.bytecode;
+            self.bytecode_hash = next.bytecode_hash;
+        }
+
+        Ok(())
+    }
+
     /// Returns a tuple of the number of bytes read, the address of the account
     /// and the decoded `AccountStateDiff`
     pub fn decode(bytes: &[u8]) -> Result<(usize, Address, Self), StateDiffError> {
@@ -560,3 +602,109 @@ async fn account_info_from_cache(
     };
     Ok(account_info)
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn account_diff(
+        new_balance: Option<u64>,
+        nonce_diff: u16,
+        storage: &[(u64, u64)],
+    ) -> AccountStateDiff {
+        AccountStateDiff {
+            new_balance: new_balance.map(U256::from),
+            nonce_diff,
+            storage: storage
+                .iter()
+                .map(|(key, value)| (H256::from_low_u64_be(*key), U256::from(*value)))
+                .collect(),
+            bytecode: None,
+            bytecode_hash: None,
+        }
+    }
+
+    fn state_diff(number: BlockNumber, accounts: Vec<(Address, AccountStateDiff)>) -> StateDiff {
+        StateDiff {
+            header: BlockHeader {
+                number,
+                ..Default::default()
+            },
+            modified_accounts: accounts.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merge_keeps_the_latest_writes() {
+        let address = Address::from_low_u64_be(1);
+        let other_address = Address::from_low_u64_be(2);
+        let mut diff = state_diff(
+            1,
+            vec![
+                (address, account_diff(Some(100), 1, &[(1, 10), (2, 20)])),
+                (other_address, account_diff(Some(5), 0, &[])),
+            ],
+        );
+        let next = state_diff(
+            2,
+            vec![(address, account_diff(Some(50), 2, &[(2, 0), (3, 30)]))],
+        );
+        diff.merge(next).expect("merged");
+
+        assert_eq!(diff.header.number, 2);
+        let merged = &diff
+            .modified_accounts
+            .get(&address)
+            .expect("modified account");
+        assert_eq!(merged.new_balance, Some(U256::from(50)));
+        assert_eq!(merged.nonce_diff, 3);
+        assert_eq!(
+            merged.storage,
+            HashMap::from([
+                (H256::from_low_u64_be(1), U256::from(10)),
+                (H256::from_low_u64_be(2), U256::zero()),
+                (H256::from_low_u64_be(3), U256::from(30)),
+            ])
+        );
+        // Accounts not modified by the next block are kept as they were
+        assert_eq!(
+            diff.modified_accounts
+                .get(&other_address)
+                .expect("modified account")
+                .new_balance,
+            Some(U256::from(5))
+        );
+    }
+
+    #[test]
+    fn merge_keeps_the_balance_not_modified_by_the_next_block() {
+        let address = Address::from_low_u64_be(1);
+        let mut diff = state_diff(1, vec![(address, account_diff(Some(100), 0, &[]))]);
+        let bytecode = Bytes::from_static(&[0x60, 0x00]);
+        let mut next_account = account_diff(None, 1, &[(1, 1)]);
+        next_account.bytecode = Some(bytecode.clone());
+        diff.merge(state_diff(2, vec![(address, next_account)]))
+            .expect("merged");
+
+        let merged = &diff
+            .modified_accounts
+            .get(&address)
+            .expect("modified account");
+        assert_eq!(merged.new_balance, Some(U256::from(100)));
+        assert_eq!(merged.nonce_diff, 1);
+        assert_eq!(merged.bytecode, Some(bytecode));
+    }
+
+    #[test]
+    fn merge_fails_when_the_nonce_diff_overflows() {
+        let address = Address::from_low_u64_be(1);
+        let mut diff = state_diff(1, vec![(address, account_diff(None, u16::MAX, &[]))]);
+        let next = state_diff(2, vec![(address, account_diff(None, 1, &[]))]);
+        assert!(matches!(
+            diff.merge(next),
+            Err(StateDiffError::NonceDiffOverflow)
+        ));
+    }
+}
@@ -738,6 +738,394 @@ async fn l2_sdk_deploy() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Test the full flow of bridging an ERC20 token from L1 to L2 and back.
+///
+/// 1. Deploy an ERC20 token on L1
+/// 2. Deposit the token from L1 to L2, which mints its bridged version on L2
+/// 3. Withdraw part of the bridged tokens from L2 to L1, which burns them
+/// 4. Claim the withdrawn tokens on L1
+/// 5. Check the token balances and the amount locked in the bridge
+#[tokio::test]
+async fn l2_erc20_deposit_and_withdraw() -> Result<(), Box<dyn std::error::Error>> {
+    let eth_client = eth_client();
+    let proposer_client = proposer_client();
+
+    read_env_file_by_config(ConfigMode::Sequencer)?;
+
+    let l1_rich_wallet_address = l1_rich_wallet_address();
+
+    // 1. Deploy an ERC20 token on L1
+
+    println!("Deploying ERC20 token on L1");
+    // The constructor mints 1M tokens to the deployer
+    let init_code =
+        hex::decode(include_str!("../../../test_data/ERC20/ERC20.bin/TestToken.bin").trim_ascii())?;
+    let (_, token_l1) = eth_client
+        .deploy(
+            l1_rich_wallet_address,
+            l1_rich_wallet_private_key(),
+            init_code.into(),
+            Overrides::default(),
+        )
+        .await?;
+    println!("Token deployed on L1: {token_l1:#x}");
+
+    let l1_initial_token_balance =
+        erc20_balance(&eth_client, token_l1, l1_rich_wallet_address).await?;
+
+    // 2. Deposit the token from L1 to L2
+
+    println!("Depositing ERC20 from L1 to L2");
+    let deposit_value = U256::from(1000u64);
+    let deposit_tx = ethrex_l2_sdk::deposit_erc20(
+        token_l1,
+        deposit_value,
+        l1_rich_wallet_address,
+        l1_rich_wallet_address,
+        l1_rich_wallet_private_key(),
+        &eth_client,
+    )
+    .await?;
+    ethrex_l2_sdk::wait_for_transaction_receipt(deposit_tx, &eth_client, 5).await?;
+
+    assert_eq!(
+        erc20_balance(&eth_client, token_l1, l1_rich_wallet_address).await?,
+        l1_initial_token_balance - deposit_value,
+        "L1 token balance should decrease with the deposit value"
+    );
+    assert_eq!(
+        deposited_erc20(&eth_client, token_l1).await?,
+        deposit_value,
+        "The deposited tokens should be locked in the CommonBridge"
+    );
+
+    // The bridged token is deployed by the L2 bridge on the first deposit
+    let mut token_l2 = Address::zero();
+    let mut l2_token_balance = U256::zero();
+    let mut retries = 0;
+    while retries < 1000 && l2_token_balance < deposit_value {
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        println!("[{retries}/1000] Waiting for L2 token balance to update after deposit");
+        token_l2 = bridged_token(&proposer_client, token_l1).await?;
+        if !token_l2.is_zero() {
+            l2_token_balance =
+                erc20_balance(&proposer_client, token_l2, l1_rich_wallet_address).await?;
+        }
+        retries += 1;
+    }
+    assert_ne!(
+        retries, 1000,
+        "L2 token balance did not update after deposit"
+    );
+    assert_eq!(l2_token_balance, deposit_value);
+    println!("Bridged token on L2: {token_l2:#x}");
+
+    // 3. Withdraw part of the bridged tokens from L2 to L1
+
+    println!("Withdrawing ERC20 from L2 to L1");
+    let withdraw_value = U256::from(400u64);
+    let withdraw_tx = ethrex_l2_sdk::withdraw_erc20(
+        token_l1,
+        withdraw_value,
+        l1_rich_wallet_address,
+        l1_rich_wallet_private_key(),
+        &proposer_client,
+    )
+    .await?;
+    let withdraw_tx_receipt =
+        ethrex_l2_sdk::wait_for_transaction_receipt(withdraw_tx, &proposer_client, 1000)
+            .await
+            .expect("Withdraw tx receipt not found");
+
+    assert_eq!(
+        ethrex_l2_sdk::get_erc20_withdrawal_amount(&withdraw_tx_receipt),
+        Some(withdraw_value),
+        "The withdrawal receipt should carry the withdrawn amount"
+    );
+    assert_eq!(
+        erc20_balance(&proposer_client, token_l2, l1_rich_wallet_address).await?,
+        deposit_value - withdraw_value,
+        "The withdrawn tokens should be burned on L2"
+    );
+
+    // 4. Claim the withdrawn tokens on L1
+
+    println!("Claiming ERC20 withdrawal on L1");
+    while u64::from_str_radix(
+        eth_client
+            .call(
+                Address::from_str(
+                    &std::env::var("COMMITTER_ON_CHAIN_PROPOSER_ADDRESS")
+                        .expect("ON_CHAIN_PROPOSER env var not set"),
+                )
+                .unwrap(),
+                // lastVerifiedBlock()
+                Bytes::from_static(&[0x2f, 0xde, 0x80, 0xe5]),
+                Overrides::default(),
+            )
+            .await?
+            .get(2..)
+            .unwrap(),
+        16,
+    )
+    .unwrap()
+        < withdraw_tx_receipt.block_info.block_number
+    {
+        println!("Withdrawal is not verified on L1 yet");
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+
+    let claim_tx = ethrex_l2_sdk::claim_erc20_withdraw(
+        token_l1,
+        withdraw_tx,
+        l1_rich_wallet_address,
+        l1_rich_wallet_private_key(),
+        &proposer_client,
+        &eth_client,
+    )
+    .await?;
+    let claim_tx_receipt =
+        ethrex_l2_sdk::wait_for_transaction_receipt(claim_tx, &eth_client, 15).await?;
+    assert!(
+        claim_tx_receipt.receipt.status,
+        "ERC20 withdrawal claim failed"
+    );
+
+    // 5. Check the token balances and the amount locked in the bridge
+
+    assert_eq!(
+        erc20_balance(&eth_client, token_l1, l1_rich_wallet_address).await?,
+        l1_initial_token_balance - deposit_value + withdraw_value,
+        "L1 token balance should increase with the claimed value"
+    );
+    assert_eq!(
+        deposited_erc20(&eth_client, token_l1).await?,
+        deposit_value - withdraw_value,
+        "The claimed tokens should be released by the CommonBridge"
+    );
+
+    println!("l2_erc20_deposit_and_withdraw is done");
+    Ok(())
+}
+
+async fn erc20_balance(
+    client: &EthClient,
+    token: Address,
+    account: Address,
+) -> Result<U256, Box<dyn std::error::Error>> {
+    let calldata = calldata::encode_calldata("balanceOf(address)", &[Value::Address(account)])?;
+    let hex_str = client
+        .call(token, calldata.into(), Overrides::default())
+        .await?;
+    Ok(from_hex_string_to_u256(&hex_str)?)
+}
+
+async fn deposited_erc20(
+    eth_client: &EthClient,
+    token_l1: Address,
+) -> Result<U256, Box<dyn std::error::Error>> {
+    let calldata =
+        calldata::encode_calldata("depositedERC20(address)", &[Value::Address(token_l1)])?;
+    let hex_str = eth_client
+        .call(
+            common_bridge_address(),
+            calldata.into(),
+            Overrides::default(),
+        )
+        .await?;
+    Ok(from_hex_string_to_u256(&hex_str)?)
+}
+
+/// Returns the address of the bridged version of an L1 token, or zero if it wasn't deployed yet
+async fn bridged_token(
+    proposer_client: &EthClient,
+    token_l1: Address,
+) -> Result<Address, Box<dyn std::error::Error>> {
+    let calldata =
+        calldata::encode_calldata("bridgedTokens(address)", &[Value::Address(token_l1)])?;
+    let hex_str = proposer_client
+        .call(
+            ethrex_l2_sdk::COMMON_BRIDGE_L2_ADDRESS,
+            calldata.into(),
+            Overrides::default(),
+        )
+        .await?;
+    let word = H256::from_str(hex_str.trim_start_matches("0x"))?;
+    Ok(Address::from(word))
+}
+
+/// Test that the OnChainProposer enforces the forced inclusion deadline.
+///
+/// 1. Send a forced transaction through the CommonBridge
+/// 2. Simulate, one day later, a commit that skips it and check that it's rejected
+/// 3. Simulate, one day later, a commit that includes it and check that it's accepted
+/// 4. Check that the sequencer includes it on L2
+#[tokio::test]
+async fn l2_forced_inclusion_deadline() -> Result<(), Box<dyn std::error::Error>> {
+    let eth_client = eth_client();
+    let proposer_client = proposer_client();
+
+    read_env_file_by_config(ConfigMode::Sequencer)?;
+
+    let on_chain_proposer = Address::from_str(
+        &std::env::var("COMMITTER_ON_CHAIN_PROPOSER_ADDRESS")
+            .expect("ON_CHAIN_PROPOSER env var not set"),
+    )?;
+    let committer = Address::from_str(
+        &std::env::var("COMMITTER_L1_ADDRESS").expect("COMMITTER_L1_ADDRESS env var not set"),
+    )?;
+
+    // 1. Send a forced transaction through the CommonBridge
+
+    println!("Sending forced transaction");
+    let (recipient, _) = random_account();
+    let forced_value = U256::from(1000000000000000000u128);
+    let forced_gas_limit = 21000 * 5;
+    let calldata = calldata::encode_calldata(
+        "sendForcedTransaction((address,address,uint256,bytes))",
+        &[Value::Tuple(vec![
+            Value::Address(recipient),
+            Value::Address(recipient),
+            Value::Uint(U256::from(forced_gas_limit)),
+            Value::Bytes(Bytes::new()),
+        ])],
+    )?;
+    let forced_tx = eth_client
+        .build_eip1559_transaction(
+            common_bridge_address(),
+            l1_rich_wallet_address(),
+            calldata.into(),
+            Overrides {
+                value: Some(forced_value),
+                from: Some(l1_rich_wallet_address()),
+                ..Overrides::default()
+            },
+        )
+        .await?;
+    let forced_tx_hash = eth_client
+        .send_eip1559_transaction(&forced_tx, &l1_rich_wallet_private_key())
+        .await?;
+    let forced_tx_receipt =
+        ethrex_l2_sdk::wait_for_transaction_receipt(forced_tx_hash, &eth_client, 5).await?;
+    assert!(
+        forced_tx_receipt.receipt.status,
+        "Forced transaction failed"
+    );
+    // The L2 gas is paid out of the sent value at the L1 base fee
+    let forced_tx_base_fee = eth_client
+        .get_block_by_number(BlockByNumber::Number(
+            forced_tx_receipt.block_info.block_number,
+        ))
+        .await?
+        .header
+        .base_fee_per_gas
+        .unwrap_or_default();
+    let minted_value = forced_value - U256::from(forced_gas_limit * forced_tx_base_fee);
+
+    let last_committed_batch =
+        call_u256(&eth_client, on_chain_proposer, "lastCommittedBatch()").await?;
+    let last_committed_block =
+        call_u256(&eth_client, on_chain_proposer, "lastCommittedBlock()").await?;
+    let committed_deposit_logs =
+        call_u256(&eth_client, on_chain_proposer, "committedDepositLogs()").await?;
+    let pending_deposit_logs = pending_deposit_logs_count(&eth_client).await?;
+    assert!(
+        pending_deposit_logs > committed_deposit_logs,
+        "The forced transaction was committed before the simulations"
+    );
+
+    let latest_block = eth_client
+        .get_block_by_number(BlockByNumber::Latest)
+        .await?;
+    let after_deadline = latest_block.header.timestamp + 24 * 60 * 60 + 60;
+
+    let commit = |deposit_logs_hash: H256| {
+        calldata::encode_calldata(
+            "commit(uint256,uint256,uint256,bytes32,bytes32,bytes32,bytes32)",
+            &[
+                Value::Uint(last_committed_batch + 1),
+                Value::Uint(last_committed_block + 1),
+                Value::Uint(last_committed_block + 1),
+                Value::FixedBytes(H256::repeat_byte(1).as_bytes().to_vec().into()),
+                Value::FixedBytes(H256::zero().as_bytes().to_vec().into()),
+                Value::FixedBytes(H256::zero().as_bytes().to_vec().into()),
+                Value::FixedBytes(deposit_logs_hash.as_bytes().to_vec().into()),
+            ],
+        )
+    };
+
+    // 2. Simulate a commit that skips it
+
+    println!("Simulating a commit that skips the forced transaction after the deadline");
+    let (success, return_data) = simulate_call(
+        committer,
+        on_chain_proposer,
+        commit(H256::zero())?.into(),
+        after_deadline,
+    )
+    .await?;
+    assert!(
+        !success,
+        "A commit that skips an expired forced transaction should revert"
+    );
+    assert!(
+        return_data
+            .windows(b"batch skips expired forced transactions".len())
+            .any(|window| window == b"batch skips expired forced transactions"),
+        "Unexpected revert reason: {}",
+        hex::encode(&return_data)
+    );
+
+    // 3. Simulate a commit that includes it
+
+    println!("Simulating a commit that includes the forced transaction after the deadline");
+    let included_logs = pending_deposit_logs - committed_deposit_logs;
+    let hash_calldata = calldata::encode_calldata(
+        "getPendingDepositLogsVersionedHash(uint16)",
+        &[Value::Uint(included_logs)],
+    )?;
+    let deposit_logs_hash = H256::from_str(
+        eth_client
+            .call(
+                common_bridge_address(),
+                hash_calldata.into(),
+                Overrides::default(),
+            )
+            .await?
+            .trim_start_matches("0x"),
+    )?;
+    let (success, return_data) = simulate_call(
+        committer,
+        on_chain_proposer,
+        commit(deposit_logs_hash)?.into(),
+        after_deadline,
+    )
+    .await?;
+    assert!(
+        success,
+        "A commit that includes the expired forced transaction should succeed: {}",
+        hex::encode(&return_data)
+    );
+
+    // 4. Check that the sequencer includes it on L2
+
+    let mut recipient_balance = U256::zero();
+    let mut retries = 0;
+    while retries < 1000 && recipient_balance < minted_value {
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        println!("[{retries}/1000] Waiting for the forced transaction to be included on L2");
+        recipient_balance = proposer_client
+            .get_balance(recipient, BlockByNumber::Latest)
+            .await?;
+        retries += 1;
+    }
+    assert_ne!(retries, 1000, "Forced transaction was not included on L2");
+    assert_eq!(recipient_balance, minted_value);
+
+    println!("l2_forced_inclusion_deadline is done");
+    Ok(())
+}
+
 #[derive(Debug)]
 struct FeesDetails {
     total_fees: U256,
@@ -769,6 +1157,82 @@ async fn get_fees_details_l2(tx_receipt: RpcReceipt, l2_client: &EthClient) -> F
     }
 }
 
+async fn call_u256(
+    client: &EthClient,
+    to: Address,
+    signature: &str,
+) -> Result<U256, Box<dyn std::error::Error>> {
+    let calldata = calldata::encode_calldata(signature, &[])?;
+    let hex_str = client
+        .call(to, calldata.into(), Overrides::default())
+        .await?;
+    Ok(from_hex_string_to_u256(&hex_str)?)
+}
+
+/// Returns the length of the CommonBridge pending deposit logs queue
+async fn pending_deposit_logs_count(
+    eth_client: &EthClient,
+) -> Result<U256, Box<dyn std::error::Error>> {
+    let calldata = calldata::encode_calldata("getPendingDepositLogs()", &[])?;
+    let hex_str = eth_client
+        .call(
+            common_bridge_address(),
+            calldata.into(),
+            Overrides::default(),
+        )
+        .await?;
+    // The returned array is encoded as its offset, its length and its elements
+    let encoded = hex::decode(hex_str.trim_start_matches("0x"))?;
+    let length = encoded.get(32..64).ok_or("Invalid pending deposit logs")?;
+    Ok(U256::from_big_endian(length))
+}
+
+/// Simulates a call on a new L1 block with the given timestamp, through `eth_simulateV1`.
+/// Returns whether it succeeded along with its return data.
+async fn simulate_call(
+    from: Address,
+    to: Address,
+    calldata: Bytes,
+    timestamp: u64,
+) -> Result<(bool, Vec<u8>), Box<dyn std::error::Error>> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_simulateV1",
+        "params": [{
+            "blockStateCalls": [{
+                "blockOverrides": {
+                    "time": format!("{timestamp:#x}"),
+                    "baseFeePerGas": "0x0",
+                },
+                "calls": [{
+                    "from": format!("{from:#x}"),
+                    "to": format!("{to:#x}"),
+                    "input": format!("0x{}", hex::encode(&calldata)),
+                }],
+            }],
+        }, "latest"],
+    });
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(std::env::var("ETH_URL").unwrap_or(DEFAULT_ETH_URL.to_owned()))
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
+    let call = response
+        .pointer("/result/0/calls/0")
+        .ok_or(format!("Unexpected eth_simulateV1 response: {response}"))?;
+    let success = call.get("status").and_then(|status| status.as_str()) == Some("0x1");
+    let return_data = hex::decode(
+        call.get("returnData")
+            .and_then(|data| data.as_str())
+            .unwrap_or_default()
+            .trim_start_matches("0x"),
+    )?;
+    Ok((success, return_data))
+}
+
 fn eth_client() -> EthClient {
     EthClient::new(&std::env::var("ETH_URL").unwrap_or(DEFAULT_ETH_URL.to_owned()))
 }
@@ -12,6 +12,7 @@ pub struct CommitterConfig {
     #[serde(deserialize_with = "secret_key_deserializer")]
     pub l1_private_key: SecretKey,
     pub commit_time_ms: u64,
+    pub max_blocks_per_batch: u64,
     pub arbitrary_base_blob_gas_price: u64,
     pub validium: bool,
 }
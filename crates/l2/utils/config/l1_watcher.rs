@@ -10,6 +10,7 @@ pub struct L1WatcherConfig {
     pub bridge_address: Address,
     pub check_interval_ms: u64,
     pub max_block_step: U256,
+    pub confirmations: u64,
     #[serde(deserialize_with = "secret_key_deserializer")]
     pub l2_proposer_private_key: SecretKey,
 }
@@ -14,6 +14,11 @@ pub struct ProofCoordinatorConfig {
     pub listen_port: u16,
     pub proof_send_interval_ms: u64,
     pub dev_mode: bool,
+    /// Addresses of the provers allowed to request batches and submit proofs
+    pub prover_whitelist: Vec<Address>,
+    /// Time after which a batch assigned to a prover that stopped sending heartbeats
+    /// can be assigned to another one
+    pub lease_timeout_ms: u64,
 }
 
 impl ProofCoordinatorConfig {
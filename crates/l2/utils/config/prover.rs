@@ -1,3 +1,5 @@
+use ethrex_l2_sdk::secret_key_deserializer;
+use secp256k1::SecretKey;
 use serde::Deserialize;
 
 use super::errors::ConfigError;
@@ -6,6 +8,10 @@ use super::errors::ConfigError;
 pub struct ProverConfig {
     pub prover_server_endpoint: String,
     pub proving_time_ms: u64,
+    /// Key used to sign the requests sent to the ProofCoordinator,
+    /// its address has to be in the ProofCoordinator's whitelist
+    #[serde(deserialize_with = "secret_key_deserializer")]
+    pub prover_private_key: SecretKey,
 }
 
 impl ProverConfig {
@@ -65,6 +65,7 @@ struct Watcher {
     bridge_address: String,
     check_interval_ms: u64,
     max_block_step: u64,
+    confirmations: u64,
     l2_proposer_private_key: String,
 }
 
@@ -76,11 +77,13 @@ impl Watcher {
 {prefix}_BRIDGE_ADDRESS={}
 {prefix}_CHECK_INTERVAL_MS={}
 {prefix}_MAX_BLOCK_STEP={}
+{prefix}_CONFIRMATIONS={}
 {prefix}_L2_PROPOSER_PRIVATE_KEY={}
 ",
             self.bridge_address,
             self.check_interval_ms,
             self.max_block_step,
+            self.confirmations,
             self.l2_proposer_private_key
         )
     }
@@ -111,6 +114,7 @@ struct Committer {
     l1_address: String,
     l1_private_key: String,
     commit_time_ms: u64,
+    max_blocks_per_batch: u64,
     arbitrary_base_blob_gas_price: u64,
     validium: bool,
 }
@@ -124,6 +128,7 @@ impl Committer {
 {prefix}_L1_ADDRESS={}
 {prefix}_L1_PRIVATE_KEY={}
 {prefix}_COMMIT_TIME_MS={}
+{prefix}_MAX_BLOCKS_PER_BATCH={}
 {prefix}_ARBITRARY_BASE_BLOB_GAS_PRICE={}
 {prefix}_VALIDIUM={}
 ",
@@ -131,6 +136,7 @@ impl Committer {
             self.l1_address,
             self.l1_private_key,
             self.commit_time_ms,
+            self.max_blocks_per_batch,
             self.arbitrary_base_blob_gas_price,
             self.validium,
         )
@@ -141,6 +147,7 @@ impl Committer {
 struct ProverClient {
     prover_server_endpoint: String,
     proving_time_ms: u64,
+    prover_private_key: String,
 }
 
 impl ProverClient {
@@ -149,8 +156,9 @@ impl ProverClient {
         format!(
             "{prefix}_PROVER_SERVER_ENDPOINT={}
 {prefix}_PROVING_TIME_MS={}
+{prefix}_PROVER_PRIVATE_KEY={}
 ",
-            self.prover_server_endpoint, self.proving_time_ms
+            self.prover_server_endpoint, self.proving_time_ms, self.prover_private_key
         )
     }
 }
@@ -163,6 +171,8 @@ struct ProverServer {
     listen_port: u64,
     dev_mode: bool,
     proof_send_interval_ms: u64,
+    prover_whitelist: Vec<String>,
+    lease_timeout_ms: u64,
 }
 
 impl ProverServer {
@@ -176,13 +186,17 @@ impl ProverServer {
 {prefix}_LISTEN_PORT={}
 {prefix}_DEV_MODE={}
 {prefix}_PROOF_SEND_INTERVAL_MS={}
+{prefix}_PROVER_WHITELIST={}
+{prefix}_LEASE_TIMEOUT_MS={}
 ",
             self.l1_address,
             self.l1_private_key,
             self.listen_ip,
             self.listen_port,
             self.dev_mode,
-            self.proof_send_interval_ms
+            self.proof_send_interval_ms,
+            self.prover_whitelist.join(","),
+            self.lease_timeout_ms
         )
     }
 }
@@ -11,3 +11,23 @@ pub enum SaveStateError {
     #[error("{0}")]
     Custom(String),
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProtocolError {
+    #[error("Failed to interact with IO: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Failed to de/serialize: {0}")]
+    Bincode(#[from] bincode::Error),
+    #[error("Unsupported protocol version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Message of {0} bytes exceeds the size limit")]
+    MessageTooLarge(usize),
+    #[error("Message signed at {0} is too old or too far in the future")]
+    StaleMessage(u64),
+    #[error("Message wasn't signed with the challenge of this connection")]
+    ChallengeMismatch,
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("{0}")]
+    Custom(String),
+}
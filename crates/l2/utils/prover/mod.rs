@@ -1,3 +1,4 @@
 pub mod errors;
+pub mod protocol;
 pub mod proving_systems;
 pub mod save_state;
@@ -0,0 +1,272 @@
+use super::errors::ProtocolError;
+use ethereum_types::{Address, H256};
+use keccak_hash::keccak;
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message, SecretKey, SECP256K1,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// Wire protocol spoken between the ProofCoordinator and the provers.
+// Every message is sent as a frame made of:
+//     version (u8) | payload length (u32, big endian) | payload (bincode)
+// On every connection the ProofCoordinator first sends a random challenge, the prover's
+// request is wrapped in a [SignedMessage] that commits to it so that it can't be replayed
+// on another connection. The responses sent by the ProofCoordinator are not signed.
+
+/// Version of the protocol, frames with a different version are rejected
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// Maximum size of the requests sent by the provers.
+/// Requests are read before their signature can be checked, so this bounds the memory
+/// an unauthenticated peer can make the ProofCoordinator allocate.
+pub const MAX_REQUEST_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Maximum size of the responses sent by the ProofCoordinator. Prover inputs carry the
+/// blocks of a batch along with the state needed to execute them, so they can be large.
+pub const MAX_RESPONSE_SIZE: u32 = 512 * 1024 * 1024;
+
+/// Maximum size of the challenge sent by the ProofCoordinator
+pub const MAX_CHALLENGE_SIZE: u32 = 128;
+
+/// Maximum difference, in seconds, between the timestamp of a signed message and the
+/// local clock. Older messages are rejected so that they can't be replayed later.
+pub const MAX_MESSAGE_AGE_SECS: u64 = 60;
+
+/// Message signed by a prover, the signer is authenticated by recovering its address
+/// from the signature.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignedMessage<T> {
+    /// Unix timestamp (in seconds) of the moment the message was signed
+    pub timestamp: u64,
+    /// Challenge sent by the ProofCoordinator on the connection the message is sent through
+    pub challenge: H256,
+    pub data: T,
+    /// Recoverable secp256k1 signature of the message digest, as `r || s || v`
+    pub signature: Vec<u8>,
+}
+
+impl<T: Serialize> SignedMessage<T> {
+    pub fn sign(data: T, challenge: H256, signer: &SecretKey) -> Result<Self, ProtocolError> {
+        let timestamp = unix_timestamp()?;
+        let digest = message_digest(timestamp, challenge, &data)?;
+        let (recovery_id, signature) = SECP256K1
+            .sign_ecdsa_recoverable(&Message::from_digest(digest.0), signer)
+            .serialize_compact();
+        let recovery_id = u8::try_from(recovery_id.to_i32())
+            .map_err(|e| ProtocolError::InvalidSignature(e.to_string()))?;
+
+        let mut signature = signature.to_vec();
+        signature.push(recovery_id);
+        Ok(Self {
+            timestamp,
+            challenge,
+            data,
+            signature,
+        })
+    }
+
+    /// Checks that the message answers the given challenge and isn't stale,
+    /// and returns the address of its signer
+    pub fn recover_signer(&self, challenge: H256) -> Result<Address, ProtocolError> {
+        if self.challenge != challenge {
+            return Err(ProtocolError::ChallengeMismatch);
+        }
+        if unix_timestamp()?.abs_diff(self.timestamp) > MAX_MESSAGE_AGE_SECS {
+            return Err(ProtocolError::StaleMessage(self.timestamp));
+        }
+
+        let (Some(signature), Some(recovery_id), 65) = (
+            self.signature.get(..64),
+            self.signature.get(64),
+            self.signature.len(),
+        ) else {
+            return Err(ProtocolError::InvalidSignature(format!(
+                "expected 65 bytes, got {}",
+                self.signature.len()
+            )));
+        };
+        let recovery_id = RecoveryId::from_i32(i32::from(*recovery_id))
+            .map_err(|e| ProtocolError::InvalidSignature(e.to_string()))?;
+        let signature = RecoverableSignature::from_compact(signature, recovery_id)
+            .map_err(|e| ProtocolError::InvalidSignature(e.to_string()))?;
+
+        let digest = message_digest(self.timestamp, self.challenge, &self.data)?;
+        let public_key = SECP256K1
+            .recover_ecdsa(&Message::from_digest(digest.0), &signature)
+            .map_err(|e| ProtocolError::InvalidSignature(e.to_string()))?;
+        let public_key = public_key.serialize_uncompressed();
+        let public_key = public_key.get(1..).ok_or(ProtocolError::InvalidSignature(
+            "invalid public key".to_owned(),
+        ))?;
+        Ok(Address::from(keccak(public_key)))
+    }
+}
+
+/// Digest signed by the provers, it commits to the protocol version so that a signed
+/// message can't be reinterpreted by a different version of the protocol
+fn message_digest<T: Serialize>(
+    timestamp: u64,
+    challenge: H256,
+    data: &T,
+) -> Result<H256, ProtocolError> {
+    let encoded = bincode::serialize(&(PROTOCOL_VERSION, timestamp, challenge, data))?;
+    Ok(keccak(encoded))
+}
+
+fn unix_timestamp() -> Result<u64, ProtocolError> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| ProtocolError::Custom(e.to_string()))?
+        .as_secs())
+}
+
+/// Sends a fresh random challenge, which the request read from the stream must be signed with
+pub async fn send_challenge(stream: &mut (impl AsyncWrite + Unpin)) -> Result<H256, ProtocolError> {
+    let challenge = H256(rand::random());
+    write_message(stream, &challenge).await?;
+    Ok(challenge)
+}
+
+/// Reads the challenge the request written to the stream must be signed with
+pub async fn read_challenge(stream: &mut (impl AsyncRead + Unpin)) -> Result<H256, ProtocolError> {
+    read_message(stream, MAX_CHALLENGE_SIZE).await
+}
+
+/// Encodes the message and writes it to the stream as a single frame
+pub async fn write_message<T: Serialize>(
+    stream: &mut (impl AsyncWrite + Unpin),
+    message: &T,
+) -> Result<(), ProtocolError> {
+    let payload = bincode::serialize(message)?;
+    let length =
+        u32::try_from(payload.len()).map_err(|_| ProtocolError::MessageTooLarge(payload.len()))?;
+
+    stream.write_u8(PROTOCOL_VERSION).await?;
+    stream.write_u32(length).await?;
+    stream.write_all(&payload).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Reads a single frame from the stream and decodes its message,
+/// failing if the payload is bigger than `max_size`
+pub async fn read_message<T: DeserializeOwned>(
+    stream: &mut (impl AsyncRead + Unpin),
+    max_size: u32,
+) -> Result<T, ProtocolError> {
+    let version = stream.read_u8().await?;
+    if version != PROTOCOL_VERSION {
+        return Err(ProtocolError::UnsupportedVersion(version));
+    }
+
+    let length = stream.read_u32().await?;
+    let length = usize::try_from(length).map_err(|_| ProtocolError::MessageTooLarge(usize::MAX))?;
+    if length > usize::try_from(max_size).unwrap_or(usize::MAX) {
+        return Err(ProtocolError::MessageTooLarge(length));
+    }
+
+    let mut payload = vec![0; length];
+    stream.read_exact(&mut payload).await?;
+    Ok(bincode::deserialize(&payload)?)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use ethrex_l2_sdk::get_address_from_secret_key;
+
+    fn test_key() -> SecretKey {
+        SecretKey::from_slice(&[1; 32]).expect("valid secret key")
+    }
+
+    #[tokio::test]
+    async fn signed_message_roundtrip() {
+        let key = test_key();
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let challenge = send_challenge(&mut server).await.expect("challenge sent");
+        let received_challenge = read_challenge(&mut client).await.expect("challenge read");
+        assert_eq!(received_challenge, challenge);
+
+        let message = SignedMessage::sign("batch request".to_owned(), received_challenge, &key)
+            .expect("signed");
+        write_message(&mut client, &message).await.expect("written");
+        let received: SignedMessage<String> = read_message(&mut server, MAX_REQUEST_SIZE)
+            .await
+            .expect("read");
+
+        assert_eq!(received.data, "batch request");
+        assert_eq!(
+            received.recover_signer(challenge).expect("valid signature"),
+            get_address_from_secret_key(&key).expect("valid address")
+        );
+    }
+
+    #[test]
+    fn tampered_message_has_different_signer() {
+        let key = test_key();
+        let challenge = H256::from_low_u64_be(1);
+        let mut message = SignedMessage::sign(1_u64, challenge, &key).expect("signed");
+        message.data = 2;
+        assert_ne!(
+            message.recover_signer(challenge).ok(),
+            get_address_from_secret_key(&key).ok()
+        );
+    }
+
+    #[test]
+    fn stale_message_is_rejected() {
+        let challenge = H256::from_low_u64_be(1);
+        let mut message = SignedMessage::sign(1_u64, challenge, &test_key()).expect("signed");
+        message.timestamp -= MAX_MESSAGE_AGE_SECS + 1;
+        assert!(matches!(
+            message.recover_signer(challenge),
+            Err(ProtocolError::StaleMessage(_))
+        ));
+    }
+
+    #[test]
+    fn replayed_message_is_rejected() {
+        let key = test_key();
+        let message = SignedMessage::sign(1_u64, H256::from_low_u64_be(1), &key).expect("signed");
+        // Sent again on a connection with a different challenge
+        assert!(matches!(
+            message.recover_signer(H256::from_low_u64_be(2)),
+            Err(ProtocolError::ChallengeMismatch)
+        ));
+
+        // Rewriting the challenge breaks the signature
+        let mut replayed = message;
+        replayed.challenge = H256::from_low_u64_be(2);
+        assert_ne!(
+            replayed.recover_signer(H256::from_low_u64_be(2)).ok(),
+            get_address_from_secret_key(&key).ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_and_unknown_frames_are_rejected() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        write_message(&mut client, &vec![0_u8; 64])
+            .await
+            .expect("written");
+        assert!(matches!(
+            read_message::<Vec<u8>>(&mut server, 16).await,
+            Err(ProtocolError::MessageTooLarge(_))
+        ));
+
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client
+            .write_u8(PROTOCOL_VERSION + 1)
+            .await
+            .expect("written");
+        assert!(matches!(
+            read_message::<Vec<u8>>(&mut server, MAX_REQUEST_SIZE).await,
+            Err(ProtocolError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,342 @@
+use bytes::{BufMut, Bytes};
+use ethrex_rlp::{
+    decode::{decode_rlp_item, get_item_with_prefix, RLPDecode},
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+use std::{fmt::Display, net::IpAddr};
+
+use crate::types::NodeRecord;
+
+/// Messages of the discv5 protocol, sent encrypted inside message and handshake packets.
+/// <https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire.md#protocol-messages>
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Ping(PingMessage),
+    Pong(PongMessage),
+    FindNode(FindNodeMessage),
+    Nodes(NodesMessage),
+    TalkReq(TalkReqMessage),
+    TalkRes(TalkResMessage),
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let variant = match self {
+            Message::Ping(_) => "Ping",
+            Message::Pong(_) => "Pong",
+            Message::FindNode(_) => "FindNode",
+            Message::Nodes(_) => "Nodes",
+            Message::TalkReq(_) => "TalkReq",
+            Message::TalkRes(_) => "TalkRes",
+        };
+        write!(f, "{}", variant)
+    }
+}
+
+impl Message {
+    fn message_type(&self) -> u8 {
+        match self {
+            Message::Ping(_) => 0x01,
+            Message::Pong(_) => 0x02,
+            Message::FindNode(_) => 0x03,
+            Message::Nodes(_) => 0x04,
+            Message::TalkReq(_) => 0x05,
+            Message::TalkRes(_) => 0x06,
+        }
+    }
+
+    pub fn req_id(&self) -> &Bytes {
+        match self {
+            Message::Ping(msg) => &msg.req_id,
+            Message::Pong(msg) => &msg.req_id,
+            Message::FindNode(msg) => &msg.req_id,
+            Message::Nodes(msg) => &msg.req_id,
+            Message::TalkReq(msg) => &msg.req_id,
+            Message::TalkRes(msg) => &msg.req_id,
+        }
+    }
+
+    /// Encodes the message as `message-type || rlp(message-data)`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![self.message_type()];
+        match self {
+            Message::Ping(msg) => msg.encode(&mut buf),
+            Message::Pong(msg) => msg.encode(&mut buf),
+            Message::FindNode(msg) => msg.encode(&mut buf),
+            Message::Nodes(msg) => msg.encode(&mut buf),
+            Message::TalkReq(msg) => msg.encode(&mut buf),
+            Message::TalkRes(msg) => msg.encode(&mut buf),
+        }
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, RLPDecodeError> {
+        let Some((message_type, msg)) = data.split_first() else {
+            return Err(RLPDecodeError::InvalidLength);
+        };
+        match message_type {
+            0x01 => Ok(Message::Ping(PingMessage::decode(msg)?)),
+            0x02 => Ok(Message::Pong(PongMessage::decode(msg)?)),
+            0x03 => Ok(Message::FindNode(FindNodeMessage::decode(msg)?)),
+            0x04 => Ok(Message::Nodes(NodesMessage::decode(msg)?)),
+            0x05 => Ok(Message::TalkReq(TalkReqMessage::decode(msg)?)),
+            0x06 => Ok(Message::TalkRes(TalkResMessage::decode(msg)?)),
+            other => Err(RLPDecodeError::Custom(format!(
+                "Unknown discv5 message type {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PingMessage {
+    pub req_id: Bytes,
+    /// The ENR sequence number of the sender.
+    pub enr_seq: u64,
+}
+
+impl RLPEncode for PingMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.enr_seq)
+            .finish();
+    }
+}
+
+impl RLPDecode for PingMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (enr_seq, decoder) = decoder.decode_field("enr_seq")?;
+        let remaining = decoder.finish_unchecked();
+        Ok((PingMessage { req_id, enr_seq }, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PongMessage {
+    pub req_id: Bytes,
+    /// The ENR sequence number of the sender.
+    pub enr_seq: u64,
+    /// The address the ping was received from.
+    pub recipient_ip: IpAddr,
+    pub recipient_port: u16,
+}
+
+impl RLPEncode for PongMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.enr_seq)
+            .encode_field(&self.recipient_ip)
+            .encode_field(&self.recipient_port)
+            .finish();
+    }
+}
+
+impl RLPDecode for PongMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (enr_seq, decoder) = decoder.decode_field("enr_seq")?;
+        let (recipient_ip, decoder) = decoder.decode_field("recipient_ip")?;
+        let (recipient_port, decoder) = decoder.decode_field("recipient_port")?;
+        let remaining = decoder.finish_unchecked();
+        let msg = PongMessage {
+            req_id,
+            enr_seq,
+            recipient_ip,
+            recipient_port,
+        };
+        Ok((msg, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FindNodeMessage {
+    pub req_id: Bytes,
+    /// The log2 distances to the recipient of the requested nodes, `0` being the recipient itself.
+    pub distances: Vec<u64>,
+}
+
+impl RLPEncode for FindNodeMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.distances)
+            .finish();
+    }
+}
+
+impl RLPDecode for FindNodeMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (distances, decoder) = decoder.decode_field("distances")?;
+        let remaining = decoder.finish_unchecked();
+        Ok((FindNodeMessage { req_id, distances }, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodesMessage {
+    pub req_id: Bytes,
+    /// The number of NODES messages the response is split into.
+    pub total: u64,
+    pub nodes: Vec<NodeRecord>,
+}
+
+impl RLPEncode for NodesMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.total)
+            .encode_field(&self.nodes)
+            .finish();
+    }
+}
+
+impl RLPDecode for NodesMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (total, decoder) = decoder.decode_field("total")?;
+        let (encoded_nodes, decoder) = decoder.get_encoded_item()?;
+        let remaining = decoder.finish_unchecked();
+
+        // The records are decoded one by one, as `NodeRecord` rejects inputs
+        // larger than a single record, which the rest of the list would be.
+        let (is_list, mut payload, _) = decode_rlp_item(&encoded_nodes)?;
+        if !is_list {
+            return Err(RLPDecodeError::UnexpectedString);
+        }
+        let mut nodes = Vec::new();
+        while !payload.is_empty() {
+            let (record, rest) = get_item_with_prefix(payload)?;
+            nodes.push(NodeRecord::decode(record)?);
+            payload = rest;
+        }
+
+        Ok((
+            NodesMessage {
+                req_id,
+                total,
+                nodes,
+            },
+            remaining,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TalkReqMessage {
+    pub req_id: Bytes,
+    pub protocol: Bytes,
+    pub request: Bytes,
+}
+
+impl RLPEncode for TalkReqMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.protocol)
+            .encode_field(&self.request)
+            .finish();
+    }
+}
+
+impl RLPDecode for TalkReqMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (protocol, decoder) = decoder.decode_field("protocol")?;
+        let (request, decoder) = decoder.decode_field("request")?;
+        let remaining = decoder.finish_unchecked();
+        let msg = TalkReqMessage {
+            req_id,
+            protocol,
+            request,
+        };
+        Ok((msg, remaining))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TalkResMessage {
+    pub req_id: Bytes,
+    pub response: Bytes,
+}
+
+impl RLPEncode for TalkResMessage {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.req_id)
+            .encode_field(&self.response)
+            .finish();
+    }
+}
+
+impl RLPDecode for TalkResMessage {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (req_id, decoder) = decoder.decode_field("req_id")?;
+        let (response, decoder) = decoder.decode_field("response")?;
+        let remaining = decoder.finish_unchecked();
+        Ok((TalkResMessage { req_id, response }, remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{network::node_id_from_signing_key, types::Node};
+    use k256::ecdsa::SigningKey;
+    use rand::rngs::OsRng;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn ping_encoding() {
+        // the plaintext of the ping message packet test vector
+        let ping = Message::Ping(PingMessage {
+            req_id: Bytes::from_static(&[0, 0, 0, 1]),
+            enr_seq: 2,
+        });
+        assert_eq!(ping.encode(), hex_literal::hex!("01c6840000000102"));
+    }
+
+    #[test]
+    fn pong_round_trip() {
+        let pong = Message::Pong(PongMessage {
+            req_id: Bytes::from_static(&[7]),
+            enr_seq: 1,
+            recipient_ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            recipient_port: 30303,
+        });
+        assert_eq!(Message::decode(&pong.encode()).unwrap(), pong);
+    }
+
+    #[test]
+    fn nodes_round_trip() {
+        let records: Vec<NodeRecord> = (0..3)
+            .map(|i| {
+                let signer = SigningKey::random(&mut OsRng);
+                let node = Node {
+                    ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, i)),
+                    udp_port: 30303,
+                    tcp_port: 30303,
+                    node_id: node_id_from_signing_key(&signer),
+                };
+                NodeRecord::from_node(node, 1, &signer).unwrap()
+            })
+            .collect();
+        let nodes = Message::Nodes(NodesMessage {
+            req_id: Bytes::from_static(&[1, 2]),
+            total: 1,
+            nodes: records,
+        });
+        assert_eq!(Message::decode(&nodes.encode()).unwrap(), nodes);
+    }
+}
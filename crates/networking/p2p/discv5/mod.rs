@@ -0,0 +1,4 @@
+pub(super) mod messages;
+pub(super) mod packet;
+pub mod server;
+pub(super) mod session;
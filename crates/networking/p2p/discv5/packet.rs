@@ -0,0 +1,418 @@
+use aes::cipher::{KeyIvInit, StreamCipher};
+use ethrex_common::H256;
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+
+use crate::types::NodeRecord;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+pub type Nonce = [u8; 12];
+pub type MaskingIv = [u8; 16];
+pub type IdNonce = [u8; 16];
+
+const PROTOCOL_ID: &[u8; 6] = b"discv5";
+const PROTOCOL_VERSION: u16 = 1;
+const MASKING_IV_SIZE: usize = 16;
+// protocol-id || version || flag || nonce || authdata-size
+const STATIC_HEADER_SIZE: usize = 6 + 2 + 1 + 12 + 2;
+const MIN_PACKET_SIZE: usize = 63;
+pub const MAX_PACKET_SIZE: usize = 1280;
+
+const FLAG_MESSAGE: u8 = 0;
+const FLAG_WHOAREYOU: u8 = 1;
+const FLAG_HANDSHAKE: u8 = 2;
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum PacketError {
+    #[error("Invalid packet size {0}")]
+    WrongSize(usize),
+    #[error("Invalid protocol id")]
+    UnknownProtocolId,
+    #[error("Unsupported protocol version {0}")]
+    UnsupportedVersion(u16),
+    #[error("Unknown packet flag {0}")]
+    UnknownFlag(u8),
+    #[error("Invalid authdata")]
+    MalformedAuthData,
+}
+
+/// The flag-dependent part of the packet header.
+/// <https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire.md#packet-encoding>
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthData {
+    /// An ordinary message packet, encrypted with the session keys.
+    Message { src_id: H256 },
+    /// The handshake challenge, sent when a message can't be decrypted.
+    WhoAreYou { id_nonce: IdNonce, enr_seq: u64 },
+    /// The answer to a challenge, which carries the data needed to establish the session
+    /// along with the first encrypted message.
+    Handshake {
+        src_id: H256,
+        id_signature: Vec<u8>,
+        ephemeral_pubkey: Vec<u8>,
+        record: Option<NodeRecord>,
+    },
+}
+
+impl AuthData {
+    fn flag(&self) -> u8 {
+        match self {
+            AuthData::Message { .. } => FLAG_MESSAGE,
+            AuthData::WhoAreYou { .. } => FLAG_WHOAREYOU,
+            AuthData::Handshake { .. } => FLAG_HANDSHAKE,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            AuthData::Message { src_id } => src_id.as_bytes().to_vec(),
+            AuthData::WhoAreYou { id_nonce, enr_seq } => {
+                let mut buf = id_nonce.to_vec();
+                buf.extend_from_slice(&enr_seq.to_be_bytes());
+                buf
+            }
+            AuthData::Handshake {
+                src_id,
+                id_signature,
+                ephemeral_pubkey,
+                record,
+            } => {
+                let mut buf = src_id.as_bytes().to_vec();
+                // signatures and keys are 64 and 33 bytes long, the casts can't truncate
+                buf.push(id_signature.len() as u8);
+                buf.push(ephemeral_pubkey.len() as u8);
+                buf.extend_from_slice(id_signature);
+                buf.extend_from_slice(ephemeral_pubkey);
+                if let Some(record) = record {
+                    record.encode(&mut buf);
+                }
+                buf
+            }
+        }
+    }
+
+    fn decode(flag: u8, data: &[u8]) -> Result<Self, PacketError> {
+        match flag {
+            FLAG_MESSAGE => {
+                if data.len() != 32 {
+                    return Err(PacketError::MalformedAuthData);
+                }
+                Ok(AuthData::Message {
+                    src_id: H256::from_slice(data),
+                })
+            }
+            FLAG_WHOAREYOU => {
+                let (Ok(id_nonce), Ok(enr_seq)) = (
+                    data.get(..16).unwrap_or_default().try_into(),
+                    data.get(16..).unwrap_or_default().try_into(),
+                ) else {
+                    return Err(PacketError::MalformedAuthData);
+                };
+                Ok(AuthData::WhoAreYou {
+                    id_nonce,
+                    enr_seq: u64::from_be_bytes(enr_seq),
+                })
+            }
+            FLAG_HANDSHAKE => {
+                if data.len() < 34 {
+                    return Err(PacketError::MalformedAuthData);
+                }
+                let src_id = H256::from_slice(&data[..32]);
+                let signature_size = data[32] as usize;
+                let key_size = data[33] as usize;
+                let key_end = 34 + signature_size + key_size;
+                if data.len() < key_end {
+                    return Err(PacketError::MalformedAuthData);
+                }
+                let record = match &data[key_end..] {
+                    [] => None,
+                    record => Some(
+                        NodeRecord::decode(record).map_err(|_| PacketError::MalformedAuthData)?,
+                    ),
+                };
+                Ok(AuthData::Handshake {
+                    src_id,
+                    id_signature: data[34..34 + signature_size].to_vec(),
+                    ephemeral_pubkey: data[34 + signature_size..key_end].to_vec(),
+                    record,
+                })
+            }
+            flag => Err(PacketError::UnknownFlag(flag)),
+        }
+    }
+}
+
+/// A decoded discv5 packet, its message is still encrypted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packet {
+    pub masking_iv: MaskingIv,
+    pub nonce: Nonce,
+    pub auth_data: AuthData,
+    /// The unmasked header, kept as received as it authenticates the message.
+    header: Vec<u8>,
+    pub message: Vec<u8>,
+}
+
+impl Packet {
+    pub fn new(masking_iv: MaskingIv, nonce: Nonce, auth_data: AuthData) -> Self {
+        let auth_data_bytes = auth_data.encode();
+        let mut header = Vec::with_capacity(STATIC_HEADER_SIZE + auth_data_bytes.len());
+        header.extend_from_slice(PROTOCOL_ID);
+        header.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+        header.push(auth_data.flag());
+        header.extend_from_slice(&nonce);
+        // the authdata is bounded by the packet size, the cast can't truncate
+        header.extend_from_slice(&(auth_data_bytes.len() as u16).to_be_bytes());
+        header.extend_from_slice(&auth_data_bytes);
+        Self {
+            masking_iv,
+            nonce,
+            auth_data,
+            header,
+            message: Vec::new(),
+        }
+    }
+
+    /// The data that authenticates the message: `masking-iv || header`.
+    /// For WHOAREYOU packets, this is the challenge data used by the handshake.
+    pub fn authenticated_data(&self) -> Vec<u8> {
+        [self.masking_iv.as_slice(), &self.header].concat()
+    }
+
+    /// Encodes the packet, masking its header with the id of the node it is sent to.
+    pub fn encode(&self, dest_id: &H256) -> Vec<u8> {
+        let mut masked_header = self.header.clone();
+        masking_cipher(dest_id, &self.masking_iv).apply_keystream(&mut masked_header);
+        [self.masking_iv.as_slice(), &masked_header, &self.message].concat()
+    }
+
+    /// Decodes a packet sent to us, unmasking its header with our node id.
+    pub fn decode(local_id: &H256, data: &[u8]) -> Result<Self, PacketError> {
+        if !(MIN_PACKET_SIZE..=MAX_PACKET_SIZE).contains(&data.len()) {
+            return Err(PacketError::WrongSize(data.len()));
+        }
+        let mut masking_iv = MaskingIv::default();
+        masking_iv.copy_from_slice(&data[..MASKING_IV_SIZE]);
+        let mut cipher = masking_cipher(local_id, &masking_iv);
+
+        let mut header = data[MASKING_IV_SIZE..MASKING_IV_SIZE + STATIC_HEADER_SIZE].to_vec();
+        cipher.apply_keystream(&mut header);
+        if &header[..6] != PROTOCOL_ID {
+            return Err(PacketError::UnknownProtocolId);
+        }
+        let version = u16::from_be_bytes([header[6], header[7]]);
+        if version != PROTOCOL_VERSION {
+            return Err(PacketError::UnsupportedVersion(version));
+        }
+        let flag = header[8];
+        let mut nonce = Nonce::default();
+        nonce.copy_from_slice(&header[9..21]);
+        let auth_data_size = u16::from_be_bytes([header[21], header[22]]) as usize;
+
+        let header_end = MASKING_IV_SIZE + STATIC_HEADER_SIZE + auth_data_size;
+        let Some(masked_auth_data) = data.get(MASKING_IV_SIZE + STATIC_HEADER_SIZE..header_end)
+        else {
+            return Err(PacketError::MalformedAuthData);
+        };
+        let mut auth_data = masked_auth_data.to_vec();
+        cipher.apply_keystream(&mut auth_data);
+        header.extend_from_slice(&auth_data);
+
+        Ok(Self {
+            masking_iv,
+            nonce,
+            auth_data: AuthData::decode(flag, &auth_data)?,
+            header,
+            message: data[header_end..].to_vec(),
+        })
+    }
+}
+
+fn masking_cipher(dest_id: &H256, masking_iv: &MaskingIv) -> Aes128Ctr {
+    let mut key = [0; 16];
+    key.copy_from_slice(&dest_id[..16]);
+    Aes128Ctr::new(&key.into(), masking_iv.into())
+}
+
+/// Test vectors from <https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire-test-vectors.md#packet-encodings>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discv5::{
+        messages::{Message, PingMessage},
+        session::{
+            decrypt_message, derive_keys, ecdh, encrypt_message, node_id, public_key_from_slice,
+            verify_id_signature,
+        },
+    };
+    use crate::network::node_id_from_signing_key;
+    use bytes::Bytes;
+    use hex_literal::hex;
+    use k256::ecdsa::SigningKey;
+
+    const NODE_A_KEY: [u8; 32] =
+        hex!("eef77acb6c6a6eebc5b363a475ac583ec7eccdb42b6481424c60f59aa326547f");
+    const NODE_B_KEY: [u8; 32] =
+        hex!("66fb62bfbd66b9177a138c1e5cddbe4f7c30c343e94e68df8769459cb1cde628");
+    const NODE_A_ID: H256 = H256(hex!(
+        "aaaa8419e9f49d0083561b48287df592939a8d19947d8c0ef88f2a4856a69fbb"
+    ));
+    const NODE_B_ID: H256 = H256(hex!(
+        "bbbb9d047f0488c0b5a93c1c3f2d8bafc7c8ff337024a55434a0d0555de64db9"
+    ));
+
+    fn node_id_of(key: &[u8; 32]) -> H256 {
+        let signer = SigningKey::from_slice(key).unwrap();
+        node_id(&node_id_from_signing_key(&signer))
+    }
+
+    #[test]
+    fn node_ids_match_test_vector_keys() {
+        assert_eq!(node_id_of(&NODE_A_KEY), NODE_A_ID);
+        assert_eq!(node_id_of(&NODE_B_KEY), NODE_B_ID);
+    }
+
+    #[test]
+    fn ping_message_packet() {
+        let encoded = hex!(
+            "00000000000000000000000000000000088b3d4342774649325f313964a39e55"
+            "ea96c005ad52be8c7560413a7008f16c9e6d2f43bbea8814a546b7409ce783d3"
+            "4c4f53245d08dab84102ed931f66d1492acb308fa1c6715b9d139b81acbdcc"
+        );
+        let read_key = [0; 16];
+
+        let packet = Packet::decode(&NODE_B_ID, &encoded).unwrap();
+        assert_eq!(packet.nonce, [0xff; 12]);
+        assert_eq!(packet.auth_data, AuthData::Message { src_id: NODE_A_ID });
+        let plaintext = decrypt_message(
+            &read_key,
+            &packet.nonce,
+            &packet.message,
+            &packet.authenticated_data(),
+        )
+        .unwrap();
+        let ping = Message::Ping(PingMessage {
+            req_id: Bytes::from_static(&[0, 0, 0, 1]),
+            enr_seq: 2,
+        });
+        assert_eq!(Message::decode(&plaintext).unwrap(), ping);
+
+        let mut packet = Packet::new([0; 16], [0xff; 12], AuthData::Message { src_id: NODE_A_ID });
+        packet.message = encrypt_message(
+            &read_key,
+            &packet.nonce,
+            &ping.encode(),
+            &packet.authenticated_data(),
+        )
+        .unwrap();
+        assert_eq!(packet.encode(&NODE_B_ID), encoded);
+    }
+
+    #[test]
+    fn whoareyou_packet() {
+        let encoded = hex!(
+            "00000000000000000000000000000000088b3d434277464933a1ccc59f5967ad"
+            "1d6035f15e528627dde75cd68292f9e6c27d6b66c8100a873fcbaed4e16b8d"
+        );
+        let challenge_data = hex!("000000000000000000000000000000006469736376350001010102030405060708090a0b0c00180102030405060708090a0b0c0d0e0f100000000000000000");
+
+        let packet = Packet::decode(&NODE_B_ID, &encoded).unwrap();
+        assert_eq!(packet.nonce, hex!("0102030405060708090a0b0c"));
+        assert_eq!(
+            packet.auth_data,
+            AuthData::WhoAreYou {
+                id_nonce: hex!("0102030405060708090a0b0c0d0e0f10"),
+                enr_seq: 0,
+            }
+        );
+        assert!(packet.message.is_empty());
+        assert_eq!(packet.authenticated_data(), challenge_data);
+
+        let packet = Packet::new(
+            [0; 16],
+            hex!("0102030405060708090a0b0c"),
+            AuthData::WhoAreYou {
+                id_nonce: hex!("0102030405060708090a0b0c0d0e0f10"),
+                enr_seq: 0,
+            },
+        );
+        assert_eq!(packet.encode(&NODE_B_ID), encoded);
+    }
+
+    #[test]
+    fn ping_handshake_packet() {
+        let encoded = hex!(
+            "00000000000000000000000000000000088b3d4342774649305f313964a39e55"
+            "ea96c005ad521d8c7560413a7008f16c9e6d2f43bbea8814a546b7409ce783d3"
+            "4c4f53245d08da4bb252012b2cba3f4f374a90a75cff91f142fa9be3e0a5f3ef"
+            "268ccb9065aeecfd67a999e7fdc137e062b2ec4a0eb92947f0d9a74bfbf44dfb"
+            "a776b21301f8b65efd5796706adff216ab862a9186875f9494150c4ae06fa4d1"
+            "f0396c93f215fa4ef524f1eadf5f0f4126b79336671cbcf7a885b1f8bd2a5d83"
+            "9cf8"
+        );
+        let challenge_data = hex!("000000000000000000000000000000006469736376350001010102030405060708090a0b0c00180102030405060708090a0b0c0d0e0f100000000000000001");
+
+        let packet = Packet::decode(&NODE_B_ID, &encoded).unwrap();
+        assert_eq!(packet.nonce, [0xff; 12]);
+        let AuthData::Handshake {
+            src_id,
+            id_signature,
+            ephemeral_pubkey,
+            record,
+        } = &packet.auth_data
+        else {
+            panic!("expected a handshake packet, got {:?}", packet.auth_data);
+        };
+        assert_eq!(*src_id, NODE_A_ID);
+        assert_eq!(
+            ephemeral_pubkey.as_slice(),
+            hex!("039a003ba6517b473fa0cd74aefe99dadfdb34627f90fec6362df85803908f53a5")
+        );
+        assert!(record.is_none());
+
+        // node B verifies the handshake and derives the session keys from it
+        let node_a_key = SigningKey::from_slice(&NODE_A_KEY).unwrap();
+        verify_id_signature(
+            &node_a_key.verifying_key().into(),
+            id_signature,
+            &challenge_data,
+            ephemeral_pubkey,
+            &NODE_B_ID,
+        )
+        .unwrap();
+        let node_b_key = SigningKey::from_slice(&NODE_B_KEY).unwrap();
+        let secret = ecdh(
+            &node_b_key,
+            &public_key_from_slice(ephemeral_pubkey).unwrap(),
+        );
+        let (initiator_key, _) = derive_keys(&secret, &challenge_data, &NODE_A_ID, &NODE_B_ID);
+        assert_eq!(initiator_key, hex!("4f9fac6de7567d1e3b1241dffe90f662"));
+
+        let plaintext = decrypt_message(
+            &initiator_key,
+            &packet.nonce,
+            &packet.message,
+            &packet.authenticated_data(),
+        )
+        .unwrap();
+        assert_eq!(
+            Message::decode(&plaintext).unwrap(),
+            Message::Ping(PingMessage {
+                req_id: Bytes::from_static(&[0, 0, 0, 1]),
+                enr_seq: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_packets_for_other_nodes() {
+        let encoded = hex!(
+            "00000000000000000000000000000000088b3d434277464933a1ccc59f5967ad"
+            "1d6035f15e528627dde75cd68292f9e6c27d6b66c8100a873fcbaed4e16b8d"
+        );
+        assert_eq!(
+            Packet::decode(&NODE_A_ID, &encoded),
+            Err(PacketError::UnknownProtocolId)
+        );
+    }
+}
@@ -0,0 +1,1046 @@
+use super::{
+    messages::{FindNodeMessage, Message, NodesMessage, PingMessage, PongMessage, TalkResMessage},
+    packet::{AuthData, Nonce, Packet, MAX_PACKET_SIZE},
+    session::{
+        decrypt_message, derive_keys, ecdh, encrypt_message, node_id, public_key_from_slice,
+        sign_id_nonce, verify_id_signature, SessionKeys,
+    },
+};
+use crate::{
+    discv4::{helpers::current_unix_time, server::DiscoveryError},
+    kademlia::{bucket_number, MAX_NODES_PER_BUCKET},
+    network::{handle_peer_as_initiator, P2PContext},
+    rlpx::connection::MAX_PEERS_TCP_CONNECTIONS,
+    types::{Node, NodeRecord},
+};
+use bytes::Bytes;
+use ethrex_common::{H256, H512};
+use k256::ecdsa::SigningKey;
+use rand::rngs::OsRng;
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{net::UdpSocket, sync::Mutex};
+use tracing::{debug, error};
+
+// These interval times are arbitrary numbers, maybe we should read them from a cfg or a cli param
+const REVALIDATION_INTERVAL_IN_SECONDS: u64 = 30;
+const LOOKUP_INTERVAL_IN_SECONDS: u64 = 60;
+/// Time after which requests without response and unanswered challenges are dropped.
+const REQUEST_EXPIRATION_IN_SECONDS: u64 = 20;
+/// ENRs are at most 300 bytes long, so this many fit in a single packet.
+const MAX_NODES_PER_MESSAGE: usize = 3;
+/// Size of the random message of the packets sent before a session is established.
+const RANDOM_MESSAGE_SIZE: usize = 20;
+
+/// A request sent to a node and not fully answered yet.
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    node: Node,
+    message: Message,
+    sent_at: u64,
+    /// Number of responses received, a FINDNODE may be answered by several NODES messages.
+    responses: u64,
+}
+
+/// An established session with a node.
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    keys: SessionKeys,
+    node: Node,
+}
+
+/// A WHOAREYOU challenge waiting for the node to answer it with a handshake.
+#[derive(Debug, Clone)]
+struct Challenge {
+    data: Vec<u8>,
+    sent_at: u64,
+}
+
+#[derive(Debug, Default)]
+struct Discv5State {
+    /// Sessions by the discv5 node id of the peer.
+    sessions: HashMap<H256, Session>,
+    /// Requests by the nonce of the last packet that carried them, as a WHOAREYOU
+    /// refers to it when the request has to be resent within a handshake.
+    pending_requests: HashMap<Nonce, PendingRequest>,
+    /// Challenges by the discv5 node id of the peer they were sent to.
+    challenges: HashMap<H256, Challenge>,
+}
+
+impl Discv5State {
+    /// Returns the request of a node a response is answering.
+    fn find_request(
+        &mut self,
+        src_id: H256,
+        req_id: &Bytes,
+    ) -> Option<(Nonce, &mut PendingRequest)> {
+        self.pending_requests
+            .iter_mut()
+            .find(|(_, request)| {
+                request.message.req_id() == req_id && node_id(&request.node.node_id) == src_id
+            })
+            .map(|(nonce, request)| (*nonce, request))
+    }
+
+    fn remove_expired(&mut self) {
+        let now = current_unix_time();
+        self.pending_requests.retain(|_, request| {
+            now.saturating_sub(request.sent_at) < REQUEST_EXPIRATION_IN_SECONDS
+        });
+        self.challenges.retain(|_, challenge| {
+            now.saturating_sub(challenge.sent_at) < REQUEST_EXPIRATION_IN_SECONDS
+        });
+    }
+}
+
+/// Implements the discv5 protocol see: https://github.com/ethereum/devp2p/blob/master/discv5/discv5.md
+///
+/// Only the node discovery part of the protocol is supported: talk requests are answered
+/// with empty responses and topic advertisement isn't implemented.
+/// The nodes found are stored in the same [`KademliaTable`](crate::kademlia::KademliaTable) used by discv4,
+/// whose distance metric is the same.
+#[derive(Debug, Clone)]
+pub struct Discv5Server {
+    ctx: P2PContext,
+    udp_socket: Arc<UdpSocket>,
+    local_id: H256,
+    state: Arc<Mutex<Discv5State>>,
+    revalidation_interval_seconds: u64,
+    lookup_interval_seconds: u64,
+}
+
+impl Discv5Server {
+    /// Initializes a Discv5 UDP socket and creates a new `Discv5Server` instance.
+    /// Returns an error if the socket binding fails.
+    pub async fn try_new(ctx: P2PContext) -> Result<Self, DiscoveryError> {
+        let udp_socket = UdpSocket::bind(ctx.local_node.udp_addr())
+            .await
+            .map_err(DiscoveryError::BindSocket)?;
+
+        Ok(Self {
+            local_id: node_id(&ctx.local_node.node_id),
+            ctx,
+            udp_socket: Arc::new(udp_socket),
+            state: Arc::new(Mutex::new(Discv5State::default())),
+            revalidation_interval_seconds: REVALIDATION_INTERVAL_IN_SECONDS,
+            lookup_interval_seconds: LOOKUP_INTERVAL_IN_SECONDS,
+        })
+    }
+
+    /// Initializes the discovery server. It:
+    /// - Spawns tasks to handle incoming packets, revalidate known nodes and look for new ones.
+    /// - Pings the bootnodes, which establishes a session with them.
+    pub async fn start(&self, bootnodes: Vec<Node>) -> Result<(), DiscoveryError> {
+        self.ctx.tracker.spawn({
+            let self_clone = self.clone();
+            async move { self_clone.receive().await }
+        });
+        self.ctx.tracker.spawn({
+            let self_clone = self.clone();
+            async move { self_clone.start_revalidation().await }
+        });
+        self.ctx.tracker.spawn({
+            let self_clone = self.clone();
+            async move { self_clone.start_lookups(10).await }
+        });
+        for node in bootnodes {
+            if let Err(e) = self.try_add_peer_and_ping(node).await {
+                debug!("Error while adding bootnode to table: {:?}", e);
+            };
+        }
+
+        Ok(())
+    }
+
+    pub async fn receive(&self) {
+        let mut buf = vec![0; MAX_PACKET_SIZE];
+
+        loop {
+            let (read, from) = match self.udp_socket.recv_from(&mut buf).await {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("Error receiving data from socket: {e}. Stopping discovery server");
+                    return;
+                }
+            };
+            debug!("Received {read} bytes from {from}");
+
+            match Packet::decode(&self.local_id, &buf[..read]) {
+                Err(e) => debug!("Could not decode packet: {e}"),
+                Ok(packet) => {
+                    if let Err(e) = self.handle_packet(packet, from).await {
+                        debug!("Error while processing packet from {from}: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn handle_packet(&self, packet: Packet, from: SocketAddr) -> Result<(), DiscoveryError> {
+        match &packet.auth_data {
+            AuthData::Message { src_id } => {
+                let session = self.state.lock().await.sessions.get(src_id).copied();
+                let decrypted = session.and_then(|session| {
+                    decrypt_message(
+                        &session.keys.read_key,
+                        &packet.nonce,
+                        &packet.message,
+                        &packet.authenticated_data(),
+                    )
+                    .ok()
+                });
+                // Without a session, or with an outdated one, we challenge the node to do a handshake
+                let (Some(session), Some(plaintext)) = (session, decrypted) else {
+                    return self.send_whoareyou(*src_id, packet.nonce, from).await;
+                };
+                let msg = Message::decode(&plaintext)
+                    .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+                let node = Node {
+                    ip: from.ip(),
+                    udp_port: from.port(),
+                    ..session.node
+                };
+                self.handle_message(node, msg).await
+            }
+            AuthData::WhoAreYou { enr_seq, .. } => {
+                let request = self
+                    .state
+                    .lock()
+                    .await
+                    .pending_requests
+                    .remove(&packet.nonce);
+                let Some(request) = request else {
+                    return Err(DiscoveryError::InvalidMessage(
+                        "WHOAREYOU doesn't match any request".into(),
+                    ));
+                };
+                self.send_handshake(request, &packet.authenticated_data(), *enr_seq)
+                    .await
+            }
+            AuthData::Handshake {
+                src_id,
+                id_signature,
+                ephemeral_pubkey,
+                record,
+            } => {
+                let challenge = self.state.lock().await.challenges.remove(src_id);
+                let Some(challenge) = challenge else {
+                    return Err(DiscoveryError::InvalidMessage(
+                        "handshake without a previous challenge".into(),
+                    ));
+                };
+
+                // The record is only sent if the one we have is outdated, otherwise the node has to be known
+                let node = match record {
+                    Some(record) => self.node_from_handshake_record(*src_id, record, from)?,
+                    None => self.find_node(*src_id, from).await?,
+                };
+
+                let public_key = public_key_from_slice(node.node_id.as_bytes())
+                    .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+                verify_id_signature(
+                    &public_key,
+                    id_signature,
+                    &challenge.data,
+                    ephemeral_pubkey,
+                    &self.local_id,
+                )
+                .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+
+                let ephemeral_pubkey = public_key_from_slice(ephemeral_pubkey)
+                    .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+                let secret = ecdh(&self.ctx.signer, &ephemeral_pubkey);
+                let (initiator_key, recipient_key) =
+                    derive_keys(&secret, &challenge.data, src_id, &self.local_id);
+                let keys = SessionKeys::new_recipient(initiator_key, recipient_key);
+
+                let plaintext = decrypt_message(
+                    &keys.read_key,
+                    &packet.nonce,
+                    &packet.message,
+                    &packet.authenticated_data(),
+                )
+                .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+                let msg = Message::decode(&plaintext)
+                    .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+
+                self.state
+                    .lock()
+                    .await
+                    .sessions
+                    .insert(*src_id, Session { keys, node });
+                debug!("Established session with {}", node.node_id);
+
+                let inserted = self.try_add_peer(node).await;
+                if let Some(record) = record {
+                    self.update_record(node.node_id, record).await;
+                }
+                self.handle_message(node, msg).await?;
+                // new nodes are pinged, like the ones found in any other way
+                if inserted {
+                    self.ping(node).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn handle_message(&self, node: Node, msg: Message) -> Result<(), DiscoveryError> {
+        debug!("Message: {msg} from {}", node.node_id);
+        match msg {
+            Message::Ping(ping) => {
+                let pong = Message::Pong(PongMessage {
+                    req_id: ping.req_id,
+                    enr_seq: self.ctx.enr_seq,
+                    recipient_ip: node.ip,
+                    recipient_port: node.udp_port,
+                });
+                self.send_message(node, pong).await?;
+
+                let peer = {
+                    let table = self.ctx.table.lock().await;
+                    table.get_by_node_id(node.node_id).cloned()
+                };
+                match peer {
+                    None => self.try_add_peer_and_ping(node).await,
+                    Some(peer) if ping.enr_seq > peer.record.seq => {
+                        debug!("Found outdated enr-seq, requesting the node record");
+                        self.request_record(node).await
+                    }
+                    Some(_) => Ok(()),
+                }
+            }
+            Message::Pong(pong) => {
+                self.take_response_request(&node, &pong.req_id, 1).await?;
+
+                let peer = {
+                    let mut table = self.ctx.table.lock().await;
+                    table.pong_answered(node.node_id, current_unix_time());
+                    table.get_by_node_id(node.node_id).cloned()
+                };
+                let Some(peer) = peer else {
+                    return Ok(());
+                };
+                if pong.enr_seq > peer.record.seq {
+                    debug!("Found outdated enr-seq, requesting the node record");
+                    self.request_record(node).await?;
+                }
+
+                // We won't initiate a connection if we are already connected.
+                // This will typically be the case when revalidating a node.
+                if peer.is_connected {
+                    return Ok(());
+                }
+
+                // We won't initiate a connection if we have reached the maximum number of peers.
+                let active_connections = {
+                    let table = self.ctx.table.lock().await;
+                    table.count_connected_peers()
+                };
+                if active_connections >= MAX_PEERS_TCP_CONNECTIONS {
+                    return Ok(());
+                }
+
+                let ctx = self.ctx.clone();
+                self.ctx
+                    .tracker
+                    .spawn(async move { handle_peer_as_initiator(ctx, peer.node).await });
+                Ok(())
+            }
+            Message::FindNode(msg) => {
+                let records = self.records_at_distances(&msg.distances).await?;
+                // we always answer with at least one message, even if there are no records to send
+                let chunks: Vec<&[NodeRecord]> = if records.is_empty() {
+                    vec![&[]]
+                } else {
+                    records.chunks(MAX_NODES_PER_MESSAGE).collect()
+                };
+                let total = chunks.len() as u64;
+                for chunk in chunks {
+                    let nodes = Message::Nodes(NodesMessage {
+                        req_id: msg.req_id.clone(),
+                        total,
+                        nodes: chunk.to_vec(),
+                    });
+                    self.send_message(node, nodes).await?;
+                }
+                Ok(())
+            }
+            Message::Nodes(msg) => {
+                if msg.total > MAX_NODES_PER_BUCKET as u64 {
+                    return Err(DiscoveryError::InvalidMessage(
+                        "nodes response split in too many messages".into(),
+                    ));
+                }
+                let request = self
+                    .take_response_request(&node, &msg.req_id, msg.total)
+                    .await?;
+                let Message::FindNode(FindNodeMessage { distances, .. }) = request.message else {
+                    return Err(DiscoveryError::InvalidMessage(
+                        "nodes received for a request other than findnode".into(),
+                    ));
+                };
+                if msg.nodes.len() > MAX_NODES_PER_BUCKET * distances.len() {
+                    return Err(DiscoveryError::InvalidMessage(
+                        "sent more than allowed nodes".into(),
+                    ));
+                }
+
+                for record in msg.nodes {
+                    if !record.verify_signature() {
+                        debug!("Discarding node record with an invalid signature");
+                        continue;
+                    }
+                    let Some(record_node_id) = record.node_id() else {
+                        continue;
+                    };
+                    if !distances.contains(&log_distance(node.node_id, record_node_id)) {
+                        debug!("Discarding node record at a distance that wasn't requested");
+                        continue;
+                    }
+                    if record_node_id == node.node_id {
+                        self.update_record(node.node_id, &record).await;
+                        continue;
+                    }
+                    let Ok(new_node) = Node::from_record(&record) else {
+                        continue;
+                    };
+                    if self.try_add_peer_and_ping(new_node).await.is_ok() {
+                        self.update_record(new_node.node_id, &record).await;
+                    }
+                }
+                Ok(())
+            }
+            Message::TalkReq(msg) => {
+                // No talk protocols are supported, the spec requires an empty response for those
+                let response = Message::TalkRes(TalkResMessage {
+                    req_id: msg.req_id,
+                    response: Bytes::new(),
+                });
+                self.send_message(node, response).await
+            }
+            Message::TalkRes(msg) => {
+                self.take_response_request(&node, &msg.req_id, 1).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the request answered by a response. The request is removed from the pending ones
+    /// once all its expected responses arrived, a FINDNODE may be answered by several NODES messages.
+    async fn take_response_request(
+        &self,
+        node: &Node,
+        req_id: &Bytes,
+        expected_responses: u64,
+    ) -> Result<PendingRequest, DiscoveryError> {
+        let mut state = self.state.lock().await;
+        let Some((nonce, request)) = state.find_request(node_id(&node.node_id), req_id) else {
+            return Err(DiscoveryError::InvalidMessage(
+                "response doesn't match any request".into(),
+            ));
+        };
+        request.responses += 1;
+        let request = request.clone();
+        if request.responses >= expected_responses {
+            state.pending_requests.remove(&nonce);
+        }
+        Ok(request)
+    }
+
+    /// Returns the known records of the nodes at the given distances, including our own for distance 0.
+    async fn records_at_distances(
+        &self,
+        distances: &[u64],
+    ) -> Result<Vec<NodeRecord>, DiscoveryError> {
+        let mut records = vec![];
+        let distances: HashSet<u64> = distances.iter().copied().collect();
+        let table = self.ctx.table.lock().await;
+        for distance in distances {
+            if records.len() >= MAX_NODES_PER_BUCKET {
+                break;
+            }
+            if distance == 0 {
+                records.push(self.local_record()?);
+                continue;
+            }
+            let Some(bucket) = table.buckets().get(distance as usize - 1) else {
+                continue;
+            };
+            records.extend(
+                bucket
+                    .peers
+                    .iter()
+                    .filter(|peer| peer.record != NodeRecord::default())
+                    .map(|peer| peer.record.clone()),
+            );
+        }
+        records.truncate(MAX_NODES_PER_BUCKET);
+        Ok(records)
+    }
+
+    /// Starts a tokio scheduler that:
+    /// - performs periodic revalidation of the current nodes (sends a ping to the old nodes).
+    /// - drops the requests and challenges which weren't answered in time.
+    ///
+    /// Revalidation works like in [`Discv4Server`](crate::discv4::server::Discv4Server).
+    async fn start_revalidation(&self) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.revalidation_interval_seconds));
+
+        // first tick starts immediately
+        interval.tick().await;
+
+        let mut previously_pinged_peers = HashSet::new();
+        loop {
+            interval.tick().await;
+            debug!("Running peer revalidation");
+            self.state.lock().await.remove_expired();
+
+            // first check that the peers we ping have responded
+            for node_id in previously_pinged_peers {
+                let mut table_lock = self.ctx.table.lock().await;
+                let Some(peer) = table_lock.get_by_node_id_mut(node_id) else {
+                    continue;
+                };
+
+                if let Some(has_answered) = peer.revalidation {
+                    if has_answered {
+                        peer.increment_liveness();
+                    } else {
+                        peer.decrement_liveness();
+                    }
+                }
+
+                peer.revalidation = None;
+
+                if peer.liveness == 0 {
+                    let new_peer = table_lock.replace_peer(node_id);
+                    if let Some(new_peer) = new_peer {
+                        drop(table_lock);
+                        let _ = self.ping(new_peer.node).await;
+                    }
+                }
+            }
+
+            // now send a ping to the least recently pinged peers
+            let peers = self
+                .ctx
+                .table
+                .lock()
+                .await
+                .get_least_recently_pinged_peers(3);
+            previously_pinged_peers = HashSet::default();
+            for peer in peers {
+                debug!("Pinging peer {:?} to re-validate!", peer.node.node_id);
+                let _ = self.ping(peer.node).await;
+                previously_pinged_peers.insert(peer.node.node_id);
+                let mut table = self.ctx.table.lock().await;
+                let peer = table.get_by_node_id_mut(peer.node.node_id);
+                if let Some(peer) = peer {
+                    peer.revalidation = Some(false);
+                }
+            }
+
+            debug!("Peer revalidation finished");
+        }
+    }
+
+    /// Periodically looks for nodes close to a random target: the nodes we know closest to
+    /// it are asked for the nodes they have at around the target's distance from them.
+    /// The nodes received are added to the table and pinged by the NODES handler.
+    async fn start_lookups(&self, initial_interval_wait_seconds: u64) {
+        tokio::time::sleep(Duration::from_secs(initial_interval_wait_seconds)).await;
+        let mut interval = tokio::time::interval(Duration::from_secs(self.lookup_interval_seconds));
+
+        loop {
+            interval.tick().await;
+            debug!("Starting lookup");
+
+            let target = H512::random_using(&mut OsRng);
+            let closest_nodes = self.ctx.table.lock().await.get_closest_nodes(target);
+            for node in closest_nodes.into_iter().take(3) {
+                let distance = log_distance(target, node.node_id);
+                let distances = [distance, distance + 1, distance.saturating_sub(1)]
+                    .into_iter()
+                    .filter(|distance| (1..=256).contains(distance))
+                    .collect();
+                let find_node = Message::FindNode(FindNodeMessage {
+                    req_id: random_req_id(),
+                    distances,
+                });
+                if let Err(e) = self.send_message(node, find_node).await {
+                    debug!("Error while sending findnode: {:?}", e);
+                }
+            }
+
+            debug!("Lookup finished");
+        }
+    }
+
+    /// Attempts to add a node to the Kademlia table and send a ping if necessary.
+    ///
+    /// - If the node is **not found** in the table and there is enough space, it will be added,
+    ///   and a ping message will be sent to verify connectivity.
+    /// - If the node is **already present**, no action is taken.
+    async fn try_add_peer_and_ping(&self, node: Node) -> Result<(), DiscoveryError> {
+        if self.try_add_peer(node).await {
+            self.ping(node).await?;
+        }
+        Ok(())
+    }
+
+    /// Adds a node to the Kademlia table, returns whether it was inserted.
+    async fn try_add_peer(&self, node: Node) -> bool {
+        // sanity check to make sure we are not storing ourselves
+        if node.node_id == self.ctx.local_node.node_id {
+            return false;
+        }
+        let (_, inserted) = self.ctx.table.lock().await.insert_node(node);
+        inserted
+    }
+
+    /// Stores the record of a peer if it is newer than the one we have.
+    async fn update_record(&self, peer_id: H512, record: &NodeRecord) {
+        let mut table = self.ctx.table.lock().await;
+        let Some(peer) = table.get_by_node_id_mut(peer_id) else {
+            return;
+        };
+        if peer.record != NodeRecord::default() && record.seq <= peer.record.seq {
+            return;
+        }
+        if let Ok(node) = Node::from_record(record) {
+            peer.node.tcp_port = node.tcp_port;
+        }
+        peer.record = record.clone();
+        debug!(
+            "Node with id {:?} record has been successfully updated",
+            peer_id
+        );
+    }
+
+    async fn ping(&self, node: Node) -> Result<(), DiscoveryError> {
+        let ping = Message::Ping(PingMessage {
+            req_id: random_req_id(),
+            enr_seq: self.ctx.enr_seq,
+        });
+        self.send_message(node, ping).await?;
+        self.ctx
+            .table
+            .lock()
+            .await
+            .update_peer_ping(node.node_id, None, current_unix_time());
+        Ok(())
+    }
+
+    /// Requests the record of a node, which is the only one at distance 0 from it.
+    async fn request_record(&self, node: Node) -> Result<(), DiscoveryError> {
+        let find_node = Message::FindNode(FindNodeMessage {
+            req_id: random_req_id(),
+            distances: vec![0],
+        });
+        self.send_message(node, find_node).await
+    }
+
+    /// Sends a message to a node, encrypted with the session keys.
+    /// Without a session, a packet with random content is sent, so that the node challenges us
+    /// and the message is sent again within the handshake.
+    async fn send_message(&self, node: Node, message: Message) -> Result<(), DiscoveryError> {
+        let dest_id = node_id(&node.node_id);
+        let nonce: Nonce = rand::random();
+        let mut packet = Packet::new(
+            rand::random(),
+            nonce,
+            AuthData::Message {
+                src_id: self.local_id,
+            },
+        );
+
+        let mut state = self.state.lock().await;
+        packet.message = match state.sessions.get(&dest_id) {
+            Some(session) => encrypt_message(
+                &session.keys.write_key,
+                &nonce,
+                &message.encode(),
+                &packet.authenticated_data(),
+            )
+            .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?,
+            None => (0..RANDOM_MESSAGE_SIZE).map(|_| rand::random()).collect(),
+        };
+        if matches!(
+            message,
+            Message::Ping(_) | Message::FindNode(_) | Message::TalkReq(_)
+        ) {
+            state.pending_requests.insert(
+                nonce,
+                PendingRequest {
+                    node,
+                    message,
+                    sent_at: current_unix_time(),
+                    responses: 0,
+                },
+            );
+        }
+        drop(state);
+
+        self.send_packet(&packet, &dest_id, node.udp_addr()).await
+    }
+
+    async fn send_whoareyou(
+        &self,
+        src_id: H256,
+        request_nonce: Nonce,
+        to: SocketAddr,
+    ) -> Result<(), DiscoveryError> {
+        let known_enr_seq = self
+            .ctx
+            .table
+            .lock()
+            .await
+            .iter_peers()
+            .find(|peer| node_id(&peer.node.node_id) == src_id)
+            .map(|peer| peer.record.seq)
+            .unwrap_or_default();
+        let packet = Packet::new(
+            rand::random(),
+            request_nonce,
+            AuthData::WhoAreYou {
+                id_nonce: rand::random(),
+                enr_seq: known_enr_seq,
+            },
+        );
+        self.state.lock().await.challenges.insert(
+            src_id,
+            Challenge {
+                data: packet.authenticated_data(),
+                sent_at: current_unix_time(),
+            },
+        );
+        self.send_packet(&packet, &src_id, to).await
+    }
+
+    /// Answers a WHOAREYOU challenge, establishing a session and resending the request
+    /// that triggered it.
+    async fn send_handshake(
+        &self,
+        request: PendingRequest,
+        challenge_data: &[u8],
+        enr_seq: u64,
+    ) -> Result<(), DiscoveryError> {
+        let node = request.node;
+        let dest_id = node_id(&node.node_id);
+        let dest_pubkey = public_key_from_slice(node.node_id.as_bytes())
+            .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+
+        let ephemeral_key = SigningKey::random(&mut OsRng);
+        let ephemeral_pubkey = ephemeral_key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec();
+        let secret = ecdh(&ephemeral_key, &dest_pubkey);
+        let (initiator_key, recipient_key) =
+            derive_keys(&secret, challenge_data, &self.local_id, &dest_id);
+        let keys = SessionKeys::new_initiator(initiator_key, recipient_key);
+        let id_signature = sign_id_nonce(
+            &self.ctx.signer,
+            challenge_data,
+            &ephemeral_pubkey,
+            &dest_id,
+        )
+        .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+        // the record is only sent if the one the node has is outdated
+        let record = if enr_seq < self.ctx.enr_seq {
+            Some(self.local_record()?)
+        } else {
+            None
+        };
+
+        let nonce: Nonce = rand::random();
+        let mut packet = Packet::new(
+            rand::random(),
+            nonce,
+            AuthData::Handshake {
+                src_id: self.local_id,
+                id_signature: id_signature.to_vec(),
+                ephemeral_pubkey,
+                record,
+            },
+        );
+        packet.message = encrypt_message(
+            &keys.write_key,
+            &nonce,
+            &request.message.encode(),
+            &packet.authenticated_data(),
+        )
+        .map_err(|e| DiscoveryError::InvalidMessage(e.to_string()))?;
+
+        {
+            let mut state = self.state.lock().await;
+            state.sessions.insert(dest_id, Session { keys, node });
+            state.pending_requests.insert(
+                nonce,
+                PendingRequest {
+                    sent_at: current_unix_time(),
+                    ..request
+                },
+            );
+        }
+        self.send_packet(&packet, &dest_id, node.udp_addr()).await
+    }
+
+    async fn send_packet(
+        &self,
+        packet: &Packet,
+        dest_id: &H256,
+        to: SocketAddr,
+    ) -> Result<(), DiscoveryError> {
+        let buf = packet.encode(dest_id);
+        let bytes_sent = self
+            .udp_socket
+            .send_to(&buf, to)
+            .await
+            .map_err(DiscoveryError::MessageSendFailure)?;
+
+        if bytes_sent != buf.len() {
+            return Err(DiscoveryError::PartialMessageSent);
+        }
+        Ok(())
+    }
+
+    fn local_record(&self) -> Result<NodeRecord, DiscoveryError> {
+        NodeRecord::from_node(self.ctx.local_node, self.ctx.enr_seq, &self.ctx.signer)
+            .map_err(|_| DiscoveryError::InvalidMessage("could not build local node record".into()))
+    }
+
+    /// Builds the node of a handshake from the record it carries. The packet's source
+    /// address is used, as it is the one the node can be reached at.
+    fn node_from_handshake_record(
+        &self,
+        src_id: H256,
+        record: &NodeRecord,
+        from: SocketAddr,
+    ) -> Result<Node, DiscoveryError> {
+        if !record.verify_signature() {
+            return Err(DiscoveryError::InvalidMessage(
+                "Signature verification invalid".into(),
+            ));
+        }
+        let Some(public_key) = record.node_id() else {
+            return Err(DiscoveryError::InvalidMessage(
+                "record does not have a valid public key".into(),
+            ));
+        };
+        if node_id(&public_key) != src_id {
+            return Err(DiscoveryError::InvalidMessage(
+                "record doesn't belong to the handshake sender".into(),
+            ));
+        }
+        Ok(Node {
+            ip: from.ip(),
+            udp_port: from.port(),
+            tcp_port: record.decode_pairs().tcp_port.unwrap_or(from.port()),
+            node_id: public_key,
+        })
+    }
+
+    /// Finds the node of a discv5 node id among the ones we have a session with or in the table.
+    async fn find_node(&self, src_id: H256, from: SocketAddr) -> Result<Node, DiscoveryError> {
+        let session_node = self
+            .state
+            .lock()
+            .await
+            .sessions
+            .get(&src_id)
+            .map(|session| session.node);
+        let node = match session_node {
+            Some(node) => Some(node),
+            None => self
+                .ctx
+                .table
+                .lock()
+                .await
+                .iter_peers()
+                .find(|peer| node_id(&peer.node.node_id) == src_id)
+                .map(|peer| peer.node),
+        };
+        let Some(node) = node else {
+            return Err(DiscoveryError::InvalidMessage(
+                "handshake from an unknown node without record".into(),
+            ));
+        };
+        Ok(Node {
+            ip: from.ip(),
+            udp_port: from.port(),
+            ..node
+        })
+    }
+}
+
+/// The logarithmic distance between the discv5 node ids of two nodes, `0` if they are the same.
+/// <https://github.com/ethereum/devp2p/blob/master/discv5/discv5-theory.md#nodes-records-and-distances>
+fn log_distance(node_1: H512, node_2: H512) -> u64 {
+    if node_1 == node_2 {
+        0
+    } else {
+        bucket_number(node_1, node_2) as u64 + 1
+    }
+}
+
+fn random_req_id() -> Bytes {
+    Bytes::copy_from_slice(&rand::random::<[u8; 8]>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kademlia::KademliaTable,
+        network::{node_id_from_signing_key, serve_p2p_requests, MAX_MESSAGES_TO_BROADCAST},
+        rlpx::message::Message as RLPxMessage,
+    };
+    use ethrex_blockchain::Blockchain;
+    use ethrex_storage::{EngineType, Store};
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::time::sleep;
+
+    async fn start_discovery_server(udp_port: u16) -> Result<Discv5Server, DiscoveryError> {
+        let signer = SigningKey::random(&mut OsRng);
+        let node_id = node_id_from_signing_key(&signer);
+        let local_node = Node {
+            ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            node_id,
+            udp_port,
+            tcp_port: udp_port,
+        };
+
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let blockchain = Arc::new(Blockchain::default_with_store(storage.clone()));
+        let table = Arc::new(Mutex::new(KademliaTable::new(node_id)));
+        let (broadcast, _) = tokio::sync::broadcast::channel::<(tokio::task::Id, Arc<RLPxMessage>)>(
+            MAX_MESSAGES_TO_BROADCAST,
+        );
+        let tracker = tokio_util::task::TaskTracker::new();
+
+        let ctx = P2PContext {
+            local_node,
+            enr_seq: current_unix_time(),
+            tracker: tracker.clone(),
+            signer,
+            table,
+            storage,
+            blockchain,
+            broadcast,
+        };
+
+        let discv5 = Discv5Server::try_new(ctx.clone()).await?;
+        tracker.spawn({
+            let discv5 = discv5.clone();
+            async move { discv5.receive().await }
+        });
+        // the nodes connect to each other via tcp once bonded, and remove each other from
+        // their tables if that fails
+        ctx.tracker.spawn(serve_p2p_requests(ctx.clone()));
+
+        Ok(discv5)
+    }
+
+    /// Makes `a` ping `b`, which establishes a session between them, and waits for the exchange to finish.
+    async fn connect_servers(
+        server_a: &Discv5Server,
+        server_b: &Discv5Server,
+    ) -> Result<(), DiscoveryError> {
+        server_a
+            .try_add_peer_and_ping(server_b.ctx.local_node)
+            .await?;
+        sleep(Duration::from_secs(1)).await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn discv5_handshake_and_ping() -> Result<(), DiscoveryError> {
+        let server_a = start_discovery_server(8100).await?;
+        let server_b = start_discovery_server(8101).await?;
+
+        connect_servers(&server_a, &server_b).await?;
+
+        for (server, other) in [(&server_a, &server_b), (&server_b, &server_a)] {
+            let other_id = other.ctx.local_node.node_id;
+            assert!(server
+                .state
+                .lock()
+                .await
+                .sessions
+                .contains_key(&node_id(&other_id)));
+            let table = server.ctx.table.lock().await;
+            let peer = table.get_by_node_id(other_id).expect("peer not in table");
+            // both nodes answered the ping of the other one
+            assert!(peer.is_proven);
+        }
+
+        // `a` sent its record in the handshake, `b` got it when answering the ping from `b`
+        let table = server_b.ctx.table.lock().await;
+        let peer = table.get_by_node_id(server_a.ctx.local_node.node_id);
+        assert_eq!(peer.map(|peer| peer.record.seq), Some(server_a.ctx.enr_seq));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn discv5_find_node() -> Result<(), DiscoveryError> {
+        let server_a = start_discovery_server(8102).await?;
+        let server_b = start_discovery_server(8103).await?;
+        let server_c = start_discovery_server(8104).await?;
+
+        connect_servers(&server_a, &server_b).await?;
+        connect_servers(&server_c, &server_b).await?;
+
+        // `a` asks `b` for the nodes at the distance `c` is from it
+        let node_b = server_b.ctx.local_node;
+        let node_c = server_c.ctx.local_node;
+        let find_node = Message::FindNode(FindNodeMessage {
+            req_id: random_req_id(),
+            distances: vec![log_distance(node_b.node_id, node_c.node_id)],
+        });
+        server_a.send_message(node_b, find_node).await?;
+        sleep(Duration::from_secs(1)).await;
+
+        let table = server_a.ctx.table.lock().await;
+        let peer = table.get_by_node_id(node_c.node_id).expect("c not found");
+        assert_eq!(peer.record.seq, server_c.ctx.enr_seq);
+        // `a` pinged `c` after finding it
+        assert!(peer.is_proven);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn discv5_record_update() -> Result<(), DiscoveryError> {
+        let server_a = start_discovery_server(8105).await?;
+        let server_b = start_discovery_server(8106).await?;
+
+        connect_servers(&server_a, &server_b).await?;
+
+        // make the record `b` has of `a` outdated
+        let node_a = server_a.ctx.local_node;
+        {
+            let mut table = server_b.ctx.table.lock().await;
+            let peer = table
+                .get_by_node_id_mut(node_a.node_id)
+                .expect("a not found");
+            peer.record = NodeRecord::from_node(node_a, 1, &server_a.ctx.signer).unwrap();
+        }
+
+        // `b` requests the record of `a` when a ping announces a newer one
+        server_a.ping(server_b.ctx.local_node).await?;
+        sleep(Duration::from_secs(1)).await;
+
+        let table = server_b.ctx.table.lock().await;
+        let peer = table.get_by_node_id(node_a.node_id);
+        assert_eq!(peer.map(|peer| peer.record.seq), Some(server_a.ctx.enr_seq));
+        Ok(())
+    }
+}
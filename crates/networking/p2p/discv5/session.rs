@@ -0,0 +1,277 @@
+use aes_gcm::{
+    aead::{Aead, Payload},
+    Aes128Gcm, KeyInit,
+};
+use ethrex_common::{H256, H512};
+use hkdf::Hkdf;
+use k256::{
+    ecdsa::{
+        signature::hazmat::{PrehashSigner, PrehashVerifier},
+        Signature, SigningKey, VerifyingKey,
+    },
+    elliptic_curve::sec1::ToEncodedPoint,
+    PublicKey,
+};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+use super::packet::Nonce;
+
+const KEY_AGREEMENT_INFO: &[u8] = b"discovery v5 key agreement";
+const ID_SIGNATURE_PREFIX: &[u8] = b"discovery v5 identity proof";
+
+pub type SessionKey = [u8; 16];
+
+/// Keys established by a handshake, see
+/// <https://github.com/ethereum/devp2p/blob/master/discv5/discv5-theory.md#sessions>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionKeys {
+    /// Key used to encrypt the messages we send to the peer.
+    pub write_key: SessionKey,
+    /// Key used to decrypt the messages the peer sends to us.
+    pub read_key: SessionKey,
+}
+
+impl SessionKeys {
+    /// Session of the node that answered the WHOAREYOU challenge.
+    pub fn new_initiator(initiator_key: SessionKey, recipient_key: SessionKey) -> Self {
+        Self {
+            write_key: initiator_key,
+            read_key: recipient_key,
+        }
+    }
+
+    /// Session of the node that sent the WHOAREYOU challenge.
+    pub fn new_recipient(initiator_key: SessionKey, recipient_key: SessionKey) -> Self {
+        Self {
+            write_key: recipient_key,
+            read_key: initiator_key,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+    #[error("Invalid id signature")]
+    InvalidSignature,
+    #[error("Could not sign the id nonce: {0}")]
+    Signing(String),
+    #[error("Could not encrypt the message")]
+    Encryption,
+    #[error("Could not decrypt the message")]
+    Decryption,
+}
+
+/// Returns the discv5 node id of a public key: the keccak256 hash of its uncompressed form.
+/// <https://github.com/ethereum/devp2p/blob/master/enr.md#v4-identity-scheme>
+pub fn node_id(public_key: &H512) -> H256 {
+    H256(Keccak256::digest(public_key).into())
+}
+
+/// Parses a public key from either its compressed (33 bytes) or uncompressed (64 or 65 bytes) form.
+pub fn public_key_from_slice(bytes: &[u8]) -> Result<PublicKey, SessionError> {
+    let parsed = if bytes.len() == 64 {
+        let mut uncompressed = [0x04; 65];
+        uncompressed[1..].copy_from_slice(bytes);
+        PublicKey::from_sec1_bytes(&uncompressed)
+    } else {
+        PublicKey::from_sec1_bytes(bytes)
+    };
+    parsed.map_err(|_| SessionError::InvalidPublicKey)
+}
+
+/// Elliptic-curve Diffie-Hellman as done by discv5: the shared secret is the
+/// compressed encoding of the shared point, not only its x coordinate.
+pub fn ecdh(secret_key: &SigningKey, public_key: &PublicKey) -> [u8; 33] {
+    let shared_point = (public_key.to_projective() * **secret_key.as_nonzero_scalar()).to_affine();
+    let mut secret = [0; 33];
+    secret.copy_from_slice(shared_point.to_encoded_point(true).as_bytes());
+    secret
+}
+
+/// Derives the session keys from the shared secret, returns the `(initiator_key, recipient_key)` pair.
+/// <https://github.com/ethereum/devp2p/blob/master/discv5/discv5-theory.md#sessions>
+pub fn derive_keys(
+    secret: &[u8],
+    challenge_data: &[u8],
+    initiator_id: &H256,
+    recipient_id: &H256,
+) -> (SessionKey, SessionKey) {
+    let mut info = KEY_AGREEMENT_INFO.to_vec();
+    info.extend_from_slice(initiator_id.as_bytes());
+    info.extend_from_slice(recipient_id.as_bytes());
+
+    let hkdf = Hkdf::<Sha256>::new(Some(challenge_data), secret);
+    let mut key_data = [0; 32];
+    // 32 bytes is way below the maximum HKDF-SHA256 output length, so this can't fail
+    hkdf.expand(&info, &mut key_data)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+
+    let mut initiator_key = SessionKey::default();
+    let mut recipient_key = SessionKey::default();
+    initiator_key.copy_from_slice(&key_data[..16]);
+    recipient_key.copy_from_slice(&key_data[16..]);
+    (initiator_key, recipient_key)
+}
+
+fn id_signature_digest(challenge_data: &[u8], ephemeral_pubkey: &[u8], dest_id: &H256) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(ID_SIGNATURE_PREFIX);
+    hasher.update(challenge_data);
+    hasher.update(ephemeral_pubkey);
+    hasher.update(dest_id.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Signs the handshake challenge, proving we own the static key of our node id.
+pub fn sign_id_nonce(
+    signer: &SigningKey,
+    challenge_data: &[u8],
+    ephemeral_pubkey: &[u8],
+    dest_id: &H256,
+) -> Result<[u8; 64], SessionError> {
+    let digest = id_signature_digest(challenge_data, ephemeral_pubkey, dest_id);
+    let signature: Signature = signer
+        .sign_prehash(&digest)
+        .map_err(|err| SessionError::Signing(err.to_string()))?;
+    Ok(signature.to_bytes().into())
+}
+
+pub fn verify_id_signature(
+    public_key: &PublicKey,
+    signature: &[u8],
+    challenge_data: &[u8],
+    ephemeral_pubkey: &[u8],
+    local_id: &H256,
+) -> Result<(), SessionError> {
+    let digest = id_signature_digest(challenge_data, ephemeral_pubkey, local_id);
+    let signature = Signature::from_slice(signature).map_err(|_| SessionError::InvalidSignature)?;
+    VerifyingKey::from(public_key)
+        .verify_prehash(&digest, &signature)
+        .map_err(|_| SessionError::InvalidSignature)
+}
+
+/// Encrypts a message with AES-128-GCM, the authenticated data being the masking iv and the unmasked header.
+pub fn encrypt_message(
+    key: &SessionKey,
+    nonce: &Nonce,
+    message: &[u8],
+    authenticated_data: &[u8],
+) -> Result<Vec<u8>, SessionError> {
+    Aes128Gcm::new(key.into())
+        .encrypt(
+            nonce.into(),
+            Payload {
+                msg: message,
+                aad: authenticated_data,
+            },
+        )
+        .map_err(|_| SessionError::Encryption)
+}
+
+pub fn decrypt_message(
+    key: &SessionKey,
+    nonce: &Nonce,
+    ciphertext: &[u8],
+    authenticated_data: &[u8],
+) -> Result<Vec<u8>, SessionError> {
+    Aes128Gcm::new(key.into())
+        .decrypt(
+            nonce.into(),
+            Payload {
+                msg: ciphertext,
+                aad: authenticated_data,
+            },
+        )
+        .map_err(|_| SessionError::Decryption)
+}
+
+/// Test vectors from <https://github.com/ethereum/devp2p/blob/master/discv5/discv5-wire-test-vectors.md#cryptographic-primitives>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    const NODE_A_ID: H256 = H256(hex!(
+        "aaaa8419e9f49d0083561b48287df592939a8d19947d8c0ef88f2a4856a69fbb"
+    ));
+    const NODE_B_ID: H256 = H256(hex!(
+        "bbbb9d047f0488c0b5a93c1c3f2d8bafc7c8ff337024a55434a0d0555de64db9"
+    ));
+    const CHALLENGE_DATA: [u8; 63] = hex!("000000000000000000000000000000006469736376350001010102030405060708090a0b0c00180102030405060708090a0b0c0d0e0f100000000000000000");
+
+    #[test]
+    fn ecdh_matches_test_vector() {
+        let public_key = public_key_from_slice(&hex!(
+            "039961e4c2356d61bedb83052c115d311acb3a96f5777296dcf297351130266231"
+        ))
+        .unwrap();
+        let secret_key = SigningKey::from_slice(&hex!(
+            "fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736"
+        ))
+        .unwrap();
+        assert_eq!(
+            ecdh(&secret_key, &public_key),
+            hex!("033b11a2a1f214567e1537ce5e509ffd9b21373247f2a3ff6841f4976f53165e7e")
+        );
+    }
+
+    #[test]
+    fn key_derivation_matches_test_vector() {
+        let ephemeral_key = SigningKey::from_slice(&hex!(
+            "fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736"
+        ))
+        .unwrap();
+        let dest_pubkey = public_key_from_slice(&hex!(
+            "0317931e6e0840220642f230037d285d122bc59063221ef3226b1f403ddc69ca91"
+        ))
+        .unwrap();
+        let secret = ecdh(&ephemeral_key, &dest_pubkey);
+        let (initiator_key, recipient_key) =
+            derive_keys(&secret, &CHALLENGE_DATA, &NODE_A_ID, &NODE_B_ID);
+        assert_eq!(initiator_key, hex!("dccc82d81bd610f4f76d3ebe97a40571"));
+        assert_eq!(recipient_key, hex!("ac74bb8773749920b0d3a8881c173ec5"));
+    }
+
+    #[test]
+    fn id_signature_matches_test_vector() {
+        let static_key = SigningKey::from_slice(&hex!(
+            "fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736"
+        ))
+        .unwrap();
+        let ephemeral_pubkey =
+            hex!("039961e4c2356d61bedb83052c115d311acb3a96f5777296dcf297351130266231");
+        let signature =
+            sign_id_nonce(&static_key, &CHALLENGE_DATA, &ephemeral_pubkey, &NODE_B_ID).unwrap();
+        assert_eq!(
+            signature,
+            hex!("94852a1e2318c4e5e9d422c98eaf19d1d90d876b29cd06ca7cb7546d0fff7b484fe86c09a064fe72bdbef73ba8e9c34df0cd2b53e9d65528c2c7f336d5dfc6e6")
+        );
+        let public_key = PublicKey::from(static_key.verifying_key());
+        assert!(verify_id_signature(
+            &public_key,
+            &signature,
+            &CHALLENGE_DATA,
+            &ephemeral_pubkey,
+            &NODE_B_ID
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn encryption_matches_test_vector() {
+        let key = hex!("9f2d77db7004bf8a1a85107ac686990b");
+        let nonce = hex!("27b5af763c446acd2749fe8e");
+        let plaintext = hex!("01c20101");
+        let authenticated_data =
+            hex!("93a7400fa0d6a694ebc24d5cf570f65d04215b6ac00757875e3f3a5f42107903");
+        let ciphertext = encrypt_message(&key, &nonce, &plaintext, &authenticated_data).unwrap();
+        assert_eq!(ciphertext, hex!("a5d12a2d94b8ccb3ba55558229867dc13bfa3648"));
+        assert_eq!(
+            decrypt_message(&key, &nonce, &ciphertext, &authenticated_data).unwrap(),
+            plaintext
+        );
+    }
+}
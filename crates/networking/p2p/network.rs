@@ -9,6 +9,7 @@ use crate::{
         helpers::current_unix_time,
         server::{DiscoveryError, Discv4Server},
     },
+    discv5::server::Discv5Server,
     rlpx::utils::log_peer_error,
 };
 use ethrex_blockchain::Blockchain;
@@ -37,6 +38,14 @@ pub fn peer_table(signer: SigningKey) -> Arc<Mutex<KademliaTable>> {
     Arc::new(Mutex::new(KademliaTable::new(local_node_id)))
 }
 
+/// The protocol used to discover peers, both of them feed the same [`KademliaTable`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiscoveryProtocol {
+    #[default]
+    V4,
+    V5,
+}
+
 #[derive(Debug)]
 pub enum NetworkError {
     DiscoveryStart(DiscoveryError),
@@ -54,6 +63,7 @@ pub struct P2PContext {
     pub enr_seq: u64,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn start_network(
     local_node: Node,
     tracker: TaskTracker,
@@ -62,6 +72,7 @@ pub async fn start_network(
     peer_table: Arc<Mutex<KademliaTable>>,
     storage: Store,
     blockchain: Arc<Blockchain>,
+    discovery_protocol: DiscoveryProtocol,
 ) -> Result<(), NetworkError> {
     let (channel_broadcast_send_end, _) = tokio::sync::broadcast::channel::<(
         tokio::task::Id,
@@ -81,18 +92,30 @@ pub async fn start_network(
         blockchain,
         broadcast: channel_broadcast_send_end,
     };
-    let discovery = Discv4Server::try_new(context.clone())
-        .await
-        .map_err(NetworkError::DiscoveryStart)?;
-
     info!(
-        "Starting discovery service at {}",
+        "Starting {discovery_protocol:?} discovery service at {}",
         context.local_node.udp_addr()
     );
-    discovery
-        .start(bootnodes)
-        .await
-        .map_err(NetworkError::DiscoveryStart)?;
+    match discovery_protocol {
+        DiscoveryProtocol::V4 => {
+            let discovery = Discv4Server::try_new(context.clone())
+                .await
+                .map_err(NetworkError::DiscoveryStart)?;
+            discovery
+                .start(bootnodes)
+                .await
+                .map_err(NetworkError::DiscoveryStart)?;
+        }
+        DiscoveryProtocol::V5 => {
+            let discovery = Discv5Server::try_new(context.clone())
+                .await
+                .map_err(NetworkError::DiscoveryStart)?;
+            discovery
+                .start(bootnodes)
+                .await
+                .map_err(NetworkError::DiscoveryStart)?;
+        }
+    }
 
     info!(
         "Listening for requests at {}",
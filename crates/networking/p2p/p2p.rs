@@ -1,4 +1,5 @@
 pub(crate) mod discv4;
+pub(crate) mod discv5;
 pub mod kademlia;
 pub mod network;
 pub mod peer_handler;
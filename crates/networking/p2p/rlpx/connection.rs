@@ -6,6 +6,7 @@ use crate::{
             backend,
             blocks::{BlockBodies, BlockHeaders},
             receipts::{GetReceipts, Receipts},
+            status::BlockRangeUpdate,
             transactions::{GetPooledTransactions, Transactions},
         },
         frame::RLPxCodec,
@@ -21,7 +22,7 @@ use crate::{
 };
 use ethrex_blockchain::Blockchain;
 use ethrex_common::{
-    types::{MempoolTransaction, Transaction},
+    types::{BlockNumber, MempoolTransaction, Transaction},
     H256, H512,
 };
 use ethrex_storage::Store;
@@ -47,12 +48,16 @@ use super::{
 
 const CAP_P2P_5: (Capability, u8) = (Capability::P2p, 5);
 const CAP_ETH_68: (Capability, u8) = (Capability::Eth, 68);
+const CAP_ETH_69: (Capability, u8) = (Capability::Eth, 69);
 const CAP_SNAP_1: (Capability, u8) = (Capability::Snap, 1);
-const SUPPORTED_CAPABILITIES: [(Capability, u8); 3] = [CAP_P2P_5, CAP_ETH_68, CAP_SNAP_1];
+const SUPPORTED_CAPABILITIES: [(Capability, u8); 4] =
+    [CAP_P2P_5, CAP_ETH_68, CAP_ETH_69, CAP_SNAP_1];
 const PERIODIC_PING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
 const PERIODIC_TX_BROADCAST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
 const PERIODIC_TASKS_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
 pub const MAX_PEERS_TCP_CONNECTIONS: usize = 100;
+/// Amount of new blocks after which eth/69 peers are sent a BlockRangeUpdate
+const BLOCK_RANGE_UPDATE_INTERVAL: u64 = 32;
 
 pub(crate) type Aes256Ctr64BE = ctr::Ctr64BE<aes::Aes256>;
 
@@ -84,6 +89,8 @@ pub(crate) struct RLPxConnection<S> {
     next_periodic_ping: Instant,
     next_tx_broadcast: Instant,
     broadcasted_txs: HashSet<H256>,
+    /// Latest block advertised to an eth/69 peer, through the Status or BlockRangeUpdate messages
+    last_advertised_block: BlockNumber,
     /// Send end of the channel used to broadcast messages
     /// to other connected peers, is ok to have it here,
     /// since internally it's an Arc.
@@ -117,6 +124,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             next_periodic_ping: Instant::now() + PERIODIC_TASKS_CHECK_INTERVAL,
             next_tx_broadcast: Instant::now() + PERIODIC_TX_BROADCAST_INTERVAL,
             broadcasted_txs: HashSet::new(),
+            last_advertised_block: 0,
             connection_broadcast_send: connection_broadcast,
         }
     }
@@ -274,6 +282,9 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
                         CAP_ETH_68 if CAP_ETH_68.1 > negotiated_eth_cap.1 => {
                             negotiated_eth_cap = CAP_ETH_68
                         }
+                        CAP_ETH_69 if CAP_ETH_69.1 > negotiated_eth_cap.1 => {
+                            negotiated_eth_cap = CAP_ETH_69
+                        }
                         CAP_SNAP_1 if CAP_SNAP_1.1 > negotiated_snap_cap.1 => {
                             negotiated_snap_cap = CAP_SNAP_1
                         }
@@ -287,6 +298,8 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
                     return Err(RLPxError::NoMatchingCapabilities());
                 }
                 self.negotiated_eth_version = negotiated_eth_cap.1;
+                // Some eth messages are encoded differently depending on the version
+                self.framed.codec_mut().eth_version = negotiated_eth_cap.1;
 
                 if negotiated_snap_cap.1 != 0 {
                     self.negotiated_snap_version = negotiated_snap_cap.1;
@@ -384,11 +397,27 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             self.send_new_pooled_tx_hashes().await?;
             self.next_tx_broadcast = Instant::now() + PERIODIC_TX_BROADCAST_INTERVAL;
         }
+        self.send_block_range_update().await?;
         Ok(())
     }
 
+    /// Lets eth/69 peers know about the new blocks once the head has advanced enough
+    async fn send_block_range_update(&mut self) -> Result<(), RLPxError> {
+        if self.negotiated_eth_version < CAP_ETH_69.1 {
+            return Ok(());
+        }
+        let latest_block = self.storage.get_latest_block_number().await?;
+        if latest_block < self.last_advertised_block + BLOCK_RANGE_UPDATE_INTERVAL {
+            return Ok(());
+        }
+        let block_range = backend::get_block_range(&self.storage).await?;
+        self.last_advertised_block = block_range.latest_block;
+        self.send(Message::BlockRangeUpdate(BlockRangeUpdate { block_range }))
+            .await
+    }
+
     async fn send_new_pooled_tx_hashes(&mut self) -> Result<(), RLPxError> {
-        if self.capabilities.contains(&CAP_ETH_68) {
+        if self.negotiated_eth_version >= CAP_ETH_68.1 {
             let filter =
                 |tx: &Transaction| -> bool { !self.broadcasted_txs.contains(&tx.compute_hash()) };
             let txs: Vec<MempoolTransaction> = self
@@ -448,6 +477,21 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
                 )
                 .await?
             }
+            Message::BlockRangeUpdate(update) if self.negotiated_eth_version >= CAP_ETH_69.1 => {
+                if !update.block_range.is_valid() {
+                    return Err(RLPxError::BadRequest(format!(
+                        "Invalid block range {:?}",
+                        update.block_range
+                    )));
+                }
+                log_peer_debug(
+                    &self.node,
+                    &format!(
+                        "Peer serves blocks {} to {}",
+                        update.block_range.earliest_block, update.block_range.latest_block
+                    ),
+                );
+            }
             Message::GetAccountRange(req) => {
                 let response = process_account_range_request(req, self.storage.clone())?;
                 self.send(Message::AccountRange(response)).await?
@@ -566,6 +610,9 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         {
             let status =
                 backend::get_status(&self.storage, self.negotiated_eth_version as u32).await?;
+            if let Some(block_range) = &status.block_range {
+                self.last_advertised_block = block_range.latest_block;
+            }
             log_peer_debug(&self.node, "Sending status");
             self.send(Message::Status(status)).await?;
             // The next immediate message in the ETH protocol is the
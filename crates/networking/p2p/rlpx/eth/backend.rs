@@ -3,7 +3,7 @@ use ethrex_storage::Store;
 
 use crate::rlpx::error::RLPxError;
 
-use super::status::StatusMessage;
+use super::status::{BlockRange, StatusMessage, ETH_VERSION_69};
 
 pub async fn get_status(storage: &Store, eth_version: u32) -> Result<StatusMessage, RLPxError> {
     let chain_config = storage.get_chain_config()?;
@@ -27,6 +27,15 @@ pub async fn get_status(storage: &Store, eth_version: u32) -> Result<StatusMessa
         block_header.timestamp,
         block_number,
     );
+    let block_range = if eth_version >= ETH_VERSION_69 {
+        Some(BlockRange {
+            earliest_block: storage.get_earliest_block_number().await?,
+            latest_block: block_number,
+            latest_block_hash: block_hash,
+        })
+    } else {
+        None
+    };
     Ok(StatusMessage {
         eth_version,
         network_id,
@@ -34,6 +43,21 @@ pub async fn get_status(storage: &Store, eth_version: u32) -> Result<StatusMessa
         block_hash,
         genesis,
         fork_id,
+        block_range,
+    })
+}
+
+/// Returns the range of blocks this node can serve to its peers
+pub async fn get_block_range(storage: &Store) -> Result<BlockRange, RLPxError> {
+    let latest_block = storage.get_latest_block_number().await?;
+    let latest_block_hash = storage
+        .get_canonical_block_hash(latest_block)
+        .await?
+        .ok_or(RLPxError::NotFound(format!("Block {latest_block}")))?;
+    Ok(BlockRange {
+        earliest_block: storage.get_earliest_block_number().await?,
+        latest_block,
+        latest_block_hash,
     })
 }
 
@@ -72,6 +96,15 @@ pub async fn validate_status(
             "Eth protocol version does not match".to_string(),
         ));
     }
+    // Check the advertised block range, only sent from eth/69
+    if eth_version >= ETH_VERSION_69
+        && !msg_data
+            .block_range
+            .as_ref()
+            .is_some_and(|block_range| block_range.is_valid())
+    {
+        return Err(RLPxError::HandshakeError("Invalid block range".to_string()));
+    }
     //Check Genesis
     if msg_data.genesis != genesis_hash {
         return Err(RLPxError::HandshakeError(
@@ -94,8 +127,8 @@ pub async fn validate_status(
 
 #[cfg(test)]
 mod tests {
-    use super::validate_status;
-    use crate::rlpx::eth::status::StatusMessage;
+    use super::{get_status, validate_status};
+    use crate::rlpx::eth::status::{BlockRange, StatusMessage, ETH_VERSION_69};
     use ethrex_common::{
         types::{ForkId, Genesis},
         H256, U256,
@@ -133,8 +166,42 @@ mod tests {
             block_hash: H256::random(),
             genesis: genesis_hash,
             fork_id,
+            block_range: None,
         };
         let result = validate_status(message, &storage, eth_version).await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_validate_eth69_status_block_range() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let file = File::open("../../../test_data/genesis-execution-api.json")
+            .expect("Failed to open genesis file");
+        let reader = BufReader::new(file);
+        let genesis: Genesis =
+            serde_json::from_reader(reader).expect("Failed to deserialize genesis file");
+        storage
+            .add_initial_state(genesis.clone())
+            .await
+            .expect("Failed to add genesis block to DB");
+
+        let eth_version = ETH_VERSION_69;
+        let status = get_status(&storage, eth_version)
+            .await
+            .expect("Failed to build status");
+        let result = validate_status(status, &storage, eth_version).await;
+        assert!(result.is_ok());
+
+        let mut status = get_status(&storage, eth_version)
+            .await
+            .expect("Failed to build status");
+        status.block_range = Some(BlockRange {
+            earliest_block: 10,
+            latest_block: 5,
+            latest_block_hash: H256::random(),
+        });
+        let result = validate_status(status, &storage, eth_version).await;
+        assert!(result.is_err());
+    }
 }
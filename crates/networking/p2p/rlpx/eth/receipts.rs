@@ -3,8 +3,10 @@ use crate::rlpx::{
     utils::{snappy_compress, snappy_decompress},
 };
 use bytes::BufMut;
-use ethrex_common::types::{BlockHash, Receipt};
+use ethrex_common::types::{BlockHash, Receipt, TxType};
 use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
     error::{RLPDecodeError, RLPEncodeError},
     structs::{Decoder, Encoder},
 };
@@ -88,9 +90,76 @@ impl RLPxMessage for Receipts {
     }
 }
 
+impl Receipts {
+    /// Encodes the message with the eth/69 format, where receipts don't include their bloom filter
+    pub(crate) fn encode_eth69(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
+        let receipts: Vec<Vec<Eth69Receipt>> = self
+            .receipts
+            .iter()
+            .map(|block_receipts| block_receipts.iter().cloned().map(Eth69Receipt).collect())
+            .collect();
+        let mut encoded_data = vec![];
+        Encoder::new(&mut encoded_data)
+            .encode_field(&self.id)
+            .encode_field(&receipts)
+            .finish();
+
+        let msg_data = snappy_compress(encoded_data)?;
+        buf.put_slice(&msg_data);
+        Ok(())
+    }
+
+    /// Decodes a message with the eth/69 format, computing the bloom filter of each receipt
+    pub(crate) fn decode_eth69(msg_data: &[u8]) -> Result<Self, RLPDecodeError> {
+        let decompressed_data = snappy_decompress(msg_data)?;
+        let decoder = Decoder::new(&decompressed_data)?;
+        let (id, decoder): (u64, _) = decoder.decode_field("request-id")?;
+        let (receipts, _): (Vec<Vec<Eth69Receipt>>, _) = decoder.decode_field("receipts")?;
+        let receipts = receipts
+            .into_iter()
+            .map(|block_receipts| block_receipts.into_iter().map(|r| r.0).collect())
+            .collect();
+
+        Ok(Self::new(id, receipts))
+    }
+}
+
+/// Receipt as sent from eth/69: `[tx-type, post-state-or-status, cumulative-gas, logs]`
+struct Eth69Receipt(Receipt);
+
+impl RLPEncode for Eth69Receipt {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&u8::from(self.0.tx_type))
+            .encode_field(&self.0.succeeded)
+            .encode_field(&self.0.cumulative_gas_used)
+            .encode_field(&self.0.logs)
+            .finish();
+    }
+}
+
+impl RLPDecode for Eth69Receipt {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (tx_type, decoder): (u8, _) = decoder.decode_field("tx-type")?;
+        let tx_type = TxType::from_u8(tx_type).ok_or(RLPDecodeError::Custom(format!(
+            "Invalid transaction type: {tx_type}"
+        )))?;
+        let (succeeded, decoder) = decoder.decode_field("post-state-or-status")?;
+        let (cumulative_gas_used, decoder) = decoder.decode_field("cumulative-gas")?;
+        let (logs, decoder) = decoder.decode_field("logs")?;
+        let receipt = Receipt::new(tx_type, succeeded, cumulative_gas_used, logs);
+        Ok((Eth69Receipt(receipt), decoder.finish()?))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use ethrex_common::types::{BlockHash, Receipt};
+    use bytes::Bytes;
+    use ethrex_common::{
+        types::{BlockHash, Log, Receipt, TxType},
+        Address, H256,
+    };
 
     use crate::rlpx::{
         eth::receipts::{GetReceipts, Receipts},
@@ -139,4 +208,29 @@ mod tests {
         assert_eq!(decoded.id, 1);
         assert_eq!(decoded.receipts, Vec::<Vec<Receipt>>::new());
     }
+
+    #[test]
+    fn eth69_receipts_message() {
+        let log = Log {
+            address: Address::from_low_u64_be(1),
+            topics: vec![H256::from_low_u64_be(2)],
+            data: Bytes::from_static(b"data"),
+        };
+        let receipts = vec![vec![
+            Receipt::new(TxType::Legacy, true, 21000, vec![]),
+            Receipt::new(TxType::EIP1559, false, 50000, vec![log]),
+        ]];
+        let message = Receipts::new(1, receipts.clone());
+
+        let mut buf = Vec::new();
+        message.encode_eth69(&mut buf).unwrap();
+
+        let decoded = Receipts::decode_eth69(&buf).unwrap();
+        assert_eq!(decoded.id, 1);
+        assert_eq!(decoded.receipts, receipts);
+        // The eth/68 encoding includes the bloom filters
+        let mut eth68_buf = Vec::new();
+        message.encode(&mut eth68_buf).unwrap();
+        assert!(Receipts::decode_eth69(&eth68_buf).is_err());
+    }
 }
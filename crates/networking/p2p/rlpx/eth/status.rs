@@ -4,7 +4,7 @@ use crate::rlpx::{
 };
 use bytes::BufMut;
 use ethrex_common::{
-    types::{BlockHash, ForkId},
+    types::{BlockHash, BlockNumber, ForkId},
     U256,
 };
 use ethrex_rlp::{
@@ -12,28 +12,65 @@ use ethrex_rlp::{
     structs::{Decoder, Encoder},
 };
 
+/// First version of the eth protocol whose status advertises the range of available blocks
+/// instead of the total difficulty, and whose receipts don't include the bloom filter
+pub(crate) const ETH_VERSION_69: u32 = 69;
+
 #[derive(Debug)]
 pub(crate) struct StatusMessage {
     pub(crate) eth_version: u32,
     pub(crate) network_id: u64,
+    /// Only sent up to eth/68
     pub(crate) total_difficulty: U256,
+    /// Hash of the latest block
     pub(crate) block_hash: BlockHash,
     pub(crate) genesis: BlockHash,
     pub(crate) fork_id: ForkId,
+    /// Only sent from eth/69 onwards
+    pub(crate) block_range: Option<BlockRange>,
+}
+
+/// Range of blocks a peer is able to serve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlockRange {
+    pub(crate) earliest_block: BlockNumber,
+    pub(crate) latest_block: BlockNumber,
+    pub(crate) latest_block_hash: BlockHash,
+}
+
+impl BlockRange {
+    pub(crate) fn is_valid(&self) -> bool {
+        self.earliest_block <= self.latest_block && !self.latest_block_hash.is_zero()
+    }
 }
 
 impl RLPxMessage for StatusMessage {
     const CODE: u8 = 0x10;
     fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
         let mut encoded_data = vec![];
-        Encoder::new(&mut encoded_data)
-            .encode_field(&self.eth_version)
-            .encode_field(&self.network_id)
-            .encode_field(&self.total_difficulty)
-            .encode_field(&self.block_hash)
-            .encode_field(&self.genesis)
-            .encode_field(&self.fork_id)
-            .finish();
+        if self.eth_version >= ETH_VERSION_69 {
+            let block_range = self.block_range.as_ref().ok_or(RLPEncodeError::Custom(
+                "Missing block range in eth/69 status".to_string(),
+            ))?;
+            Encoder::new(&mut encoded_data)
+                .encode_field(&self.eth_version)
+                .encode_field(&self.network_id)
+                .encode_field(&self.genesis)
+                .encode_field(&self.fork_id)
+                .encode_field(&block_range.earliest_block)
+                .encode_field(&block_range.latest_block)
+                .encode_field(&block_range.latest_block_hash)
+                .finish();
+        } else {
+            Encoder::new(&mut encoded_data)
+                .encode_field(&self.eth_version)
+                .encode_field(&self.network_id)
+                .encode_field(&self.total_difficulty)
+                .encode_field(&self.block_hash)
+                .encode_field(&self.genesis)
+                .encode_field(&self.fork_id)
+                .finish();
+        }
 
         let msg_data = snappy_compress(encoded_data)?;
         buf.put_slice(&msg_data);
@@ -44,10 +81,34 @@ impl RLPxMessage for StatusMessage {
         let decompressed_data = snappy_decompress(msg_data)?;
         let decoder = Decoder::new(&decompressed_data)?;
         let (eth_version, decoder): (u32, _) = decoder.decode_field("protocolVersion")?;
+        let (network_id, decoder): (u64, _) = decoder.decode_field("networkId")?;
 
-        assert_eq!(eth_version, 68, "only eth version 68 is supported");
+        if eth_version >= ETH_VERSION_69 {
+            let (genesis, decoder): (BlockHash, _) = decoder.decode_field("genesis")?;
+            let (fork_id, decoder): (ForkId, _) = decoder.decode_field("forkId")?;
+            let (earliest_block, decoder): (BlockNumber, _) =
+                decoder.decode_field("earliestBlock")?;
+            let (latest_block, decoder): (BlockNumber, _) = decoder.decode_field("latestBlock")?;
+            let (latest_block_hash, decoder): (BlockHash, _) =
+                decoder.decode_field("latestBlockHash")?;
+            // Implementations must ignore any additional list elements
+            let _padding = decoder.finish_unchecked();
+
+            return Ok(Self {
+                eth_version,
+                network_id,
+                total_difficulty: U256::zero(),
+                block_hash: latest_block_hash,
+                genesis,
+                fork_id,
+                block_range: Some(BlockRange {
+                    earliest_block,
+                    latest_block,
+                    latest_block_hash,
+                }),
+            });
+        }
 
-        let (network_id, decoder): (u64, _) = decoder.decode_field("networkId")?;
         let (total_difficulty, decoder): (U256, _) = decoder.decode_field("totalDifficulty")?;
         let (block_hash, decoder): (BlockHash, _) = decoder.decode_field("blockHash")?;
         let (genesis, decoder): (BlockHash, _) = decoder.decode_field("genesis")?;
@@ -62,6 +123,122 @@ impl RLPxMessage for StatusMessage {
             block_hash,
             genesis,
             fork_id,
+            block_range: None,
         })
     }
 }
+
+// https://github.com/ethereum/devp2p/blob/master/caps/eth.md#blockrangeupdate-0x11
+#[derive(Debug)]
+pub(crate) struct BlockRangeUpdate {
+    pub(crate) block_range: BlockRange,
+}
+
+impl RLPxMessage for BlockRangeUpdate {
+    const CODE: u8 = 0x21;
+    fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
+        let mut encoded_data = vec![];
+        Encoder::new(&mut encoded_data)
+            .encode_field(&self.block_range.earliest_block)
+            .encode_field(&self.block_range.latest_block)
+            .encode_field(&self.block_range.latest_block_hash)
+            .finish();
+
+        let msg_data = snappy_compress(encoded_data)?;
+        buf.put_slice(&msg_data);
+        Ok(())
+    }
+
+    fn decode(msg_data: &[u8]) -> Result<Self, RLPDecodeError> {
+        let decompressed_data = snappy_decompress(msg_data)?;
+        let decoder = Decoder::new(&decompressed_data)?;
+        let (earliest_block, decoder): (BlockNumber, _) = decoder.decode_field("earliestBlock")?;
+        let (latest_block, decoder): (BlockNumber, _) = decoder.decode_field("latestBlock")?;
+        let (latest_block_hash, decoder): (BlockHash, _) =
+            decoder.decode_field("latestBlockHash")?;
+        let _padding = decoder.finish_unchecked();
+
+        Ok(Self {
+            block_range: BlockRange {
+                earliest_block,
+                latest_block,
+                latest_block_hash,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::H256;
+    use ethrex_rlp::decode::RLPDecode;
+
+    fn fork_id() -> ForkId {
+        // Arbitrary fork hash with no next fork
+        ForkId::decode(&[0xc6, 0x84, 0x97, 0xc0, 0x0c, 0x96, 0x80]).unwrap()
+    }
+
+    #[test]
+    fn eth68_status_roundtrip() {
+        let status = StatusMessage {
+            eth_version: 68,
+            network_id: 1,
+            total_difficulty: U256::from(100),
+            block_hash: H256::from_low_u64_be(1),
+            genesis: H256::from_low_u64_be(2),
+            fork_id: fork_id(),
+            block_range: None,
+        };
+        let mut buf = Vec::new();
+        status.encode(&mut buf).unwrap();
+
+        let decoded = StatusMessage::decode(&buf).unwrap();
+        assert_eq!(decoded.total_difficulty, status.total_difficulty);
+        assert_eq!(decoded.block_hash, status.block_hash);
+        assert_eq!(decoded.block_range, None);
+    }
+
+    #[test]
+    fn eth69_status_roundtrip() {
+        let block_range = BlockRange {
+            earliest_block: 0,
+            latest_block: 10,
+            latest_block_hash: H256::from_low_u64_be(1),
+        };
+        let status = StatusMessage {
+            eth_version: ETH_VERSION_69,
+            network_id: 1,
+            total_difficulty: U256::zero(),
+            block_hash: block_range.latest_block_hash,
+            genesis: H256::from_low_u64_be(2),
+            fork_id: fork_id(),
+            block_range: Some(block_range.clone()),
+        };
+        let mut buf = Vec::new();
+        status.encode(&mut buf).unwrap();
+
+        let decoded = StatusMessage::decode(&buf).unwrap();
+        assert_eq!(decoded.eth_version, ETH_VERSION_69);
+        assert_eq!(decoded.genesis, status.genesis);
+        assert_eq!(decoded.block_hash, block_range.latest_block_hash);
+        assert_eq!(decoded.block_range, Some(block_range));
+    }
+
+    #[test]
+    fn block_range_update_roundtrip() {
+        let update = BlockRangeUpdate {
+            block_range: BlockRange {
+                earliest_block: 5,
+                latest_block: 100,
+                latest_block_hash: H256::from_low_u64_be(3),
+            },
+        };
+        let mut buf = Vec::new();
+        update.encode(&mut buf).unwrap();
+
+        let decoded = BlockRangeUpdate::decode(&buf).unwrap();
+        assert_eq!(decoded.block_range, update.block_range);
+        assert!(decoded.block_range.is_valid());
+    }
+}
@@ -25,6 +25,8 @@ pub(crate) struct RLPxCodec {
     pub(crate) egress_mac: Keccak256,
     pub(crate) ingress_aes: Aes256Ctr64BE,
     pub(crate) egress_aes: Aes256Ctr64BE,
+    /// Negotiated version of the eth capability, 0 until the Hello messages are exchanged
+    pub(crate) eth_version: u8,
 }
 
 impl RLPxCodec {
@@ -63,6 +65,7 @@ impl RLPxCodec {
             egress_mac,
             ingress_aes,
             egress_aes,
+            eth_version: 0,
         }
     }
 }
@@ -189,7 +192,11 @@ impl Decoder for RLPxCodec {
         let (frame_data, _padding) = frame_ciphertext.split_at(frame_size);
 
         let (msg_id, msg_data): (u8, _) = RLPDecode::decode_unfinished(frame_data)?;
-        Ok(Some(rlpx::Message::decode(msg_id, msg_data)?))
+        Ok(Some(rlpx::Message::decode(
+            msg_id,
+            msg_data,
+            self.eth_version,
+        )?))
     }
 
     fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
@@ -221,7 +228,7 @@ impl Encoder<rlpx::Message> for RLPxCodec {
 
     fn encode(&mut self, message: rlpx::Message, buffer: &mut BytesMut) -> Result<(), Self::Error> {
         let mut frame_data = vec![];
-        message.encode(&mut frame_data)?;
+        message.encode(&mut frame_data, self.eth_version)?;
 
         let mac_aes_cipher = Aes256Enc::new_from_slice(&self.mac_key.0)?;
 
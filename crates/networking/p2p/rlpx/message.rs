@@ -4,7 +4,7 @@ use std::fmt::Display;
 
 use super::eth::blocks::{BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders};
 use super::eth::receipts::{GetReceipts, Receipts};
-use super::eth::status::StatusMessage;
+use super::eth::status::{BlockRangeUpdate, StatusMessage, ETH_VERSION_69};
 use super::eth::transactions::{
     GetPooledTransactions, NewPooledTransactionHashes, PooledTransactions, Transactions,
 };
@@ -41,6 +41,7 @@ pub(crate) enum Message {
     NewPooledTransactionHashes(NewPooledTransactionHashes),
     GetPooledTransactions(GetPooledTransactions),
     PooledTransactions(PooledTransactions),
+    BlockRangeUpdate(BlockRangeUpdate),
     // snap capability
     GetAccountRange(GetAccountRange),
     AccountRange(AccountRange),
@@ -70,6 +71,7 @@ impl Message {
             Message::NewPooledTransactionHashes(_) => NewPooledTransactionHashes::CODE,
             Message::GetPooledTransactions(_) => GetPooledTransactions::CODE,
             Message::PooledTransactions(_) => PooledTransactions::CODE,
+            Message::BlockRangeUpdate(_) => BlockRangeUpdate::CODE,
             Message::GetAccountRange(_) => GetAccountRange::CODE,
             Message::AccountRange(_) => AccountRange::CODE,
             Message::GetStorageRanges(_) => GetStorageRanges::CODE,
@@ -80,7 +82,47 @@ impl Message {
             Message::TrieNodes(_) => TrieNodes::CODE,
         }
     }
-    pub fn decode(msg_id: u8, data: &[u8]) -> Result<Message, RLPDecodeError> {
+
+    fn is_snap_message(&self) -> bool {
+        matches!(
+            self,
+            Message::GetAccountRange(_)
+                | Message::AccountRange(_)
+                | Message::GetStorageRanges(_)
+                | Message::StorageRanges(_)
+                | Message::GetByteCodes(_)
+                | Message::ByteCodes(_)
+                | Message::GetTrieNodes(_)
+                | Message::TrieNodes(_)
+        )
+    }
+
+    /// Returns the id of the message for the negotiated eth version.
+    /// The snap messages come right after the eth ones, and eth/69 added the BlockRangeUpdate
+    /// message, so their ids are shifted by one when eth/69 is negotiated.
+    fn msg_id(&self, eth_version: u8) -> u8 {
+        if u32::from(eth_version) >= ETH_VERSION_69 && self.is_snap_message() {
+            self.code() + 1
+        } else {
+            self.code()
+        }
+    }
+
+    /// Decodes a message, `eth_version` is the negotiated version of the eth capability,
+    /// needed for the messages whose id or format changed between versions
+    pub fn decode(msg_id: u8, data: &[u8], eth_version: u8) -> Result<Message, RLPDecodeError> {
+        let msg_id = if u32::from(eth_version) >= ETH_VERSION_69 {
+            match msg_id {
+                BlockRangeUpdate::CODE => {
+                    return Ok(Message::BlockRangeUpdate(BlockRangeUpdate::decode(data)?))
+                }
+                // Snap messages, see [Message::msg_id]
+                id if id > BlockRangeUpdate::CODE => id - 1,
+                id => id,
+            }
+        } else {
+            msg_id
+        };
         match msg_id {
             HelloMessage::CODE => Ok(Message::Hello(HelloMessage::decode(data)?)),
             DisconnectMessage::CODE => Ok(Message::Disconnect(DisconnectMessage::decode(data)?)),
@@ -112,6 +154,9 @@ impl Message {
                 PooledTransactions::decode(data)?,
             )),
             GetReceipts::CODE => Ok(Message::GetReceipts(GetReceipts::decode(data)?)),
+            Receipts::CODE if u32::from(eth_version) >= ETH_VERSION_69 => {
+                Ok(Message::Receipts(Receipts::decode_eth69(data)?))
+            }
             Receipts::CODE => Ok(Message::Receipts(Receipts::decode(data)?)),
             GetAccountRange::CODE => Ok(Message::GetAccountRange(GetAccountRange::decode(data)?)),
             AccountRange::CODE => Ok(Message::AccountRange(AccountRange::decode(data)?)),
@@ -127,8 +172,9 @@ impl Message {
         }
     }
 
-    pub fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
-        self.code().encode(buf);
+    /// Encodes a message, `eth_version` is the negotiated version of the eth capability
+    pub fn encode(&self, buf: &mut dyn BufMut, eth_version: u8) -> Result<(), RLPEncodeError> {
+        self.msg_id(eth_version).encode(buf);
         match self {
             Message::Hello(msg) => msg.encode(buf),
             Message::Disconnect(msg) => msg.encode(buf),
@@ -144,7 +190,11 @@ impl Message {
             Message::GetPooledTransactions(msg) => msg.encode(buf),
             Message::PooledTransactions(msg) => msg.encode(buf),
             Message::GetReceipts(msg) => msg.encode(buf),
+            Message::Receipts(msg) if u32::from(eth_version) >= ETH_VERSION_69 => {
+                msg.encode_eth69(buf)
+            }
             Message::Receipts(msg) => msg.encode(buf),
+            Message::BlockRangeUpdate(msg) => msg.encode(buf),
             Message::GetAccountRange(msg) => msg.encode(buf),
             Message::AccountRange(msg) => msg.encode(buf),
             Message::GetStorageRanges(msg) => msg.encode(buf),
@@ -175,6 +225,7 @@ impl Display for Message {
             Message::GetBlockBodies(_) => "eth:GetBlockBodies".fmt(f),
             Message::GetReceipts(_) => "eth:GetReceipts".fmt(f),
             Message::Receipts(_) => "eth:Receipts".fmt(f),
+            Message::BlockRangeUpdate(_) => "eth:BlockRangeUpdate".fmt(f),
             Message::GetAccountRange(_) => "snap:GetAccountRange".fmt(f),
             Message::AccountRange(_) => "snap:AccountRange".fmt(f),
             Message::GetStorageRanges(_) => "snap:GetStorageRanges".fmt(f),
@@ -186,3 +237,36 @@ impl Display for Message {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::H256;
+
+    fn get_byte_codes() -> Message {
+        Message::GetByteCodes(GetByteCodes {
+            id: 1,
+            hashes: vec![H256::from_low_u64_be(1)],
+            bytes: 1024,
+        })
+    }
+
+    fn roundtrip(message: Message, eth_version: u8) -> (u8, Message) {
+        let mut buf = Vec::new();
+        message.encode(&mut buf, eth_version).unwrap();
+        let (msg_id, data): (u8, _) =
+            ethrex_rlp::decode::RLPDecode::decode_unfinished(&buf).unwrap();
+        (msg_id, Message::decode(msg_id, data, eth_version).unwrap())
+    }
+
+    #[test]
+    fn snap_message_ids_follow_the_eth_ones() {
+        let (msg_id, decoded) = roundtrip(get_byte_codes(), 68);
+        assert_eq!(msg_id, GetByteCodes::CODE);
+        assert!(matches!(decoded, Message::GetByteCodes(_)));
+
+        let (msg_id, decoded) = roundtrip(get_byte_codes(), 69);
+        assert_eq!(msg_id, GetByteCodes::CODE + 1);
+        assert!(matches!(decoded, Message::GetByteCodes(_)));
+    }
+}
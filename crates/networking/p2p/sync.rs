@@ -331,6 +331,12 @@ impl Syncer {
                     // Snap sync was not completed, abort and resume it on the next cycle
                     return Ok(());
                 }
+                // The state changes of the blocks up to the pivot are not available
+                store.restart_state_history(pivot_header.number + 1).await?;
+                // The state snapshot must be generated from the rebuilt state
+                store
+                    .rebuild_state_snapshot(all_block_hashes[pivot_idx])
+                    .await?;
                 // Wait for all bodies to be downloaded
                 store_bodies_handle.await??;
                 // For all blocks before the pivot: Store the bodies and fetch the receipts (TODO)
@@ -6,7 +6,7 @@ use ethrex_rlp::{
     error::RLPDecodeError,
     structs::{self, Decoder, Encoder},
 };
-use k256::ecdsa::{SigningKey, VerifyingKey};
+use k256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, SigningKey, VerifyingKey};
 use sha3::{Digest, Keccak256};
 use std::{
     fmt::Display,
@@ -141,12 +141,14 @@ impl Node {
         let base64_decoded = ethrex_common::base64::decode(enr[4..].as_bytes());
         let record = NodeRecord::decode(&base64_decoded)
             .map_err(|_| "Could not build node record from enr")?;
+        Self::from_record(&record)
+    }
+
+    pub fn from_record(record: &NodeRecord) -> Result<Self, String> {
         let pairs = record.decode_pairs();
-        let public_key = pairs.secp256k1.ok_or("public key not found in record")?;
-        let verifying_key = VerifyingKey::from_sec1_bytes(public_key.as_bytes())
-            .map_err(|_| "public key could no be built from msg pub key bytes")?;
-        let encoded = verifying_key.to_encoded_point(false);
-        let node_id = H512::from_slice(&encoded.as_bytes()[1..]);
+        let node_id = record
+            .node_id()
+            .ok_or("public key not found in record or invalid")?;
 
         let ip = pairs
             .ip
@@ -306,6 +308,35 @@ impl NodeRecord {
         Ok(H512::from_slice(&signature_bytes))
     }
 
+    /// Returns the uncompressed public key of the record, which is the id of its node.
+    pub fn node_id(&self) -> Option<H512> {
+        let public_key = self.decode_pairs().secp256k1?;
+        let verifying_key = VerifyingKey::from_sec1_bytes(public_key.as_bytes()).ok()?;
+        let encoded = verifying_key.to_encoded_point(false);
+        Some(H512::from_slice(&encoded.as_bytes()[1..]))
+    }
+
+    /// Checks the record was signed by the key it holds, following the "v4" identity scheme.
+    /// <https://github.com/ethereum/devp2p/blob/master/enr.md#v4-identity-scheme>
+    pub fn verify_signature(&self) -> bool {
+        let pairs = self.decode_pairs();
+        if pairs.id.as_deref() != Some("v4") {
+            return false;
+        }
+        let Some(public_key) = pairs.secp256k1 else {
+            return false;
+        };
+        let Ok(verifying_key) = VerifyingKey::from_sec1_bytes(public_key.as_bytes()) else {
+            return false;
+        };
+        let Ok(signature) = Signature::from_slice(self.signature.as_bytes()) else {
+            return false;
+        };
+        verifying_key
+            .verify_prehash(&self.get_signature_digest(), &signature)
+            .is_ok()
+    }
+
     pub fn get_signature_digest(&self) -> Vec<u8> {
         let mut rlp = vec![];
         structs::Encoder::new(&mut rlp)
@@ -466,4 +497,27 @@ mod tests {
 
         assert_eq!(record.enr_url().unwrap(), expected_enr_string);
     }
+
+    #[test]
+    fn verify_node_record_signature() {
+        let signer = SigningKey::from_slice(&[
+            16, 125, 177, 238, 167, 212, 168, 215, 239, 165, 77, 224, 199, 143, 55, 205, 9, 194,
+            87, 139, 92, 46, 30, 191, 74, 37, 68, 242, 38, 225, 104, 246,
+        ])
+        .unwrap();
+        let addr = std::net::SocketAddr::from_str("127.0.0.1:30303").unwrap();
+        let node = Node {
+            ip: addr.ip(),
+            node_id: node_id_from_signing_key(&signer),
+            tcp_port: addr.port(),
+            udp_port: addr.port(),
+        };
+        let mut record = NodeRecord::from_node(node, 1, &signer).unwrap();
+        assert!(record.verify_signature());
+        assert_eq!(record.node_id(), Some(node.node_id));
+        assert_eq!(Node::from_record(&record), Ok(node));
+
+        record.seq = 2;
+        assert!(!record.verify_signature());
+    }
 }
@@ -8,6 +8,8 @@ pub enum EthClientError {
     ReqwestError(#[from] reqwest::Error),
     #[error("eth_gasPrice request error: {0}")]
     GetGasPriceError(#[from] GetGasPriceError),
+    #[error("eth_blobBaseFee request error: {0}")]
+    GetBlobBaseFeeError(#[from] GetBlobBaseFeeError),
     #[error("eth_estimateGas request error: {0}")]
     EstimateGasPriceError(#[from] EstimateGasPriceError),
     #[error("eth_sendRawTransaction request error: {0}")]
@@ -200,6 +202,16 @@ pub enum GetTransactionByHashError {
     ParseIntError(#[from] std::num::ParseIntError),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum GetBlobBaseFeeError {
+    #[error("{0}")]
+    ReqwestError(#[from] reqwest::Error),
+    #[error("{0}")]
+    SerdeJSONError(#[from] serde_json::Error),
+    #[error("{0}")]
+    RPCError(String),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum CalldataEncodeError {
     #[error("Failed to parse function signature: {0}")]
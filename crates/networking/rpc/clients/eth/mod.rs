@@ -9,9 +9,10 @@ use crate::{
 };
 use bytes::Bytes;
 use errors::{
-    EstimateGasPriceError, EthClientError, GetBalanceError, GetBlockByHashError,
-    GetBlockByNumberError, GetBlockNumberError, GetCodeError, GetGasPriceError, GetLogsError,
-    GetNonceError, GetTransactionByHashError, GetTransactionReceiptError, SendRawTransactionError,
+    EstimateGasPriceError, EthClientError, GetBalanceError, GetBlobBaseFeeError,
+    GetBlockByHashError, GetBlockByNumberError, GetBlockNumberError, GetCodeError,
+    GetGasPriceError, GetLogsError, GetNonceError, GetTransactionByHashError,
+    GetTransactionReceiptError, SendRawTransactionError,
 };
 use eth_sender::Overrides;
 use ethrex_common::{
@@ -386,6 +387,25 @@ impl EthClient {
         }
     }
 
+    pub async fn get_blob_base_fee(&self) -> Result<U256, EthClientError> {
+        let request = RpcRequest {
+            id: RpcRequestId::Number(1),
+            jsonrpc: "2.0".to_string(),
+            method: "eth_blobBaseFee".to_string(),
+            params: None,
+        };
+
+        match self.send_request(request).await {
+            Ok(RpcResponse::Success(result)) => serde_json::from_value(result.result)
+                .map_err(GetBlobBaseFeeError::SerdeJSONError)
+                .map_err(EthClientError::from),
+            Ok(RpcResponse::Error(error_response)) => {
+                Err(GetBlobBaseFeeError::RPCError(error_response.error.message).into())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     pub async fn get_gas_price_with_extra(
         &self,
         bump_percent: u64,
@@ -901,6 +921,112 @@ impl EthClient {
         .await
     }
 
+    pub async fn get_last_committed_batch(
+        eth_client: &EthClient,
+        on_chain_proposer_address: Address,
+    ) -> Result<u64, EthClientError> {
+        Self::_call_block_variable(
+            eth_client,
+            b"lastCommittedBatch()",
+            on_chain_proposer_address,
+        )
+        .await
+    }
+
+    pub async fn get_last_verified_batch(
+        eth_client: &EthClient,
+        on_chain_proposer_address: Address,
+    ) -> Result<u64, EthClientError> {
+        Self::_call_block_variable(
+            eth_client,
+            b"lastVerifiedBatch()",
+            on_chain_proposer_address,
+        )
+        .await
+    }
+
+    /// Returns the first and last block numbers of a committed batch.
+    /// Both are zero if the batch wasn't committed.
+    pub async fn get_batch_block_range(
+        eth_client: &EthClient,
+        on_chain_proposer_address: Address,
+        batch_number: u64,
+    ) -> Result<(u64, u64), EthClientError> {
+        let mut calldata = keccak(b"batchCommitments(uint256)")
+            .as_bytes()
+            .get(..4)
+            .ok_or(EthClientError::Custom("Failed to get selector.".to_owned()))?
+            .to_vec();
+        calldata.extend_from_slice(&U256::from(batch_number).to_big_endian());
+
+        let hex_string = eth_client
+            .call(
+                on_chain_proposer_address,
+                calldata.into(),
+                Overrides::default(),
+            )
+            .await?;
+        let hex_string = hex_string.strip_prefix("0x").ok_or(EthClientError::Custom(
+            "Couldn't strip prefix from request.".to_owned(),
+        ))?;
+
+        // The getter returns the fields of the struct as a tuple, the block range
+        // being the first two words.
+        let get_word = |index: usize| -> Result<u64, EthClientError> {
+            let word =
+                hex_string
+                    .get(index * 64..(index + 1) * 64)
+                    .ok_or(EthClientError::Custom(
+                        "Failed to get batch block range, response too short.".to_owned(),
+                    ))?;
+            U256::from_str_radix(word, 16)
+                .map_err(|_| {
+                    EthClientError::Custom("Failed to parse batch block range.".to_owned())
+                })?
+                .try_into()
+                .map_err(|_| EthClientError::Custom("Block number does not fit in u64.".to_owned()))
+        };
+
+        Ok((get_word(0)?, get_word(1)?))
+    }
+
+    /// Returns the number of the committed batch that contains the given block,
+    /// or `None` if the block wasn't committed yet.
+    pub async fn get_batch_number_by_block(
+        eth_client: &EthClient,
+        on_chain_proposer_address: Address,
+        block_number: u64,
+    ) -> Result<Option<u64>, EthClientError> {
+        let mut low = 1;
+        let mut high =
+            Self::get_last_committed_batch(eth_client, on_chain_proposer_address).await?;
+
+        // Batches cover consecutive block ranges, so they can be binary searched.
+        while low <= high {
+            let batch_number = low + (high - low) / 2;
+            let (first_block, last_block) =
+                Self::get_batch_block_range(eth_client, on_chain_proposer_address, batch_number)
+                    .await?;
+            if block_number < first_block {
+                high = batch_number - 1;
+            } else if block_number > last_block {
+                low = batch_number + 1;
+            } else {
+                return Ok(Some(batch_number));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub async fn get_on_chain_proposer_address(
+        eth_client: &EthClient,
+        common_bridge_address: Address,
+    ) -> Result<Address, EthClientError> {
+        Self::_call_address_variable(eth_client, b"ON_CHAIN_PROPOSER()", common_bridge_address)
+            .await
+    }
+
     pub async fn get_last_fetched_l1_block(
         eth_client: &EthClient,
         common_bridge_address: Address,
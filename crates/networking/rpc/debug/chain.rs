@@ -0,0 +1,53 @@
+use ethrex_common::types::BlockNumber;
+use ethrex_storage::error::StoreError;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    rpc::{RpcApiContext, RpcHandler},
+    utils::{parse_json_hex, RpcErr},
+};
+
+/// Rewinds the canonical chain to the given block, as geth's `debug_setHead`.
+/// Only served on the authenticated RPC.
+pub struct SetHeadRequest {
+    pub block_number: BlockNumber,
+}
+
+impl RpcHandler for SetHeadRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams("Expected 1 param".to_owned()));
+        };
+        let block_number = parse_json_hex(&params[0]).map_err(|_| RpcErr::BadHexFormat(0))?;
+        Ok(SetHeadRequest { block_number })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested chain rewind to block {}", self.block_number);
+        match context.blockchain.rewind(self.block_number).await {
+            Ok(()) => Ok(Value::Null),
+            Err(
+                error @ (StoreError::RewindTargetNotCanonical(_)
+                | StoreError::RewindStateUnavailable(_)),
+            ) => Err(RpcErr::BadParams(error.to_string())),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_block_number() {
+        let request = SetHeadRequest::parse(&Some(vec![json!("0x1f")])).unwrap();
+        assert_eq!(request.block_number, 31);
+        assert!(SetHeadRequest::parse(&Some(vec![json!("latest")])).is_err());
+    }
+}
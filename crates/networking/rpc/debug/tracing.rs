@@ -0,0 +1,400 @@
+use ethrex_common::{
+    types::{Block, BlockHeader, GenericTransaction},
+    H256,
+};
+use ethrex_storage::Store;
+use ethrex_vm::{
+    tracers::{
+        call_tracer::{CallTracer, CallTracerConfig},
+        prestate_tracer::PrestateTracer,
+        struct_logger::{StructLogger, StructLoggerConfig},
+        tracer::Tracer,
+    },
+    Evm, EvmEngine,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    rpc::{RpcApiContext, RpcHandler},
+    types::block_identifier::BlockIdentifier,
+    utils::RpcErr,
+};
+
+pub struct TraceTransactionRequest {
+    pub transaction_hash: H256,
+    pub config: TraceConfig,
+}
+
+pub struct TraceBlockByNumberRequest {
+    pub block: BlockIdentifier,
+    pub config: TraceConfig,
+}
+
+pub struct TraceBlockByHashRequest {
+    pub block_hash: H256,
+    pub config: TraceConfig,
+}
+
+pub struct TraceCallRequest {
+    pub transaction: GenericTransaction,
+    pub block: BlockIdentifier,
+    pub config: TraceConfig,
+}
+
+/// Tracing options accepted by the `debug_trace*` methods, as defined by geth.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceConfig {
+    pub tracer: Option<String>,
+    pub tracer_config: Option<Value>,
+    #[serde(default)]
+    pub disable_stack: bool,
+    #[serde(default)]
+    pub disable_storage: bool,
+    #[serde(default)]
+    pub enable_memory: bool,
+    #[serde(default)]
+    pub enable_return_data: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CallTracerOptions {
+    #[serde(default)]
+    only_top_call: bool,
+    #[serde(default)]
+    with_log: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrestateTracerOptions {
+    #[serde(default)]
+    diff_mode: bool,
+}
+
+enum TracerKind {
+    StructLogger(StructLoggerConfig),
+    Call(CallTracerConfig),
+    Prestate,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockTraceResult {
+    tx_hash: H256,
+    result: Value,
+}
+
+impl TraceConfig {
+    fn parse(params: &[Value], index: usize) -> Result<Self, RpcErr> {
+        match params.get(index) {
+            Some(Value::Null) | None => Ok(Self::default()),
+            Some(value) => Ok(serde_json::from_value(value.clone())?),
+        }
+    }
+
+    fn tracer_kind(&self) -> Result<TracerKind, RpcErr> {
+        let tracer_config = self.tracer_config.clone().unwrap_or(Value::Null);
+        match self.tracer.as_deref() {
+            None | Some("") => Ok(TracerKind::StructLogger(StructLoggerConfig {
+                disable_stack: self.disable_stack,
+                disable_storage: self.disable_storage,
+                enable_memory: self.enable_memory,
+                enable_return_data: self.enable_return_data,
+            })),
+            Some("callTracer") => {
+                let options: CallTracerOptions = if tracer_config.is_null() {
+                    Default::default()
+                } else {
+                    serde_json::from_value(tracer_config)?
+                };
+                Ok(TracerKind::Call(CallTracerConfig {
+                    only_top_call: options.only_top_call,
+                    with_log: options.with_log,
+                }))
+            }
+            Some("prestateTracer") => {
+                let options: PrestateTracerOptions = if tracer_config.is_null() {
+                    Default::default()
+                } else {
+                    serde_json::from_value(tracer_config)?
+                };
+                if options.diff_mode {
+                    return Err(RpcErr::BadParams(
+                        "prestateTracer diffMode is not supported".to_owned(),
+                    ));
+                }
+                Ok(TracerKind::Prestate)
+            }
+            Some(other) => Err(RpcErr::BadParams(format!("Unsupported tracer: {other}"))),
+        }
+    }
+
+    /// Re-executes `block` with the configured tracer, returning the result of each traced transaction.
+    fn trace_block(
+        &self,
+        evm: &mut Evm,
+        block: &Block,
+        tx_index: Option<usize>,
+    ) -> Result<Vec<(H256, Value)>, RpcErr> {
+        match self.tracer_kind()? {
+            TracerKind::StructLogger(config) => collect_results(
+                evm.trace_block(block, tx_index, || StructLogger::new(config))?,
+                |tracer| serde_json::to_value(tracer.into_result()),
+            ),
+            TracerKind::Call(config) => collect_results(
+                evm.trace_block(block, tx_index, || CallTracer::new(config))?,
+                |tracer| serde_json::to_value(tracer.into_result()),
+            ),
+            TracerKind::Prestate => collect_results(
+                evm.trace_block(block, tx_index, PrestateTracer::new)?,
+                |tracer| serde_json::to_value(tracer.into_result()),
+            ),
+        }
+    }
+
+    fn trace_call(
+        &self,
+        evm: &mut Evm,
+        transaction: &GenericTransaction,
+        header: &BlockHeader,
+    ) -> Result<Value, RpcErr> {
+        match self.tracer_kind()? {
+            TracerKind::StructLogger(config) => {
+                let mut tracer = StructLogger::new(config);
+                evm.trace_tx_from_generic(transaction, header, &mut tracer)?;
+                Ok(serde_json::to_value(tracer.into_result())?)
+            }
+            TracerKind::Call(config) => {
+                let mut tracer = CallTracer::new(config);
+                evm.trace_tx_from_generic(transaction, header, &mut tracer)?;
+                Ok(serde_json::to_value(tracer.into_result())?)
+            }
+            TracerKind::Prestate => {
+                let mut tracer = PrestateTracer::new();
+                evm.trace_tx_from_generic(transaction, header, &mut tracer)?;
+                Ok(serde_json::to_value(tracer.into_result())?)
+            }
+        }
+    }
+}
+
+fn collect_results<T: Tracer>(
+    traces: Vec<(H256, T)>,
+    into_value: impl Fn(T) -> Result<Value, serde_json::Error>,
+) -> Result<Vec<(H256, Value)>, RpcErr> {
+    traces
+        .into_iter()
+        .map(|(tx_hash, tracer)| Ok((tx_hash, into_value(tracer)?)))
+        .collect()
+}
+
+/// Tracing always runs on LEVM, as it's the only engine providing execution hooks.
+fn tracing_evm(storage: Store, parent_hash: H256) -> Evm {
+    Evm::new(EvmEngine::LEVM, storage, parent_hash)
+}
+
+/// Runs a re-execution on a blocking thread, so long traces don't stall the async runtime.
+async fn run_blocking<T: Send + 'static>(
+    trace: impl FnOnce() -> Result<T, RpcErr> + Send + 'static,
+) -> Result<T, RpcErr> {
+    tokio::task::spawn_blocking(trace)
+        .await
+        .map_err(|error| RpcErr::Internal(error.to_string()))?
+}
+
+async fn trace_block(block: Block, config: TraceConfig, storage: Store) -> Result<Value, RpcErr> {
+    run_blocking(move || {
+        let mut evm = tracing_evm(storage, block.header.parent_hash);
+        let results: Vec<BlockTraceResult> = config
+            .trace_block(&mut evm, &block, None)?
+            .into_iter()
+            .map(|(tx_hash, result)| BlockTraceResult { tx_hash, result })
+            .collect();
+        Ok(serde_json::to_value(results)?)
+    })
+    .await
+}
+
+fn expect_params(
+    params: &Option<Vec<Value>>,
+    min: usize,
+    max: usize,
+) -> Result<&Vec<Value>, RpcErr> {
+    let params = params
+        .as_ref()
+        .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+    if params.len() < min || params.len() > max {
+        return Err(RpcErr::BadParams(format!(
+            "Expected between {min} and {max} params and {} were provided",
+            params.len()
+        )));
+    }
+    Ok(params)
+}
+
+impl RpcHandler for TraceTransactionRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = expect_params(params, 1, 2)?;
+        Ok(TraceTransactionRequest {
+            transaction_hash: serde_json::from_value(params[0].clone())?,
+            config: TraceConfig::parse(params, 1)?,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!(
+            "Requested trace of transaction {:#x}",
+            self.transaction_hash
+        );
+        let Some((_, block_hash, index)) = context
+            .storage
+            .get_transaction_location(self.transaction_hash)
+            .await?
+        else {
+            return Ok(Value::Null);
+        };
+        let Some(block) = context.storage.get_block_by_hash(block_hash).await? else {
+            return Ok(Value::Null);
+        };
+        let index = usize::try_from(index).map_err(|error| RpcErr::Internal(error.to_string()))?;
+
+        let config = self.config.clone();
+        run_blocking(move || {
+            let mut evm = tracing_evm(context.storage, block.header.parent_hash);
+            let (_, result) = config
+                .trace_block(&mut evm, &block, Some(index))?
+                .pop()
+                .ok_or(RpcErr::Internal(
+                    "Transaction not found in its block".to_owned(),
+                ))?;
+            Ok(result)
+        })
+        .await
+    }
+}
+
+impl RpcHandler for TraceBlockByNumberRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = expect_params(params, 1, 2)?;
+        Ok(TraceBlockByNumberRequest {
+            block: BlockIdentifier::parse(params[0].clone(), 0)?,
+            config: TraceConfig::parse(params, 1)?,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested trace of block {}", self.block);
+        let Some(block_number) = self.block.resolve_block_number(&context.storage).await? else {
+            return Ok(Value::Null);
+        };
+        let header = context.storage.get_block_header(block_number)?;
+        let body = context.storage.get_block_body(block_number).await?;
+        let (Some(header), Some(body)) = (header, body) else {
+            return Ok(Value::Null);
+        };
+        trace_block(
+            Block::new(header, body),
+            self.config.clone(),
+            context.storage,
+        )
+        .await
+    }
+}
+
+impl RpcHandler for TraceBlockByHashRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = expect_params(params, 1, 2)?;
+        Ok(TraceBlockByHashRequest {
+            block_hash: serde_json::from_value(params[0].clone())?,
+            config: TraceConfig::parse(params, 1)?,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested trace of block {:#x}", self.block_hash);
+        let Some(block) = context.storage.get_block_by_hash(self.block_hash).await? else {
+            return Ok(Value::Null);
+        };
+        trace_block(block, self.config.clone(), context.storage).await
+    }
+}
+
+impl RpcHandler for TraceCallRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = expect_params(params, 1, 3)?;
+        let block = match params.get(1) {
+            Some(value) => BlockIdentifier::parse(value.clone(), 1)?,
+            None => BlockIdentifier::default(),
+        };
+        Ok(TraceCallRequest {
+            transaction: serde_json::from_value(params[0].clone())?,
+            block,
+            config: TraceConfig::parse(params, 2)?,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested call trace on block: {}", self.block);
+        let Some(header) = self.block.resolve_block_header(&context.storage).await? else {
+            return Ok(Value::Null);
+        };
+        let (config, transaction) = (self.config.clone(), self.transaction.clone());
+        run_blocking(move || {
+            let mut evm = tracing_evm(context.storage, header.compute_block_hash());
+            config.trace_call(&mut evm, &transaction, &header)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_default_tracer() {
+        let request =
+            TraceTransactionRequest::parse(&Some(vec![json!(format!("{:#x}", H256::zero()))]))
+                .unwrap();
+        assert!(matches!(
+            request.config.tracer_kind().unwrap(),
+            TracerKind::StructLogger(StructLoggerConfig {
+                disable_stack: false,
+                enable_memory: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_call_tracer_config() {
+        let request = TraceBlockByNumberRequest::parse(&Some(vec![
+            json!("latest"),
+            json!({"tracer": "callTracer", "tracerConfig": {"onlyTopCall": true}}),
+        ]))
+        .unwrap();
+        assert!(matches!(
+            request.config.tracer_kind().unwrap(),
+            TracerKind::Call(CallTracerConfig {
+                only_top_call: true,
+                with_log: false,
+            })
+        ));
+    }
+
+    #[test]
+    fn reject_unknown_tracer() {
+        let request = TraceCallRequest::parse(&Some(vec![
+            json!({"to": format!("{:#x}", ethrex_common::Address::zero())}),
+            json!("latest"),
+            json!({"tracer": "4byteTracer"}),
+        ]))
+        .unwrap();
+        assert!(request.config.tracer_kind().is_err());
+    }
+}
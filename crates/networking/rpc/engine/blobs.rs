@@ -0,0 +1,58 @@
+use ethrex_common::{
+    serde_utils,
+    types::{Blob, Proof},
+    H256,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    rpc::{RpcApiContext, RpcHandler},
+    utils::RpcErr,
+};
+
+// -> https://github.com/ethereum/execution-apis/blob/main/src/engine/cancun.md#specification-3
+const GET_BLOBS_V1_REQUEST_MAX_SIZE: usize = 128;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobAndProofV1 {
+    #[serde(with = "serde_utils::blob")]
+    pub blob: Blob,
+    #[serde(with = "serde_utils::bytes48")]
+    pub proof: Proof,
+}
+
+pub struct GetBlobsV1Request {
+    versioned_hashes: Vec<H256>,
+}
+
+impl RpcHandler for GetBlobsV1Request {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams("Expected 1 param".to_owned()));
+        };
+        Ok(GetBlobsV1Request {
+            versioned_hashes: serde_json::from_value(params[0].clone())?,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        if self.versioned_hashes.len() > GET_BLOBS_V1_REQUEST_MAX_SIZE {
+            return Err(RpcErr::TooLargeRequest);
+        }
+        let mut blobs_and_proofs: Vec<Option<BlobAndProofV1>> = Vec::new();
+        for versioned_hash in self.versioned_hashes.iter() {
+            let blob_and_proof = context
+                .blockchain
+                .mempool
+                .get_blob_and_proof(versioned_hash)?
+                .map(|(blob, proof)| BlobAndProofV1 { blob, proof });
+            blobs_and_proofs.push(blob_and_proof);
+        }
+        serde_json::to_value(blobs_and_proofs).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
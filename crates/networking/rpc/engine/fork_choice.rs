@@ -1,6 +1,5 @@
 use ethrex_blockchain::{
     error::{ChainError, InvalidForkChoice},
-    fork_choice::apply_fork_choice,
     latest_canonical_block_hash,
     payload::{create_payload, BuildPayloadArgs},
 };
@@ -256,15 +255,27 @@ async fn handle_forkchoice(
         return Ok((None, PayloadStatus::syncing().into()));
     }
 
-    match apply_fork_choice(
-        &context.storage,
-        fork_choice_state.head_block_hash,
-        fork_choice_state.safe_block_hash,
-        fork_choice_state.finalized_block_hash,
-    )
-    .await
+    // Subscribers are only notified when the head of the canonical chain changes
+    let previous_head_hash = context
+        .storage
+        .get_latest_canonical_block_hash()
+        .await
+        .ok()
+        .flatten();
+
+    match context
+        .blockchain
+        .apply_fork_choice(
+            fork_choice_state.head_block_hash,
+            fork_choice_state.safe_block_hash,
+            fork_choice_state.finalized_block_hash,
+        )
+        .await
     {
         Ok(head) => {
+            if previous_head_hash != Some(head.compute_block_hash()) {
+                context.blockchain.notify_new_head(head.clone());
+            }
             // Remove included transactions from the mempool after we accept the fork choice
             // TODO(#797): The remove of transactions from the mempool could be incomplete (i.e. REORGS)
             match context
@@ -276,7 +287,7 @@ async fn handle_forkchoice(
                     for tx in &block.body.transactions {
                         context
                             .blockchain
-                            .remove_transaction_from_pool(&tx.compute_hash())
+                            .remove_included_transaction_from_pool(&tx.compute_hash())
                             .map_err(|err| RpcErr::Internal(err.to_string()))?;
                     }
                 }
@@ -13,7 +13,8 @@ use crate::{
     utils::RpcErr,
 };
 use ethrex_common::types::{
-    calculate_base_fee_per_blob_gas, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, Receipt,
+    calculate_base_fee_per_blob_gas, Block, BlockBody, BlockHash, BlockHeader, BlockNumber,
+    L1FeeParams, Receipt,
 };
 use ethrex_storage::Store;
 
@@ -352,6 +353,11 @@ pub async fn get_all_block_rpc_receipts(
             .unwrap_or_default(),
     );
 
+    // The L1 fee params of L2 blocks are set by the sequencer in the extra data
+    let l1_fee_params = config
+        .l1_fee_vault
+        .map(|_| L1FeeParams::from_extra_data(&header.extra_data));
+
     // Fetch receipt info from block
     let block_info = RpcReceiptBlockInfo::from_block_header(header);
     // Fetch receipt for each tx in the block and add block and tx info
@@ -364,8 +370,13 @@ pub async fn get_all_block_rpc_receipts(
             _ => return Err(RpcErr::Internal("Could not get receipt".to_owned())),
         };
         let gas_used = receipt.cumulative_gas_used - last_cumulative_gas_used;
-        let tx_info =
-            RpcReceiptTxInfo::from_transaction(tx.clone(), index, gas_used, blob_base_fee);
+        let tx_info = RpcReceiptTxInfo::from_transaction(
+            tx.clone(),
+            index,
+            gas_used,
+            blob_base_fee,
+            l1_fee_params,
+        );
         let receipt = RpcReceipt::new(
             receipt.clone(),
             tx_info,
@@ -18,7 +18,7 @@ use crate::{
 };
 use serde_json::{json, Value};
 
-use super::logs::{fetch_logs_with_filter, LogsFilter};
+use super::logs::{fetch_logs_with_filter, LogQueryLimits, LogsFilter};
 
 #[derive(Debug, Clone)]
 pub struct NewFilterRequest {
@@ -185,6 +185,7 @@ impl FilterChangesRequest {
         &self,
         storage: ethrex_storage::Store,
         filters: ActiveFilters,
+        log_query_limits: LogQueryLimits,
     ) -> Result<serde_json::Value, crate::utils::RpcErr> {
         let latest_block_num = storage.get_latest_block_number().await?;
         // Box needed to keep the future Sync
@@ -221,7 +222,8 @@ impl FilterChangesRequest {
                 // Drop the lock early to process this filter's query
                 // and not keep the lock more than we should.
                 drop(active_filters_guard);
-                let logs = fetch_logs_with_filter(&filter.filter_data, storage).await?;
+                let logs =
+                    fetch_logs_with_filter(&filter.filter_data, storage, log_query_limits).await?;
                 serde_json::to_value(logs).map_err(|error| {
                     tracing::error!("Log filtering request failed with: {error}");
                     RpcErr::Internal("Failed to filter logs".to_string())
@@ -242,9 +244,10 @@ impl FilterChangesRequest {
         req: &RpcRequest,
         storage: ethrex_storage::Store,
         filters: ActiveFilters,
+        log_query_limits: LogQueryLimits,
     ) -> Result<serde_json::Value, crate::utils::RpcErr> {
         let request = Self::parse(&req.params)?;
-        request.handle(storage, filters).await
+        request.handle(storage, filters, log_query_limits).await
     }
 }
 
@@ -452,6 +455,7 @@ mod tests {
             local_node_record: example_local_node_record(),
             active_filters: filters_pointer.clone(),
             syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
             #[cfg(feature = "based")]
             gateway_eth_client: EthClient::new(""),
             #[cfg(feature = "based")]
@@ -525,6 +529,7 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: active_filters.clone(),
             syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
             #[cfg(feature = "based")]
             gateway_eth_client: EthClient::new(""),
             #[cfg(feature = "based")]
@@ -562,6 +567,7 @@ mod tests {
             active_filters: active_filters.clone(),
             jwt_secret: Default::default(),
             syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
             #[cfg(feature = "based")]
             gateway_eth_client: EthClient::new(""),
             #[cfg(feature = "based")]
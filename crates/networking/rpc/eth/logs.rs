@@ -7,11 +7,30 @@ use crate::{
     types::{block_identifier::BlockIdentifier, receipt::RpcLog},
     utils::RpcErr,
 };
-use ethrex_common::{H160, H256};
-use ethrex_storage::Store;
+use ethrex_common::{types::BlockHash, H160, H256};
+use ethrex_storage::{BloomFilter, Store};
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashSet;
+
+/// Limits applied to the log queries, to bound the work done by a single request
+#[derive(Debug, Clone, Copy)]
+pub struct LogQueryLimits {
+    /// Maximum amount of blocks in the range of a query
+    pub max_block_range: u64,
+    /// Maximum amount of logs returned by a query
+    pub max_results: usize,
+}
+
+impl Default for LogQueryLimits {
+    fn default() -> Self {
+        Self {
+            max_block_range: 100_000,
+            max_results: 10_000,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum AddressFilter {
@@ -86,7 +105,8 @@ impl RpcHandler for LogsFilter {
         }
     }
     async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
-        let filtered_logs = fetch_logs_with_filter(self, context.storage).await?;
+        let filtered_logs =
+            fetch_logs_with_filter(self, context.storage, context.log_query_limits).await?;
         serde_json::to_value(filtered_logs).map_err(|error| {
             tracing::error!("Log filtering request failed with: {error}");
             RpcErr::Internal("Failed to filter logs".to_string())
@@ -106,6 +126,7 @@ impl RpcHandler for LogsFilter {
 pub(crate) async fn fetch_logs_with_filter(
     filter: &LogsFilter,
     storage: Store,
+    limits: LogQueryLimits,
 ) -> Result<Vec<RpcLog>, RpcErr> {
     let from = filter
         .from_block
@@ -120,18 +141,28 @@ pub(crate) async fn fetch_logs_with_filter(
     if (from..=to).is_empty() {
         return Err(RpcErr::BadParams("Empty range".to_string()));
     }
+    if to - from >= limits.max_block_range {
+        return Err(RpcErr::LimitExceeded(format!(
+            "query exceeds the max block range of {}",
+            limits.max_block_range
+        )));
+    }
     let address_filter: HashSet<_> = match &filter.address_filters {
         Some(AddressFilter::Single(address)) => std::iter::once(address).collect(),
         Some(AddressFilter::Many(addresses)) => addresses.iter().collect(),
         None => HashSet::new(),
     };
 
+    // Only the blocks whose logs bloom contains the addresses and topics
+    // of the filter can have matching logs, so the rest are skipped.
+    let candidate_blocks = storage
+        .get_blocks_matching_bloom(from, to, &bloom_filter(filter))
+        .await?;
+
     let mut logs: Vec<RpcLog> = Vec::new();
-    // The idea here is to fetch every log and filter by address, if given.
-    // For that, we'll need each block in range, and its transactions,
-    // and for each transaction, we'll need its receipts, which
-    // contain the actual logs we want.
-    for block_num in from..=to {
+    // For each candidate block we'll need its transactions, and for each
+    // transaction its receipt, which contains the actual logs we want.
+    for block_num in candidate_blocks {
         // Take the header of the block, we
         // will use it to access the transactions.
         let block_body = storage
@@ -160,7 +191,15 @@ pub(crate) async fn fetch_logs_with_filter(
 
             if receipt.succeeded {
                 for log in &receipt.logs {
-                    if address_filter.is_empty() || address_filter.contains(&log.address) {
+                    if (address_filter.is_empty() || address_filter.contains(&log.address))
+                        && matches_topics(&filter.topics, &log.topics)
+                    {
+                        if logs.len() >= limits.max_results {
+                            return Err(RpcErr::LimitExceeded(format!(
+                                "query returned more than {} results",
+                                limits.max_results
+                            )));
+                        }
                         // Some extra data is needed when
                         // forming the RPC response.
                         logs.push(RpcLog {
@@ -178,41 +217,158 @@ pub(crate) async fn fetch_logs_with_filter(
             }
         }
     }
-    // Now that we have the logs filtered by address,
-    // we still need to filter by topics if it was a given parameter.
-
-    let filtered_logs = if filter.topics.is_empty() {
-        logs
-    } else {
-        logs.into_iter()
-            .filter(|rpc_log| {
-                if filter.topics.len() > rpc_log.log.topics.len() {
-                    return false;
-                }
-                for (i, topic_filter) in filter.topics.iter().enumerate() {
-                    match topic_filter {
-                        TopicFilter::Topic(t) => {
-                            if let Some(topic) = t {
-                                if rpc_log.log.topics[i] != *topic {
-                                    return false;
-                                }
-                            }
-                        }
-                        TopicFilter::Topics(sub_topics) => {
-                            if !sub_topics.is_empty()
-                                && !sub_topics
-                                    .iter()
-                                    .any(|st| st.map_or(true, |t| rpc_log.log.topics[i] == t))
-                            {
-                                return false;
-                            }
-                        }
+
+    Ok(logs)
+}
+
+/// Returns the logs of the block with the given hash that match the addresses and topics of
+/// the filter, its block range is ignored. The block doesn't need to be canonical, `removed`
+/// is set in the logs of the blocks that left the canonical chain.
+pub(crate) async fn fetch_block_logs_with_filter(
+    filter: &LogsFilter,
+    storage: &Store,
+    block_hash: BlockHash,
+    removed: bool,
+) -> Result<Vec<RpcLog>, RpcErr> {
+    let header = storage
+        .get_block_header_by_hash(block_hash)?
+        .ok_or(RpcErr::Internal(format!(
+            "Could not get header for block {block_hash:#x}"
+        )))?;
+    if !bloom_filter(filter).matches(&header.logs_bloom) {
+        return Ok(vec![]);
+    }
+    let body = storage
+        .get_block_body_by_hash(block_hash)
+        .await?
+        .ok_or(RpcErr::Internal(format!(
+            "Could not get body for block {block_hash:#x}"
+        )))?;
+    let receipts = storage.get_receipts_for_block(&block_hash)?;
+    let address_filter: HashSet<_> = match &filter.address_filters {
+        Some(AddressFilter::Single(address)) => std::iter::once(address).collect(),
+        Some(AddressFilter::Many(addresses)) => addresses.iter().collect(),
+        None => HashSet::new(),
+    };
+
+    let mut logs = Vec::new();
+    let mut block_log_index = 0_u64;
+    for (tx_index, (tx, receipt)) in body.transactions.iter().zip(receipts).enumerate() {
+        if !receipt.succeeded {
+            continue;
+        }
+        for log in receipt.logs {
+            if (address_filter.is_empty() || address_filter.contains(&log.address))
+                && matches_topics(&filter.topics, &log.topics)
+            {
+                logs.push(RpcLog {
+                    log: log.into(),
+                    log_index: block_log_index,
+                    transaction_hash: tx.compute_hash(),
+                    transaction_index: tx_index as u64,
+                    block_number: header.number,
+                    block_hash,
+                    removed,
+                });
+            }
+            block_log_index += 1;
+        }
+    }
+    Ok(logs)
+}
+
+/// Builds the filter matched against the logs bloom of the blocks: one group
+/// with the filtered addresses, and one for each position with filtered topics
+fn bloom_filter(filter: &LogsFilter) -> BloomFilter {
+    let addresses = match &filter.address_filters {
+        Some(AddressFilter::Single(address)) => vec![address.as_bytes()],
+        Some(AddressFilter::Many(addresses)) => addresses.iter().map(H160::as_bytes).collect(),
+        None => vec![],
+    };
+    let topics = filter.topics.iter().map(|topic_filter| match topic_filter {
+        TopicFilter::Topic(Some(topic)) => vec![topic.as_bytes()],
+        // A wildcard matches any topic, so it doesn't restrict the blocks
+        TopicFilter::Topic(None) => vec![],
+        TopicFilter::Topics(topics) => topics
+            .iter()
+            .map(|topic| topic.as_ref().map(H256::as_bytes))
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default(),
+    });
+    BloomFilter::new(std::iter::once(addresses).chain(topics))
+}
+
+fn matches_topics(filter_topics: &[TopicFilter], log_topics: &[H256]) -> bool {
+    if filter_topics.len() > log_topics.len() {
+        return false;
+    }
+    for (i, topic_filter) in filter_topics.iter().enumerate() {
+        match topic_filter {
+            TopicFilter::Topic(t) => {
+                if let Some(topic) = t {
+                    if log_topics[i] != *topic {
+                        return false;
                     }
                 }
-                true
-            })
-            .collect::<Vec<RpcLog>>()
-    };
+            }
+            TopicFilter::Topics(sub_topics) => {
+                if !sub_topics.is_empty()
+                    && !sub_topics
+                        .iter()
+                        .any(|st| st.map_or(true, |t| log_topics[i] == t))
+                {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::{Bloom, BloomInput};
+    use ethrex_storage::EngineType;
+
+    fn logs_filter(from: u64, to: u64) -> LogsFilter {
+        LogsFilter {
+            from_block: BlockIdentifier::Number(from),
+            to_block: BlockIdentifier::Number(to),
+            address_filters: None,
+            topics: vec![],
+        }
+    }
 
-    Ok(filtered_logs)
+    #[tokio::test]
+    async fn block_range_limit_is_enforced() {
+        let storage = Store::new("", EngineType::InMemory).unwrap();
+        let limits = LogQueryLimits {
+            max_block_range: 10,
+            ..Default::default()
+        };
+        let result = fetch_logs_with_filter(&logs_filter(0, 10), storage, limits).await;
+        assert!(matches!(result, Err(RpcErr::LimitExceeded(_))));
+    }
+
+    #[test]
+    fn bloom_filter_matches_addresses_and_topics() {
+        let address = H160::from_low_u64_be(1);
+        let topic = H256::from_low_u64_be(2);
+        let mut bloom = Bloom::zero();
+        bloom.accrue(BloomInput::Raw(address.as_bytes()));
+        bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+
+        let mut filter = logs_filter(0, 0);
+        filter.address_filters = Some(AddressFilter::Many(vec![address, H160::zero()]));
+        filter.topics = vec![TopicFilter::Topic(None), TopicFilter::Topic(Some(topic))];
+        assert!(bloom_filter(&filter).matches(&bloom));
+
+        filter.topics = vec![TopicFilter::Topic(Some(H256::zero()))];
+        assert!(!bloom_filter(&filter).matches(&bloom));
+
+        // A wildcard within a set of topics matches any topic
+        filter.topics = vec![TopicFilter::Topics(vec![Some(H256::zero()), None])];
+        assert!(bloom_filter(&filter).matches(&bloom));
+    }
 }
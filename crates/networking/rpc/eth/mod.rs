@@ -4,6 +4,8 @@ pub(crate) mod client;
 pub(crate) mod fee_market;
 pub(crate) mod filter;
 pub(crate) mod logs;
+pub(crate) mod simulate;
+pub(crate) mod subscription;
 pub(crate) mod transaction;
 
 pub(crate) mod fee_calculator;
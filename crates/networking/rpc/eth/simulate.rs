@@ -0,0 +1,554 @@
+use bytes::Bytes;
+use ethrex_common::{
+    serde_utils,
+    types::{
+        compute_receipts_root, compute_transactions_root, compute_withdrawals_root, BlockBody,
+        BlockHeader, ChainConfig, EIP1559Transaction, GenericTransaction, LegacyTransaction,
+        Receipt, Transaction, DEFAULT_OMMERS_HASH, DEFAULT_REQUESTS_HASH,
+    },
+    Bloom, H256,
+};
+use ethrex_vm::{
+    tracers::transfer_log_tracer::TransferLogTracer, Evm, EvmEngine, ExecutionResult, StateOverride,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    rpc::{RpcApiContext, RpcHandler},
+    types::{
+        block::RpcBlock,
+        block_identifier::BlockIdentifier,
+        overrides::BlockOverrides,
+        receipt::{RpcLog, RpcLogInfo},
+    },
+    utils::{RpcErr, RpcErrorMetadata},
+};
+
+/// Maximum amount of blocks a simulation can span, including the empty blocks
+/// added to fill the gaps between the requested block numbers
+const MAX_SIMULATED_BLOCKS: u64 = 256;
+
+/// Seconds between simulated blocks whose timestamp is not overridden
+const SIMULATED_BLOCK_TIME: u64 = 12;
+
+pub struct SimulateV1Request {
+    payload: SimulatePayload,
+    block: BlockIdentifier,
+}
+
+/// First param of `eth_simulateV1`, as defined by the execution-apis spec
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulatePayload {
+    block_state_calls: Vec<SimulatedBlockCalls>,
+    #[serde(default)]
+    trace_transfers: bool,
+    #[serde(default)]
+    validation: bool,
+    #[serde(default)]
+    return_full_transactions: bool,
+}
+
+/// Calls executed in a simulated block, along with the overrides applied before them
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulatedBlockCalls {
+    #[serde(default)]
+    block_overrides: Option<BlockOverrides>,
+    #[serde(default)]
+    state_overrides: Option<StateOverride>,
+    #[serde(default)]
+    calls: Vec<GenericTransaction>,
+}
+
+#[derive(Debug, Serialize)]
+struct SimulatedBlock {
+    #[serde(flatten)]
+    block: RpcBlock,
+    calls: Vec<SimulatedCall>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulatedCall {
+    #[serde(with = "serde_utils::u64::hex_str")]
+    status: u64,
+    #[serde(with = "serde_utils::bytes")]
+    return_data: Bytes,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    gas_used: u64,
+    logs: Vec<RpcLog>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcErrorMetadata>,
+}
+
+impl RpcHandler for SimulateV1Request {
+    fn parse(params: &Option<Vec<Value>>) -> Result<SimulateV1Request, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.is_empty() || params.len() > 2 {
+            return Err(RpcErr::BadParams(format!(
+                "Expected one or two params and {} were provided",
+                params.len()
+            )));
+        }
+        let payload: SimulatePayload = serde_json::from_value(params[0].clone())?;
+        if payload.validation {
+            return Err(RpcErr::BadParams(
+                "Validation of simulated transactions is not supported".to_owned(),
+            ));
+        }
+        let block = match params.get(1) {
+            Some(value) => BlockIdentifier::parse(value.clone(), 1)?,
+            None => BlockIdentifier::default(),
+        };
+        Ok(SimulateV1Request { payload, block })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!(
+            "Requested simulation of {} blocks on top of block: {}",
+            self.payload.block_state_calls.len(),
+            self.block
+        );
+        let Some(base_header) = self.block.resolve_block_header(&context.storage).await? else {
+            return Ok(Value::Null);
+        };
+        let chain_config = context.storage.get_chain_config()?;
+        // Every block is simulated with the same VM, so that the changes made by each transaction
+        // stay in LEVM's cache and are seen by the following ones
+        let mut vm = Evm::new(
+            EvmEngine::LEVM,
+            context.storage.clone(),
+            base_header.compute_block_hash(),
+        );
+
+        let mut blocks: Vec<SimulatedBlock> = Vec::new();
+        for block_calls in &self.payload.block_state_calls {
+            let parent = blocks
+                .last()
+                .map_or(&base_header, |block| &block.block.header)
+                .clone();
+            let number = block_calls
+                .block_overrides
+                .as_ref()
+                .and_then(|overrides| overrides.number)
+                .unwrap_or(parent.number + 1);
+            if number <= parent.number {
+                return Err(RpcErr::BadParams(format!(
+                    "Block numbers must be in order: {number} <= {}",
+                    parent.number
+                )));
+            }
+            if number - base_header.number > MAX_SIMULATED_BLOCKS {
+                return Err(RpcErr::LimitExceeded(format!(
+                    "Simulations can span up to {MAX_SIMULATED_BLOCKS} blocks"
+                )));
+            }
+
+            // Fill the gap up to the requested block number with empty blocks
+            let mut parent = parent;
+            while parent.number + 1 < number {
+                let header = simulated_header(&parent, None);
+                let block = self.simulate_block(&mut vm, header, &[], &chain_config)?;
+                parent = block.block.header.clone();
+                blocks.push(block);
+            }
+
+            let header = simulated_header(&parent, block_calls.block_overrides.as_ref());
+            if header.timestamp <= parent.timestamp {
+                return Err(RpcErr::BadParams(format!(
+                    "Block timestamps must be in order: {} <= {}",
+                    header.timestamp, parent.timestamp
+                )));
+            }
+            if let Some(state_overrides) = &block_calls.state_overrides {
+                vm.apply_state_override(state_overrides)?;
+            }
+            let block = self.simulate_block(&mut vm, header, &block_calls.calls, &chain_config)?;
+            blocks.push(block);
+        }
+
+        serde_json::to_value(blocks).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl SimulateV1Request {
+    /// Executes the calls of a block on top of the state left by the previous ones
+    /// and completes its header with their results
+    fn simulate_block(
+        &self,
+        vm: &mut Evm,
+        mut header: BlockHeader,
+        calls: &[GenericTransaction],
+        chain_config: &ChainConfig,
+    ) -> Result<SimulatedBlock, RpcErr> {
+        let fork = chain_config.get_fork(header.timestamp);
+        let mut transactions = Vec::new();
+        let mut senders = Vec::new();
+        let mut receipts = Vec::new();
+        let mut results = Vec::new();
+        let mut gas_used: u64 = 0;
+
+        for call in calls {
+            let mut call = call.clone();
+            if call.nonce.is_none() {
+                call.nonce = Some(vm.get_nonce(call.from)?);
+            }
+            let remaining_gas = header.gas_limit.saturating_sub(gas_used);
+            let gas = call.gas.unwrap_or(remaining_gas);
+            if gas > remaining_gas {
+                return Err(RpcErr::BadParams(format!(
+                    "Block gas limit reached: call needs {gas} gas and {remaining_gas} are left"
+                )));
+            }
+            call.gas = Some(gas);
+
+            let (result, logs) = if self.payload.trace_transfers {
+                let mut tracer = TransferLogTracer::new();
+                let result = vm.trace_tx_from_generic(&call, &header, &mut tracer)?;
+                (result, tracer.into_logs())
+            } else {
+                let result = vm.simulate_tx_from_generic(&call, &header, fork)?;
+                let logs = result.logs();
+                (result, logs)
+            };
+            let error = match &result {
+                ExecutionResult::Success { .. } => None,
+                ExecutionResult::Revert { output, .. } => {
+                    Some(RpcErrorMetadata::from(RpcErr::Revert {
+                        data: format!("0x{}", hex::encode(output)),
+                    }))
+                }
+                ExecutionResult::Halt { reason, .. } => Some(RpcErrorMetadata {
+                    code: -32015,
+                    data: None,
+                    message: reason.clone(),
+                }),
+            };
+
+            gas_used = gas_used.saturating_add(result.gas_used());
+            let transaction = simulated_transaction(&call, chain_config.chain_id);
+            receipts.push(Receipt::new(
+                transaction.tx_type(),
+                result.is_success(),
+                gas_used,
+                logs.clone(),
+            ));
+            transactions.push(transaction);
+            senders.push(call.from);
+            results.push((result, logs, error));
+        }
+
+        let mut logs_bloom = Bloom::zero();
+        for receipt in &receipts {
+            logs_bloom.accrue_bloom(&receipt.bloom);
+        }
+        header.gas_used = gas_used;
+        header.transactions_root = compute_transactions_root(&transactions);
+        header.receipts_root = compute_receipts_root(&receipts);
+        header.logs_bloom = logs_bloom;
+        let hash = header.compute_block_hash();
+
+        let mut log_index = 0;
+        let calls = results
+            .into_iter()
+            .zip(&transactions)
+            .enumerate()
+            .map(|(index, ((result, logs, error), transaction))| {
+                let transaction_hash = transaction.compute_hash();
+                let logs = logs
+                    .into_iter()
+                    .map(|log| {
+                        let log = RpcLog {
+                            log: RpcLogInfo::from(log),
+                            log_index,
+                            removed: false,
+                            transaction_hash,
+                            transaction_index: index as u64,
+                            block_hash: hash,
+                            block_number: header.number,
+                        };
+                        log_index += 1;
+                        log
+                    })
+                    .collect();
+                SimulatedCall {
+                    status: u64::from(result.is_success()),
+                    return_data: result.output(),
+                    gas_used: result.gas_used(),
+                    logs,
+                    error,
+                }
+            })
+            .collect();
+
+        let body = BlockBody {
+            transactions,
+            ommers: Vec::new(),
+            withdrawals: header.withdrawals_root.map(|_| Vec::new()),
+        };
+        let block = RpcBlock::build_with_senders(
+            header,
+            body,
+            &senders,
+            hash,
+            self.payload.return_full_transactions,
+        );
+        Ok(SimulatedBlock { block, calls })
+    }
+}
+
+/// Header of the block simulated on top of `parent`, before executing its calls.
+/// The state root is the one of the parent, as simulated changes are never committed.
+fn simulated_header(parent: &BlockHeader, overrides: Option<&BlockOverrides>) -> BlockHeader {
+    let header = BlockHeader {
+        parent_hash: parent.compute_block_hash(),
+        ommers_hash: *DEFAULT_OMMERS_HASH,
+        number: parent.number + 1,
+        timestamp: parent.timestamp + SIMULATED_BLOCK_TIME,
+        prev_randao: H256::zero(),
+        // Without validation, simulated transactions don't pay a base fee unless it is overridden
+        base_fee_per_gas: parent.base_fee_per_gas.map(|_| 0),
+        withdrawals_root: parent
+            .withdrawals_root
+            .map(|_| compute_withdrawals_root(&[])),
+        blob_gas_used: parent.blob_gas_used.map(|_| 0),
+        requests_hash: parent.requests_hash.map(|_| *DEFAULT_REQUESTS_HASH),
+        ..parent.clone()
+    };
+    match overrides {
+        Some(overrides) => overrides.apply(&header),
+        None => header,
+    }
+}
+
+/// Unsigned transaction included in the simulated block for a call
+fn simulated_transaction(call: &GenericTransaction, chain_id: u64) -> Transaction {
+    let gas = call.gas.unwrap_or_default();
+    let nonce = call.nonce.unwrap_or_default();
+    if call.max_fee_per_gas.is_none() && call.gas_price != 0 {
+        return Transaction::LegacyTransaction(LegacyTransaction {
+            nonce,
+            gas_price: call.gas_price,
+            gas,
+            to: call.to.clone(),
+            value: call.value,
+            data: call.input.clone(),
+            ..Default::default()
+        });
+    }
+    Transaction::EIP1559Transaction(EIP1559Transaction {
+        chain_id,
+        nonce,
+        max_priority_fee_per_gas: call.max_priority_fee_per_gas.unwrap_or_default(),
+        max_fee_per_gas: call.max_fee_per_gas.unwrap_or_default(),
+        gas_limit: gas,
+        to: call.to.clone(),
+        value: call.value,
+        data: call.input.clone(),
+        access_list: call
+            .access_list
+            .iter()
+            .map(|entry| (entry.address, entry.storage_keys.clone()))
+            .collect(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eth::test_utils::setup_store,
+        utils::test_utils::{example_local_node_record, example_p2p_node},
+    };
+    #[cfg(feature = "based")]
+    use crate::{EngineClient, EthClient};
+    use ethrex_blockchain::Blockchain;
+    use ethrex_common::{Address, U256};
+    use ethrex_p2p::sync_manager::SyncManager;
+    use ethrex_vm::tracers::transfer_log_tracer::{TRANSFER_LOG_ADDRESS, TRANSFER_TOPIC};
+    #[cfg(feature = "l2")]
+    use secp256k1::{rand, SecretKey};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    const SENDER: &str = "0x000000000000000000000000000000000000dead";
+    const RECEIVER: &str = "0x000000000000000000000000000000000000beef";
+    const CONTRACT: &str = "0x000000000000000000000000000000000000c0de";
+
+    async fn default_context() -> RpcApiContext {
+        let storage = setup_store().await;
+        let blockchain = Arc::new(Blockchain::default_with_store(storage.clone()));
+        RpcApiContext {
+            storage,
+            blockchain,
+            jwt_secret: Default::default(),
+            local_p2p_node: example_p2p_node(),
+            local_node_record: example_local_node_record(),
+            active_filters: Default::default(),
+            syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
+            #[cfg(feature = "based")]
+            gateway_eth_client: EthClient::new(""),
+            #[cfg(feature = "based")]
+            gateway_auth_client: EngineClient::new("", Bytes::default()),
+            #[cfg(feature = "based")]
+            gateway_pubkey: Default::default(),
+            #[cfg(feature = "l2")]
+            valid_delegation_addresses: Vec::new(),
+            #[cfg(feature = "l2")]
+            sponsor_pk: SecretKey::new(&mut rand::thread_rng()),
+        }
+    }
+
+    async fn simulate(payload: Value) -> Value {
+        SimulateV1Request::parse(&Some(vec![payload, json!("latest")]))
+            .unwrap()
+            .handle(default_context().await)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_simulate_payload() {
+        let request = SimulateV1Request::parse(&Some(vec![
+            json!({
+                "blockStateCalls": [
+                    {
+                        "blockOverrides": { "number": "0x10" },
+                        "stateOverrides": { "0x000000000000000000000000000000000000dead": { "balance": "0x1" } },
+                        "calls": [{ "from": "0x000000000000000000000000000000000000dead", "to": "0x000000000000000000000000000000000000beef" }]
+                    },
+                    {}
+                ],
+                "traceTransfers": true
+            }),
+            json!("latest"),
+        ]))
+        .unwrap();
+        assert_eq!(request.payload.block_state_calls.len(), 2);
+        assert!(request.payload.trace_transfers);
+        assert!(!request.payload.return_full_transactions);
+        assert_eq!(request.payload.block_state_calls[0].calls.len(), 1);
+        assert!(request.payload.block_state_calls[1].calls.is_empty());
+
+        let validation = json!({ "blockStateCalls": [], "validation": true });
+        assert!(SimulateV1Request::parse(&Some(vec![validation])).is_err());
+    }
+
+    #[test]
+    fn simulated_header_follows_parent() {
+        let parent = BlockHeader {
+            number: 5,
+            timestamp: 100,
+            base_fee_per_gas: Some(7),
+            ..Default::default()
+        };
+        let header = simulated_header(&parent, None);
+        assert_eq!(header.parent_hash, parent.compute_block_hash());
+        assert_eq!(header.number, 6);
+        assert_eq!(header.timestamp, 100 + SIMULATED_BLOCK_TIME);
+        assert_eq!(header.base_fee_per_gas, Some(0));
+
+        let overrides = BlockOverrides {
+            time: Some(1000),
+            base_fee_per_gas: Some(3),
+            ..Default::default()
+        };
+        let header = simulated_header(&parent, Some(&overrides));
+        assert_eq!(header.timestamp, 1000);
+        assert_eq!(header.base_fee_per_gas, Some(3));
+    }
+
+    #[tokio::test]
+    async fn simulated_calls_see_the_state_left_by_previous_ones() {
+        // Increments the counter in slot 0 and returns its new value
+        let counter = "0x6000546001018060005560005260206000f3";
+        let blocks = simulate(json!({
+            "blockStateCalls": [
+                {
+                    "stateOverrides": {
+                        CONTRACT: { "code": counter },
+                        SENDER: { "balance": "0x3e8" }
+                    },
+                    "calls": [
+                        { "from": SENDER, "to": CONTRACT },
+                        { "from": SENDER, "to": RECEIVER, "value": "0x3e8" }
+                    ]
+                },
+                {
+                    "calls": [
+                        { "from": SENDER, "to": CONTRACT },
+                        // Only succeeds if the transfer of the previous block was kept
+                        { "from": RECEIVER, "to": SENDER, "value": "0x3e8" }
+                    ]
+                }
+            ],
+            "returnFullTransactions": true
+        }))
+        .await;
+
+        let return_value = |block: usize, call: usize| {
+            U256::from_str_radix(
+                blocks[block]["calls"][call]["returnData"]
+                    .as_str()
+                    .unwrap()
+                    .trim_start_matches("0x"),
+                16,
+            )
+            .unwrap()
+        };
+        assert_eq!(return_value(0, 0), U256::from(1));
+        assert_eq!(return_value(1, 0), U256::from(2));
+        assert_eq!(blocks[1]["calls"][1]["status"], json!("0x1"));
+        // The sender's nonce is carried over from the previous calls
+        assert_eq!(blocks[1]["transactions"][0]["nonce"], json!("0x2"));
+        assert_eq!(blocks[1]["parentHash"], blocks[0]["hash"]);
+    }
+
+    #[tokio::test]
+    async fn traced_transfers_are_logged() {
+        // Forwards 400 wei of the received value to RECEIVER
+        let forwarder = "0x600060006000600061019061beef5af100";
+        let blocks = simulate(json!({
+            "blockStateCalls": [{
+                "stateOverrides": {
+                    CONTRACT: { "code": forwarder },
+                    SENDER: { "balance": "0x3e8" }
+                },
+                "calls": [{ "from": SENDER, "to": CONTRACT, "value": "0x3e8" }]
+            }],
+            "traceTransfers": true
+        }))
+        .await;
+
+        let logs = blocks[0]["calls"][0]["logs"].as_array().unwrap();
+        let transfers: Vec<(Address, Address, U256)> = logs
+            .iter()
+            .map(|log| {
+                let log: RpcLog = serde_json::from_value(log.clone()).unwrap();
+                assert_eq!(log.log.address, TRANSFER_LOG_ADDRESS);
+                assert_eq!(log.log.topics[0], TRANSFER_TOPIC);
+                (
+                    Address::from(log.log.topics[1]),
+                    Address::from(log.log.topics[2]),
+                    U256::from_big_endian(&log.log.data),
+                )
+            })
+            .collect();
+        let address = |address: &str| address.parse::<Address>().unwrap();
+        assert_eq!(
+            transfers,
+            vec![
+                (address(SENDER), address(CONTRACT), U256::from(1000)),
+                (address(CONTRACT), address(RECEIVER), U256::from(400)),
+            ]
+        );
+        assert_eq!(logs[1]["logIndex"], json!("0x1"));
+    }
+}
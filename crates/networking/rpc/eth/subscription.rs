@@ -0,0 +1,449 @@
+// The behaviour of the subscription endpoints is based on:
+// - Go-Ethereum, specifically: https://github.com/ethereum/go-ethereum/blob/master/eth/filters/api.go
+// - Geth's docs: https://geth.ethereum.org/docs/interacting-with-geth/rpc/pubsub
+use crate::{
+    eth::logs::{fetch_block_logs_with_filter, AddressFilter, LogsFilter, TopicFilter},
+    rpc::RpcApiContext,
+    types::{block_identifier::BlockIdentifier, receipt::RpcLog},
+    utils::RpcErr,
+};
+use ethrex_common::{types::BlockHeader, H256};
+use ethrex_storage::Store;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    runtime::Handle,
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+use tracing::warn;
+
+/// Subscriptions of a single websocket connection, by id.
+pub type ActiveSubscriptions = std::collections::HashMap<String, JoinHandle<()>>;
+
+/// Channel used to send subscription notifications to the websocket connection.
+pub type NotificationSender = mpsc::Sender<Value>;
+
+/// Number of notifications a websocket connection can have pending to be sent.
+/// The subscriptions of a client that falls further behind are ended.
+pub const NOTIFICATION_BUFFER_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogsSubscriptionFilter {
+    pub address: Option<AddressFilter>,
+    pub topics: Option<Vec<TopicFilter>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SubscriptionKind {
+    NewHeads,
+    Logs(LogsSubscriptionFilter),
+    NewPendingTransactions,
+}
+
+/// Header notified to `newHeads` subscribers.
+#[derive(Serialize)]
+struct RpcHeader {
+    hash: H256,
+    #[serde(flatten)]
+    header: BlockHeader,
+}
+
+pub struct SubscribeRequest {
+    pub kind: SubscriptionKind,
+}
+
+pub struct UnsubscribeRequest {
+    pub id: String,
+}
+
+impl SubscribeRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        let kind = match params.first().and_then(Value::as_str) {
+            Some("newHeads") => SubscriptionKind::NewHeads,
+            Some("logs") => {
+                let filter = match params.get(1) {
+                    Some(filter) => serde_json::from_value(filter.clone())?,
+                    None => LogsSubscriptionFilter::default(),
+                };
+                SubscriptionKind::Logs(filter)
+            }
+            Some("newPendingTransactions") => SubscriptionKind::NewPendingTransactions,
+            Some(other) => {
+                return Err(RpcErr::BadParams(format!(
+                    "Unsupported subscription type: {other}"
+                )))
+            }
+            None => return Err(RpcErr::MissingParam("subscription type".to_owned())),
+        };
+        Ok(SubscribeRequest { kind })
+    }
+
+    /// Starts forwarding the events of the subscription to `notifications`.
+    /// Returns the id of the new subscription.
+    pub fn handle(
+        &self,
+        context: RpcApiContext,
+        subscriptions: &mut ActiveSubscriptions,
+        notifications: NotificationSender,
+    ) -> Result<Value, RpcErr> {
+        let id = format!("0x{:032x}", rand::random::<u128>());
+        let task = match self.kind.clone() {
+            SubscriptionKind::NewHeads => {
+                let heads = context.blockchain.subscribe_new_heads();
+                tokio::spawn(forward_new_heads(id.clone(), heads, notifications))
+            }
+            SubscriptionKind::Logs(filter) => {
+                let heads = context.blockchain.subscribe_new_heads();
+                tokio::spawn(forward_logs(
+                    id.clone(),
+                    filter,
+                    heads,
+                    context,
+                    notifications,
+                ))
+            }
+            SubscriptionKind::NewPendingTransactions => {
+                let transactions = context.blockchain.subscribe_pending_transactions();
+                tokio::spawn(forward_pending_transactions(
+                    id.clone(),
+                    transactions,
+                    notifications,
+                ))
+            }
+        };
+        subscriptions.insert(id.clone(), task);
+        Ok(Value::String(id))
+    }
+}
+
+impl UnsubscribeRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        match params.as_deref() {
+            Some([Value::String(id)]) => Ok(UnsubscribeRequest { id: id.clone() }),
+            _ => Err(RpcErr::BadParams(
+                "Expected a subscription id as the only param".to_owned(),
+            )),
+        }
+    }
+
+    pub fn handle(&self, subscriptions: &mut ActiveSubscriptions) -> Result<Value, RpcErr> {
+        match subscriptions.remove(&self.id) {
+            Some(task) => {
+                task.abort();
+                Ok(true.into())
+            }
+            None => Ok(false.into()),
+        }
+    }
+}
+
+fn notification(id: &str, result: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": {
+            "subscription": id,
+            "result": result,
+        }
+    })
+}
+
+/// Queues a notification for the websocket connection. Returns false if the subscription
+/// must end, because the connection was closed or the client isn't keeping up with it.
+fn notify(notifications: &NotificationSender, id: &str, result: Value) -> bool {
+    match notifications.try_send(notification(id, result)) {
+        Ok(()) => true,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            warn!("Subscription {id} fell behind its notifications, ending it");
+            false
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => false,
+    }
+}
+
+/// Waits for the next event, skipping the ones missed by a lagging receiver.
+/// Returns `None` once the sender is dropped.
+async fn next_event<T: Clone>(events: &mut broadcast::Receiver<T>, id: &str) -> Option<T> {
+    loop {
+        match events.recv().await {
+            Ok(event) => return Some(event),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Subscription {id} lagged behind, {missed} notifications were dropped");
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+async fn forward_new_heads(
+    id: String,
+    mut heads: broadcast::Receiver<BlockHeader>,
+    notifications: NotificationSender,
+) {
+    while let Some(header) = next_event(&mut heads, &id).await {
+        let header = RpcHeader {
+            hash: header.compute_block_hash(),
+            header,
+        };
+        let Ok(result) = serde_json::to_value(header) else {
+            continue;
+        };
+        if !notify(&notifications, &id, result) {
+            return;
+        }
+    }
+}
+
+async fn forward_logs(
+    id: String,
+    filter: LogsSubscriptionFilter,
+    mut heads: broadcast::Receiver<BlockHeader>,
+    context: RpcApiContext,
+    notifications: NotificationSender,
+) {
+    let logs_filter = LogsFilter {
+        from_block: BlockIdentifier::default(),
+        to_block: BlockIdentifier::default(),
+        address_filters: filter.address,
+        topics: filter.topics.unwrap_or_default(),
+    };
+    let mut last_head: Option<BlockHeader> = None;
+    while let Some(head) = next_event(&mut heads, &id).await {
+        // The headers and receipts are read synchronously, out of the async runtime
+        let (storage, filter, subscription_id) =
+            (context.storage.clone(), logs_filter.clone(), id.clone());
+        let max_blocks = context.log_query_limits.max_block_range;
+        let previous_head = last_head.replace(head.clone());
+        let logs = tokio::task::spawn_blocking(move || {
+            head_change_logs(
+                &subscription_id,
+                &storage,
+                &filter,
+                previous_head.as_ref(),
+                &head,
+                max_blocks,
+            )
+        })
+        .await
+        .unwrap_or_default();
+        for log in logs {
+            let Ok(result) = serde_json::to_value(log) else {
+                continue;
+            };
+            if !notify(&notifications, &id, result) {
+                return;
+            }
+        }
+    }
+}
+
+/// Returns the logs to notify when the head moves from `last_head` to `head`: the ones of every
+/// block in between, and the ones of the blocks that left the canonical chain as removed.
+/// Blocks on the store, so it must run outside of the async runtime.
+fn head_change_logs(
+    id: &str,
+    storage: &Store,
+    filter: &LogsFilter,
+    last_head: Option<&BlockHeader>,
+    head: &BlockHeader,
+    max_blocks: u64,
+) -> Vec<RpcLog> {
+    let head_hash = head.compute_block_hash();
+    let (removed, added) = match last_head {
+        Some(last_head) => match canonical_route(storage, last_head, head, max_blocks) {
+            Ok(route) => route,
+            Err(error) => {
+                warn!("Failed to find the blocks between the last head and {head_hash:#x} for subscription {id}: {error:?}");
+                (vec![], vec![head_hash])
+            }
+        },
+        None => (vec![], vec![head_hash]),
+    };
+
+    let blocks = removed
+        .into_iter()
+        .map(|block_hash| (block_hash, true))
+        .chain(added.into_iter().map(|block_hash| (block_hash, false)));
+    let mut logs = Vec::new();
+    for (block_hash, removed) in blocks {
+        match Handle::current().block_on(fetch_block_logs_with_filter(
+            filter, storage, block_hash, removed,
+        )) {
+            Ok(block_logs) => logs.extend(block_logs),
+            Err(error) => {
+                warn!("Failed to fetch logs of block {block_hash:#x} for subscription {id}: {error:?}")
+            }
+        }
+    }
+    logs
+}
+
+/// Returns the hashes of the blocks that left the canonical chain when its head moved from
+/// `old_head` to `new_head`, from the old head down, and the hashes of the blocks that joined
+/// it, up to the new head. Fails if more than `max_blocks` blocks are part of the route.
+fn canonical_route(
+    storage: &Store,
+    old_head: &BlockHeader,
+    new_head: &BlockHeader,
+    max_blocks: u64,
+) -> Result<(Vec<H256>, Vec<H256>), RpcErr> {
+    let parent = |header: &BlockHeader| {
+        storage
+            .get_block_header_by_hash(header.parent_hash)?
+            .ok_or(RpcErr::Internal(format!(
+                "Could not get header for block {:#x}",
+                header.parent_hash
+            )))
+    };
+    let (mut old, mut new) = (old_head.clone(), new_head.clone());
+    let (mut removed, mut added) = (vec![], vec![]);
+    loop {
+        let (old_hash, new_hash) = (old.compute_block_hash(), new.compute_block_hash());
+        if old_hash == new_hash {
+            break;
+        }
+        if (removed.len() + added.len()) as u64 >= max_blocks {
+            return Err(RpcErr::LimitExceeded(format!(
+                "the head moved more than {max_blocks} blocks"
+            )));
+        }
+        // Walk back the highest of both chains until they meet
+        if new.number >= old.number {
+            added.push(new_hash);
+            new = parent(&new)?;
+        } else {
+            removed.push(old_hash);
+            old = parent(&old)?;
+        }
+    }
+    added.reverse();
+    Ok((removed, added))
+}
+
+async fn forward_pending_transactions(
+    id: String,
+    mut transactions: broadcast::Receiver<H256>,
+    notifications: NotificationSender,
+) {
+    while let Some(hash) = next_event(&mut transactions, &id).await {
+        let result = Value::String(format!("{hash:#x}"));
+        if !notify(&notifications, &id, result) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_storage::EngineType;
+
+    #[test]
+    fn parse_subscription_kinds() {
+        let request = SubscribeRequest::parse(&Some(vec![json!("newHeads")])).unwrap();
+        assert!(matches!(request.kind, SubscriptionKind::NewHeads));
+
+        let request = SubscribeRequest::parse(&Some(vec![
+            json!("logs"),
+            json!({"address": "0x0000000000000000000000000000000000000001", "topics": []}),
+        ]))
+        .unwrap();
+        assert!(matches!(
+            request.kind,
+            SubscriptionKind::Logs(LogsSubscriptionFilter {
+                address: Some(AddressFilter::Single(_)),
+                ..
+            })
+        ));
+
+        let request =
+            SubscribeRequest::parse(&Some(vec![json!("newPendingTransactions")])).unwrap();
+        assert!(matches!(
+            request.kind,
+            SubscriptionKind::NewPendingTransactions
+        ));
+
+        assert!(SubscribeRequest::parse(&Some(vec![json!("syncing")])).is_err());
+    }
+
+    #[tokio::test]
+    async fn canonical_route_walks_reorged_and_new_blocks() {
+        let storage = Store::new("", EngineType::InMemory).unwrap();
+        let add_chain = |parent: &BlockHeader, length: u64, timestamp: u64| {
+            let mut parent = parent.clone();
+            (0..length)
+                .map(|_| {
+                    let header = BlockHeader {
+                        number: parent.number + 1,
+                        parent_hash: parent.compute_block_hash(),
+                        timestamp,
+                        ..Default::default()
+                    };
+                    parent = header.clone();
+                    header
+                })
+                .collect::<Vec<_>>()
+        };
+        let genesis = BlockHeader::default();
+        // 0 <- 1 <- 2 <- 3
+        //        <- 2' <- 3' <- 4'
+        let main = add_chain(&genesis, 3, 1);
+        let fork = add_chain(&main[0], 3, 2);
+        for header in std::iter::once(&genesis).chain(&main).chain(&fork) {
+            storage
+                .add_block_header(header.compute_block_hash(), header.clone())
+                .await
+                .unwrap();
+        }
+        let hashes = |headers: &[BlockHeader]| {
+            headers
+                .iter()
+                .map(BlockHeader::compute_block_hash)
+                .collect::<Vec<_>>()
+        };
+
+        let route = canonical_route(&storage, &main[2], &main[2], 10).unwrap();
+        assert_eq!(route, (vec![], vec![]));
+
+        // The head advanced several blocks at once
+        let route = canonical_route(&storage, &genesis, &main[2], 10).unwrap();
+        assert_eq!(route, (vec![], hashes(&main)));
+
+        // Reorg to a longer fork
+        let route = canonical_route(&storage, &main[2], &fork[2], 10).unwrap();
+        let mut removed = hashes(&main[1..]);
+        removed.reverse();
+        assert_eq!(route, (removed, hashes(&fork)));
+
+        // Rewind to an ancestor
+        let route = canonical_route(&storage, &fork[2], &main[0], 10).unwrap();
+        let mut removed = hashes(&fork);
+        removed.reverse();
+        assert_eq!(route, (removed, vec![]));
+
+        assert!(canonical_route(&storage, &main[2], &fork[2], 4).is_err());
+    }
+
+    #[test]
+    fn subscriptions_that_fall_behind_are_ended() {
+        let (notifications, mut receiver) = mpsc::channel(2);
+        assert!(notify(&notifications, "0x1", json!("0x2")));
+        assert!(notify(&notifications, "0x1", json!("0x3")));
+        assert!(!notify(&notifications, "0x1", json!("0x4")));
+
+        receiver.close();
+        assert!(!notify(&notifications, "0x1", json!("0x5")));
+    }
+
+    #[test]
+    fn notification_format() {
+        let value = notification("0x1", json!("0x2"));
+        assert_eq!(value["method"], "eth_subscription");
+        assert_eq!(value["params"]["subscription"], "0x1");
+        assert_eq!(value["params"]["result"], "0x2");
+    }
+}
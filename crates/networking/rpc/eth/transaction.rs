@@ -7,15 +7,14 @@ use crate::{
     rpc::{RpcApiContext, RpcHandler},
     types::{
         block_identifier::BlockIdentifier,
+        overrides::CallOverrides,
         transaction::{RpcTransaction, SendRawTransactionRequest},
     },
     utils::RpcErr,
 };
 use ethrex_blockchain::Blockchain;
 use ethrex_common::{
-    types::{
-        AccessListEntry, BlockHash, BlockHeader, BlockNumber, Fork, GenericTransaction, TxKind,
-    },
+    types::{AccessListEntry, BlockHash, BlockHeader, GenericTransaction, TxKind},
     H256, U256,
 };
 
@@ -35,6 +34,7 @@ pub const TRANSACTION_GAS: u64 = 21_000; // Per transaction not creating a contr
 pub struct CallRequest {
     transaction: GenericTransaction,
     block: Option<BlockIdentifier>,
+    overrides: CallOverrides,
 }
 
 pub struct GetTransactionByBlockNumberAndIndexRequest {
@@ -62,6 +62,7 @@ pub struct CreateAccessListRequest {
 pub struct EstimateGasRequest {
     pub transaction: GenericTransaction,
     pub block: Option<BlockIdentifier>,
+    pub overrides: CallOverrides,
 }
 
 pub struct GetRawTransaction {
@@ -86,9 +87,9 @@ impl RpcHandler for CallRequest {
         if params.is_empty() {
             return Err(RpcErr::BadParams("No params provided".to_owned()));
         }
-        if params.len() > 2 {
+        if params.len() > 4 {
             return Err(RpcErr::BadParams(format!(
-                "Expected one or two params and {} were provided",
+                "Expected one to four params and {} were provided",
                 params.len()
             )));
         }
@@ -100,6 +101,7 @@ impl RpcHandler for CallRequest {
         Ok(CallRequest {
             transaction: serde_json::from_value(params[0].clone())?,
             block,
+            overrides: CallOverrides::parse(params)?,
         })
     }
     async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
@@ -110,15 +112,13 @@ impl RpcHandler for CallRequest {
             // Block not found
             _ => return Ok(Value::Null),
         };
-        let chain_config = context.storage.get_chain_config()?;
-        let fork = chain_config.get_fork(header.timestamp);
         // Run transaction
         let result = simulate_tx(
             &self.transaction,
             &header,
             context.storage,
             context.blockchain,
-            fork,
+            &self.overrides,
         )?;
         serde_json::to_value(format!("0x{:#x}", result.output()))
             .map_err(|error| RpcErr::Internal(error.to_string()))
@@ -414,9 +414,9 @@ impl RpcHandler for EstimateGasRequest {
         if params.is_empty() {
             return Err(RpcErr::BadParams("No params provided".to_owned()));
         }
-        if params.len() > 2 {
+        if params.len() > 4 {
             return Err(RpcErr::BadParams(format!(
-                "Expected one or two params and {} were provided",
+                "Expected one to four params and {} were provided",
                 params.len()
             )));
         }
@@ -428,6 +428,7 @@ impl RpcHandler for EstimateGasRequest {
         Ok(EstimateGasRequest {
             transaction: serde_json::from_value(params[0].clone())?,
             block,
+            overrides: CallOverrides::parse(params)?,
         })
     }
     async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
@@ -441,12 +442,25 @@ impl RpcHandler for EstimateGasRequest {
             _ => return Ok(Value::Null),
         };
 
+        let sender_override = self
+            .overrides
+            .state
+            .as_ref()
+            .and_then(|state| state.get(&self.transaction.from));
         let transaction = match self.transaction.nonce {
             Some(_nonce) => self.transaction.clone(),
             None => {
-                let transaction_nonce = storage
-                    .get_nonce_by_account_address(block_header.number, self.transaction.from)
-                    .await?;
+                let transaction_nonce = match sender_override.and_then(|account| account.nonce) {
+                    Some(nonce) => Some(nonce),
+                    None => {
+                        storage
+                            .get_nonce_by_account_address(
+                                block_header.number,
+                                self.transaction.from,
+                            )
+                            .await?
+                    }
+                };
 
                 let mut cloned_transaction = self.transaction.clone();
                 cloned_transaction.nonce = transaction_nonce;
@@ -454,16 +468,19 @@ impl RpcHandler for EstimateGasRequest {
             }
         };
 
-        let chain_config = storage.get_chain_config()?;
-        let fork = chain_config.get_fork(block_header.timestamp);
-
         // If the transaction is a plain value transfer, short circuit estimation.
+        // Overridden recipients are always simulated, as their code may have been replaced.
         if let TxKind::Call(address) = transaction.to {
             let account_info = storage
                 .get_account_info(block_header.number, address)
                 .await?;
             let code = account_info.map(|info| storage.get_account_code(info.code_hash));
-            if code.is_none() {
+            let is_overridden = self
+                .overrides
+                .state
+                .as_ref()
+                .is_some_and(|state| state.contains_key(&address));
+            if code.is_none() && !is_overridden {
                 let mut value_transfer_transaction = transaction.clone();
                 value_transfer_transaction.gas = Some(TRANSACTION_GAS);
                 let result: Result<ExecutionResult, RpcErr> = simulate_tx(
@@ -471,7 +488,7 @@ impl RpcHandler for EstimateGasRequest {
                     &block_header,
                     storage.clone(),
                     blockchain.clone(),
-                    fork,
+                    &self.overrides,
                 );
                 if let Ok(ExecutionResult::Success { .. }) = result {
                     return serde_json::to_value(format!("{:#x}", TRANSACTION_GAS))
@@ -481,19 +498,23 @@ impl RpcHandler for EstimateGasRequest {
         }
 
         // Prepare binary search
+        let block_gas_limit = self.overrides.block_header(&block_header).gas_limit;
         let mut highest_gas_limit = match transaction.gas {
-            Some(gas) => gas.min(block_header.gas_limit),
-            None => block_header.gas_limit,
+            Some(gas) => gas.min(block_gas_limit),
+            None => block_gas_limit,
         };
 
         if transaction.gas_price != 0 {
-            highest_gas_limit = recap_with_account_balances(
-                highest_gas_limit,
-                &transaction,
-                storage,
-                block_header.number,
-            )
-            .await?;
+            let account_balance = match sender_override.and_then(|account| account.balance) {
+                Some(balance) => balance,
+                None => storage
+                    .get_account_info(block_header.number, transaction.from)
+                    .await?
+                    .map(|acc| acc.balance)
+                    .unwrap_or_default(),
+            };
+            highest_gas_limit =
+                recap_with_account_balance(highest_gas_limit, &transaction, account_balance);
         }
 
         // Check whether the execution is possible
@@ -504,7 +525,7 @@ impl RpcHandler for EstimateGasRequest {
             &block_header,
             storage.clone(),
             blockchain.clone(),
-            fork,
+            &self.overrides,
         )?;
 
         let gas_used = result.gas_used();
@@ -533,7 +554,7 @@ impl RpcHandler for EstimateGasRequest {
                 &block_header,
                 storage.clone(),
                 blockchain.clone(),
-                fork,
+                &self.overrides,
             );
             if let Ok(ExecutionResult::Success { .. }) = result {
                 highest_gas_limit = middle_gas_limit;
@@ -548,36 +569,37 @@ impl RpcHandler for EstimateGasRequest {
     }
 }
 
-async fn recap_with_account_balances(
+fn recap_with_account_balance(
     highest_gas_limit: u64,
     transaction: &GenericTransaction,
-    storage: &Store,
-    block_number: BlockNumber,
-) -> Result<u64, RpcErr> {
-    let account_balance = storage
-        .get_account_info(block_number, transaction.from)
-        .await?
-        .map(|acc| acc.balance)
-        .unwrap_or_default();
+    account_balance: U256,
+) -> u64 {
     let account_gas =
         account_balance.saturating_sub(transaction.value) / U256::from(transaction.gas_price);
-    Ok(highest_gas_limit.min(account_gas.as_u64()))
+    highest_gas_limit.min(account_gas.as_u64())
 }
 
+/// Simulates the transaction on top of the state of the given block,
+/// after applying the state and block overrides
 fn simulate_tx(
     transaction: &GenericTransaction,
     block_header: &BlockHeader,
     storage: Store,
     blockchain: Arc<Blockchain>,
-    fork: Fork,
+    overrides: &CallOverrides,
 ) -> Result<ExecutionResult, RpcErr> {
     let mut vm = Evm::new(
         blockchain.evm_engine,
         storage.clone(),
         block_header.compute_block_hash(),
     );
+    if let Some(state_override) = &overrides.state {
+        vm.apply_state_override(state_override)?;
+    }
+    let block_header = overrides.block_header(block_header);
+    let fork = storage.get_chain_config()?.get_fork(block_header.timestamp);
 
-    match vm.simulate_tx_from_generic(transaction, block_header, fork)? {
+    match vm.simulate_tx_from_generic(transaction, &block_header, fork)? {
         ExecutionResult::Revert {
             gas_used: _,
             output,
@@ -642,20 +664,10 @@ impl RpcHandler for SendRawTransactionRequest {
     }
 
     async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
-        let hash = if let SendRawTransactionRequest::EIP4844(wrapped_blob_tx) = self {
-            context
-                .blockchain
-                .add_blob_transaction_to_pool(
-                    wrapped_blob_tx.tx.clone(),
-                    wrapped_blob_tx.blobs_bundle.clone(),
-                )
-                .await
-        } else {
-            context
-                .blockchain
-                .add_transaction_to_pool(self.to_transaction())
-                .await
-        }?;
+        let hash = context
+            .blockchain
+            .add_local_transaction_to_pool(self.to_p2p_transaction())
+            .await?;
         serde_json::to_value(format!("{:#x}", hash))
             .map_err(|error| RpcErr::Internal(error.to_string()))
     }
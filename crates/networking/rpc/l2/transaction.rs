@@ -189,6 +189,7 @@ impl RpcHandler for SponsoredTx {
         let estimate_gas_request = EstimateGasRequest {
             transaction: generic,
             block: None,
+            overrides: Default::default(),
         }
         .handle(context.clone())
         .await?;
@@ -2,12 +2,14 @@ mod admin;
 mod authentication;
 #[cfg(feature = "based")]
 mod based;
+mod debug;
 mod engine;
 mod eth;
 #[cfg(feature = "l2")]
 mod l2;
 mod net;
 mod rpc;
+mod txpool;
 mod web3;
 
 pub mod clients;
@@ -15,4 +17,5 @@ pub mod types;
 pub mod utils;
 pub use clients::{EngineClient, EthClient};
 
+pub use eth::logs::LogQueryLimits;
 pub use rpc::start_api;
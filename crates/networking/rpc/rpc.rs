@@ -1,5 +1,10 @@
 use crate::authentication::authenticate;
+use crate::debug::chain::SetHeadRequest;
+use crate::debug::tracing::{
+    TraceBlockByHashRequest, TraceBlockByNumberRequest, TraceCallRequest, TraceTransactionRequest,
+};
 use crate::engine::{
+    blobs::GetBlobsV1Request,
     exchange_transition_config::ExchangeTransitionConfigV1Req,
     fork_choice::{ForkChoiceUpdatedV1, ForkChoiceUpdatedV2, ForkChoiceUpdatedV3},
     payload::{
@@ -23,13 +28,19 @@ use crate::eth::{
     fee_market::FeeHistoryRequest,
     filter::{self, ActiveFilters, DeleteFilterRequest, FilterChangesRequest, NewFilterRequest},
     gas_price::GasPrice,
-    logs::LogsFilter,
+    logs::{LogQueryLimits, LogsFilter},
+    simulate::SimulateV1Request,
+    subscription::{
+        ActiveSubscriptions, NotificationSender, SubscribeRequest, UnsubscribeRequest,
+        NOTIFICATION_BUFFER_SIZE,
+    },
     transaction::{
         CallRequest, CreateAccessListRequest, EstimateGasRequest, GetRawTransaction,
         GetTransactionByBlockHashAndIndexRequest, GetTransactionByBlockNumberAndIndexRequest,
         GetTransactionByHashRequest, GetTransactionReceiptRequest,
     },
 };
+use crate::txpool::{TxPoolContent, TxPoolContentFrom, TxPoolInspect, TxPoolStatus};
 use crate::types::transaction::SendRawTransactionRequest;
 use crate::utils::{
     RpcErr, RpcErrorMetadata, RpcErrorResponse, RpcNamespace, RpcRequest, RpcRequestId,
@@ -39,7 +50,10 @@ use crate::{admin, net};
 use crate::{eth, web3};
 #[cfg(feature = "based")]
 use crate::{EngineClient, EthClient};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::extract::State;
+use axum::response::Response;
+use axum::routing::any;
 use axum::{routing::post, Json, Router};
 use axum_extra::{
     headers::{authorization::Bearer, Authorization},
@@ -63,6 +77,7 @@ use std::{
     time::Duration,
 };
 use tokio::net::TcpListener;
+use tokio::sync::mpsc;
 use tower_http::cors::CorsLayer;
 use tracing::info;
 
@@ -93,6 +108,7 @@ pub struct RpcApiContext {
     pub local_node_record: NodeRecord,
     pub active_filters: ActiveFilters,
     pub syncer: Arc<SyncManager>,
+    pub log_query_limits: LogQueryLimits,
     #[cfg(feature = "based")]
     pub gateway_eth_client: EthClient,
     #[cfg(feature = "based")]
@@ -139,6 +155,7 @@ pub const FILTER_DURATION: Duration = {
 #[allow(clippy::too_many_arguments)]
 pub async fn start_api(
     http_addr: SocketAddr,
+    ws_addr: Option<SocketAddr>,
     authrpc_addr: SocketAddr,
     storage: Store,
     blockchain: Arc<Blockchain>,
@@ -146,6 +163,7 @@ pub async fn start_api(
     local_p2p_node: Node,
     local_node_record: NodeRecord,
     syncer: SyncManager,
+    log_query_limits: LogQueryLimits,
     #[cfg(feature = "based")] gateway_eth_client: EthClient,
     #[cfg(feature = "based")] gateway_auth_client: EngineClient,
     #[cfg(feature = "based")] gateway_pubkey: Public,
@@ -163,6 +181,7 @@ pub async fn start_api(
         local_node_record,
         active_filters: active_filters.clone(),
         syncer: Arc::new(syncer),
+        log_query_limits,
         #[cfg(feature = "based")]
         gateway_eth_client,
         #[cfg(feature = "based")]
@@ -203,10 +222,25 @@ pub async fn start_api(
         .into_future();
     info!("Starting HTTP server at {http_addr}");
 
+    let ws_service_context = service_context.clone();
+    let ws_server = async move {
+        let Some(ws_addr) = ws_addr else {
+            return Ok(());
+        };
+        let ws_router = Router::new()
+            .route("/", any(handle_ws_upgrade))
+            .with_state(ws_service_context);
+        let ws_listener = TcpListener::bind(ws_addr).await?;
+        info!("Starting WS server at {ws_addr}");
+        axum::serve(ws_listener, ws_router)
+            .with_graceful_shutdown(shutdown_signal())
+            .await
+    };
+
     if cfg!(any(feature = "l2", feature = "based")) {
         info!("Not starting Auth-RPC server. The address passed as argument is {authrpc_addr}");
 
-        let _ = tokio::try_join!(http_server)
+        let _ = tokio::try_join!(http_server, ws_server)
             .inspect_err(|e| info!("Error shutting down servers: {e:?}"));
     } else {
         let authrpc_handler =
@@ -220,7 +254,7 @@ pub async fn start_api(
             .into_future();
         info!("Starting Auth-RPC server at {authrpc_addr}");
 
-        let _ = tokio::try_join!(authrpc_server, http_server)
+        let _ = tokio::try_join!(authrpc_server, http_server, ws_server)
             .inspect_err(|e| info!("Error shutting down servers: {e:?}"));
     }
 }
@@ -256,6 +290,111 @@ async fn handle_http_request(
     Json(res)
 }
 
+async fn handle_ws_upgrade(
+    ws: WebSocketUpgrade,
+    State(service_context): State<RpcApiContext>,
+) -> Response {
+    ws.on_upgrade(|socket| handle_ws_connection(socket, service_context))
+}
+
+/// Serves the requests of a websocket connection and pushes the notifications
+/// of its subscriptions until the client disconnects.
+async fn handle_ws_connection(mut socket: WebSocket, service_context: RpcApiContext) {
+    let mut subscriptions = ActiveSubscriptions::new();
+    let (notification_sender, mut notification_receiver) = mpsc::channel(NOTIFICATION_BUFFER_SIZE);
+    loop {
+        let reply = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(body))) => {
+                    handle_ws_request(
+                        body.as_str(),
+                        service_context.clone(),
+                        &mut subscriptions,
+                        &notification_sender,
+                    )
+                    .await
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum, binary messages are not supported.
+                Some(Ok(_)) => continue,
+            },
+            Some(notification) = notification_receiver.recv() => notification,
+        };
+        if socket
+            .send(Message::Text(reply.to_string().into()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+        // A subscription only ends on its own when the client falls behind its notifications,
+        // the connection is closed so the client notices and subscribes again
+        if subscriptions.values().any(|task| task.is_finished()) {
+            break;
+        }
+    }
+    for (_, task) in subscriptions {
+        task.abort();
+    }
+}
+
+async fn handle_ws_request(
+    body: &str,
+    service_context: RpcApiContext,
+    subscriptions: &mut ActiveSubscriptions,
+    notification_sender: &NotificationSender,
+) -> Value {
+    match serde_json::from_str::<RpcRequestWrapper>(body) {
+        Ok(RpcRequestWrapper::Single(request)) => {
+            let res = map_ws_requests(
+                &request,
+                service_context,
+                subscriptions,
+                notification_sender,
+            )
+            .await;
+            rpc_response(request.id, res)
+        }
+        Ok(RpcRequestWrapper::Multiple(requests)) => {
+            let mut responses = Vec::new();
+            for req in requests {
+                let res = map_ws_requests(
+                    &req,
+                    service_context.clone(),
+                    subscriptions,
+                    notification_sender,
+                )
+                .await;
+                responses.push(rpc_response(req.id, res));
+            }
+            serde_json::to_value(responses).unwrap()
+        }
+        Err(_) => rpc_response(
+            RpcRequestId::String("".to_string()),
+            Err(RpcErr::BadParams("Invalid request body".to_string())),
+        ),
+    }
+}
+
+/// Handle requests sent through a websocket connection, which on top of the http
+/// ones can manage subscriptions
+async fn map_ws_requests(
+    req: &RpcRequest,
+    context: RpcApiContext,
+    subscriptions: &mut ActiveSubscriptions,
+    notification_sender: &NotificationSender,
+) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "eth_subscribe" => SubscribeRequest::parse(&req.params)?.handle(
+            context,
+            subscriptions,
+            notification_sender.clone(),
+        ),
+        "eth_unsubscribe" => UnsubscribeRequest::parse(&req.params)?.handle(subscriptions),
+        _ => map_http_requests(req, context).await,
+    }
+}
+
 pub async fn handle_authrpc_request(
     State(service_context): State<RpcApiContext>,
     auth_header: Option<TypedHeader<Authorization<Bearer>>>,
@@ -288,6 +427,7 @@ pub async fn map_http_requests(req: &RpcRequest, context: RpcApiContext) -> Resu
         Ok(RpcNamespace::Debug) => map_debug_requests(req, context).await,
         Ok(RpcNamespace::Web3) => map_web3_requests(req, context),
         Ok(RpcNamespace::Net) => map_net_requests(req, context),
+        Ok(RpcNamespace::TxPool) => map_txpool_requests(req, context).await,
         Ok(RpcNamespace::Engine) => Err(RpcErr::Internal(
             "Engine namespace not allowed in map_http_requests".to_owned(),
         )),
@@ -307,6 +447,10 @@ pub async fn map_authrpc_requests(
     match req.namespace() {
         Ok(RpcNamespace::Engine) => map_engine_requests(req, context).await,
         Ok(RpcNamespace::Eth) => map_eth_requests(req, context).await,
+        // Rewinding the chain is only exposed to authenticated callers
+        Ok(RpcNamespace::Debug) if req.method == "debug_setHead" => {
+            SetHeadRequest::call(req, context).await
+        }
         _ => Err(RpcErr::MethodNotFound(req.method.clone())),
     }
 }
@@ -338,6 +482,7 @@ pub async fn map_eth_requests(req: &RpcRequest, context: RpcApiContext) -> Resul
         "eth_createAccessList" => CreateAccessListRequest::call(req, context).await,
         "eth_blockNumber" => BlockNumberRequest::call(req, context).await,
         "eth_call" => CallRequest::call(req, context).await,
+        "eth_simulateV1" => SimulateV1Request::call(req, context).await,
         "eth_blobBaseFee" => GetBlobBaseFee::call(req, context).await,
         "eth_getTransactionCount" => GetTransactionCountRequest::call(req, context).await,
         "eth_feeHistory" => FeeHistoryRequest::call(req, context).await,
@@ -350,7 +495,13 @@ pub async fn map_eth_requests(req: &RpcRequest, context: RpcApiContext) -> Resul
             DeleteFilterRequest::stateful_call(req, context.storage, context.active_filters)
         }
         "eth_getFilterChanges" => {
-            FilterChangesRequest::stateful_call(req, context.storage, context.active_filters).await
+            FilterChangesRequest::stateful_call(
+                req,
+                context.storage,
+                context.active_filters,
+                context.log_query_limits,
+            )
+            .await
         }
         "eth_sendRawTransaction" => {
             cfg_if::cfg_if! {
@@ -376,6 +527,10 @@ pub async fn map_debug_requests(req: &RpcRequest, context: RpcApiContext) -> Res
         "debug_getRawBlock" => GetRawBlockRequest::call(req, context).await,
         "debug_getRawTransaction" => GetRawTransaction::call(req, context).await,
         "debug_getRawReceipts" => GetRawReceipts::call(req, context).await,
+        "debug_traceTransaction" => TraceTransactionRequest::call(req, context).await,
+        "debug_traceBlockByNumber" => TraceBlockByNumberRequest::call(req, context).await,
+        "debug_traceBlockByHash" => TraceBlockByHashRequest::call(req, context).await,
+        "debug_traceCall" => TraceCallRequest::call(req, context).await,
         unknown_debug_method => Err(RpcErr::MethodNotFound(unknown_debug_method.to_owned())),
     }
 }
@@ -430,6 +585,7 @@ pub async fn map_engine_requests(
         "engine_getPayloadBodiesByRangeV1" => {
             GetPayloadBodiesByRangeV1Request::call(req, context).await
         }
+        "engine_getBlobsV1" => GetBlobsV1Request::call(req, context).await,
         unknown_engine_method => Err(RpcErr::MethodNotFound(unknown_engine_method.to_owned())),
     }
 }
@@ -460,6 +616,19 @@ pub fn map_net_requests(req: &RpcRequest, contex: RpcApiContext) -> Result<Value
     }
 }
 
+pub async fn map_txpool_requests(
+    req: &RpcRequest,
+    context: RpcApiContext,
+) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "txpool_status" => TxPoolStatus::call(req, context).await,
+        "txpool_content" => TxPoolContent::call(req, context).await,
+        "txpool_contentFrom" => TxPoolContentFrom::call(req, context).await,
+        "txpool_inspect" => TxPoolInspect::call(req, context).await,
+        unknown_txpool_method => Err(RpcErr::MethodNotFound(unknown_txpool_method.to_owned())),
+    }
+}
+
 #[cfg(feature = "based")]
 pub fn map_based_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
     match req.method.as_str() {
@@ -505,8 +674,8 @@ mod tests {
     use crate::utils::test_utils::{example_local_node_record, example_p2p_node};
     use ethrex_blockchain::Blockchain;
     use ethrex_common::{
-        types::{ChainConfig, Genesis},
-        H160,
+        types::{BlobsBundle, ChainConfig, Genesis, BYTES_PER_BLOB},
+        H160, H256,
     };
     use ethrex_storage::{EngineType, Store};
     use sha3::{Digest, Keccak256};
@@ -547,6 +716,7 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
             #[cfg(feature = "based")]
             gateway_eth_client: EthClient::new(""),
             #[cfg(feature = "based")]
@@ -646,6 +816,7 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
             #[cfg(feature = "based")]
             gateway_eth_client: EthClient::new(""),
             #[cfg(feature = "based")]
@@ -690,6 +861,7 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
             #[cfg(feature = "based")]
             gateway_eth_client: EthClient::new(""),
             #[cfg(feature = "based")]
@@ -717,6 +889,133 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn simulate_v1_chains_blocks() {
+        // The first block funds 0xbeef through two transfers, the last one spends them
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"eth_simulateV1","params":[{
+            "blockStateCalls": [
+                {
+                    "stateOverrides": {"0x000000000000000000000000000000000000dead": {"balance": "0x1000"}},
+                    "calls": [
+                        {"from": "0x000000000000000000000000000000000000dead", "to": "0x000000000000000000000000000000000000beef", "value": "0x100"},
+                        {"from": "0x000000000000000000000000000000000000dead", "to": "0x000000000000000000000000000000000000beef", "value": "0x100"}
+                    ]
+                },
+                {
+                    "blockOverrides": {"number": "0x3"},
+                    "calls": [
+                        {"from": "0x000000000000000000000000000000000000beef", "to": "0x000000000000000000000000000000000000cafe", "value": "0x150"}
+                    ]
+                }
+            ],
+            "traceTransfers": true
+        }, "latest"]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let blockchain = Arc::new(Blockchain::default_with_store(storage.clone()));
+        storage
+            .add_initial_state(read_execution_api_genesis_file())
+            .await
+            .expect("Failed to add genesis block to DB");
+        let context = RpcApiContext {
+            local_p2p_node: example_p2p_node(),
+            local_node_record: example_local_node_record(),
+            storage,
+            blockchain,
+            jwt_secret: Default::default(),
+            active_filters: Default::default(),
+            syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
+            #[cfg(feature = "based")]
+            gateway_eth_client: EthClient::new(""),
+            #[cfg(feature = "based")]
+            gateway_auth_client: EngineClient::new("", Bytes::default()),
+            #[cfg(feature = "based")]
+            gateway_pubkey: Default::default(),
+            #[cfg(feature = "l2")]
+            valid_delegation_addresses: Vec::new(),
+            #[cfg(feature = "l2")]
+            sponsor_pk: SecretKey::new(&mut rand::thread_rng()),
+        };
+        let blocks = map_http_requests(&request, context).await.unwrap();
+        let blocks = blocks.as_array().unwrap();
+
+        // The gap before block 3 is filled with an empty block
+        let numbers: Vec<_> = blocks.iter().map(|block| block["number"].clone()).collect();
+        assert_eq!(numbers, ["0x1", "0x2", "0x3"]);
+        assert_eq!(blocks[0]["calls"].as_array().unwrap().len(), 2);
+        assert!(blocks[1]["calls"].as_array().unwrap().is_empty());
+        assert_eq!(blocks[1]["parentHash"], blocks[0]["hash"]);
+
+        let call = &blocks[2]["calls"][0];
+        assert_eq!(call["status"], "0x1");
+        assert_eq!(call["gasUsed"], "0x5208");
+        let transfer = &call["logs"][0];
+        assert_eq!(
+            transfer["address"],
+            "0xeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee"
+        );
+        assert_eq!(
+            transfer["topics"][2],
+            "0x000000000000000000000000000000000000000000000000000000000000cafe"
+        );
+        assert_eq!(transfer["blockHash"], blocks[2]["hash"]);
+    }
+
+    #[tokio::test]
+    async fn get_blobs_v1_request() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let blockchain = Arc::new(Blockchain::default_with_store(storage.clone()));
+        let bundle = BlobsBundle {
+            blobs: vec![[1; BYTES_PER_BLOB]],
+            commitments: vec![[2; 48]],
+            proofs: vec![[3; 48]],
+        };
+        let versioned_hash = bundle.generate_versioned_hashes()[0];
+        blockchain
+            .mempool
+            .add_blobs_bundle(H256::random(), bundle)
+            .unwrap();
+        let context = RpcApiContext {
+            local_p2p_node: example_p2p_node(),
+            local_node_record: example_local_node_record(),
+            storage,
+            blockchain,
+            jwt_secret: Default::default(),
+            active_filters: Default::default(),
+            syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
+            #[cfg(feature = "based")]
+            gateway_eth_client: EthClient::new(""),
+            #[cfg(feature = "based")]
+            gateway_auth_client: EngineClient::new("", Bytes::default()),
+            #[cfg(feature = "based")]
+            gateway_pubkey: Default::default(),
+            #[cfg(feature = "l2")]
+            valid_delegation_addresses: Vec::new(),
+            #[cfg(feature = "l2")]
+            sponsor_pk: SecretKey::new(&mut rand::thread_rng()),
+        };
+        let request = RpcRequest {
+            method: "engine_getBlobsV1".to_string(),
+            params: Some(vec![serde_json::json!([versioned_hash, H256::zero()])]),
+            ..Default::default()
+        };
+        let result = map_engine_requests(&request, context).await.unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!([
+                {
+                    "blob": format!("0x{}", hex::encode([1; BYTES_PER_BLOB])),
+                    "proof": format!("0x{}", hex::encode([3; 48])),
+                },
+                null
+            ])
+        );
+    }
+
     fn example_chain_config() -> ChainConfig {
         ChainConfig {
             chain_id: 3151908_u64,
@@ -768,6 +1067,7 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(SyncManager::dummy()),
+            log_query_limits: Default::default(),
             #[cfg(feature = "based")]
             gateway_eth_client: EthClient::new(""),
             #[cfg(feature = "based")]
@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, HashMap};
+
+use ethrex_blockchain::mempool::Mempool;
+use ethrex_common::{
+    types::{MempoolTransaction, Transaction, TxKind},
+    Address,
+};
+use keccak_hash::keccak;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    rpc::{RpcApiContext, RpcHandler},
+    types::transaction::RpcTransaction,
+    utils::RpcErr,
+};
+
+pub struct TxPoolStatus;
+pub struct TxPoolContent;
+pub struct TxPoolInspect;
+pub struct TxPoolContentFrom {
+    pub address: Address,
+}
+
+/// Transactions of a sender indexed by nonce.
+/// Like in geth, nonces are formatted as decimal strings and sorted lexicographically
+type NonceMap<T> = BTreeMap<String, T>;
+
+/// Transactions indexed by sender and nonce.
+/// Like in geth, senders are formatted with their EIP-55 checksum
+type SenderMap<T> = BTreeMap<String, NonceMap<T>>;
+
+#[derive(Serialize)]
+struct PoolContent<T> {
+    pending: T,
+    queued: T,
+}
+
+impl RpcHandler for TxPoolStatus {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {})
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let content = pool_content(&context.blockchain.mempool)?;
+        let count = |txs: HashMap<Address, Vec<MempoolTransaction>>| {
+            format!("{:#x}", txs.values().map(Vec::len).sum::<usize>())
+        };
+        let status = PoolContent {
+            pending: count(content.pending),
+            queued: count(content.queued),
+        };
+        serde_json::to_value(status).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for TxPoolContent {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {})
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let content = pool_content(&context.blockchain.mempool)?;
+        let content = PoolContent {
+            pending: group_by_sender(content.pending, rpc_transaction),
+            queued: group_by_sender(content.queued, rpc_transaction),
+        };
+        serde_json::to_value(content).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for TxPoolContentFrom {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams(format!(
+                "Expected one param and {} were provided",
+                params.len()
+            )));
+        };
+        Ok(TxPoolContentFrom {
+            address: serde_json::from_value(params[0].clone())?,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let mut content = pool_content(&context.blockchain.mempool)?;
+        let sender_content = |txs: &mut HashMap<Address, Vec<MempoolTransaction>>| {
+            index_by_nonce(
+                txs.remove(&self.address).unwrap_or_default(),
+                rpc_transaction,
+            )
+        };
+        let content = PoolContent {
+            pending: sender_content(&mut content.pending),
+            queued: sender_content(&mut content.queued),
+        };
+        serde_json::to_value(content).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for TxPoolInspect {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {})
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let content = pool_content(&context.blockchain.mempool)?;
+        let content = PoolContent {
+            pending: group_by_sender(content.pending, inspect_summary),
+            queued: group_by_sender(content.queued, inspect_summary),
+        };
+        serde_json::to_value(content).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+/// Returns the pending and queued transactions of the mempool grouped by sender
+fn pool_content(
+    mempool: &Mempool,
+) -> Result<PoolContent<HashMap<Address, Vec<MempoolTransaction>>>, RpcErr> {
+    Ok(PoolContent {
+        pending: mempool.filter_transactions_with_filter_fn(&|_| true)?,
+        queued: mempool.queued_transactions()?,
+    })
+}
+
+fn group_by_sender<T>(
+    txs_by_sender: HashMap<Address, Vec<MempoolTransaction>>,
+    format_tx: fn(MempoolTransaction) -> T,
+) -> SenderMap<T> {
+    txs_by_sender
+        .into_iter()
+        .map(|(sender, txs)| (to_checksum_address(&sender), index_by_nonce(txs, format_tx)))
+        .collect()
+}
+
+fn index_by_nonce<T>(
+    txs: Vec<MempoolTransaction>,
+    format_tx: fn(MempoolTransaction) -> T,
+) -> NonceMap<T> {
+    txs.into_iter()
+        .map(|tx| (tx.nonce().to_string(), format_tx(tx)))
+        .collect()
+}
+
+fn rpc_transaction(tx: MempoolTransaction) -> RpcTransaction {
+    let sender = tx.sender();
+    RpcTransaction::build_pending(tx.into(), sender)
+}
+
+/// Summarizes a transaction the way geth's `txpool_inspect` does:
+/// `<to>: <value> wei + <gas limit> gas × <gas price> wei`
+fn inspect_summary(tx: MempoolTransaction) -> String {
+    let tx: Transaction = tx.into();
+    let recipient = match tx.to() {
+        TxKind::Call(to) => to_checksum_address(&to),
+        TxKind::Create => "contract creation".to_owned(),
+    };
+    format!(
+        "{recipient}: {} wei + {} gas × {} wei",
+        tx.value(),
+        tx.gas_limit(),
+        tx.gas_price()
+    )
+}
+
+/// Formats an address with the mixed-case checksum defined in EIP-55
+fn to_checksum_address(address: &Address) -> String {
+    let address = hex::encode(address);
+    let hash = keccak(address.as_bytes());
+    let checksummed: String = address
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{checksummed}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::{types::LegacyTransaction, H256, U256};
+    use std::str::FromStr;
+
+    fn mempool_transaction(sender: Address, nonce: u64, to: TxKind) -> (H256, MempoolTransaction) {
+        let tx = Transaction::LegacyTransaction(LegacyTransaction {
+            nonce,
+            gas_price: 1_000_000_000,
+            gas: 21_000,
+            to,
+            value: U256::from(1000),
+            ..Default::default()
+        });
+        (tx.compute_hash(), MempoolTransaction::new(tx, sender))
+    }
+
+    #[test]
+    fn checksum_address() {
+        let address = Address::from_str("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        assert_eq!(
+            to_checksum_address(&address),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+    }
+
+    #[test]
+    fn inspect_transactions() {
+        let sender = Address::from_low_u64_be(1);
+        let to = Address::from_str("0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359").unwrap();
+        let (_, call) = mempool_transaction(sender, 0, TxKind::Call(to));
+        let (_, create) = mempool_transaction(sender, 1, TxKind::Create);
+        assert_eq!(
+            inspect_summary(call),
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359: 1000 wei + 21000 gas × 1000000000 wei"
+        );
+        assert_eq!(
+            inspect_summary(create),
+            "contract creation: 1000 wei + 21000 gas × 1000000000 wei"
+        );
+    }
+
+    #[test]
+    fn content_is_grouped_by_sender_and_nonce() {
+        let mempool = Mempool::new();
+        let sender = Address::from_str("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        let to = Address::from_low_u64_be(2);
+        for nonce in [0, 1, 2, 10] {
+            let (hash, tx) = mempool_transaction(sender, nonce, TxKind::Call(to));
+            mempool.insert_transaction(hash, tx, 0).unwrap();
+        }
+
+        let content = pool_content(&mempool).unwrap();
+        let pending = group_by_sender(content.pending, inspect_summary);
+        let queued = group_by_sender(content.queued, inspect_summary);
+        let sender = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert_eq!(
+            pending[sender].keys().collect::<Vec<_>>(),
+            vec!["0", "1", "2"]
+        );
+        assert_eq!(queued[sender].keys().collect::<Vec<_>>(), vec!["10"]);
+    }
+}
@@ -2,7 +2,7 @@ use super::transaction::RpcTransaction;
 use ethrex_common::{
     serde_utils,
     types::{Block, BlockBody, BlockHash, BlockHeader, BlockNumber, Withdrawal},
-    H256,
+    Address, H256,
 };
 use ethrex_rlp::encode::RLPEncode;
 
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcBlock {
-    hash: H256,
+    pub hash: H256,
     #[serde(with = "serde_utils::u64::hex_str")]
     size: u64,
     #[serde(flatten)]
@@ -55,11 +55,46 @@ impl RpcBlock {
         let body_wrapper = if full_transactions {
             BlockBodyWrapper::Full(FullBlockBody::from_body(body, header.number, hash))
         } else {
-            BlockBodyWrapper::OnlyHashes(OnlyHashesBlockBody {
-                transactions: body.transactions.iter().map(|t| t.compute_hash()).collect(),
+            BlockBodyWrapper::OnlyHashes(OnlyHashesBlockBody::from_body(body))
+        };
+
+        RpcBlock {
+            hash,
+            size: size as u64,
+            header,
+            body: body_wrapper,
+        }
+    }
+
+    /// Same as [RpcBlock::build] but with the senders of the transactions given,
+    /// used for blocks of unsigned transactions such as the simulated ones
+    pub fn build_with_senders(
+        header: BlockHeader,
+        body: BlockBody,
+        senders: &[Address],
+        hash: H256,
+        full_transactions: bool,
+    ) -> RpcBlock {
+        let size = Block::new(header.clone(), body.clone())
+            .encode_to_vec()
+            .len();
+        let body_wrapper = if full_transactions {
+            let transactions = body
+                .transactions
+                .into_iter()
+                .zip(senders)
+                .enumerate()
+                .map(|(index, (tx, sender))| {
+                    RpcTransaction::build_with_sender(tx, *sender, header.number, hash, index)
+                })
+                .collect();
+            BlockBodyWrapper::Full(FullBlockBody {
+                transactions,
                 uncles: body.ommers,
                 withdrawals: body.withdrawals.unwrap_or_default(),
             })
+        } else {
+            BlockBodyWrapper::OnlyHashes(OnlyHashesBlockBody::from_body(body))
         };
 
         RpcBlock {
@@ -71,6 +106,16 @@ impl RpcBlock {
     }
 }
 
+impl OnlyHashesBlockBody {
+    fn from_body(body: BlockBody) -> OnlyHashesBlockBody {
+        OnlyHashesBlockBody {
+            transactions: body.transactions.iter().map(|t| t.compute_hash()).collect(),
+            uncles: body.ommers,
+            withdrawals: body.withdrawals.unwrap_or_default(),
+        }
+    }
+}
+
 impl FullBlockBody {
     pub fn from_body(
         body: BlockBody,
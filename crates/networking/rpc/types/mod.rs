@@ -2,6 +2,7 @@ pub mod account_proof;
 pub mod block;
 pub mod block_identifier;
 pub mod fork_choice;
+pub mod overrides;
 pub mod payload;
 pub mod receipt;
 pub mod transaction;
@@ -0,0 +1,147 @@
+use ethrex_common::{types::BlockHeader, Address, H256};
+use ethrex_vm::StateOverride;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use crate::utils::RpcErr;
+
+/// Overrides of the fields of the block a call is simulated on.
+/// Follows the format of geth's `blockOverrides` parameter.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverrides {
+    #[serde(default, with = "ethrex_common::serde_utils::u64::hex_str_opt")]
+    pub number: Option<u64>,
+    #[serde(default, with = "ethrex_common::serde_utils::u64::hex_str_opt")]
+    pub time: Option<u64>,
+    #[serde(default, with = "ethrex_common::serde_utils::u64::hex_str_opt")]
+    pub gas_limit: Option<u64>,
+    #[serde(default, alias = "coinbase")]
+    pub fee_recipient: Option<Address>,
+    #[serde(default)]
+    pub prev_randao: Option<H256>,
+    #[serde(default, with = "ethrex_common::serde_utils::u64::hex_str_opt")]
+    pub base_fee_per_gas: Option<u64>,
+}
+
+impl BlockOverrides {
+    /// Returns a copy of the header with the overridden fields replaced
+    pub fn apply(&self, header: &BlockHeader) -> BlockHeader {
+        let mut header = header.clone();
+        if let Some(number) = self.number {
+            header.number = number;
+        }
+        if let Some(time) = self.time {
+            header.timestamp = time;
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            header.gas_limit = gas_limit;
+        }
+        if let Some(fee_recipient) = self.fee_recipient {
+            header.coinbase = fee_recipient;
+        }
+        if let Some(prev_randao) = self.prev_randao {
+            header.prev_randao = prev_randao;
+        }
+        if let Some(base_fee_per_gas) = self.base_fee_per_gas {
+            header.base_fee_per_gas = Some(base_fee_per_gas);
+        }
+        header
+    }
+}
+
+/// State and block overrides of a simulated call, given as the optional
+/// third and fourth params of `eth_call` and `eth_estimateGas`
+#[derive(Debug, Clone, Default)]
+pub struct CallOverrides {
+    pub state: Option<StateOverride>,
+    pub block: Option<BlockOverrides>,
+}
+
+impl CallOverrides {
+    pub fn parse(params: &[Value]) -> Result<Self, RpcErr> {
+        Ok(Self {
+            state: parse_optional_param(params, 2, "stateOverride")?,
+            block: parse_optional_param(params, 3, "blockOverrides")?,
+        })
+    }
+
+    /// Returns the header of the block the call is simulated on, with the block overrides applied
+    pub fn block_header(&self, header: &BlockHeader) -> BlockHeader {
+        match &self.block {
+            Some(block_overrides) => block_overrides.apply(header),
+            None => header.clone(),
+        }
+    }
+}
+
+/// Parses the param at `index`, which is `None` if it is missing or null
+fn parse_optional_param<T: DeserializeOwned>(
+    params: &[Value],
+    index: usize,
+    name: &str,
+) -> Result<Option<T>, RpcErr> {
+    match params.get(index) {
+        Some(param) => serde_json::from_value::<Option<T>>(param.clone())
+            .map_err(|_| RpcErr::WrongParam(name.to_string())),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::U256;
+    use ethrex_vm::AccountOverride;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[test]
+    fn parse_call_overrides() {
+        let address = Address::from_str("0x000000000000000000000000000000000000dead").unwrap();
+        let params = [
+            json!({}),
+            json!("latest"),
+            json!({
+                "0x000000000000000000000000000000000000dEaD": {
+                    "balance": "0x10",
+                    "nonce": "0x2",
+                    "code": "0x6000",
+                    "stateDiff": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001":
+                            "0x00000000000000000000000000000000000000000000000000000000000000ff"
+                    }
+                }
+            }),
+            json!({ "number": "0x100", "time": "0x5", "coinbase": address, "baseFeePerGas": "0x7" }),
+        ];
+        let overrides = CallOverrides::parse(&params).unwrap();
+
+        let expected = AccountOverride {
+            balance: Some(U256::from(0x10)),
+            nonce: Some(2),
+            code: Some(vec![0x60, 0x00].into()),
+            state: None,
+            state_diff: Some(
+                [(H256::from_low_u64_be(1), H256::from_low_u64_be(0xff))]
+                    .into_iter()
+                    .collect(),
+            ),
+        };
+        assert_eq!(overrides.state.as_ref().unwrap()[&address], expected);
+
+        let header = overrides.block_header(&BlockHeader::default());
+        assert_eq!(header.number, 0x100);
+        assert_eq!(header.timestamp, 5);
+        assert_eq!(header.coinbase, address);
+        assert_eq!(header.base_fee_per_gas, Some(7));
+        assert_eq!(header.gas_limit, BlockHeader::default().gas_limit);
+    }
+
+    #[test]
+    fn missing_and_null_overrides_are_ignored() {
+        let overrides = CallOverrides::parse(&[json!({}), json!("latest"), Value::Null]).unwrap();
+        assert!(overrides.state.is_none() && overrides.block.is_none());
+        assert!(CallOverrides::parse(&[json!({}), json!("latest"), json!(1)]).is_err());
+    }
+}
@@ -1,8 +1,10 @@
 use ethrex_common::{
     constants::GAS_PER_BLOB,
     serde_utils,
-    types::{BlockHash, BlockHeader, BlockNumber, Log, Receipt, Transaction, TxKind, TxType},
-    Address, Bloom, Bytes, H256,
+    types::{
+        BlockHash, BlockHeader, BlockNumber, L1FeeParams, Log, Receipt, Transaction, TxKind, TxType,
+    },
+    Address, Bloom, Bytes, H256, U256,
 };
 use ethrex_vm::create_contract_address;
 
@@ -159,6 +161,9 @@ pub struct RpcReceiptTxInfo {
         default = "Option::default"
     )]
     pub blob_gas_used: Option<u64>,
+    /// L2 only, fee charged for the L1 resources consumed by the transaction
+    #[serde(skip_serializing_if = "Option::is_none", default = "Option::default")]
+    pub l1_fee: Option<U256>,
 }
 
 impl RpcReceiptTxInfo {
@@ -167,6 +172,7 @@ impl RpcReceiptTxInfo {
         index: u64,
         gas_used: u64,
         block_blob_gas_price: u64,
+        l1_fee_params: Option<L1FeeParams>,
     ) -> Self {
         let nonce = transaction.nonce();
         let from = transaction.sender();
@@ -180,6 +186,11 @@ impl RpcReceiptTxInfo {
             ),
             _ => (None, None),
         };
+        // Privileged transactions are sent by L1, so they don't pay the L1 fee
+        let l1_fee = match &transaction {
+            Transaction::PrivilegedL2Transaction(_) => None,
+            _ => l1_fee_params.map(|params| params.l1_fee(&transaction)),
+        };
         let (contract_address, to) = match transaction.to() {
             TxKind::Create => (Some(create_contract_address(from, nonce)), None),
             TxKind::Call(addr) => (None, Some(addr)),
@@ -194,6 +205,7 @@ impl RpcReceiptTxInfo {
             effective_gas_price,
             blob_gas_price,
             blob_gas_used,
+            l1_fee,
         }
     }
 }
@@ -233,6 +245,7 @@ mod tests {
                 effective_gas_price: 157,
                 blob_gas_price: None,
                 blob_gas_used: None,
+                l1_fee: None,
             },
             RpcReceiptBlockInfo {
                 block_hash: BlockHash::zero(),
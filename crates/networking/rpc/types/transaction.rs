@@ -2,7 +2,8 @@ use ethrex_common::{
     serde_utils,
     types::{
         BlockHash, BlockNumber, EIP1559Transaction, EIP2930Transaction, EIP7702Transaction,
-        LegacyTransaction, PrivilegedL2Transaction, Transaction, WrappedEIP4844Transaction,
+        LegacyTransaction, P2PTransaction, PrivilegedL2Transaction, Transaction,
+        WrappedEIP4844Transaction,
     },
     Address, H256,
 };
@@ -15,13 +16,13 @@ use serde::{Deserialize, Serialize};
 pub struct RpcTransaction {
     #[serde(flatten)]
     pub tx: Transaction,
-    #[serde(with = "serde_utils::u64::hex_str")]
-    block_number: BlockNumber,
-    block_hash: BlockHash,
+    #[serde(with = "serde_utils::u64::hex_str_opt")]
+    block_number: Option<BlockNumber>,
+    block_hash: Option<BlockHash>,
     from: Address,
     pub hash: H256,
-    #[serde(with = "serde_utils::u64::hex_str")]
-    transaction_index: u64,
+    #[serde(with = "serde_utils::u64::hex_str_opt")]
+    transaction_index: Option<u64>,
 }
 
 impl RpcTransaction {
@@ -32,15 +33,39 @@ impl RpcTransaction {
         transaction_index: usize,
     ) -> Self {
         let from = tx.sender();
+        Self::build_with_sender(tx, from, block_number, block_hash, transaction_index)
+    }
+
+    /// Same as [RpcTransaction::build] but with a known sender, used for unsigned transactions
+    pub fn build_with_sender(
+        tx: Transaction,
+        from: Address,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        transaction_index: usize,
+    ) -> Self {
         let hash = tx.compute_hash();
         let transaction_index = transaction_index as u64;
         RpcTransaction {
             tx,
-            block_number,
-            block_hash,
+            block_number: Some(block_number),
+            block_hash: Some(block_hash),
+            from,
+            hash,
+            transaction_index: Some(transaction_index),
+        }
+    }
+
+    /// Builds a transaction that is not included in a block yet
+    pub fn build_pending(tx: Transaction, from: Address) -> Self {
+        let hash = tx.compute_hash();
+        RpcTransaction {
+            tx,
+            block_number: None,
+            block_hash: None,
             from,
             hash,
-            transaction_index,
+            transaction_index: None,
         }
     }
 }
@@ -68,6 +93,21 @@ impl SendRawTransactionRequest {
         }
     }
 
+    pub fn to_p2p_transaction(&self) -> P2PTransaction {
+        match self {
+            SendRawTransactionRequest::Legacy(t) => P2PTransaction::LegacyTransaction(t.clone()),
+            SendRawTransactionRequest::EIP1559(t) => P2PTransaction::EIP1559Transaction(t.clone()),
+            SendRawTransactionRequest::EIP2930(t) => P2PTransaction::EIP2930Transaction(t.clone()),
+            SendRawTransactionRequest::EIP4844(t) => {
+                P2PTransaction::EIP4844TransactionWithBlobs(t.clone())
+            }
+            SendRawTransactionRequest::EIP7702(t) => P2PTransaction::EIP7702Transaction(t.clone()),
+            SendRawTransactionRequest::PrivilegedL2(t) => {
+                P2PTransaction::PrivilegedL2Transaction(t.clone())
+            }
+        }
+    }
+
     pub fn decode_canonical(bytes: &[u8]) -> Result<Self, RLPDecodeError> {
         // Look at the first byte to check if it corresponds to a TransactionType
         match bytes.first() {
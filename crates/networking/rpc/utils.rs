@@ -1,3 +1,4 @@
+use ethrex_common::U256;
 use ethrex_storage::error::StoreError;
 use ethrex_vm::EvmError;
 use serde::{Deserialize, Serialize};
@@ -28,6 +29,7 @@ pub enum RpcErr {
     InvalidForkChoiceState(String),
     InvalidPayloadAttributes(String),
     UnknownPayload(String),
+    LimitExceeded(String),
     #[cfg(feature = "based")]
     InvalidBasedMessage(String),
     #[cfg(feature = "l2")]
@@ -86,11 +88,11 @@ impl From<RpcErr> for RpcErrorMetadata {
                 // This code (3) was hand-picked to match hive tests.
                 // Could not find proper documentation about it.
                 code: 3,
-                data: Some(data.clone()),
-                message: format!(
-                    "execution reverted: {}",
-                    get_message_from_revert_data(&data)
-                ),
+                message: match get_message_from_revert_data(&data) {
+                    Some(reason) => format!("execution reverted: {reason}"),
+                    None => "execution reverted".to_string(),
+                },
+                data: Some(data),
             },
             RpcErr::Halt { reason, gas_used } => RpcErrorMetadata {
                 // Just copy the `Revert` error code.
@@ -131,6 +133,11 @@ impl From<RpcErr> for RpcErrorMetadata {
                 data: None,
                 message: format!("Unknown payload: {context}"),
             },
+            RpcErr::LimitExceeded(context) => RpcErrorMetadata {
+                code: -32005,
+                data: None,
+                message: format!("Limit exceeded: {context}"),
+            },
             #[cfg(feature = "based")]
             RpcErr::InvalidBasedMessage(context) => RpcErrorMetadata {
                 code: -38003,
@@ -171,6 +178,7 @@ pub enum RpcNamespace {
     Debug,
     Web3,
     Net,
+    TxPool,
     #[cfg(feature = "l2")]
     EthrexL2,
     #[cfg(feature = "based")]
@@ -203,6 +211,7 @@ impl RpcRequest {
                 "debug" => Ok(RpcNamespace::Debug),
                 "web3" => Ok(RpcNamespace::Web3),
                 "net" => Ok(RpcNamespace::Net),
+                "txpool" => Ok(RpcNamespace::TxPool),
                 #[cfg(feature = "l2")]
                 "ethrex" => Ok(RpcNamespace::EthrexL2),
                 #[cfg(feature = "based")]
@@ -261,12 +270,58 @@ impl From<EvmError> for RpcErr {
     }
 }
 
-fn get_message_from_revert_data(_data: &str) -> String {
-    // TODO
-    // Hive tests are not failing when revert message does not match, but currently it is not matching
-    // It should be fixed
-    // See https://github.com/ethereum/go-ethereum/blob/8fd43c80132434dca896d8ae5004ae2aac1450d3/accounts/abi/abi.go#L275
-    "".to_owned()
+/// Selector of Solidity's `Error(string)`, used by `require` and `revert` with a reason
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of Solidity's `Panic(uint256)`, used by failed assertions and runtime checks
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Decodes the reason of a revert from its `0x` prefixed output.
+/// Returns `None` if the output is not an `Error(string)` nor a `Panic(uint256)`, as is the case for custom errors.
+/// See https://github.com/ethereum/go-ethereum/blob/8fd43c80132434dca896d8ae5004ae2aac1450d3/accounts/abi/abi.go#L275
+fn get_message_from_revert_data(data: &str) -> Option<String> {
+    let data = hex::decode(data.trim_start_matches("0x")).ok()?;
+    let (selector, args) = data.split_at_checked(4)?;
+    if selector == ERROR_SELECTOR {
+        let offset = abi_word_as_usize(args, 0)?;
+        let length = abi_word_as_usize(args, offset)?;
+        let start = offset.checked_add(32)?;
+        let reason = args.get(start..start.checked_add(length)?)?;
+        String::from_utf8(reason.to_vec()).ok()
+    } else if selector == PANIC_SELECTOR {
+        let code = U256::from_big_endian(args.get(..32)?);
+        Some(match panic_reason(code) {
+            Some(reason) => reason.to_string(),
+            None => format!("unknown panic code: {code:#x}"),
+        })
+    } else {
+        None
+    }
+}
+
+/// Reads the ABI encoded word at `offset` as a `usize`, failing if it doesn't fit
+fn abi_word_as_usize(args: &[u8], offset: usize) -> Option<usize> {
+    let word = U256::from_big_endian(args.get(offset..offset.checked_add(32)?)?);
+    usize::try_from(word).ok()
+}
+
+/// Description of the panic codes emitted by the Solidity compiler
+fn panic_reason(code: U256) -> Option<&'static str> {
+    if code > U256::from(u8::MAX) {
+        return None;
+    }
+    match code.low_u64() {
+        0x00 => Some("generic panic"),
+        0x01 => Some("assert(false)"),
+        0x11 => Some("arithmetic underflow or overflow"),
+        0x12 => Some("division or modulo by zero"),
+        0x21 => Some("enum overflow"),
+        0x22 => Some("invalid encoded storage byte array accessed"),
+        0x31 => Some("out-of-bounds array access; popping on an empty array"),
+        0x32 => Some("out-of-bounds access of an array or bytesN"),
+        0x41 => Some("out of memory"),
+        0x51 => Some("uninitialized function"),
+        _ => None,
+    }
 }
 
 pub fn parse_json_hex(hex: &serde_json::Value) -> Result<u64, String> {
@@ -357,6 +412,7 @@ pub mod test_utils {
         let sponsor_pk = SecretKey::new(&mut rand::thread_rng());
         start_api(
             http_addr,
+            None,
             authrpc_addr,
             storage,
             blockchain,
@@ -364,6 +420,7 @@ pub mod test_utils {
             local_p2p_node,
             example_local_node_record(),
             SyncManager::dummy(),
+            Default::default(),
             #[cfg(feature = "based")]
             gateway_eth_client,
             #[cfg(feature = "based")]
@@ -378,3 +435,50 @@ pub mod test_utils {
         .await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_error_string_revert() {
+        // Output of `revert("Insufficient balance")`
+        let data = "0x08c379a0\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            0000000000000000000000000000000000000000000000000000000000000014\
+            496e73756666696369656e742062616c616e6365000000000000000000000000";
+        assert_eq!(
+            get_message_from_revert_data(data),
+            Some("Insufficient balance".to_string())
+        );
+        let metadata = RpcErrorMetadata::from(RpcErr::Revert {
+            data: data.to_string(),
+        });
+        assert_eq!(metadata.message, "execution reverted: Insufficient balance");
+    }
+
+    #[test]
+    fn decode_panic_revert() {
+        let panic = |code: &str| format!("0x4e487b71{code:0>64}");
+        assert_eq!(
+            get_message_from_revert_data(&panic("11")),
+            Some("arithmetic underflow or overflow".to_string())
+        );
+        assert_eq!(
+            get_message_from_revert_data(&panic("99")),
+            Some("unknown panic code: 0x99".to_string())
+        );
+    }
+
+    #[test]
+    fn custom_error_revert_keeps_data() {
+        let data = "0xcafebabe0000000000000000000000000000000000000000000000000000000000000001";
+        assert_eq!(get_message_from_revert_data(data), None);
+        assert_eq!(get_message_from_revert_data("0x"), None);
+        let metadata = RpcErrorMetadata::from(RpcErr::Revert {
+            data: data.to_string(),
+        });
+        assert_eq!(metadata.message, "execution reverted");
+        assert_eq!(metadata.data, Some(data.to_string()));
+    }
+}
@@ -6,7 +6,13 @@ use ethrex_common::types::{
 };
 use std::{collections::HashMap, fmt::Debug, panic::RefUnwindSafe};
 
-use crate::{error::StoreError, store::STATE_TRIE_SEGMENTS};
+use crate::{
+    error::StoreError,
+    pruning::StateJournal,
+    state_history::{AccountChanges, StateChangeSet, StorageChanges},
+    state_snapshot::SnapshotDiff,
+    store::STATE_TRIE_SEGMENTS,
+};
 use ethrex_trie::{Nibbles, Trie};
 
 // We need async_trait because the stabilized feature lacks support for object safety
@@ -108,6 +114,12 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
         locations: Vec<(H256, BlockNumber, BlockHash, Index)>,
     ) -> Result<(), StoreError>;
 
+    /// Remove transaction locations in batch, used when their blocks are no longer part of the chain
+    async fn remove_transaction_locations(
+        &self,
+        locations: Vec<(H256, BlockNumber, BlockHash, Index)>,
+    ) -> Result<(), StoreError>;
+
     /// Obtain transaction location (block hash and index)
     async fn get_transaction_location(
         &self,
@@ -135,6 +147,9 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
         receipts: HashMap<BlockHash, Vec<Receipt>>,
     ) -> Result<(), StoreError>;
 
+    /// Remove all the receipts of a block
+    async fn remove_receipts(&self, block_hash: BlockHash) -> Result<(), StoreError>;
+
     /// Obtain receipt for a canonical block represented by the block number.
     async fn get_receipt(
         &self,
@@ -209,6 +224,15 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     /// Obtain earliest block number
     async fn get_earliest_block_number(&self) -> Result<Option<BlockNumber>, StoreError>;
 
+    /// Update the number of the earliest block whose state is retained
+    async fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError>;
+
+    /// Obtain the number of the earliest block whose state is retained
+    async fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError>;
+
     /// Update finalized block number
     async fn update_finalized_block_number(
         &self,
@@ -395,4 +419,114 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
         &self,
         block: BlockHash,
     ) -> Result<Option<BlockHash>, StoreError>;
+
+    /// Stores the state journal of a block, replacing any previous journal for the same state root
+    async fn add_state_journal(
+        &self,
+        block_number: BlockNumber,
+        journal: StateJournal,
+    ) -> Result<(), StoreError>;
+
+    /// Returns the state journals stored for the given block number, one for each executed block
+    async fn get_state_journals(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<StateJournal>, StoreError>;
+
+    /// Removes all the state journals stored for the given block number
+    async fn remove_state_journals(&self, block_number: BlockNumber) -> Result<(), StoreError>;
+
+    /// Stores the bloom bits of a section of blocks, see [crate::bloom_index], and marks it as
+    /// the latest indexed section. Sections must be added in order.
+    async fn add_bloom_section(
+        &self,
+        section: u64,
+        bloom_bits: Vec<(u16, Vec<u8>)>,
+    ) -> Result<(), StoreError>;
+
+    /// Returns the bit vector stored for a bloom bit of an indexed section,
+    /// or `None` if none of the blocks of the section set the bit
+    async fn get_bloom_bits(&self, section: u64, bit: u16) -> Result<Option<Vec<u8>>, StoreError>;
+
+    /// Returns the amount of sections whose bloom bits have been indexed
+    async fn get_bloom_indexed_sections(&self) -> Result<Option<u64>, StoreError>;
+
+    /// Removes the bloom bits of the sections starting from `first_section`
+    /// and marks the previous one as the latest indexed section
+    async fn remove_bloom_sections(&self, first_section: u64) -> Result<(), StoreError>;
+
+    /// Stores the state changes made by the block with the given number resulting in `state_root`,
+    /// replacing the changes of a previous execution of the same block, see [crate::state_history]
+    async fn add_state_changes(
+        &self,
+        block_number: BlockNumber,
+        state_root: H256,
+        changes: StateChangeSet,
+    ) -> Result<(), StoreError>;
+
+    /// Returns the number of the latest block up to `block_number` that modified the account,
+    /// along with the account's state after each executed block with that number
+    async fn get_account_changes(
+        &self,
+        hashed_address: H256,
+        block_number: BlockNumber,
+    ) -> Result<Option<(BlockNumber, AccountChanges)>, StoreError>;
+
+    /// Returns the number of the latest block up to `block_number` that modified the storage slot,
+    /// along with the slot's value after each executed block with that number
+    async fn get_storage_changes(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+        block_number: BlockNumber,
+    ) -> Result<Option<(BlockNumber, StorageChanges)>, StoreError>;
+
+    /// Update the number of the first block whose state changes are recorded
+    async fn update_state_history_start(&self, block_number: BlockNumber)
+        -> Result<(), StoreError>;
+
+    /// Obtain the number of the first block whose state changes are recorded
+    async fn get_state_history_start(&self) -> Result<Option<BlockNumber>, StoreError>;
+
+    /// Applies the changes to the flat tables of the state snapshot, see [crate::state_snapshot],
+    /// and, if given, sets the number and state root of the block whose state they hold.
+    /// Cleared storage slots are written as zero.
+    fn write_flat_state(
+        &self,
+        changes: StateChangeSet,
+        block: Option<(BlockNumber, H256)>,
+    ) -> Result<(), StoreError>;
+
+    /// Removes all accounts and storage slots from the flat tables of the state snapshot,
+    /// along with the block whose state they hold
+    async fn clear_flat_state(&self) -> Result<(), StoreError>;
+
+    /// Obtain the number and state root of the block whose state is held by the flat tables
+    fn get_flat_state_block(&self) -> Result<Option<(BlockNumber, H256)>, StoreError>;
+
+    /// Obtain an account from the flat tables of the state snapshot
+    fn get_flat_account(&self, hashed_address: H256) -> Result<Option<AccountState>, StoreError>;
+
+    /// Obtain a storage slot from the flat tables of the state snapshot
+    fn get_flat_storage(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<U256>, StoreError>;
+
+    /// Stores the changes made to the state by a block, replacing any previous diff with the same state root
+    async fn add_snapshot_diff(
+        &self,
+        block_number: BlockNumber,
+        diff: SnapshotDiff,
+    ) -> Result<(), StoreError>;
+
+    /// Returns the diffs stored for the given block number, one for each executed block
+    async fn get_snapshot_diffs(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<SnapshotDiff>, StoreError>;
+
+    /// Removes all the diffs stored for the given block number
+    async fn remove_snapshot_diffs(&self, block_number: BlockNumber) -> Result<(), StoreError>;
 }
@@ -0,0 +1,213 @@
+use std::collections::{BTreeSet, HashMap};
+
+use ethereum_types::{Bloom, BloomInput};
+
+// Index of the logs blooms of the canonical chain, based on geth's bloombits.
+// Blocks are grouped in sections of `BLOOM_SECTION_SIZE` blocks, and for each bit of the
+// logs bloom a section stores a bit vector with one bit per block of the section, set if the
+// block's logs bloom has that bit set. Finding the blocks that may contain a value then only
+// takes reading the vectors of the three bits the value sets, instead of every block header.
+
+/// Amount of blocks in each indexed section
+pub const BLOOM_SECTION_SIZE: u64 = 4096;
+
+/// Amount of blocks that must be built on top of a section before it is indexed,
+/// so that indexed sections are not affected by reorgs
+pub const BLOOM_INDEX_CONFIRMATIONS: u64 = 256;
+
+/// Amount of bits in a logs bloom
+const BLOOM_BITS: u16 = 2048;
+
+/// Length in bytes of the bit vectors stored for each bit of a section
+const BLOOM_VECTOR_LEN: usize = (BLOOM_SECTION_SIZE / 8) as usize;
+
+/// Key under which the bit vector of a bloom bit of a section is stored
+pub(crate) fn bloom_bits_key(section: u64, bit: u16) -> u64 {
+    section * u64::from(BLOOM_BITS) + u64::from(bit)
+}
+
+/// Returns the position of the bits set in a bloom
+fn bloom_bit_positions(bloom: &Bloom) -> impl Iterator<Item = u16> + '_ {
+    bloom
+        .as_bytes()
+        .iter()
+        .enumerate()
+        .flat_map(|(index, byte)| {
+            (0..8_usize)
+                .filter(move |bit| byte & (1 << bit) != 0)
+                .map(move |bit| (index * 8 + bit) as u16)
+        })
+}
+
+/// Builds the bit vectors of a section from the logs blooms of its blocks
+pub(crate) struct BloomSectionBuilder {
+    vectors: Vec<Vec<u8>>,
+}
+
+impl BloomSectionBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            vectors: vec![vec![0; BLOOM_VECTOR_LEN]; BLOOM_BITS as usize],
+        }
+    }
+
+    /// Adds the logs bloom of the block at the given offset within the section
+    pub(crate) fn add_bloom(&mut self, offset: u64, bloom: &Bloom) {
+        let (byte, mask) = vector_position(offset);
+        for bit in bloom_bit_positions(bloom) {
+            self.vectors[bit as usize][byte] |= mask;
+        }
+    }
+
+    /// Returns the bit vectors of the section, vectors with no bits set are left out
+    pub(crate) fn finish(self) -> Vec<(u16, Vec<u8>)> {
+        self.vectors
+            .into_iter()
+            .enumerate()
+            .filter(|(_, vector)| vector.iter().any(|byte| *byte != 0))
+            .map(|(bit, vector)| (bit as u16, vector))
+            .collect()
+    }
+}
+
+/// Byte and mask of the bit of a block in the bit vectors of its section
+fn vector_position(offset: u64) -> (usize, u8) {
+    ((offset / 8) as usize, 0x80 >> (offset % 8))
+}
+
+/// Filter matched against logs blooms.
+/// A bloom matches if, for every group, it contains at least one of the values of the group.
+#[derive(Debug, Clone, Default)]
+pub struct BloomFilter {
+    groups: Vec<Vec<Bloom>>,
+}
+
+impl BloomFilter {
+    /// Builds a filter from groups of values, such as the addresses or the topics allowed
+    /// at a position. Empty groups match every bloom.
+    pub fn new<'a>(groups: impl IntoIterator<Item = Vec<&'a [u8]>>) -> Self {
+        Self {
+            groups: groups
+                .into_iter()
+                .filter(|group| !group.is_empty())
+                .map(|group| {
+                    group
+                        .into_iter()
+                        .map(|value| Bloom::from(BloomInput::Raw(value)))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns true if the filter matches every bloom
+    pub fn matches_all(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub fn matches(&self, bloom: &Bloom) -> bool {
+        self.groups
+            .iter()
+            .all(|group| group.iter().any(|value| bloom.contains_bloom(value)))
+    }
+
+    /// Bloom bits whose vectors are needed to match a section
+    pub(crate) fn bits(&self) -> BTreeSet<u16> {
+        self.groups
+            .iter()
+            .flatten()
+            .flat_map(bloom_bit_positions)
+            .collect()
+    }
+
+    /// Returns the bit vector of the blocks of a section that match the filter, given
+    /// the vectors of the bits returned by [BloomFilter::bits]. Missing vectors have no bits set.
+    pub(crate) fn match_section(&self, vectors: &HashMap<u16, Vec<u8>>) -> Vec<u8> {
+        let empty = vec![0; BLOOM_VECTOR_LEN];
+        let mut matches = vec![0xff; BLOOM_VECTOR_LEN];
+        for group in &self.groups {
+            let mut group_matches = vec![0; BLOOM_VECTOR_LEN];
+            for value in group {
+                let mut value_matches = vec![0xff; BLOOM_VECTOR_LEN];
+                for bit in bloom_bit_positions(value) {
+                    let vector = vectors.get(&bit).unwrap_or(&empty);
+                    value_matches
+                        .iter_mut()
+                        .zip(vector)
+                        .for_each(|(byte, vector_byte)| *byte &= vector_byte);
+                }
+                group_matches
+                    .iter_mut()
+                    .zip(value_matches)
+                    .for_each(|(byte, value_byte)| *byte |= value_byte);
+            }
+            matches
+                .iter_mut()
+                .zip(group_matches)
+                .for_each(|(byte, group_byte)| *byte &= group_byte);
+        }
+        matches
+    }
+}
+
+/// Returns true if the block at the given offset within its section is set in the bit vector
+pub(crate) fn vector_contains(vector: &[u8], offset: u64) -> bool {
+    let (byte, mask) = vector_position(offset);
+    vector.get(byte).is_some_and(|byte| byte & mask != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bloom_of(values: &[&[u8]]) -> Bloom {
+        let mut bloom = Bloom::zero();
+        for value in values {
+            bloom.accrue(BloomInput::Raw(value));
+        }
+        bloom
+    }
+
+    #[test]
+    fn section_matches_same_blocks_as_headers() {
+        let address = [1_u8; 20];
+        let topic = [2_u8; 32];
+        let other_topic = [3_u8; 32];
+        let blooms = [
+            bloom_of(&[&address, &topic]),
+            bloom_of(&[&address]),
+            bloom_of(&[&topic]),
+            bloom_of(&[&address, &other_topic]),
+            Bloom::zero(),
+        ];
+        let mut builder = BloomSectionBuilder::new();
+        for (offset, bloom) in blooms.iter().enumerate() {
+            builder.add_bloom(offset as u64 * 1000, bloom);
+        }
+        let vectors: HashMap<_, _> = builder.finish().into_iter().collect();
+
+        let filters = [
+            BloomFilter::new([vec![&address[..]]]),
+            BloomFilter::new([vec![&address[..]], vec![&topic[..]]]),
+            BloomFilter::new([vec![&address[..]], vec![&topic[..], &other_topic[..]]]),
+            BloomFilter::new([vec![], vec![&other_topic[..]]]),
+        ];
+        for filter in filters {
+            let section_matches = filter.match_section(&vectors);
+            for (offset, bloom) in blooms.iter().enumerate() {
+                assert_eq!(
+                    vector_contains(&section_matches, offset as u64 * 1000),
+                    filter.matches(bloom)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn empty_vectors_are_not_stored() {
+        let mut builder = BloomSectionBuilder::new();
+        builder.add_bloom(0, &bloom_of(&[b"value"]));
+        let bits: BTreeSet<u16> = builder.finish().into_iter().map(|(bit, _)| bit).collect();
+        assert_eq!(bits, BloomFilter::new([vec![&b"value"[..]]]).bits());
+    }
+}
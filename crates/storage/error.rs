@@ -1,3 +1,4 @@
+use ethrex_common::types::BlockNumber;
 use ethrex_rlp::error::RLPDecodeError;
 use ethrex_trie::TrieError;
 #[cfg(feature = "redb")]
@@ -50,4 +51,18 @@ pub enum StoreError {
     MempoolWriteLock(String),
     #[error("Failed to lock mempool for reading")]
     MempoolReadLock(String),
+    #[error("State of block {0} was pruned, only the state of the latest blocks is retained")]
+    StatePruned(BlockNumber),
+    #[error("Failed to lock state pruner")]
+    StatePrunerLock(String),
+    #[error("The state of the blocks before {0} was pruned, archive mode can't be enabled")]
+    ArchiveStatePruned(BlockNumber),
+    #[error("Failed to lock state snapshot for writing")]
+    StateSnapshotWriteLock(String),
+    #[error("Failed to lock state snapshot for reading")]
+    StateSnapshotReadLock(String),
+    #[error("Can't rewind to block {0}, it is not part of the canonical chain")]
+    RewindTargetNotCanonical(BlockNumber),
+    #[error("Can't rewind to block {0}, its state is not available")]
+    RewindStateUnavailable(BlockNumber),
 }
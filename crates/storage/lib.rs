@@ -1,12 +1,20 @@
 mod api;
 
+mod bloom_index;
+mod pruning;
 mod rlp;
+mod state_history;
+mod state_snapshot;
 mod store;
 mod store_db;
 mod trie_db;
 mod utils;
 
 pub mod error;
+pub use bloom_index::{BloomFilter, BLOOM_INDEX_CONFIRMATIONS, BLOOM_SECTION_SIZE};
+pub use pruning::StateJournal;
+pub use state_history::{AccountChanges, StateChangeSet, StorageChanges};
+pub use state_snapshot::{SnapshotDiff, MAX_DIFF_LAYERS};
 pub use store::{
     hash_address, hash_key, AccountUpdate, EngineType, Store, MAX_SNAPSHOT_READS,
     STATE_TRIE_SEGMENTS,
@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, HashMap};
+
+use ethereum_types::H256;
+use ethrex_common::types::BlockNumber;
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+use ethrex_trie::TrieDiff;
+
+/// Trie nodes written and made unreachable when applying a block's account updates.
+/// Journals are kept for every block within the retention window so that the nodes
+/// that are no longer reachable from any retained state root can be deleted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StateJournal {
+    /// State root resulting from the updates
+    pub state_root: H256,
+    /// Changes to the state trie
+    pub state_trie: TrieDiff,
+    /// Changes to the storage tries, indexed by hashed account address
+    pub storage_tries: Vec<(H256, TrieDiff)>,
+}
+
+/// Hashed account address, inserted and removed nodes of a storage trie, as they are encoded
+type StorageTrieDiff = (H256, Vec<H256>, Vec<H256>);
+
+impl RLPEncode for StateJournal {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        let storage_tries: Vec<StorageTrieDiff> = self
+            .storage_tries
+            .iter()
+            .map(|(account, diff)| (*account, diff.inserted.clone(), diff.removed.clone()))
+            .collect();
+        Encoder::new(buf)
+            .encode_field(&self.state_root)
+            .encode_field(&self.state_trie.inserted)
+            .encode_field(&self.state_trie.removed)
+            .encode_field(&storage_tries)
+            .finish();
+    }
+}
+
+impl RLPDecode for StateJournal {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (state_root, decoder) = decoder.decode_field("state_root")?;
+        let (inserted, decoder) = decoder.decode_field("inserted")?;
+        let (removed, decoder) = decoder.decode_field("removed")?;
+        let (storage_tries, decoder): (Vec<StorageTrieDiff>, _) =
+            decoder.decode_field("storage_tries")?;
+        let journal = StateJournal {
+            state_root,
+            state_trie: TrieDiff { inserted, removed },
+            storage_tries: storage_tries
+                .into_iter()
+                .map(|(account, inserted, removed)| (account, TrieDiff { inserted, removed }))
+                .collect(),
+        };
+        Ok((journal, decoder.finish()?))
+    }
+}
+
+/// Stored nodes that can be deleted, state trie nodes are indexed with `None`
+/// and storage trie nodes with the hashed address of their account
+type NodeKey = (Option<H256>, H256);
+
+/// Keeps track of the trie nodes that became unreachable within the retention window
+#[derive(Debug)]
+pub(crate) struct StatePruner {
+    /// Amount of blocks whose state is kept
+    retention: u64,
+    /// Number of the oldest block whose state is still available
+    pub(crate) earliest_state: BlockNumber,
+    /// State roots of the journals stored for each block
+    journaled: BTreeMap<BlockNumber, Vec<H256>>,
+    /// Unreachable nodes and the latest block which removed them from its trie
+    stale_nodes: HashMap<NodeKey, BlockNumber>,
+}
+
+/// Nodes that are safe to delete after pruning a block
+#[derive(Debug, Default)]
+pub(crate) struct PrunableNodes {
+    pub(crate) state_trie: Vec<H256>,
+    pub(crate) storage_tries: HashMap<H256, Vec<H256>>,
+}
+
+impl StatePruner {
+    pub(crate) fn new(retention: u64, earliest_state: BlockNumber) -> Self {
+        Self {
+            retention,
+            earliest_state,
+            journaled: BTreeMap::new(),
+            stale_nodes: HashMap::new(),
+        }
+    }
+
+    /// Adds the journal of a block to the index.
+    /// Returns false if a journal with the same state root was already recorded for the block,
+    /// which happens when a block is executed more than once.
+    pub(crate) fn record(&mut self, block_number: BlockNumber, journal: &StateJournal) -> bool {
+        let roots = self.journaled.entry(block_number).or_default();
+        if roots.contains(&journal.state_root) {
+            return false;
+        }
+        roots.push(journal.state_root);
+
+        for (key, diff) in journal_diffs(journal) {
+            for hash in &diff.removed {
+                let stale_since = self.stale_nodes.entry((key, *hash)).or_default();
+                *stale_since = (*stale_since).max(block_number);
+            }
+        }
+        // Nodes written again by this block are reachable until a later block removes them
+        for (key, diff) in journal_diffs(journal) {
+            for hash in &diff.inserted {
+                if self
+                    .stale_nodes
+                    .get(&(key, *hash))
+                    .is_some_and(|stale_since| *stale_since <= block_number)
+                {
+                    self.stale_nodes.remove(&(key, *hash));
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the block up to which state can be pruned for the given head,
+    /// or `None` if it is still within the retention window
+    pub(crate) fn pruning_target(&self, head: BlockNumber) -> Option<BlockNumber> {
+        head.checked_sub(self.retention)
+            .filter(|target| *target > self.earliest_state)
+    }
+
+    /// Returns the blocks up to `target` that have journals pending to be pruned
+    pub(crate) fn journaled_blocks(&self, target: BlockNumber) -> Vec<BlockNumber> {
+        self.journaled.range(..=target).map(|(n, _)| *n).collect()
+    }
+
+    /// Removes the journals of a block from the index and returns the nodes that are no
+    /// longer reachable from any retained state. Only nodes removed by the canonical block
+    /// are deleted, nodes removed by blocks that are no longer part of the chain are just
+    /// forgotten, as they may still be reachable from the canonical state.
+    pub(crate) fn prune(
+        &mut self,
+        block_number: BlockNumber,
+        journals: &[StateJournal],
+        canonical_root: Option<H256>,
+    ) -> PrunableNodes {
+        self.journaled.remove(&block_number);
+
+        let mut prunable = PrunableNodes::default();
+        let is_canonical = |journal: &&StateJournal| canonical_root == Some(journal.state_root);
+        // Process the canonical journal first so nodes also removed by other blocks are not missed
+        let journals = journals
+            .iter()
+            .filter(is_canonical)
+            .chain(journals.iter().filter(|journal| !is_canonical(journal)));
+        for journal in journals {
+            let canonical = is_canonical(&journal);
+            for (key, diff) in journal_diffs(journal) {
+                for hash in &diff.removed {
+                    if self.stale_nodes.get(&(key, *hash)) != Some(&block_number) {
+                        continue;
+                    }
+                    self.stale_nodes.remove(&(key, *hash));
+                    if !canonical {
+                        continue;
+                    }
+                    match key {
+                        Some(account) => prunable
+                            .storage_tries
+                            .entry(account)
+                            .or_default()
+                            .push(*hash),
+                        None => prunable.state_trie.push(*hash),
+                    }
+                }
+            }
+        }
+        prunable
+    }
+}
+
+fn journal_diffs(journal: &StateJournal) -> impl Iterator<Item = (Option<H256>, &TrieDiff)> {
+    std::iter::once((None, &journal.state_trie)).chain(
+        journal
+            .storage_tries
+            .iter()
+            .map(|(account, diff)| (Some(*account), diff)),
+    )
+}
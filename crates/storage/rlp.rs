@@ -1,6 +1,9 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
+use crate::pruning::StateJournal;
+use crate::state_history::{AccountChanges, StorageChanges};
+use crate::state_snapshot::SnapshotDiff;
 use bytes::Bytes;
 use ethrex_common::{
     types::{
@@ -40,6 +43,20 @@ pub type TransactionHashRLP = Rlp<H256>;
 // Payload type
 pub type PayloadBundleRLP = Rlp<PayloadBundle>;
 
+// State pruning types
+#[allow(unused)]
+pub type StateJournalsRLP = Rlp<Vec<StateJournal>>;
+
+// State history types
+#[allow(unused)]
+pub type AccountChangesRLP = Rlp<AccountChanges>;
+#[allow(unused)]
+pub type StorageChangesRLP = Rlp<StorageChanges>;
+
+// State snapshot types
+#[allow(unused)]
+pub type SnapshotDiffsRLP = Rlp<Vec<SnapshotDiff>>;
+
 // Wrapper for tuples. Used mostly for indexed keys.
 pub type TupleRLP<A, B> = Rlp<(A, B)>;
 
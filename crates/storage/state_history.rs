@@ -0,0 +1,106 @@
+use bytes::Bytes;
+use ethereum_types::{H256, U256};
+use ethrex_common::types::AccountState;
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+
+/// Changes made to the state by a block, recorded when running in archive mode so the
+/// state of past blocks can be read without walking their tries.
+/// Changes are indexed by the block number and the state root resulting from the block,
+/// which tells apart the changes of the canonical block from those of other blocks
+/// executed at the same height.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StateChangeSet {
+    /// New state of the modified accounts indexed by hashed address, `None` if the account was removed
+    pub accounts: Vec<(H256, Option<AccountState>)>,
+    /// New value of the modified storage slots indexed by hashed address and hashed key,
+    /// zero if the slot was cleared
+    pub storage: Vec<(H256, H256, U256)>,
+}
+
+/// State of an account after each executed block with a given number that modified it,
+/// indexed by the state root resulting from the block
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountChanges(pub Vec<(H256, Option<AccountState>)>);
+
+/// Value of a storage slot after each executed block with a given number that modified it,
+/// indexed by the state root resulting from the block
+pub type StorageChanges = Vec<(H256, U256)>;
+
+/// Records the change made by the block resulting in `state_root`, replacing the change
+/// recorded by a previous execution of the same block
+pub(crate) fn record_change<T>(changes: &mut Vec<(H256, T)>, state_root: H256, value: T) {
+    changes.retain(|(root, _)| *root != state_root);
+    changes.push((state_root, value));
+}
+
+impl RLPEncode for StateChangeSet {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        let accounts: Vec<(H256, Bytes)> = self
+            .accounts
+            .iter()
+            .map(|(hashed_address, account_state)| {
+                (*hashed_address, encode_account_state(account_state))
+            })
+            .collect();
+        Encoder::new(buf)
+            .encode_field(&accounts)
+            .encode_field(&self.storage)
+            .finish();
+    }
+}
+
+impl RLPDecode for StateChangeSet {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (accounts, decoder): (Vec<(H256, Bytes)>, _) = decoder.decode_field("accounts")?;
+        let (storage, decoder) = decoder.decode_field("storage")?;
+        let accounts = accounts
+            .into_iter()
+            .map(|(hashed_address, encoded)| Ok((hashed_address, decode_account_state(&encoded)?)))
+            .collect::<Result<_, RLPDecodeError>>()?;
+        Ok((StateChangeSet { accounts, storage }, decoder.finish()?))
+    }
+}
+
+impl RLPEncode for AccountChanges {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        let changes: Vec<(H256, Bytes)> = self
+            .0
+            .iter()
+            .map(|(state_root, account_state)| (*state_root, encode_account_state(account_state)))
+            .collect();
+        Encoder::new(buf).encode_field(&changes).finish();
+    }
+}
+
+impl RLPDecode for AccountChanges {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (changes, decoder): (Vec<(H256, Bytes)>, _) = decoder.decode_field("changes")?;
+        let changes = changes
+            .into_iter()
+            .map(|(state_root, encoded)| Ok((state_root, decode_account_state(&encoded)?)))
+            .collect::<Result<_, RLPDecodeError>>()?;
+        Ok((AccountChanges(changes), decoder.finish()?))
+    }
+}
+
+/// Removed accounts are encoded as an empty string
+fn encode_account_state(account_state: &Option<AccountState>) -> Bytes {
+    account_state
+        .as_ref()
+        .map(|account_state| Bytes::from(account_state.encode_to_vec()))
+        .unwrap_or_default()
+}
+
+fn decode_account_state(encoded: &[u8]) -> Result<Option<AccountState>, RLPDecodeError> {
+    if encoded.is_empty() {
+        return Ok(None);
+    }
+    AccountState::decode(encoded).map(Some)
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use ethereum_types::{H256, U256};
+use ethrex_common::types::{AccountState, BlockNumber};
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+
+use crate::state_history::StateChangeSet;
+
+// Flat snapshot of the state, based on geth's snapshots.
+// The disk layer holds the state of a block as flat tables, indexed by hashed address for
+// accounts and by hashed address and hashed key for storage slots, so reading a value takes a
+// single lookup instead of walking the tries. On top of it, a diff layer is kept in memory for
+// each of the more recent blocks with the changes it made to its parent's state. Diff layers are
+// indexed by the state root resulting from their block, so any state built on top of the disk
+// layer is read by walking its diff layers down to the disk layer. Diff layers are flattened
+// into the disk layer once their block is finalized, or when too many of them pile up.
+
+/// Maximum amount of diff layers on top of the disk layer in the chain being extended,
+/// older layers are flattened into the disk layer even if their block is not finalized
+pub const MAX_DIFF_LAYERS: u64 = 128;
+
+/// Amount of accounts and storage slots written at once when generating the disk layer
+pub(crate) const GENERATION_BATCH_SIZE: usize = 10_000;
+
+/// Changes made to the state by a block, as they are persisted so that the
+/// diff layers can be restored on restart
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SnapshotDiff {
+    /// State root of the parent block
+    pub parent_root: H256,
+    /// State root resulting from the block
+    pub state_root: H256,
+    pub changes: StateChangeSet,
+}
+
+impl RLPEncode for SnapshotDiff {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.parent_root)
+            .encode_field(&self.state_root)
+            .encode_field(&self.changes)
+            .finish();
+    }
+}
+
+impl RLPDecode for SnapshotDiff {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (parent_root, decoder) = decoder.decode_field("parent_root")?;
+        let (state_root, decoder) = decoder.decode_field("state_root")?;
+        let (changes, decoder) = decoder.decode_field("changes")?;
+        let diff = SnapshotDiff {
+            parent_root,
+            state_root,
+            changes,
+        };
+        Ok((diff, decoder.finish()?))
+    }
+}
+
+/// Result of reading a value from the diff layers
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LayerRead<T> {
+    /// The value was set by one of the diff layers
+    Diff(T),
+    /// The value was not modified by the diff layers, it must be read from the disk layer
+    Disk,
+    /// The state is not covered by the snapshot, it must be read from the tries
+    Missing,
+}
+
+#[derive(Debug)]
+struct DiffLayer {
+    block_number: BlockNumber,
+    parent_root: H256,
+    /// New state of the modified accounts, `None` if the account was removed
+    accounts: HashMap<H256, Option<AccountState>>,
+    /// New value of the modified storage slots, zero if the slot was cleared
+    storage: HashMap<(H256, H256), U256>,
+}
+
+impl DiffLayer {
+    /// Returns the changes of the layer, to be written to the disk layer
+    fn changes(&self) -> StateChangeSet {
+        StateChangeSet {
+            accounts: self
+                .accounts
+                .iter()
+                .map(|(hashed_address, account_state)| (*hashed_address, account_state.clone()))
+                .collect(),
+            storage: self
+                .storage
+                .iter()
+                .map(|((hashed_address, hashed_key), value)| (*hashed_address, *hashed_key, *value))
+                .collect(),
+        }
+    }
+}
+
+/// Diff layers kept on top of the state held by the flat tables
+#[derive(Debug, Default)]
+pub(crate) struct StateSnapshot {
+    /// Number and state root of the block whose state is held by the flat tables,
+    /// `None` while they are being generated
+    disk: Option<(BlockNumber, H256)>,
+    /// Diff layers indexed by the state root resulting from their block
+    layers: HashMap<H256, DiffLayer>,
+}
+
+impl StateSnapshot {
+    pub(crate) fn new(disk: Option<(BlockNumber, H256)>) -> Self {
+        Self {
+            disk,
+            layers: HashMap::new(),
+        }
+    }
+
+    /// Returns the number and state root of the block held by the disk layer
+    pub(crate) fn disk(&self) -> Option<(BlockNumber, H256)> {
+        self.disk
+    }
+
+    /// Drops all diff layers and sets the block held by the disk layer
+    pub(crate) fn reset(&mut self, disk: Option<(BlockNumber, H256)>) {
+        self.disk = disk;
+        self.layers.clear();
+    }
+
+    /// Returns true if the state with the given root can be read from the snapshot
+    pub(crate) fn covers(&self, state_root: H256) -> bool {
+        self.disk_root() == Some(state_root) || self.layers.contains_key(&state_root)
+    }
+
+    /// Returns true if the diff can be added as a layer, which requires its parent state
+    /// to be covered by the snapshot, and its resulting state not to be covered yet
+    pub(crate) fn accepts(&self, diff: &SnapshotDiff) -> bool {
+        self.covers(diff.parent_root) && !self.covers(diff.state_root)
+    }
+
+    /// Adds the changes of a block as a diff layer on top of its parent's state.
+    /// Returns false if the layer was not added, see [StateSnapshot::accepts].
+    pub(crate) fn add_layer(&mut self, block_number: BlockNumber, diff: SnapshotDiff) -> bool {
+        if !self.accepts(&diff) {
+            return false;
+        }
+        let layer = DiffLayer {
+            block_number,
+            parent_root: diff.parent_root,
+            accounts: diff.changes.accounts.into_iter().collect(),
+            storage: diff
+                .changes
+                .storage
+                .into_iter()
+                .map(|(hashed_address, hashed_key, value)| ((hashed_address, hashed_key), value))
+                .collect(),
+        };
+        self.layers.insert(diff.state_root, layer);
+        true
+    }
+
+    /// Reads an account from the diff layers of the given state
+    pub(crate) fn get_account(
+        &self,
+        state_root: H256,
+        hashed_address: H256,
+    ) -> LayerRead<Option<AccountState>> {
+        self.read(state_root, |layer| {
+            layer.accounts.get(&hashed_address).cloned()
+        })
+    }
+
+    /// Reads a storage slot from the diff layers of the given state, cleared slots are read as zero
+    pub(crate) fn get_storage(
+        &self,
+        state_root: H256,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> LayerRead<U256> {
+        self.read(state_root, |layer| {
+            layer.storage.get(&(hashed_address, hashed_key)).copied()
+        })
+    }
+
+    /// Walks the diff layers of the given state from the most recent one until one of them
+    /// holds the value
+    fn read<T>(&self, state_root: H256, get: impl Fn(&DiffLayer) -> Option<T>) -> LayerRead<T> {
+        let Some(disk_root) = self.disk_root() else {
+            return LayerRead::Missing;
+        };
+        let mut root = state_root;
+        while root != disk_root {
+            let Some(layer) = self.layers.get(&root) else {
+                return LayerRead::Missing;
+            };
+            if let Some(value) = get(layer) {
+                return LayerRead::Diff(value);
+            }
+            root = layer.parent_root;
+        }
+        LayerRead::Disk
+    }
+
+    /// Returns the state roots of the diff layers that must be flattened, from the oldest one,
+    /// for the disk layer to hold the state of the latest block up to `max_block` in the chain
+    /// of the given state. Nothing is returned if the state is not covered by the snapshot.
+    pub(crate) fn layers_to_flatten(&self, state_root: H256, max_block: BlockNumber) -> Vec<H256> {
+        let Some(disk_root) = self.disk_root() else {
+            return vec![];
+        };
+        let mut chain = vec![];
+        let mut root = state_root;
+        while root != disk_root {
+            let Some(layer) = self.layers.get(&root) else {
+                return vec![];
+            };
+            if layer.block_number <= max_block {
+                chain.push(root);
+            }
+            root = layer.parent_root;
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Returns the number of the block of a diff layer and its changes
+    pub(crate) fn layer_changes(&self, state_root: H256) -> Option<(BlockNumber, StateChangeSet)> {
+        self.layers
+            .get(&state_root)
+            .map(|layer| (layer.block_number, layer.changes()))
+    }
+
+    /// Makes the disk layer hold the state of a diff layer on top of it, once its changes
+    /// have been written to the flat tables. Layers which are no longer built on top of
+    /// the disk layer are dropped.
+    pub(crate) fn flatten(&mut self, state_root: H256) {
+        let Some(layer) = self.layers.remove(&state_root) else {
+            return;
+        };
+        self.disk = Some((layer.block_number, state_root));
+        loop {
+            let orphans: Vec<H256> = self
+                .layers
+                .iter()
+                .filter(|(_, layer)| !self.covers(layer.parent_root))
+                .map(|(root, _)| *root)
+                .collect();
+            if orphans.is_empty() {
+                break;
+            }
+            for root in orphans {
+                self.layers.remove(&root);
+            }
+        }
+    }
+
+    fn disk_root(&self) -> Option<H256> {
+        self.disk.map(|(_, state_root)| state_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(parent_root: u64, state_root: u64, accounts: &[(u64, Option<u64>)]) -> SnapshotDiff {
+        SnapshotDiff {
+            parent_root: H256::from_low_u64_be(parent_root),
+            state_root: H256::from_low_u64_be(state_root),
+            changes: StateChangeSet {
+                accounts: accounts
+                    .iter()
+                    .map(|(address, balance)| {
+                        let account_state = balance.map(|balance| AccountState {
+                            balance: U256::from(balance),
+                            ..Default::default()
+                        });
+                        (H256::from_low_u64_be(*address), account_state)
+                    })
+                    .collect(),
+                storage: vec![],
+            },
+        }
+    }
+
+    fn balance(snapshot: &StateSnapshot, state_root: u64, address: u64) -> LayerRead<Option<u64>> {
+        match snapshot.get_account(
+            H256::from_low_u64_be(state_root),
+            H256::from_low_u64_be(address),
+        ) {
+            LayerRead::Diff(account) => {
+                LayerRead::Diff(account.map(|account| account.balance.as_u64()))
+            }
+            LayerRead::Disk => LayerRead::Disk,
+            LayerRead::Missing => LayerRead::Missing,
+        }
+    }
+
+    #[test]
+    fn reads_walk_down_to_the_disk_layer() {
+        let mut snapshot = StateSnapshot::new(Some((0, H256::from_low_u64_be(100))));
+        assert!(snapshot.add_layer(1, diff(100, 101, &[(1, Some(1)), (2, Some(1))])));
+        assert!(snapshot.add_layer(2, diff(101, 102, &[(1, Some(2)), (2, None)])));
+        // Sibling block on top of the same parent
+        assert!(snapshot.add_layer(2, diff(101, 202, &[(1, Some(3))])));
+        // Parent not covered and state already covered
+        assert!(!snapshot.add_layer(3, diff(999, 103, &[])));
+        assert!(!snapshot.add_layer(2, diff(101, 102, &[])));
+
+        assert_eq!(balance(&snapshot, 102, 1), LayerRead::Diff(Some(2)));
+        assert_eq!(balance(&snapshot, 102, 2), LayerRead::Diff(None));
+        assert_eq!(balance(&snapshot, 202, 1), LayerRead::Diff(Some(3)));
+        assert_eq!(balance(&snapshot, 202, 2), LayerRead::Diff(Some(1)));
+        assert_eq!(balance(&snapshot, 102, 3), LayerRead::Disk);
+        assert_eq!(balance(&snapshot, 100, 1), LayerRead::Disk);
+        assert_eq!(balance(&snapshot, 999, 1), LayerRead::Missing);
+    }
+
+    #[test]
+    fn flattening_drops_other_branches() {
+        let mut snapshot = StateSnapshot::new(Some((0, H256::from_low_u64_be(100))));
+        snapshot.add_layer(1, diff(100, 101, &[(1, Some(1))]));
+        snapshot.add_layer(2, diff(101, 102, &[(1, Some(2))]));
+        snapshot.add_layer(3, diff(102, 103, &[(1, Some(3))]));
+        snapshot.add_layer(1, diff(100, 201, &[(1, Some(4))]));
+        snapshot.add_layer(2, diff(201, 202, &[(1, Some(5))]));
+
+        let to_flatten = snapshot.layers_to_flatten(H256::from_low_u64_be(103), 2);
+        assert_eq!(
+            to_flatten,
+            vec![H256::from_low_u64_be(101), H256::from_low_u64_be(102)]
+        );
+        for state_root in to_flatten {
+            snapshot.flatten(state_root);
+        }
+
+        assert_eq!(snapshot.disk(), Some((2, H256::from_low_u64_be(102))));
+        assert_eq!(balance(&snapshot, 103, 1), LayerRead::Diff(Some(3)));
+        assert_eq!(balance(&snapshot, 102, 1), LayerRead::Disk);
+        assert_eq!(balance(&snapshot, 101, 1), LayerRead::Missing);
+        assert_eq!(balance(&snapshot, 202, 1), LayerRead::Missing);
+        assert!(snapshot
+            .layers_to_flatten(H256::from_low_u64_be(202), 2)
+            .is_empty());
+    }
+}
@@ -1,5 +1,14 @@
 use crate::api::StoreEngine;
+use crate::bloom_index::{
+    vector_contains, BloomFilter, BloomSectionBuilder, BLOOM_INDEX_CONFIRMATIONS,
+    BLOOM_SECTION_SIZE,
+};
 use crate::error::StoreError;
+use crate::pruning::{StateJournal, StatePruner};
+use crate::state_history::StateChangeSet;
+use crate::state_snapshot::{
+    LayerRead, SnapshotDiff, StateSnapshot, GENERATION_BATCH_SIZE, MAX_DIFF_LAYERS,
+};
 use crate::store_db::in_memory::Store as InMemoryStore;
 #[cfg(feature = "libmdbx")]
 use crate::store_db::libmdbx::Store as LibmdbxStore;
@@ -15,13 +24,16 @@ use ethrex_common::types::{
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_trie::{Nibbles, Trie};
+use ethrex_trie::{Nibbles, Trie, TrieDiff};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest as _, Keccak256};
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
-use std::sync::Arc;
-use tracing::info;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex, RwLock,
+};
+use tracing::{debug, info};
 
 /// Number of state trie segments to fetch concurrently during state sync
 pub const STATE_TRIE_SEGMENTS: usize = 2;
@@ -32,6 +44,12 @@ pub const MAX_SNAPSHOT_READS: usize = 100;
 #[derive(Debug, Clone)]
 pub struct Store {
     engine: Arc<dyn StoreEngine>,
+    // Only set when running with state pruning enabled
+    state_pruner: Option<Arc<Mutex<StatePruner>>>,
+    // Only set when running in archive mode, number of the first block whose state changes are recorded
+    state_history_start: Option<Arc<AtomicU64>>,
+    // Only set when the state snapshot is enabled
+    state_snapshot: Option<Arc<RwLock<StateSnapshot>>>,
 }
 
 #[allow(dead_code)]
@@ -77,18 +95,18 @@ impl AccountUpdate {
 impl Store {
     pub fn new(path: &str, engine_type: EngineType) -> Result<Self, StoreError> {
         info!("Starting storage engine ({engine_type:?})");
-        let store = match engine_type {
+        let engine: Arc<dyn StoreEngine> = match engine_type {
             #[cfg(feature = "libmdbx")]
-            EngineType::Libmdbx => Self {
-                engine: Arc::new(LibmdbxStore::new(path)?),
-            },
-            EngineType::InMemory => Self {
-                engine: Arc::new(InMemoryStore::new()),
-            },
+            EngineType::Libmdbx => Arc::new(LibmdbxStore::new(path)?),
+            EngineType::InMemory => Arc::new(InMemoryStore::new()),
             #[cfg(feature = "redb")]
-            EngineType::RedB => Self {
-                engine: Arc::new(RedBStore::new()?),
-            },
+            EngineType::RedB => Arc::new(RedBStore::new()?),
+        };
+        let store = Self {
+            engine,
+            state_pruner: None,
+            state_history_start: None,
+            state_snapshot: None,
         };
         info!("Started store engine");
         Ok(store)
@@ -109,15 +127,405 @@ impl Store {
         Ok(store)
     }
 
+    /// Enables state pruning, only the state of the latest `retention` blocks will be kept.
+    /// Trie nodes which are no longer reachable from any of these states are deleted as the chain advances.
+    /// Must be called after the initial state has been stored.
+    pub async fn with_state_retention(mut self, retention: u64) -> Result<Self, StoreError> {
+        let latest_block_number = self.get_latest_block_number().await?;
+        let earliest_state = match self.engine.get_earliest_state_block_number().await? {
+            Some(block_number) => block_number,
+            None => {
+                // Pruning is enabled for the first time, the state of older blocks is left as is
+                let block_number = latest_block_number.saturating_sub(retention);
+                self.engine
+                    .update_earliest_state_block_number(block_number)
+                    .await?;
+                block_number
+            }
+        };
+        info!("Enabling state pruning, retaining the state of the last {retention} blocks");
+
+        // Rebuild the index of unreachable nodes from the journals of the retained blocks
+        let mut pruner = StatePruner::new(retention, earliest_state);
+        let mut block_number = earliest_state + 1;
+        loop {
+            let journals = self.engine.get_state_journals(block_number).await?;
+            if journals.is_empty() && block_number > latest_block_number {
+                break;
+            }
+            for journal in journals.iter() {
+                pruner.record(block_number, journal);
+            }
+            block_number += 1;
+        }
+        self.state_pruner = Some(Arc::new(Mutex::new(pruner)));
+        self.prune_state(latest_block_number).await?;
+        Ok(self)
+    }
+
+    /// Enables archive mode, the state of every block is kept and the changes made to the state
+    /// by each block are recorded so the state of past blocks can be read without their tries,
+    /// see [crate::state_history]. Fails if the state of past blocks was already pruned.
+    /// Must be called after the initial state has been stored.
+    pub async fn with_archive_mode(mut self) -> Result<Self, StoreError> {
+        if let Some(earliest_state) = self.engine.get_earliest_state_block_number().await? {
+            if earliest_state > 0 {
+                return Err(StoreError::ArchiveStatePruned(earliest_state));
+            }
+        }
+        let history_start = match self.engine.get_state_history_start().await? {
+            Some(block_number) => block_number,
+            None => {
+                // The state of the existing blocks is read from their tries
+                let block_number = self.get_latest_block_number().await? + 1;
+                self.engine.update_state_history_start(block_number).await?;
+                block_number
+            }
+        };
+        info!("Running in archive mode, recording state changes since block {history_start}");
+        self.state_history_start = Some(Arc::new(AtomicU64::new(history_start)));
+        Ok(self)
+    }
+
+    /// Returns true if running in archive mode
+    pub fn is_archive(&self) -> bool {
+        self.state_history_start.is_some()
+    }
+
+    /// Makes the state history start at the given block, the state of the previous blocks is
+    /// read from their tries. Used when the state is rebuilt at a given block, as in snap sync,
+    /// where the changes of the blocks before it are not recorded.
+    /// Does nothing if not running in archive mode.
+    pub async fn restart_state_history(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        let Some(history_start) = &self.state_history_start else {
+            return Ok(());
+        };
+        self.engine.update_state_history_start(block_number).await?;
+        history_start.store(block_number, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns the number of the first block whose state changes are recorded
+    /// if the state history can be used to read the state of the given block.
+    /// The state of the blocks in the middle of a batch added with a single state transition
+    /// is not stored, so it can't be read from the history either.
+    fn state_history_start(
+        &self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+    ) -> Result<Option<BlockNumber>, StoreError> {
+        let Some(history_start) = self
+            .state_history_start
+            .as_ref()
+            .map(|history_start| history_start.load(Ordering::Relaxed))
+            .filter(|history_start| block_number >= *history_start)
+        else {
+            return Ok(None);
+        };
+        let Some(state_root) = self.state_root(block_hash)? else {
+            return Ok(None);
+        };
+        let state_stored =
+            state_root == *EMPTY_TRIE_HASH || self.contains_state_node(state_root)?;
+        Ok(state_stored.then_some(history_start))
+    }
+
+    /// Enables the state snapshot, see [crate::state_snapshot], which serves the reads of the
+    /// accounts and storage slots of the latest states without walking their tries.
+    /// The snapshot is generated from the latest state if it doesn't cover it.
+    /// Must be called after the initial state has been stored.
+    pub async fn with_state_snapshot(mut self) -> Result<Self, StoreError> {
+        let disk = self.engine.get_flat_state_block()?;
+        let mut state_snapshot = StateSnapshot::new(disk);
+        if let Some((disk_block, _)) = disk {
+            // Restore the diff layers of the blocks on top of the disk layer
+            let latest_block_number = self.get_latest_block_number().await?;
+            let mut block_number = disk_block + 1;
+            loop {
+                let diffs = self.engine.get_snapshot_diffs(block_number).await?;
+                if diffs.is_empty() && block_number > latest_block_number {
+                    break;
+                }
+                for diff in diffs {
+                    state_snapshot.add_layer(block_number, diff);
+                }
+                block_number += 1;
+            }
+        }
+        let latest_state_root = match self.get_latest_canonical_block_hash().await? {
+            Some(block_hash) => self
+                .get_block_header_by_hash(block_hash)?
+                .map(|header| (block_hash, header.state_root)),
+            None => None,
+        };
+        let is_outdated =
+            latest_state_root.is_some_and(|(_, state_root)| !state_snapshot.covers(state_root));
+        self.state_snapshot = Some(Arc::new(RwLock::new(state_snapshot)));
+        if let (true, Some((block_hash, _))) = (is_outdated, latest_state_root) {
+            self.rebuild_state_snapshot(block_hash).await?;
+        }
+        info!("State snapshot enabled");
+        Ok(self)
+    }
+
+    /// Generates the disk layer of the state snapshot from the state of the given block,
+    /// dropping all diff layers. Used when the state that new blocks are built on is not
+    /// covered by the snapshot, as when it is rebuilt during snap sync.
+    /// Does nothing if the state snapshot is not enabled.
+    pub async fn rebuild_state_snapshot(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        let Some(state_snapshot) = &self.state_snapshot else {
+            return Ok(());
+        };
+        let Some(header) = self.get_block_header_by_hash(block_hash)? else {
+            return Ok(());
+        };
+        info!(
+            "Generating state snapshot from the state of block {}",
+            header.number
+        );
+        state_snapshot
+            .write()
+            .map_err(|error| StoreError::StateSnapshotWriteLock(error.to_string()))?
+            .reset(None);
+        self.engine.clear_flat_state().await?;
+
+        let mut changes = StateChangeSet::default();
+        let write_batch = |changes: &mut StateChangeSet| {
+            if changes.accounts.len() + changes.storage.len() < GENERATION_BATCH_SIZE {
+                return Ok(());
+            }
+            self.engine.write_flat_state(std::mem::take(changes), None)
+        };
+        for (hashed_address, account_state) in self.iter_accounts(header.state_root) {
+            let storage_trie = self
+                .engine
+                .open_storage_trie(hashed_address, account_state.storage_root);
+            for (hashed_key, value) in storage_trie.into_iter().content() {
+                changes.storage.push((
+                    hashed_address,
+                    H256::from_slice(&hashed_key),
+                    U256::decode(&value)?,
+                ));
+                write_batch(&mut changes)?;
+            }
+            changes.accounts.push((hashed_address, Some(account_state)));
+            write_batch(&mut changes)?;
+        }
+        let disk = Some((header.number, header.state_root));
+        self.engine.write_flat_state(changes, disk)?;
+        state_snapshot
+            .write()
+            .map_err(|error| StoreError::StateSnapshotWriteLock(error.to_string()))?
+            .reset(disk);
+        info!("State snapshot generated");
+        Ok(())
+    }
+
+    /// Records the changes made to the state by a block, in the state history in archive mode
+    /// and as a diff layer of the state snapshot.
+    /// The changes of a batch of blocks are recorded in the state history at `history_block_number`,
+    /// the first block of the batch whose state is the resulting one.
+    async fn record_state_changes(
+        &self,
+        block_number: BlockNumber,
+        history_block_number: BlockNumber,
+        parent_root: H256,
+        state_root: H256,
+        changes: Option<StateChangeSet>,
+    ) -> Result<(), StoreError> {
+        let Some(changes) = changes else {
+            return Ok(());
+        };
+        if self.is_archive() {
+            self.engine
+                .add_state_changes(history_block_number, state_root, changes.clone())
+                .await?;
+        }
+        let diff = SnapshotDiff {
+            parent_root,
+            state_root,
+            changes,
+        };
+        self.add_snapshot_layer(block_number, diff).await
+    }
+
+    /// Adds the changes made to the state by a block as a diff layer of the state snapshot,
+    /// flattening the oldest layers of its chain if there are too many of them
+    async fn add_snapshot_layer(
+        &self,
+        block_number: BlockNumber,
+        diff: SnapshotDiff,
+    ) -> Result<(), StoreError> {
+        let Some(state_snapshot) = &self.state_snapshot else {
+            return Ok(());
+        };
+        let lock_snapshot = || {
+            state_snapshot
+                .write()
+                .map_err(|error| StoreError::StateSnapshotWriteLock(error.to_string()))
+        };
+        if !lock_snapshot()?.accepts(&diff) {
+            return Ok(());
+        }
+        // The diff is persisted first so the layer can be restored on restart
+        self.engine
+            .add_snapshot_diff(block_number, diff.clone())
+            .await?;
+        let state_root = diff.state_root;
+        lock_snapshot()?.add_layer(block_number, diff);
+        match block_number.checked_sub(MAX_DIFF_LAYERS) {
+            Some(max_block) => self.flatten_state_snapshot(state_root, max_block).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Flattens into the disk layer of the state snapshot the diff layers of the blocks up to
+    /// `max_block` in the chain of the given state
+    async fn flatten_state_snapshot(
+        &self,
+        state_root: H256,
+        max_block: BlockNumber,
+    ) -> Result<(), StoreError> {
+        let Some(state_snapshot) = &self.state_snapshot else {
+            return Ok(());
+        };
+        let (previous_disk_block, disk_block) = {
+            // Hold the lock while writing so the disk layer is not read meanwhile
+            let mut state_snapshot = state_snapshot
+                .write()
+                .map_err(|error| StoreError::StateSnapshotWriteLock(error.to_string()))?;
+            let Some((previous_disk_block, _)) = state_snapshot.disk() else {
+                return Ok(());
+            };
+            for layer_root in state_snapshot.layers_to_flatten(state_root, max_block) {
+                let Some((block_number, changes)) = state_snapshot.layer_changes(layer_root) else {
+                    break;
+                };
+                self.engine
+                    .write_flat_state(changes, Some((block_number, layer_root)))?;
+                state_snapshot.flatten(layer_root);
+            }
+            let disk_block = state_snapshot
+                .disk()
+                .map_or(previous_disk_block, |(block_number, _)| block_number);
+            (previous_disk_block, disk_block)
+        };
+        // Diffs of the blocks up to the disk layer are no longer needed
+        for block_number in previous_disk_block + 1..=disk_block {
+            self.engine.remove_snapshot_diffs(block_number).await?;
+        }
+        Ok(())
+    }
+
+    /// Reads an account from the state snapshot,
+    /// returns `None` if the snapshot doesn't cover the given state
+    fn get_account_state_from_snapshot(
+        &self,
+        state_root: H256,
+        hashed_address: H256,
+    ) -> Result<Option<Option<AccountState>>, StoreError> {
+        let Some(state_snapshot) = &self.state_snapshot else {
+            return Ok(None);
+        };
+        // Hold the lock while reading so the disk layer is not modified meanwhile
+        let state_snapshot = state_snapshot
+            .read()
+            .map_err(|error| StoreError::StateSnapshotReadLock(error.to_string()))?;
+        match state_snapshot.get_account(state_root, hashed_address) {
+            LayerRead::Diff(account_state) => Ok(Some(account_state)),
+            LayerRead::Disk => Ok(Some(self.engine.get_flat_account(hashed_address)?)),
+            LayerRead::Missing => Ok(None),
+        }
+    }
+
+    /// Reads a storage slot from the state snapshot,
+    /// returns `None` if the snapshot doesn't cover the given state
+    fn get_storage_from_snapshot(
+        &self,
+        state_root: H256,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<Option<U256>>, StoreError> {
+        let Some(state_snapshot) = &self.state_snapshot else {
+            return Ok(None);
+        };
+        // Hold the lock while reading so the disk layer is not modified meanwhile
+        let state_snapshot = state_snapshot
+            .read()
+            .map_err(|error| StoreError::StateSnapshotReadLock(error.to_string()))?;
+        match state_snapshot.get_storage(state_root, hashed_address, hashed_key) {
+            LayerRead::Diff(value) => Ok(Some(Some(value).filter(|value| !value.is_zero()))),
+            LayerRead::Disk => Ok(Some(
+                self.engine.get_flat_storage(hashed_address, hashed_key)?,
+            )),
+            LayerRead::Missing => Ok(None),
+        }
+    }
+
     pub async fn get_account_info(
         &self,
         block_number: BlockNumber,
         address: Address,
     ) -> Result<Option<AccountInfo>, StoreError> {
-        match self.get_canonical_block_hash(block_number).await? {
-            Some(block_hash) => self.get_account_info_by_hash(block_hash, address),
-            None => Ok(None),
+        let Some(block_hash) = self.get_canonical_block_hash(block_number).await? else {
+            return Ok(None);
+        };
+        if let Some(history_start) = self.state_history_start(block_number, block_hash)? {
+            let account_state = self
+                .get_account_state_from_history(history_start, block_number, address)
+                .await?;
+            return Ok(account_state.map(|account_state| AccountInfo {
+                code_hash: account_state.code_hash,
+                balance: account_state.balance,
+                nonce: account_state.nonce,
+            }));
         }
+        self.get_account_info_by_hash(block_hash, address)
+    }
+
+    /// Reads the state of an account at a canonical block from the state history
+    async fn get_account_state_from_history(
+        &self,
+        history_start: BlockNumber,
+        block_number: BlockNumber,
+        address: Address,
+    ) -> Result<Option<AccountState>, StoreError> {
+        let hashed_address = hash_address_fixed(&address);
+        let mut block_number = block_number;
+        while let Some((changed_at, changes)) = self
+            .engine
+            .get_account_changes(hashed_address, block_number)
+            .await?
+        {
+            let state_root = self.get_canonical_state_root(changed_at).await?;
+            if let Some((_, account_state)) = changes
+                .0
+                .into_iter()
+                .find(|(root, _)| Some(*root) == state_root)
+            {
+                return Ok(account_state);
+            }
+            // Only blocks that are no longer canonical modified the account at this height
+            let Some(previous) = changed_at.checked_sub(1) else {
+                break;
+            };
+            block_number = previous;
+        }
+        // The account wasn't modified since the history started
+        self.get_account_state(history_start - 1, address).await
+    }
+
+    /// Returns the state root of the canonical block with the given number
+    async fn get_canonical_state_root(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<H256>, StoreError> {
+        let Some(block_hash) = self.get_canonical_block_hash(block_number).await? else {
+            return Ok(None);
+        };
+        Ok(self
+            .get_block_header_by_hash(block_hash)?
+            .map(|header| header.state_root))
     }
 
     pub fn get_account_info_by_hash(
@@ -125,15 +533,8 @@ impl Store {
         block_hash: BlockHash,
         address: Address,
     ) -> Result<Option<AccountInfo>, StoreError> {
-        let Some(state_trie) = self.state_trie(block_hash)? else {
-            return Ok(None);
-        };
-        let hashed_address = hash_address(&address);
-        let Some(encoded_state) = state_trie.get(&hashed_address)? else {
-            return Ok(None);
-        };
-        let account_state = AccountState::decode(&encoded_state)?;
-        Ok(Some(AccountInfo {
+        let account_state = self.get_account_state_by_hash(block_hash, address)?;
+        Ok(account_state.map(|account_state| AccountInfo {
             code_hash: account_state.code_hash,
             balance: account_state.balance,
             nonce: account_state.nonce,
@@ -335,24 +736,153 @@ impl Store {
         block_hash: BlockHash,
         account_updates: &[AccountUpdate],
     ) -> Result<Option<H256>, StoreError> {
-        let Some(state_trie) = self.state_trie(block_hash)? else {
+        let Some(header) = self.get_block_header_by_hash(block_hash)? else {
             return Ok(None);
         };
+        self.apply_account_updates_batch(
+            block_hash,
+            header.number + 1,
+            header.number + 1,
+            account_updates,
+        )
+        .await
+    }
 
-        let mut state_trie = self
-            .apply_account_updates_from_trie(state_trie, account_updates)
+    /// Applies the account updates of a batch of blocks based on the state of the batch's parent block
+    /// and returns the new state root, which belongs to the block `last_block_number`.
+    /// In archive mode the changes are recorded in the state history at `history_block_number`,
+    /// the first block of the batch whose state root is the new one, as the state of the
+    /// previous blocks of the batch is not stored.
+    pub async fn apply_account_updates_batch(
+        &self,
+        parent_hash: BlockHash,
+        history_block_number: BlockNumber,
+        last_block_number: BlockNumber,
+        account_updates: &[AccountUpdate],
+    ) -> Result<Option<H256>, StoreError> {
+        let Some(state_trie) = self.state_trie(parent_hash)? else {
+            return Ok(None);
+        };
+        let parent_state_root = state_trie.hash_no_commit();
+        // The changes are recorded in the state history in archive mode,
+        // and kept as a diff layer by the state snapshot
+        let mut state_changes =
+            (self.is_archive() || self.state_snapshot.is_some()).then(StateChangeSet::default);
+
+        let Some(state_pruner) = &self.state_pruner else {
+            self.add_account_codes(account_updates).await?;
+            let mut state_trie =
+                self.update_tries(state_trie, account_updates, None, state_changes.as_mut())?;
+            let state_root = state_trie.hash()?;
+            self.record_state_changes(
+                last_block_number,
+                history_block_number,
+                parent_state_root,
+                state_root,
+                state_changes,
+            )
             .await?;
-        Ok(Some(state_trie.hash()?))
+            return Ok(Some(state_root));
+        };
+
+        // Journal the nodes written and removed by the updates so they can be pruned later on
+        self.add_account_codes(account_updates).await?;
+        let (state_root, journal) = {
+            // Hold the lock while writing so nodes are not pruned before being reinserted
+            let mut state_pruner = state_pruner
+                .lock()
+                .map_err(|error| StoreError::StatePrunerLock(error.to_string()))?;
+            let mut storage_tries = vec![];
+            let mut state_trie = self.update_tries(
+                state_trie,
+                account_updates,
+                Some(&mut storage_tries),
+                state_changes.as_mut(),
+            )?;
+            let (state_root, state_trie) = state_trie.hash_with_diff(parent_state_root)?;
+            let journal = StateJournal {
+                state_root,
+                state_trie,
+                storage_tries,
+            };
+            // Blocks executed more than once only need to be journaled the first time
+            let is_new = state_pruner.record(last_block_number, &journal);
+            (state_root, is_new.then_some(journal))
+        };
+        if let Some(journal) = journal {
+            self.engine
+                .add_state_journal(last_block_number, journal)
+                .await?;
+        }
+        self.record_state_changes(
+            last_block_number,
+            history_block_number,
+            parent_state_root,
+            state_root,
+            state_changes,
+        )
+        .await?;
+        Ok(Some(state_root))
     }
 
     pub async fn apply_account_updates_from_trie(
+        &self,
+        state_trie: Trie,
+        account_updates: &[AccountUpdate],
+    ) -> Result<Trie, StoreError> {
+        self.add_account_codes(account_updates).await?;
+        self.update_tries(state_trie, account_updates, None, None)
+    }
+
+    /// Stores the updated code of each account
+    async fn add_account_codes(&self, account_updates: &[AccountUpdate]) -> Result<(), StoreError> {
+        for update in account_updates.iter().filter(|update| !update.removed) {
+            if let (Some(info), Some(code)) = (&update.info, &update.code) {
+                self.add_account_code(info.code_hash, code.clone()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the account updates to the state trie and the storage tries.
+    /// If `storage_diffs` is set, the changes to each storage trie are appended to it.
+    /// If `state_changes` is set, the new state of the modified accounts and slots is appended to it.
+    fn update_tries(
         &self,
         mut state_trie: Trie,
         account_updates: &[AccountUpdate],
+        mut storage_diffs: Option<&mut Vec<(H256, TrieDiff)>>,
+        mut state_changes: Option<&mut StateChangeSet>,
     ) -> Result<Trie, StoreError> {
         for update in account_updates.iter() {
             let hashed_address = hash_address(&update.address);
+            let account_hash = H256::from_slice(&hashed_address);
             if update.removed {
+                if let Some(encoded_state) = state_trie.get(&hashed_address)? {
+                    let account_state = AccountState::decode(&encoded_state)?;
+                    // The whole storage trie of a removed account becomes unreachable
+                    if let Some(storage_diffs) = storage_diffs.as_mut() {
+                        let (_, diff) = self
+                            .engine
+                            .open_storage_trie(account_hash, *EMPTY_TRIE_HASH)
+                            .hash_with_diff(account_state.storage_root)?;
+                        storage_diffs.push((account_hash, diff));
+                    }
+                    // And all of its slots are cleared
+                    if let Some(state_changes) = state_changes.as_mut() {
+                        let storage_trie = self
+                            .engine
+                            .open_storage_trie(account_hash, account_state.storage_root);
+                        for (hashed_key, _) in storage_trie.into_iter().content() {
+                            state_changes.storage.push((
+                                account_hash,
+                                H256::from_slice(&hashed_key),
+                                U256::zero(),
+                            ));
+                        }
+                        state_changes.accounts.push((account_hash, None));
+                    }
+                }
                 // Remove account from trie
                 state_trie.remove(hashed_address)?;
             } else {
@@ -366,28 +896,43 @@ impl Store {
                     account_state.nonce = info.nonce;
                     account_state.balance = info.balance;
                     account_state.code_hash = info.code_hash;
-                    // Store updated code in DB
-                    if let Some(code) = &update.code {
-                        self.add_account_code(info.code_hash, code.clone()).await?;
-                    }
                 }
                 // Store the added storage in the account's storage trie and compute its new root
                 if !update.added_storage.is_empty() {
-                    let mut storage_trie = self.engine.open_storage_trie(
-                        H256::from_slice(&hashed_address),
-                        account_state.storage_root,
-                    );
+                    let mut storage_trie = self
+                        .engine
+                        .open_storage_trie(account_hash, account_state.storage_root);
                     for (storage_key, storage_value) in &update.added_storage {
                         let hashed_key = hash_key(storage_key);
+                        if let Some(state_changes) = state_changes.as_mut() {
+                            state_changes.storage.push((
+                                account_hash,
+                                H256::from_slice(&hashed_key),
+                                *storage_value,
+                            ));
+                        }
                         if storage_value.is_zero() {
                             storage_trie.remove(hashed_key)?;
                         } else {
                             storage_trie.insert(hashed_key, storage_value.encode_to_vec())?;
                         }
                     }
-                    account_state.storage_root = storage_trie.hash()?;
+                    account_state.storage_root = match storage_diffs.as_mut() {
+                        Some(storage_diffs) => {
+                            let (storage_root, diff) =
+                                storage_trie.hash_with_diff(account_state.storage_root)?;
+                            storage_diffs.push((account_hash, diff));
+                            storage_root
+                        }
+                        None => storage_trie.hash()?,
+                    };
                 }
                 state_trie.insert(hashed_address, account_state.encode_to_vec())?;
+                if let Some(state_changes) = state_changes.as_mut() {
+                    state_changes
+                        .accounts
+                        .push((account_hash, Some(account_state)));
+                }
             }
         }
 
@@ -542,7 +1087,49 @@ impl Store {
         address: Address,
         storage_key: H256,
     ) -> Result<Option<U256>, StoreError> {
-        match self.get_canonical_block_hash(block_number).await? {
+        let Some(block_hash) = self.get_canonical_block_hash(block_number).await? else {
+            return Ok(None);
+        };
+        if let Some(history_start) = self.state_history_start(block_number, block_hash)? {
+            return self
+                .get_storage_from_history(history_start, block_number, address, storage_key)
+                .await;
+        }
+        self.get_storage_at_hash(block_hash, address, storage_key)
+    }
+
+    /// Reads the value of a storage slot at a canonical block from the state history
+    async fn get_storage_from_history(
+        &self,
+        history_start: BlockNumber,
+        block_number: BlockNumber,
+        address: Address,
+        storage_key: H256,
+    ) -> Result<Option<U256>, StoreError> {
+        let hashed_address = hash_address_fixed(&address);
+        let hashed_key = H256::from_slice(&hash_key(&storage_key));
+        let mut block_number = block_number;
+        while let Some((changed_at, changes)) = self
+            .engine
+            .get_storage_changes(hashed_address, hashed_key, block_number)
+            .await?
+        {
+            let state_root = self.get_canonical_state_root(changed_at).await?;
+            if let Some((_, value)) = changes
+                .into_iter()
+                .find(|(root, _)| Some(*root) == state_root)
+            {
+                // Cleared slots are not present in the storage trie either
+                return Ok((!value.is_zero()).then_some(value));
+            }
+            // Only blocks that are no longer canonical modified the slot at this height
+            let Some(previous) = changed_at.checked_sub(1) else {
+                break;
+            };
+            block_number = previous;
+        }
+        // The slot wasn't modified since the history started
+        match self.get_canonical_block_hash(history_start - 1).await? {
             Some(block_hash) => self.get_storage_at_hash(block_hash, address, storage_key),
             None => Ok(None),
         }
@@ -554,10 +1141,21 @@ impl Store {
         address: Address,
         storage_key: H256,
     ) -> Result<Option<U256>, StoreError> {
-        let Some(storage_trie) = self.storage_trie(block_hash, address)? else {
+        let Some(state_root) = self.state_root(block_hash)? else {
             return Ok(None);
         };
+        let hashed_address = H256::from_slice(&hash_address(&address));
         let hashed_key = hash_key(&storage_key);
+        if let Some(value) = self.get_storage_from_snapshot(
+            state_root,
+            hashed_address,
+            H256::from_slice(&hashed_key),
+        )? {
+            return Ok(value);
+        }
+        let Some(storage_trie) = self.storage_trie(block_hash, address)? else {
+            return Ok(None);
+        };
         storage_trie
             .get(&hashed_key)?
             .map(|rlp| U256::decode(&rlp).map_err(StoreError::RLPDecode))
@@ -592,7 +1190,15 @@ impl Store {
     ) -> Result<(), StoreError> {
         self.engine
             .update_finalized_block_number(block_number)
-            .await
+            .await?;
+        // Finalized blocks can't be reorged, so their diff layers are flattened into the disk layer
+        if self.state_snapshot.is_none() {
+            return Ok(());
+        }
+        match self.get_canonical_state_root(block_number).await? {
+            Some(state_root) => self.flatten_state_snapshot(state_root, block_number).await,
+            None => Ok(()),
+        }
     }
 
     pub async fn get_finalized_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
@@ -614,7 +1220,72 @@ impl Store {
         &self,
         block_number: BlockNumber,
     ) -> Result<(), StoreError> {
-        self.engine.update_latest_block_number(block_number).await
+        self.engine.update_latest_block_number(block_number).await?;
+        self.prune_state(block_number).await
+    }
+
+    /// Deletes the trie nodes that are only reachable from the states that fell out of
+    /// the retention window after advancing the head to `head`
+    async fn prune_state(&self, head: BlockNumber) -> Result<(), StoreError> {
+        let Some(state_pruner) = &self.state_pruner else {
+            return Ok(());
+        };
+        let lock_pruner = || {
+            state_pruner
+                .lock()
+                .map_err(|error| StoreError::StatePrunerLock(error.to_string()))
+        };
+        let (target, block_numbers) = {
+            let state_pruner = lock_pruner()?;
+            let Some(target) = state_pruner.pruning_target(head) else {
+                return Ok(());
+            };
+            (target, state_pruner.journaled_blocks(target))
+        };
+
+        for block_number in block_numbers {
+            let journals = self.engine.get_state_journals(block_number).await?;
+            let canonical_root = match self.get_canonical_block_hash(block_number).await? {
+                Some(block_hash) => self
+                    .get_block_header_by_hash(block_hash)?
+                    .map(|header| header.state_root),
+                None => None,
+            };
+            {
+                let mut state_pruner = lock_pruner()?;
+                state_pruner.earliest_state = state_pruner.earliest_state.max(block_number);
+                let prunable = state_pruner.prune(block_number, &journals, canonical_root);
+                self.engine
+                    .open_state_trie(*EMPTY_TRIE_HASH)
+                    .state_mut()
+                    .remove_node_batch(&prunable.state_trie)?;
+                for (account_hash, node_hashes) in prunable.storage_tries {
+                    self.engine
+                        .open_storage_trie(account_hash, *EMPTY_TRIE_HASH)
+                        .state_mut()
+                        .remove_node_batch(&node_hashes)?;
+                }
+            }
+            self.engine.remove_state_journals(block_number).await?;
+        }
+
+        {
+            let mut state_pruner = lock_pruner()?;
+            state_pruner.earliest_state = state_pruner.earliest_state.max(target);
+        }
+        self.engine.update_earliest_state_block_number(target).await
+    }
+
+    /// Returns the number of the earliest block whose state is available,
+    /// or `None` if state pruning is disabled
+    pub fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        let Some(state_pruner) = &self.state_pruner else {
+            return Ok(None);
+        };
+        let state_pruner = state_pruner
+            .lock()
+            .map_err(|error| StoreError::StatePrunerLock(error.to_string()))?;
+        Ok(Some(state_pruner.earliest_state))
     }
 
     pub async fn get_latest_block_number(&self) -> Result<BlockNumber, StoreError> {
@@ -665,12 +1336,126 @@ impl Store {
         self.engine.unset_canonical_block(number).await
     }
 
+    /// Rewinds the canonical chain to the given block, making it the latest block.
+    /// The blocks after it are removed from the canonical chain along with their transaction
+    /// locations and receipts, so the state the chain continues from is the given block's.
+    /// Fails if the block is not canonical or its state is not available.
+    pub async fn rewind(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        let latest_block_number = self.get_latest_block_number().await?;
+        if block_number > latest_block_number {
+            return Err(StoreError::RewindTargetNotCanonical(block_number));
+        }
+        let Some(block_hash) = self.get_canonical_block_hash(block_number).await? else {
+            return Err(StoreError::RewindTargetNotCanonical(block_number));
+        };
+        let state_root = match self.state_root(block_hash) {
+            Ok(Some(state_root)) => state_root,
+            Ok(None) | Err(StoreError::StatePruned(_)) => {
+                return Err(StoreError::RewindStateUnavailable(block_number))
+            }
+            Err(error) => return Err(error),
+        };
+        // The state may be missing even if it wasn't pruned, as for the blocks before a snap sync
+        if state_root != *EMPTY_TRIE_HASH && !self.contains_state_node(state_root)? {
+            return Err(StoreError::RewindStateUnavailable(block_number));
+        }
+        info!("Rewinding chain from block {latest_block_number} to block {block_number}");
+
+        // The rewound blocks are removed from the top before moving the head, so an interrupted
+        // rewind leaves a canonical chain that can be rewound again
+        for number in (block_number + 1..=latest_block_number).rev() {
+            let Some(hash) = self.get_canonical_block_hash(number).await? else {
+                continue;
+            };
+            if let Some(body) = self.get_block_body_by_hash(hash).await? {
+                let locations = body
+                    .transactions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, transaction)| {
+                        (transaction.compute_hash(), number, hash, index as Index)
+                    })
+                    .collect();
+                self.engine.remove_transaction_locations(locations).await?;
+            }
+            self.engine.remove_receipts(hash).await?;
+            self.unset_canonical_block(number).await?;
+        }
+
+        // Indexed bloom sections containing rewound blocks must be indexed again
+        let first_stale_section = (block_number + 1) / BLOOM_SECTION_SIZE;
+        if self
+            .engine
+            .get_bloom_indexed_sections()
+            .await?
+            .is_some_and(|sections| sections > first_stale_section)
+        {
+            self.engine
+                .remove_bloom_sections(first_stale_section)
+                .await?;
+        }
+
+        // The state tries of the rewound blocks are left to the pruner and the state history
+        // reads only the changes of canonical blocks, but the snapshot must cover the new head
+        if let Some(state_snapshot) = &self.state_snapshot {
+            let covered = state_snapshot
+                .read()
+                .map_err(|error| StoreError::StateSnapshotReadLock(error.to_string()))?
+                .covers(state_root);
+            if !covered {
+                self.rebuild_state_snapshot(block_hash).await?;
+            }
+        }
+
+        self.update_latest_block_number(block_number).await?;
+        if self
+            .get_safe_block_number()
+            .await?
+            .is_some_and(|safe| safe > block_number)
+        {
+            self.engine.update_safe_block_number(block_number).await?;
+        }
+        if self
+            .get_finalized_block_number()
+            .await?
+            .is_some_and(|finalized| finalized > block_number)
+        {
+            self.engine
+                .update_finalized_block_number(block_number)
+                .await?;
+        }
+        if self
+            .get_pending_block_number()
+            .await?
+            .is_some_and(|pending| pending > block_number)
+        {
+            self.engine
+                .update_pending_block_number(block_number)
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Obtain the storage trie for the given block
+    /// Fails with [StoreError::StatePruned] if the block's state is no longer retained
     pub fn state_trie(&self, block_hash: BlockHash) -> Result<Option<Trie>, StoreError> {
+        Ok(self
+            .state_root(block_hash)?
+            .map(|state_root| self.engine.open_state_trie(state_root)))
+    }
+
+    /// Obtain the state root of the given block, failing if its state was pruned
+    fn state_root(&self, block_hash: BlockHash) -> Result<Option<H256>, StoreError> {
         let Some(header) = self.get_block_header_by_hash(block_hash)? else {
             return Ok(None);
         };
-        Ok(Some(self.engine.open_state_trie(header.state_root)))
+        if self
+            .get_earliest_state_block_number()?
+            .is_some_and(|earliest_state| header.number < earliest_state)
+        {
+            return Err(StoreError::StatePruned(header.number));
+        }
+        Ok(Some(header.state_root))
     }
 
     /// Obtain the storage trie for the given account on the given block
@@ -715,9 +1500,16 @@ impl Store {
         block_hash: BlockHash,
         address: Address,
     ) -> Result<Option<AccountState>, StoreError> {
-        let Some(state_trie) = self.state_trie(block_hash)? else {
+        let Some(state_root) = self.state_root(block_hash)? else {
             return Ok(None);
         };
+        let hashed_address = H256::from_slice(&hash_address(&address));
+        if let Some(account_state) =
+            self.get_account_state_from_snapshot(state_root, hashed_address)?
+        {
+            return Ok(account_state);
+        }
+        let state_trie = self.engine.open_state_trie(state_root);
         self.get_account_state_from_trie(&state_trie, address)
     }
 
@@ -1120,6 +1912,90 @@ impl Store {
             .set_latest_valid_ancestor(bad_block, latest_valid)
             .await
     }
+
+    /// Indexes the logs blooms of the next section of canonical blocks, once it is
+    /// `BLOOM_INDEX_CONFIRMATIONS` blocks deep. Returns the indexed section, if any.
+    pub async fn index_next_bloom_section(&self) -> Result<Option<u64>, StoreError> {
+        let section = self
+            .engine
+            .get_bloom_indexed_sections()
+            .await?
+            .unwrap_or_default();
+        let first_block = section * BLOOM_SECTION_SIZE;
+        let last_block = first_block + BLOOM_SECTION_SIZE - 1;
+        if self.get_latest_block_number().await? < last_block + BLOOM_INDEX_CONFIRMATIONS {
+            return Ok(None);
+        }
+
+        let mut builder = BloomSectionBuilder::new();
+        for block_number in first_block..=last_block {
+            let Some(header) = self.get_block_header(block_number)? else {
+                debug!(
+                    "Missing header for block {block_number}, cannot index bloom section {section}"
+                );
+                return Ok(None);
+            };
+            builder.add_bloom(block_number - first_block, &header.logs_bloom);
+        }
+        self.engine
+            .add_bloom_section(section, builder.finish())
+            .await?;
+        Ok(Some(section))
+    }
+
+    /// Returns the numbers of the canonical blocks within `from..=to` whose logs bloom
+    /// matches the filter. Indexed sections are matched using their bloom bits, and the
+    /// rest of the blocks using the logs bloom of their headers.
+    /// Blocks whose header is not available are returned as well.
+    pub async fn get_blocks_matching_bloom(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        filter: &BloomFilter,
+    ) -> Result<Vec<BlockNumber>, StoreError> {
+        if filter.matches_all() {
+            return Ok((from..=to).collect());
+        }
+        let indexed_sections = self
+            .engine
+            .get_bloom_indexed_sections()
+            .await?
+            .unwrap_or_default();
+
+        let mut blocks = Vec::new();
+        let mut section_start = from;
+        while section_start <= to {
+            let section = section_start / BLOOM_SECTION_SIZE;
+            let first_block = section * BLOOM_SECTION_SIZE;
+            let section_end = (first_block + BLOOM_SECTION_SIZE - 1).min(to);
+
+            if section < indexed_sections {
+                let mut vectors = HashMap::new();
+                for bit in filter.bits() {
+                    if let Some(vector) = self.engine.get_bloom_bits(section, bit).await? {
+                        vectors.insert(bit, vector);
+                    }
+                }
+                let matches = filter.match_section(&vectors);
+                blocks.extend(
+                    (section_start..=section_end).filter(|block_number| {
+                        vector_contains(&matches, block_number - first_block)
+                    }),
+                );
+            } else {
+                for block_number in section_start..=section_end {
+                    let matches = self
+                        .get_block_header(block_number)?
+                        .is_none_or(|header| filter.matches(&header.logs_bloom));
+                    if matches {
+                        blocks.push(block_number);
+                    }
+                }
+            }
+            section_start = section_end + 1;
+        }
+        Ok(blocks)
+    }
 }
 
 pub fn hash_address(address: &Address) -> Vec<u8> {
@@ -1147,7 +2023,7 @@ mod tests {
     use ethereum_types::{H256, U256};
     use ethrex_common::{
         types::{Transaction, TxType, EMPTY_KECCACK_HASH},
-        Bloom, H160,
+        Bloom, BloomInput, H160,
     };
     use ethrex_rlp::decode::RLPDecode;
     use std::{fs, panic, str::FromStr};
@@ -1201,6 +2077,560 @@ mod tests {
         run_test(test_store_block_tags, engine_type).await;
         run_test(test_chain_config_storage, engine_type).await;
         run_test(test_genesis_block, engine_type).await;
+        run_test(test_state_pruning, engine_type).await;
+        run_test(test_state_history, engine_type).await;
+        run_test(test_state_history_batch, engine_type).await;
+        run_test(test_state_snapshot, engine_type).await;
+        run_test(test_bloom_index, engine_type).await;
+        run_test(test_rewind, engine_type).await;
+    }
+
+    async fn test_bloom_index(store: Store) {
+        let address = H160::from_low_u64_be(0xaa);
+        let other_address = H160::from_low_u64_be(0xbb);
+        let matching_blocks = [5, 1000, BLOOM_SECTION_SIZE + 100];
+        let latest_block = BLOOM_SECTION_SIZE - 1 + BLOOM_INDEX_CONFIRMATIONS;
+        let blocks: Vec<Block> = (0..=latest_block)
+            .map(|number| {
+                let mut logs_bloom = Bloom::zero();
+                if matching_blocks.contains(&number) {
+                    logs_bloom.accrue(BloomInput::Raw(address.as_bytes()));
+                } else if number % 7 == 0 {
+                    logs_bloom.accrue(BloomInput::Raw(other_address.as_bytes()));
+                }
+                let header = BlockHeader {
+                    number,
+                    logs_bloom,
+                    ..Default::default()
+                };
+                Block::new(header, BlockBody::default())
+            })
+            .collect();
+        store.add_blocks(blocks.clone()).await.unwrap();
+        store.mark_chain_as_canonical(&blocks).await.unwrap();
+        store
+            .update_latest_block_number(latest_block - 1)
+            .await
+            .unwrap();
+
+        let filter = BloomFilter::new([vec![address.as_bytes()]]);
+        let matches = store
+            .get_blocks_matching_bloom(0, latest_block, &filter)
+            .await
+            .unwrap();
+        assert_eq!(matches, matching_blocks);
+
+        // The first section is indexed once it has enough confirmations
+        assert_eq!(store.index_next_bloom_section().await.unwrap(), None);
+        store
+            .update_latest_block_number(latest_block)
+            .await
+            .unwrap();
+        assert_eq!(store.index_next_bloom_section().await.unwrap(), Some(0));
+        assert_eq!(store.index_next_bloom_section().await.unwrap(), None);
+
+        let matches = store
+            .get_blocks_matching_bloom(0, latest_block, &filter)
+            .await
+            .unwrap();
+        assert_eq!(matches, matching_blocks);
+        let matches = store
+            .get_blocks_matching_bloom(6, BLOOM_SECTION_SIZE, &filter)
+            .await
+            .unwrap();
+        assert_eq!(matches, vec![1000]);
+    }
+
+    async fn test_state_pruning(store: Store) {
+        let mut parent_hash = add_kurtosis_genesis(&store).await.get_block().hash();
+        let store = store.with_state_retention(2).await.unwrap();
+
+        let address = H160::from_low_u64_be(0xaa);
+        let removed_address = H160::from_low_u64_be(0xbb);
+        let storage_key = H256::from_low_u64_be(1);
+        let mut block_hashes = vec![parent_hash];
+        let mut state_roots = vec![];
+        for block_number in 1..=5u64 {
+            let mut update = AccountUpdate::new(address);
+            update.info = Some(AccountInfo {
+                balance: U256::from(block_number),
+                ..Default::default()
+            });
+            update.added_storage = HashMap::from([(storage_key, U256::from(block_number))]);
+            let mut removed_update = update.clone();
+            removed_update.address = removed_address;
+            let updates = match block_number {
+                3 => vec![update, AccountUpdate::removed(removed_address)],
+                _ => vec![update, removed_update],
+            };
+            let state_root = store
+                .apply_account_updates(parent_hash, &updates)
+                .await
+                .unwrap()
+                .unwrap();
+
+            let header = BlockHeader {
+                number: block_number,
+                parent_hash,
+                state_root,
+                ..Default::default()
+            };
+            let block_hash = header.compute_block_hash();
+            store.add_block_header(block_hash, header).await.unwrap();
+            store
+                .set_canonical_block(block_number, block_hash)
+                .await
+                .unwrap();
+            store
+                .update_latest_block_number(block_number)
+                .await
+                .unwrap();
+            parent_hash = block_hash;
+            block_hashes.push(block_hash);
+            state_roots.push(state_root);
+        }
+
+        // Only the state of the blocks within the retention window is available
+        assert_eq!(store.get_earliest_state_block_number().unwrap(), Some(3));
+        assert!(matches!(
+            store.state_trie(block_hashes[2]),
+            Err(StoreError::StatePruned(2))
+        ));
+        assert!(!store.contains_state_node(state_roots[0]).unwrap());
+        for (block_number, block_hash) in block_hashes.into_iter().enumerate().skip(3) {
+            let account = store
+                .get_account_info_by_hash(block_hash, address)
+                .unwrap()
+                .unwrap();
+            assert_eq!(account.balance, U256::from(block_number));
+            let storage = store
+                .get_storage_at_hash(block_hash, address, storage_key)
+                .unwrap();
+            assert_eq!(storage, Some(U256::from(block_number)));
+            let removed_storage = store
+                .get_storage_at_hash(block_hash, removed_address, storage_key)
+                .unwrap();
+            let expected = (block_number > 3).then(|| U256::from(block_number));
+            assert_eq!(removed_storage, expected);
+        }
+
+        // Archive mode can't be enabled once the state was pruned
+        assert!(matches!(
+            store.clone().with_archive_mode().await,
+            Err(StoreError::ArchiveStatePruned(3))
+        ));
+    }
+
+    /// Stores the kurtosis genesis as the initial state, returning it
+    async fn add_kurtosis_genesis(store: &Store) -> Genesis {
+        const GENESIS_KURTOSIS: &str = include_str!("../../test_data/genesis-kurtosis.json");
+        let genesis: Genesis =
+            serde_json::from_str(GENESIS_KURTOSIS).expect("deserialize genesis-kurtosis.json");
+        store.add_initial_state(genesis.clone()).await.unwrap();
+        genesis
+    }
+
+    /// Chain built by [add_state_test_chain]
+    struct StateTestChain {
+        /// Account updated by every block, its slot is only written by the even blocks
+        address: Address,
+        /// Account updated by every block, except for block 3 which removes it
+        removed_address: Address,
+        storage_key: H256,
+        /// Hashes of the canonical blocks, starting with the genesis
+        block_hashes: Vec<BlockHash>,
+        /// Hashes of the blocks at each height that didn't become canonical
+        side_block_hashes: Vec<BlockHash>,
+    }
+
+    /// Adds `blocks` canonical blocks on top of the genesis, along with a block at each height
+    /// that doesn't become canonical, so that the state of different forks is stored.
+    async fn add_state_test_chain(
+        store: &Store,
+        genesis_hash: BlockHash,
+        blocks: u64,
+    ) -> StateTestChain {
+        let address = H160::from_low_u64_be(0xaa);
+        let removed_address = H160::from_low_u64_be(0xbb);
+        let storage_key = H256::from_low_u64_be(1);
+        let updates_for = |block_number: u64, balance: u64| {
+            let mut update = AccountUpdate::new(address);
+            update.info = Some(AccountInfo {
+                balance: U256::from(balance),
+                ..Default::default()
+            });
+            // Block 4 clears the slot
+            if block_number % 2 == 0 {
+                update.added_storage = HashMap::from([(storage_key, U256::from(block_number % 4))]);
+            }
+            let mut removed_update = update.clone();
+            removed_update.address = removed_address;
+            removed_update.added_storage = HashMap::from([(storage_key, U256::from(balance))]);
+            match block_number {
+                3 => vec![update, AccountUpdate::removed(removed_address)],
+                _ => vec![update, removed_update],
+            }
+        };
+
+        let mut parent_hash = genesis_hash;
+        let mut block_hashes = vec![genesis_hash];
+        let mut side_block_hashes = vec![];
+        for block_number in 1..=blocks {
+            let side_state_root = store
+                .apply_account_updates(parent_hash, &updates_for(block_number, 100))
+                .await
+                .unwrap()
+                .unwrap();
+            let side_header = BlockHeader {
+                number: block_number,
+                parent_hash,
+                state_root: side_state_root,
+                ..Default::default()
+            };
+            let side_block_hash = side_header.compute_block_hash();
+            store
+                .add_block_header(side_block_hash, side_header)
+                .await
+                .unwrap();
+            side_block_hashes.push(side_block_hash);
+
+            let state_root = store
+                .apply_account_updates(parent_hash, &updates_for(block_number, block_number))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_ne!(side_state_root, state_root);
+            let header = BlockHeader {
+                number: block_number,
+                parent_hash,
+                state_root,
+                ..Default::default()
+            };
+            let block_hash = header.compute_block_hash();
+            store.add_block_header(block_hash, header).await.unwrap();
+            store
+                .set_canonical_block(block_number, block_hash)
+                .await
+                .unwrap();
+            store
+                .update_latest_block_number(block_number)
+                .await
+                .unwrap();
+            parent_hash = block_hash;
+            block_hashes.push(block_hash);
+        }
+
+        StateTestChain {
+            address,
+            removed_address,
+            storage_key,
+            block_hashes,
+            side_block_hashes,
+        }
+    }
+
+    async fn test_state_snapshot(store: Store) {
+        let genesis = add_kurtosis_genesis(&store).await;
+        let genesis_address = *genesis.alloc.keys().next().unwrap();
+        let store = store.with_state_snapshot().await.unwrap();
+
+        let StateTestChain {
+            address,
+            removed_address,
+            storage_key,
+            block_hashes,
+            side_block_hashes,
+        } = add_state_test_chain(&store, genesis.get_block().hash(), 5).await;
+        // Reads the state of a block from its tries
+        let read_from_tries = |store: &Store, block_hash: BlockHash, address: Address| {
+            let state_trie = store.state_trie(block_hash).unwrap().unwrap();
+            let account_state = store
+                .get_account_state_from_trie(&state_trie, address)
+                .unwrap();
+            let storage = store
+                .storage_trie(block_hash, address)
+                .unwrap()
+                .and_then(|storage_trie| storage_trie.get(&hash_key(&storage_key)).unwrap())
+                .map(|rlp| U256::decode(&rlp).unwrap());
+            (account_state, storage)
+        };
+
+        // The state read from the snapshot matches the one read from the tries
+        let assert_reads_match = |store: &Store, block_hash: BlockHash| {
+            for address in [address, removed_address, genesis_address] {
+                let (account_state, storage) = read_from_tries(store, block_hash, address);
+                assert_eq!(
+                    store
+                        .get_account_state_by_hash(block_hash, address)
+                        .unwrap(),
+                    account_state
+                );
+                assert_eq!(
+                    store
+                        .get_storage_at_hash(block_hash, address, storage_key)
+                        .unwrap(),
+                    storage
+                );
+            }
+        };
+        let is_covered = |store: &Store, block_hash: BlockHash| {
+            let state_root = store.state_root(block_hash).unwrap().unwrap();
+            let hashed_address = H256::from_slice(&hash_address(&address));
+            store
+                .get_account_state_from_snapshot(state_root, hashed_address)
+                .unwrap()
+                .is_some()
+        };
+        for block_hash in block_hashes.iter().chain(side_block_hashes.iter()) {
+            assert!(is_covered(&store, *block_hash));
+            assert_reads_match(&store, *block_hash);
+        }
+
+        // The diff layers are restored on restart
+        let restarted = Store::new("", EngineType::InMemory).unwrap();
+        let restarted = Store {
+            engine: store.engine.clone(),
+            ..restarted
+        };
+        let restarted = restarted.with_state_snapshot().await.unwrap();
+        for block_hash in block_hashes.iter() {
+            assert!(is_covered(&restarted, *block_hash));
+        }
+
+        // Finalized blocks are flattened into the disk layer, along with their siblings
+        store.update_finalized_block_number(3).await.unwrap();
+        let state_root = store.state_root(block_hashes[3]).unwrap().unwrap();
+        assert_eq!(
+            store.engine.get_flat_state_block().unwrap(),
+            Some((3, state_root))
+        );
+        for block_hash in &block_hashes[3..] {
+            assert!(is_covered(&store, *block_hash));
+            assert_reads_match(&store, *block_hash);
+        }
+        for block_hash in block_hashes[..3].iter().chain(side_block_hashes.iter()) {
+            assert!(!is_covered(&store, *block_hash));
+            assert_reads_match(&store, *block_hash);
+        }
+        assert!(store.engine.get_snapshot_diffs(3).await.unwrap().is_empty());
+        assert!(!store.engine.get_snapshot_diffs(4).await.unwrap().is_empty());
+    }
+
+    async fn test_rewind(store: Store) {
+        let mut parent_hash = add_kurtosis_genesis(&store).await.get_block().hash();
+        let store = store.with_state_snapshot().await.unwrap();
+
+        let address = H160::from_low_u64_be(0xaa);
+        let (_, body_with_transactions) = create_block_for_testing();
+        let receipt = Receipt {
+            tx_type: TxType::EIP2930,
+            succeeded: true,
+            cumulative_gas_used: 1747,
+            bloom: Bloom::random(),
+            logs: vec![],
+        };
+        let mut block_hashes = vec![parent_hash];
+        let mut state_roots = vec![];
+        for block_number in 1..=4u64 {
+            let mut update = AccountUpdate::new(address);
+            update.info = Some(AccountInfo {
+                balance: U256::from(block_number),
+                ..Default::default()
+            });
+            let state_root = store
+                .apply_account_updates(parent_hash, &[update])
+                .await
+                .unwrap()
+                .unwrap();
+            let header = BlockHeader {
+                number: block_number,
+                parent_hash,
+                state_root,
+                ..Default::default()
+            };
+            // Only the last block has transactions
+            let body = match block_number {
+                4 => body_with_transactions.clone(),
+                _ => BlockBody::default(),
+            };
+            let receipts = vec![receipt.clone(); body.transactions.len()];
+            let block = Block::new(header, body);
+            let block_hash = block.hash();
+            store.add_block(block).await.unwrap();
+            store.add_receipts(block_hash, receipts).await.unwrap();
+            store
+                .set_canonical_block(block_number, block_hash)
+                .await
+                .unwrap();
+            store
+                .update_latest_block_number(block_number)
+                .await
+                .unwrap();
+            parent_hash = block_hash;
+            block_hashes.push(block_hash);
+            state_roots.push(state_root);
+        }
+        // The safe and finalized blocks past the rewind target must be moved back too
+        store.update_safe_block_number(4).await.unwrap();
+        store.update_finalized_block_number(4).await.unwrap();
+        for (index, transaction) in body_with_transactions.transactions.iter().enumerate() {
+            assert_eq!(
+                store
+                    .get_transaction_location(transaction.compute_hash())
+                    .await
+                    .unwrap(),
+                Some((4, block_hashes[4], index as Index))
+            );
+        }
+
+        assert!(matches!(
+            store.rewind(5).await,
+            Err(StoreError::RewindTargetNotCanonical(5))
+        ));
+        store.rewind(2).await.unwrap();
+
+        assert_eq!(store.get_latest_block_number().await.unwrap(), 2);
+        assert_eq!(store.get_safe_block_number().await.unwrap(), Some(2));
+        assert_eq!(store.get_finalized_block_number().await.unwrap(), Some(2));
+        assert_eq!(
+            store.get_latest_canonical_block_hash().await.unwrap(),
+            Some(block_hashes[2])
+        );
+        for block_number in 3..=4 {
+            assert_eq!(
+                store.get_canonical_block_hash(block_number).await.unwrap(),
+                None
+            );
+        }
+        for transaction in body_with_transactions.transactions.iter() {
+            assert_eq!(
+                store
+                    .get_transaction_location(transaction.compute_hash())
+                    .await
+                    .unwrap(),
+                None
+            );
+        }
+        assert!(store
+            .get_receipts_for_block(&block_hashes[4])
+            .unwrap()
+            .is_empty());
+
+        // The state is the one of the new head, and the snapshot was generated from it
+        assert_eq!(
+            store.engine.get_flat_state_block().unwrap(),
+            Some((2, state_roots[1]))
+        );
+        let account = store
+            .get_account_info_by_hash(block_hashes[2], address)
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.balance, U256::from(2));
+    }
+
+    async fn test_state_history(store: Store) {
+        let genesis = add_kurtosis_genesis(&store).await;
+        let genesis_address = *genesis.alloc.keys().next().unwrap();
+        let store = store.with_archive_mode().await.unwrap();
+
+        let StateTestChain {
+            address,
+            removed_address,
+            storage_key,
+            block_hashes,
+            ..
+        } = add_state_test_chain(&store, genesis.get_block().hash(), 5).await;
+
+        // The state read from the history matches the one read from the tries
+        for (block_number, block_hash) in block_hashes.into_iter().enumerate() {
+            let block_number = block_number as u64;
+            for address in [address, removed_address, genesis_address] {
+                assert_eq!(
+                    store.get_account_info(block_number, address).await.unwrap(),
+                    store.get_account_info_by_hash(block_hash, address).unwrap()
+                );
+                assert_eq!(
+                    store
+                        .get_storage_at(block_number, address, storage_key)
+                        .await
+                        .unwrap(),
+                    store
+                        .get_storage_at_hash(block_hash, address, storage_key)
+                        .unwrap()
+                );
+            }
+        }
+        let account = store.get_account_info(3, address).await.unwrap().unwrap();
+        assert_eq!(account.balance, U256::from(3));
+        let storage = store.get_storage_at(3, address, storage_key).await.unwrap();
+        assert_eq!(storage, Some(U256::from(2)));
+        assert_eq!(
+            store.get_account_info(3, removed_address).await.unwrap(),
+            None
+        );
+        let removed_storage = store
+            .get_storage_at(4, removed_address, storage_key)
+            .await
+            .unwrap();
+        assert_eq!(removed_storage, Some(U256::from(4)));
+    }
+
+    async fn test_state_history_batch(store: Store) {
+        let genesis = add_kurtosis_genesis(&store).await;
+        let (genesis_address, genesis_account) = genesis.alloc.iter().next().unwrap();
+        let (genesis_address, genesis_balance) = (*genesis_address, genesis_account.balance);
+        let genesis_hash = genesis.get_block().hash();
+        let store = store.with_archive_mode().await.unwrap();
+
+        // A batch of 4 blocks whose last 2 blocks don't modify the state
+        let mut update = AccountUpdate::new(genesis_address);
+        update.info = Some(AccountInfo {
+            balance: genesis_balance + 1,
+            ..Default::default()
+        });
+        let state_root = store
+            .apply_account_updates_batch(genesis_hash, 3, 4, &[update])
+            .await
+            .unwrap()
+            .unwrap();
+        let mut parent_hash = genesis_hash;
+        for block_number in 1..=4u64 {
+            let header = BlockHeader {
+                number: block_number,
+                parent_hash,
+                state_root: match block_number {
+                    1 | 2 => H256::from_low_u64_be(block_number),
+                    _ => state_root,
+                },
+                ..Default::default()
+            };
+            let block_hash = header.compute_block_hash();
+            store.add_block_header(block_hash, header).await.unwrap();
+            store
+                .set_canonical_block(block_number, block_hash)
+                .await
+                .unwrap();
+            parent_hash = block_hash;
+        }
+        store.update_latest_block_number(4).await.unwrap();
+
+        // The state of the blocks that share the batch's resulting state is read from the history
+        for block_number in [3, 4] {
+            let account = store
+                .get_account_info(block_number, genesis_address)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(account.balance, genesis_balance + 1);
+        }
+        // The state of the previous blocks of the batch was not stored, the history doesn't
+        // return the state from before the batch for them
+        for block_number in [1, 2] {
+            assert!(!matches!(
+                store.get_account_info(block_number, genesis_address).await,
+                Ok(Some(account)) if account.balance == genesis_balance
+            ));
+        }
     }
 
     async fn test_genesis_block(store: Store) {
@@ -1231,6 +2661,10 @@ mod tests {
         if std::path::Path::new(path).exists() {
             fs::remove_dir_all(path).expect("Failed to clean test db dir");
         }
+        // The redb engine always stores its database in the same file
+        if std::path::Path::new("ethrex.redb").exists() {
+            fs::remove_file("ethrex.redb").expect("Failed to clean test redb file");
+        }
     }
 
     async fn test_store_block(store: Store) {
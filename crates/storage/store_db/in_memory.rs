@@ -1,6 +1,10 @@
 use crate::{
     api::StoreEngine,
+    bloom_index::bloom_bits_key,
     error::StoreError,
+    pruning::StateJournal,
+    state_history::{record_change, AccountChanges, StateChangeSet, StorageChanges},
+    state_snapshot::SnapshotDiff,
     store::{MAX_SNAPSHOT_READS, STATE_TRIE_SEGMENTS},
 };
 use bytes::Bytes;
@@ -41,6 +45,18 @@ struct StoreInner {
     pending_blocks: HashMap<BlockHash, Block>,
     // Stores invalid blocks and their latest valid ancestor
     invalid_ancestors: HashMap<BlockHash, BlockHash>,
+    // Stores the state journals of the blocks within the state retention window
+    state_journals: HashMap<BlockNumber, Vec<StateJournal>>,
+    // Stores the bit vectors of the indexed bloom sections, see `crate::bloom_index`
+    bloom_bits: HashMap<u64, Vec<u8>>,
+    // Stores the state changes of each block in archive mode, see `crate::state_history`
+    account_history: BTreeMap<(H256, BlockNumber), AccountChanges>,
+    storage_history: BTreeMap<(H256, H256, BlockNumber), StorageChanges>,
+    // Stores the flat tables of the state snapshot, see `crate::state_snapshot`
+    flat_accounts: HashMap<H256, AccountState>,
+    flat_storage: HashMap<(H256, H256), U256>,
+    // Stores the diffs of the blocks on top of the state snapshot's disk layer
+    snapshot_diffs: HashMap<BlockNumber, Vec<SnapshotDiff>>,
     // Stores current Snap Sate
     snap_state: SnapState,
     // Stores State trie leafs from the last downloaded tries
@@ -53,11 +69,15 @@ struct StoreInner {
 struct ChainData {
     chain_config: Option<ChainConfig>,
     earliest_block_number: Option<BlockNumber>,
+    earliest_state_block_number: Option<BlockNumber>,
     finalized_block_number: Option<BlockNumber>,
     safe_block_number: Option<BlockNumber>,
     latest_block_number: Option<BlockNumber>,
     pending_block_number: Option<BlockNumber>,
     is_synced: bool,
+    bloom_indexed_sections: Option<u64>,
+    state_history_start: Option<BlockNumber>,
+    flat_state_block: Option<(BlockNumber, H256)>,
 }
 
 // Keeps track of the state left by the latest snap attempt
@@ -239,6 +259,23 @@ impl StoreEngine for Store {
         Ok(())
     }
 
+    async fn remove_transaction_locations(
+        &self,
+        locations: Vec<(H256, BlockNumber, BlockHash, Index)>,
+    ) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        for (transaction_hash, block_number, block_hash, index) in locations {
+            let Some(tx_locations) = store.transaction_locations.get_mut(&transaction_hash) else {
+                continue;
+            };
+            tx_locations.retain(|location| *location != (block_number, block_hash, index));
+            if tx_locations.is_empty() {
+                store.transaction_locations.remove(&transaction_hash);
+            }
+        }
+        Ok(())
+    }
+
     async fn get_transaction_location(
         &self,
         transaction_hash: H256,
@@ -266,6 +303,11 @@ impl StoreEngine for Store {
         Ok(())
     }
 
+    async fn remove_receipts(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.inner().receipts.remove(&block_hash);
+        Ok(())
+    }
+
     async fn get_receipt(
         &self,
         block_number: BlockNumber,
@@ -317,6 +359,21 @@ impl StoreEngine for Store {
         Ok(self.inner().chain_data.earliest_block_number)
     }
 
+    async fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.inner()
+            .chain_data
+            .earliest_state_block_number
+            .replace(block_number);
+        Ok(())
+    }
+
+    async fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        Ok(self.inner().chain_data.earliest_state_block_number)
+    }
+
     async fn update_finalized_block_number(
         &self,
         block_number: BlockNumber,
@@ -696,6 +753,220 @@ impl StoreEngine for Store {
             .insert(bad_block, latest_valid);
         Ok(())
     }
+
+    async fn add_state_journal(
+        &self,
+        block_number: BlockNumber,
+        journal: StateJournal,
+    ) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        let journals = store.state_journals.entry(block_number).or_default();
+        journals.retain(|stored| stored.state_root != journal.state_root);
+        journals.push(journal);
+        Ok(())
+    }
+
+    async fn get_state_journals(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<StateJournal>, StoreError> {
+        Ok(self
+            .inner()
+            .state_journals
+            .get(&block_number)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn remove_state_journals(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.inner().state_journals.remove(&block_number);
+        Ok(())
+    }
+
+    async fn add_bloom_section(
+        &self,
+        section: u64,
+        bloom_bits: Vec<(u16, Vec<u8>)>,
+    ) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        for (bit, vector) in bloom_bits {
+            store
+                .bloom_bits
+                .insert(bloom_bits_key(section, bit), vector);
+        }
+        store.chain_data.bloom_indexed_sections = Some(section + 1);
+        Ok(())
+    }
+
+    async fn get_bloom_bits(&self, section: u64, bit: u16) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(self
+            .inner()
+            .bloom_bits
+            .get(&bloom_bits_key(section, bit))
+            .cloned())
+    }
+
+    async fn get_bloom_indexed_sections(&self) -> Result<Option<u64>, StoreError> {
+        Ok(self.inner().chain_data.bloom_indexed_sections)
+    }
+
+    async fn remove_bloom_sections(&self, first_section: u64) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        let first_key = bloom_bits_key(first_section, 0);
+        store.bloom_bits.retain(|key, _| *key < first_key);
+        store.chain_data.bloom_indexed_sections = Some(first_section);
+        Ok(())
+    }
+
+    async fn add_state_changes(
+        &self,
+        block_number: BlockNumber,
+        state_root: H256,
+        changes: StateChangeSet,
+    ) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        for (hashed_address, account_state) in changes.accounts {
+            let account_changes = store
+                .account_history
+                .entry((hashed_address, block_number))
+                .or_default();
+            record_change(&mut account_changes.0, state_root, account_state);
+        }
+        for (hashed_address, hashed_key, value) in changes.storage {
+            let storage_changes = store
+                .storage_history
+                .entry((hashed_address, hashed_key, block_number))
+                .or_default();
+            record_change(storage_changes, state_root, value);
+        }
+        Ok(())
+    }
+
+    async fn get_account_changes(
+        &self,
+        hashed_address: H256,
+        block_number: BlockNumber,
+    ) -> Result<Option<(BlockNumber, AccountChanges)>, StoreError> {
+        Ok(self
+            .inner()
+            .account_history
+            .range((hashed_address, 0)..=(hashed_address, block_number))
+            .next_back()
+            .map(|((_, changed_at), changes)| (*changed_at, changes.clone())))
+    }
+
+    async fn get_storage_changes(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+        block_number: BlockNumber,
+    ) -> Result<Option<(BlockNumber, StorageChanges)>, StoreError> {
+        Ok(self
+            .inner()
+            .storage_history
+            .range((hashed_address, hashed_key, 0)..=(hashed_address, hashed_key, block_number))
+            .next_back()
+            .map(|((_, _, changed_at), changes)| (*changed_at, changes.clone())))
+    }
+
+    async fn update_state_history_start(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.inner()
+            .chain_data
+            .state_history_start
+            .replace(block_number);
+        Ok(())
+    }
+
+    async fn get_state_history_start(&self) -> Result<Option<BlockNumber>, StoreError> {
+        Ok(self.inner().chain_data.state_history_start)
+    }
+
+    fn write_flat_state(
+        &self,
+        changes: StateChangeSet,
+        block: Option<(BlockNumber, H256)>,
+    ) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        for (hashed_address, account_state) in changes.accounts {
+            match account_state {
+                Some(account_state) => store.flat_accounts.insert(hashed_address, account_state),
+                None => store.flat_accounts.remove(&hashed_address),
+            };
+        }
+        for (hashed_address, hashed_key, value) in changes.storage {
+            if value.is_zero() {
+                store.flat_storage.remove(&(hashed_address, hashed_key));
+            } else {
+                store
+                    .flat_storage
+                    .insert((hashed_address, hashed_key), value);
+            }
+        }
+        if block.is_some() {
+            store.chain_data.flat_state_block = block;
+        }
+        Ok(())
+    }
+
+    async fn clear_flat_state(&self) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        store.flat_accounts.clear();
+        store.flat_storage.clear();
+        store.chain_data.flat_state_block = None;
+        Ok(())
+    }
+
+    fn get_flat_state_block(&self) -> Result<Option<(BlockNumber, H256)>, StoreError> {
+        Ok(self.inner().chain_data.flat_state_block)
+    }
+
+    fn get_flat_account(&self, hashed_address: H256) -> Result<Option<AccountState>, StoreError> {
+        Ok(self.inner().flat_accounts.get(&hashed_address).cloned())
+    }
+
+    fn get_flat_storage(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<U256>, StoreError> {
+        Ok(self
+            .inner()
+            .flat_storage
+            .get(&(hashed_address, hashed_key))
+            .copied())
+    }
+
+    async fn add_snapshot_diff(
+        &self,
+        block_number: BlockNumber,
+        diff: SnapshotDiff,
+    ) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        let diffs = store.snapshot_diffs.entry(block_number).or_default();
+        diffs.retain(|stored| stored.state_root != diff.state_root);
+        diffs.push(diff);
+        Ok(())
+    }
+
+    async fn get_snapshot_diffs(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<SnapshotDiff>, StoreError> {
+        Ok(self
+            .inner()
+            .snapshot_diffs
+            .get(&block_number)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn remove_snapshot_diffs(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.inner().snapshot_diffs.remove(&block_number);
+        Ok(())
+    }
 }
 
 impl Debug for Store {
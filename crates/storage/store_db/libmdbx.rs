@@ -1,10 +1,14 @@
 use crate::api::StoreEngine;
+use crate::bloom_index::bloom_bits_key;
 use crate::error::StoreError;
+use crate::pruning::StateJournal;
 use crate::rlp::{
-    AccountCodeHashRLP, AccountCodeRLP, AccountHashRLP, AccountStateRLP, BlockBodyRLP,
-    BlockHashRLP, BlockHeaderRLP, BlockRLP, PayloadBundleRLP, Rlp, TransactionHashRLP,
-    TriePathsRLP, TupleRLP,
+    AccountChangesRLP, AccountCodeHashRLP, AccountCodeRLP, AccountHashRLP, AccountStateRLP,
+    BlockBodyRLP, BlockHashRLP, BlockHeaderRLP, BlockRLP, PayloadBundleRLP, Rlp, SnapshotDiffsRLP,
+    StateJournalsRLP, StorageChangesRLP, TransactionHashRLP, TriePathsRLP, TupleRLP,
 };
+use crate::state_history::{record_change, AccountChanges, StateChangeSet, StorageChanges};
+use crate::state_snapshot::SnapshotDiff;
 use crate::store::{MAX_SNAPSHOT_READS, STATE_TRIE_SEGMENTS};
 use crate::trie_db::libmdbx::LibmdbxTrieDB;
 use crate::trie_db::libmdbx_dupsort::LibmdbxDupsortTrieDB;
@@ -305,6 +309,25 @@ impl StoreEngine for Store {
         self.write_batch::<Receipts>(entries).await
     }
 
+    async fn remove_receipts(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            // Receipts are stored with consecutive indexes starting from zero,
+            // deleting a key removes all the chunks of its receipt
+            let mut index: Index = 0;
+            while txn
+                .delete::<Receipts>((block_hash, index).into(), None)
+                .map_err(StoreError::LibmdbxError)?
+            {
+                index += 1;
+            }
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
     async fn get_receipt(
         &self,
         block_number: BlockNumber,
@@ -397,6 +420,29 @@ impl StoreEngine for Store {
         }
     }
 
+    async fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.write::<ChainData>(
+            ChainDataIndex::EarliestStateBlockNumber,
+            block_number.encode_to_vec(),
+        )
+        .await
+    }
+
+    async fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self
+            .read::<ChainData>(ChainDataIndex::EarliestStateBlockNumber)
+            .await?
+        {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
     async fn update_finalized_block_number(
         &self,
         block_number: BlockNumber,
@@ -614,6 +660,26 @@ impl StoreEngine for Store {
         self.write_batch::<TransactionLocations>(key_values).await
     }
 
+    async fn remove_transaction_locations(
+        &self,
+        locations: Vec<(H256, BlockNumber, BlockHash, Index)>,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            for (tx_hash, block_number, block_hash, index) in locations {
+                txn.delete::<TransactionLocations>(
+                    tx_hash.into(),
+                    Some((block_number, block_hash, index).into()),
+                )
+                .map_err(StoreError::LibmdbxError)?;
+            }
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
     async fn add_receipts(
         &self,
         block_hash: BlockHash,
@@ -974,6 +1040,365 @@ impl StoreEngine for Store {
         self.write::<InvalidAncestors>(bad_block.into(), latest_valid.into())
             .await
     }
+
+    async fn add_state_journal(
+        &self,
+        block_number: BlockNumber,
+        journal: StateJournal,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            let mut journals = txn
+                .get::<StateJournals>(block_number)
+                .map_err(StoreError::LibmdbxError)?
+                .map(|journals| journals.to())
+                .unwrap_or_default();
+            journals.retain(|stored: &StateJournal| stored.state_root != journal.state_root);
+            journals.push(journal);
+            txn.upsert::<StateJournals>(block_number, journals.into())
+                .map_err(StoreError::LibmdbxError)?;
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_state_journals(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<StateJournal>, StoreError> {
+        Ok(self
+            .read::<StateJournals>(block_number)
+            .await?
+            .map(|journals| journals.to())
+            .unwrap_or_default())
+    }
+
+    async fn remove_state_journals(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            txn.delete::<StateJournals>(block_number, None)
+                .map_err(StoreError::LibmdbxError)?;
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn add_bloom_section(
+        &self,
+        section: u64,
+        bloom_bits: Vec<(u16, Vec<u8>)>,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            for (bit, vector) in bloom_bits {
+                txn.upsert::<BloomBits>(bloom_bits_key(section, bit), vector)
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+            txn.upsert::<ChainData>(
+                ChainDataIndex::BloomIndexedSections,
+                (section + 1).encode_to_vec(),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_bloom_bits(&self, section: u64, bit: u16) -> Result<Option<Vec<u8>>, StoreError> {
+        self.read::<BloomBits>(bloom_bits_key(section, bit)).await
+    }
+
+    async fn get_bloom_indexed_sections(&self) -> Result<Option<u64>, StoreError> {
+        match self
+            .read::<ChainData>(ChainDataIndex::BloomIndexedSections)
+            .await?
+        {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    async fn remove_bloom_sections(&self, first_section: u64) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            let keys: Vec<u64> = {
+                let cursor = txn
+                    .cursor::<BloomBits>()
+                    .map_err(StoreError::LibmdbxError)?;
+                cursor
+                    .walk(Some(bloom_bits_key(first_section, 0)))
+                    .map(|entry| entry.map(|(key, _)| key))
+                    .collect::<Result<_, _>>()
+                    .map_err(StoreError::LibmdbxError)?
+            };
+            for key in keys {
+                txn.delete::<BloomBits>(key, None)
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+            txn.upsert::<ChainData>(
+                ChainDataIndex::BloomIndexedSections,
+                first_section.encode_to_vec(),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn add_state_changes(
+        &self,
+        block_number: BlockNumber,
+        state_root: H256,
+        changes: StateChangeSet,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            for (hashed_address, account_state) in changes.accounts {
+                let key = (hashed_address.0, block_number);
+                let mut account_changes: AccountChanges = txn
+                    .get::<AccountHistory>(key)
+                    .map_err(StoreError::LibmdbxError)?
+                    .map(|changes| changes.to())
+                    .unwrap_or_default();
+                record_change(&mut account_changes.0, state_root, account_state);
+                txn.upsert::<AccountHistory>(key, account_changes.into())
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+            for (hashed_address, hashed_key, value) in changes.storage {
+                let key = (storage_slot_key(hashed_address, hashed_key), block_number);
+                let mut storage_changes: StorageChanges = txn
+                    .get::<StorageHistory>(key)
+                    .map_err(StoreError::LibmdbxError)?
+                    .map(|changes| changes.to())
+                    .unwrap_or_default();
+                record_change(&mut storage_changes, state_root, value);
+                txn.upsert::<StorageHistory>(key, storage_changes.into())
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_account_changes(
+        &self,
+        hashed_address: H256,
+        block_number: BlockNumber,
+    ) -> Result<Option<(BlockNumber, AccountChanges)>, StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_read().map_err(StoreError::LibmdbxError)?;
+            let mut cursor = txn
+                .cursor::<AccountHistory>()
+                .map_err(StoreError::LibmdbxError)?;
+            let key = (hashed_address.0, block_number);
+            // Position the cursor at the last entry up to the key
+            let entry = match cursor.seek_closest(key).map_err(StoreError::LibmdbxError)? {
+                Some(entry) if entry.0 == key => Some(entry),
+                Some(_) => cursor.prev().map_err(StoreError::LibmdbxError)?,
+                None => cursor.last().map_err(StoreError::LibmdbxError)?,
+            };
+            Ok(entry
+                .filter(|((address, _), _)| *address == hashed_address.0)
+                .map(|((_, changed_at), changes)| (changed_at, changes.to())))
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_storage_changes(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+        block_number: BlockNumber,
+    ) -> Result<Option<(BlockNumber, StorageChanges)>, StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_read().map_err(StoreError::LibmdbxError)?;
+            let mut cursor = txn
+                .cursor::<StorageHistory>()
+                .map_err(StoreError::LibmdbxError)?;
+            let slot = storage_slot_key(hashed_address, hashed_key);
+            let key = (slot, block_number);
+            // Position the cursor at the last entry up to the key
+            let entry = match cursor.seek_closest(key).map_err(StoreError::LibmdbxError)? {
+                Some(entry) if entry.0 == key => Some(entry),
+                Some(_) => cursor.prev().map_err(StoreError::LibmdbxError)?,
+                None => cursor.last().map_err(StoreError::LibmdbxError)?,
+            };
+            Ok(entry
+                .filter(|((stored_slot, _), _)| *stored_slot == slot)
+                .map(|((_, changed_at), changes)| (changed_at, changes.to())))
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn update_state_history_start(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.write::<ChainData>(
+            ChainDataIndex::StateHistoryStart,
+            block_number.encode_to_vec(),
+        )
+        .await
+    }
+
+    async fn get_state_history_start(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self
+            .read::<ChainData>(ChainDataIndex::StateHistoryStart)
+            .await?
+        {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn write_flat_state(
+        &self,
+        changes: StateChangeSet,
+        block: Option<(BlockNumber, H256)>,
+    ) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        for (hashed_address, account_state) in changes.accounts {
+            match account_state {
+                Some(account_state) => txn
+                    .upsert::<FlatAccounts>(hashed_address.into(), account_state.into())
+                    .map_err(StoreError::LibmdbxError)?,
+                None => {
+                    txn.delete::<FlatAccounts>(hashed_address.into(), None)
+                        .map_err(StoreError::LibmdbxError)?;
+                }
+            }
+        }
+        for (hashed_address, hashed_key, value) in changes.storage {
+            let key = storage_slot_key(hashed_address, hashed_key);
+            if value.is_zero() {
+                txn.delete::<FlatStorage>(key, None)
+                    .map_err(StoreError::LibmdbxError)?;
+            } else {
+                txn.upsert::<FlatStorage>(key, value.into())
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+        }
+        if let Some(block) = block {
+            txn.upsert::<ChainData>(ChainDataIndex::FlatStateBlock, block.encode_to_vec())
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
+    async fn clear_flat_state(&self) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            txn.clear_table::<FlatAccounts>()
+                .map_err(StoreError::LibmdbxError)?;
+            txn.clear_table::<FlatStorage>()
+                .map_err(StoreError::LibmdbxError)?;
+            txn.delete::<ChainData>(ChainDataIndex::FlatStateBlock, None)
+                .map_err(StoreError::LibmdbxError)?;
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    fn get_flat_state_block(&self) -> Result<Option<(BlockNumber, H256)>, StoreError> {
+        self.read_sync::<ChainData>(ChainDataIndex::FlatStateBlock)?
+            .map(|ref rlp| <(BlockNumber, H256)>::decode(rlp))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn get_flat_account(&self, hashed_address: H256) -> Result<Option<AccountState>, StoreError> {
+        Ok(self
+            .read_sync::<FlatAccounts>(hashed_address.into())?
+            .map(|account_state| account_state.to()))
+    }
+
+    fn get_flat_storage(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<U256>, StoreError> {
+        Ok(self
+            .read_sync::<FlatStorage>(storage_slot_key(hashed_address, hashed_key))?
+            .map(U256::from))
+    }
+
+    async fn add_snapshot_diff(
+        &self,
+        block_number: BlockNumber,
+        diff: SnapshotDiff,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            let mut diffs = txn
+                .get::<SnapshotDiffs>(block_number)
+                .map_err(StoreError::LibmdbxError)?
+                .map(|diffs| diffs.to())
+                .unwrap_or_default();
+            diffs.retain(|stored: &SnapshotDiff| stored.state_root != diff.state_root);
+            diffs.push(diff);
+            txn.upsert::<SnapshotDiffs>(block_number, diffs.into())
+                .map_err(StoreError::LibmdbxError)?;
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_snapshot_diffs(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<SnapshotDiff>, StoreError> {
+        Ok(self
+            .read::<SnapshotDiffs>(block_number)
+            .await?
+            .map(|diffs| diffs.to())
+            .unwrap_or_default())
+    }
+
+    async fn remove_snapshot_diffs(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = db.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+            txn.delete::<SnapshotDiffs>(block_number, None)
+                .map_err(StoreError::LibmdbxError)?;
+            txn.commit().map_err(StoreError::LibmdbxError)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+}
+
+/// Key of a storage slot in the storage history and flat storage tables:
+/// its hashed address followed by its hashed key
+fn storage_slot_key(hashed_address: H256, hashed_key: H256) -> [u8; 64] {
+    let mut key = [0; 64];
+    key[..32].copy_from_slice(hashed_address.as_bytes());
+    key[32..].copy_from_slice(hashed_key.as_bytes());
+    key
 }
 
 impl Debug for Store {
@@ -1185,6 +1610,43 @@ table!(
     ( InvalidAncestors ) BlockHashRLP => BlockHashRLP
 );
 
+table!(
+    /// Trie nodes written and removed by the blocks within the state retention window
+    ( StateJournals ) BlockNumber => StateJournalsRLP
+);
+
+table!(
+    /// Bit vectors of the indexed bloom sections, indexed by section and bloom bit
+    ( BloomBits ) u64 => Vec<u8>
+);
+
+table!(
+    /// State of the accounts after each block that modified them, indexed by hashed address and block number
+    ( AccountHistory ) ([u8; 32], BlockNumber) => AccountChangesRLP
+);
+
+table!(
+    /// Value of the storage slots after each block that modified them, indexed by hashed address
+    /// and hashed key (see [storage_slot_key]) and block number
+    ( StorageHistory ) ([u8; 64], BlockNumber) => StorageChangesRLP
+);
+
+table!(
+    /// Accounts of the state held by the state snapshot's disk layer, indexed by hashed address
+    ( FlatAccounts ) AccountHashRLP => AccountStateRLP
+);
+
+table!(
+    /// Storage slots of the state held by the state snapshot's disk layer, indexed by hashed
+    /// address and hashed key (see [storage_slot_key])
+    ( FlatStorage ) [u8; 64] => AccountStorageValueBytes
+);
+
+table!(
+    /// Changes made to the state by the blocks on top of the state snapshot's disk layer
+    ( SnapshotDiffs ) BlockNumber => SnapshotDiffsRLP
+);
+
 // Storage values are stored as bytes instead of using their rlp encoding
 // As they are stored in a dupsort table, they need to have a fixed size, and encoding them doesn't preserve their size
 pub struct AccountStorageKeyBytes(pub [u8; 32]);
@@ -1287,6 +1749,13 @@ pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
         table_info!(StorageSnapShot),
         table_info!(StorageHealPaths),
         table_info!(InvalidAncestors),
+        table_info!(StateJournals),
+        table_info!(BloomBits),
+        table_info!(AccountHistory),
+        table_info!(StorageHistory),
+        table_info!(FlatAccounts),
+        table_info!(FlatStorage),
+        table_info!(SnapshotDiffs),
     ]
     .into_iter()
     .collect();
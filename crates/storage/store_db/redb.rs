@@ -1,8 +1,13 @@
 use std::{borrow::Borrow, collections::HashMap, panic::RefUnwindSafe, sync::Arc};
 
+use crate::bloom_index::bloom_bits_key;
+use crate::pruning::StateJournal;
 use crate::rlp::{
-    AccountHashRLP, AccountStateRLP, BlockRLP, Rlp, TransactionHashRLP, TriePathsRLP,
+    AccountChangesRLP, AccountHashRLP, AccountStateRLP, BlockRLP, Rlp, SnapshotDiffsRLP,
+    StateJournalsRLP, StorageChangesRLP, TransactionHashRLP, TriePathsRLP,
 };
+use crate::state_history::{record_change, AccountChanges, StateChangeSet, StorageChanges};
+use crate::state_snapshot::SnapshotDiff;
 use crate::store::MAX_SNAPSHOT_READS;
 use crate::trie_db::{redb::RedBTrie, redb_multitable::RedBMultiTableTrieDB};
 use crate::{
@@ -24,7 +29,10 @@ use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_rlp::error::RLPDecodeError;
 use ethrex_trie::{Nibbles, Trie};
-use redb::{AccessGuard, Database, Key, MultimapTableDefinition, TableDefinition, TypeName, Value};
+use redb::{
+    AccessGuard, Database, Key, MultimapTableDefinition, ReadableTable, TableDefinition, TypeName,
+    Value,
+};
 
 use crate::utils::SnapStateIndex;
 use crate::{api::StoreEngine, utils::ChainDataIndex};
@@ -63,6 +71,19 @@ const STATE_SNAPSHOT_TABLE: TableDefinition<AccountHashRLP, AccountStateRLP> =
     TableDefinition::new("StateSnapshot");
 const STORAGE_SNAPSHOT_TABLE: MultimapTableDefinition<AccountHashRLP, ([u8; 32], [u8; 32])> =
     MultimapTableDefinition::new("StorageSnapshotTable");
+const STATE_JOURNALS_TABLE: TableDefinition<BlockNumber, StateJournalsRLP> =
+    TableDefinition::new("StateJournals");
+const BLOOM_BITS_TABLE: TableDefinition<u64, Vec<u8>> = TableDefinition::new("BloomBits");
+const ACCOUNT_HISTORY_TABLE: TableDefinition<([u8; 32], BlockNumber), AccountChangesRLP> =
+    TableDefinition::new("AccountHistory");
+const STORAGE_HISTORY_TABLE: TableDefinition<([u8; 32], [u8; 32], BlockNumber), StorageChangesRLP> =
+    TableDefinition::new("StorageHistory");
+const FLAT_ACCOUNTS_TABLE: TableDefinition<AccountHashRLP, AccountStateRLP> =
+    TableDefinition::new("FlatAccounts");
+const FLAT_STORAGE_TABLE: TableDefinition<([u8; 32], [u8; 32]), [u8; 32]> =
+    TableDefinition::new("FlatStorage");
+const SNAPSHOT_DIFFS_TABLE: TableDefinition<BlockNumber, SnapshotDiffsRLP> =
+    TableDefinition::new("SnapshotDiffs");
 const STORAGE_HEAL_PATHS_TABLE: TableDefinition<AccountHashRLP, TriePathsRLP> =
     TableDefinition::new("StorageHealPaths");
 
@@ -706,6 +727,30 @@ impl StoreEngine for RedBStore {
         }
     }
 
+    async fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA_TABLE,
+            ChainDataIndex::EarliestStateBlockNumber,
+            block_number.encode_to_vec(),
+        )
+        .await
+    }
+
+    async fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self
+            .read(CHAIN_DATA_TABLE, ChainDataIndex::EarliestStateBlockNumber)
+            .await?
+        {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
     async fn update_finalized_block_number(
         &self,
         block_number: BlockNumber,
@@ -891,6 +936,59 @@ impl StoreEngine for RedBStore {
         Ok(())
     }
 
+    async fn remove_transaction_locations(
+        &self,
+        locations: Vec<(H256, BlockNumber, BlockHash, Index)>,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let write_txn = db.begin_write()?;
+            {
+                let mut table = write_txn.open_multimap_table(TRANSACTION_LOCATIONS_TABLE)?;
+                for (tx_hash, block_number, block_hash, index) in locations {
+                    table.remove(
+                        <H256 as Into<TransactionHashRLP>>::into(tx_hash),
+                        <(u64, H256, u64) as Into<Rlp<(BlockNumber, BlockHash, Index)>>>::into((
+                            block_number,
+                            block_hash,
+                            index,
+                        )),
+                    )?;
+                }
+            }
+            write_txn.commit()?;
+
+            Ok(())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn remove_receipts(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let write_txn = db.begin_write()?;
+            {
+                let mut table = write_txn.open_table(RECEIPTS_TABLE)?;
+                // Receipts are stored with consecutive indexes starting from zero
+                let mut index = 0;
+                while table
+                    .remove(<(H256, u64) as Into<TupleRLP<BlockHash, Index>>>::into((
+                        block_hash, index,
+                    )))?
+                    .is_some()
+                {
+                    index += 1;
+                }
+            }
+            write_txn.commit()?;
+
+            Ok(())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
     async fn update_payload(
         &self,
         payload_id: u64,
@@ -1251,6 +1349,360 @@ impl StoreEngine for RedBStore {
         )
         .await
     }
+
+    async fn add_state_journal(
+        &self,
+        block_number: BlockNumber,
+        journal: StateJournal,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let write_txn = db.begin_write()?;
+            {
+                let mut table = write_txn.open_table(STATE_JOURNALS_TABLE)?;
+                let mut journals = table
+                    .get(block_number)?
+                    .map(|journals| journals.value().to())
+                    .unwrap_or_default();
+                journals.retain(|stored: &StateJournal| stored.state_root != journal.state_root);
+                journals.push(journal);
+                table.insert(
+                    block_number,
+                    <Vec<StateJournal> as Into<StateJournalsRLP>>::into(journals),
+                )?;
+            }
+            write_txn.commit()?;
+
+            Ok(())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_state_journals(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<StateJournal>, StoreError> {
+        Ok(self
+            .read(STATE_JOURNALS_TABLE, block_number)
+            .await?
+            .map(|journals| journals.value().to())
+            .unwrap_or_default())
+    }
+
+    async fn remove_state_journals(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.delete(STATE_JOURNALS_TABLE, block_number)
+    }
+
+    async fn add_bloom_section(
+        &self,
+        section: u64,
+        bloom_bits: Vec<(u16, Vec<u8>)>,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let write_txn = db.begin_write()?;
+            {
+                let mut table = write_txn.open_table(BLOOM_BITS_TABLE)?;
+                for (bit, vector) in bloom_bits {
+                    table.insert(bloom_bits_key(section, bit), vector)?;
+                }
+                let mut table = write_txn.open_table(CHAIN_DATA_TABLE)?;
+                table.insert(
+                    ChainDataIndex::BloomIndexedSections,
+                    (section + 1).encode_to_vec(),
+                )?;
+            }
+            write_txn.commit()?;
+
+            Ok(())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_bloom_bits(&self, section: u64, bit: u16) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(self
+            .read(BLOOM_BITS_TABLE, bloom_bits_key(section, bit))
+            .await?
+            .map(|vector| vector.value()))
+    }
+
+    async fn get_bloom_indexed_sections(&self) -> Result<Option<u64>, StoreError> {
+        match self
+            .read(CHAIN_DATA_TABLE, ChainDataIndex::BloomIndexedSections)
+            .await?
+        {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    async fn remove_bloom_sections(&self, first_section: u64) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let write_txn = db.begin_write()?;
+            {
+                let mut table = write_txn.open_table(BLOOM_BITS_TABLE)?;
+                table.retain_in(bloom_bits_key(first_section, 0).., |_, _| false)?;
+                let mut table = write_txn.open_table(CHAIN_DATA_TABLE)?;
+                table.insert(
+                    ChainDataIndex::BloomIndexedSections,
+                    first_section.encode_to_vec(),
+                )?;
+            }
+            write_txn.commit()?;
+
+            Ok(())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn add_state_changes(
+        &self,
+        block_number: BlockNumber,
+        state_root: H256,
+        changes: StateChangeSet,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let write_txn = db.begin_write()?;
+            {
+                let mut table = write_txn.open_table(ACCOUNT_HISTORY_TABLE)?;
+                for (hashed_address, account_state) in changes.accounts {
+                    let key = (hashed_address.0, block_number);
+                    let mut account_changes: AccountChanges = table
+                        .get(key)?
+                        .map(|changes| changes.value().to())
+                        .unwrap_or_default();
+                    record_change(&mut account_changes.0, state_root, account_state);
+                    table.insert(
+                        key,
+                        <AccountChanges as Into<AccountChangesRLP>>::into(account_changes),
+                    )?;
+                }
+                let mut table = write_txn.open_table(STORAGE_HISTORY_TABLE)?;
+                for (hashed_address, hashed_key, value) in changes.storage {
+                    let key = (hashed_address.0, hashed_key.0, block_number);
+                    let mut storage_changes: StorageChanges = table
+                        .get(key)?
+                        .map(|changes| changes.value().to())
+                        .unwrap_or_default();
+                    record_change(&mut storage_changes, state_root, value);
+                    table.insert(
+                        key,
+                        <StorageChanges as Into<StorageChangesRLP>>::into(storage_changes),
+                    )?;
+                }
+            }
+            write_txn.commit()?;
+
+            Ok(())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_account_changes(
+        &self,
+        hashed_address: H256,
+        block_number: BlockNumber,
+    ) -> Result<Option<(BlockNumber, AccountChanges)>, StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let read_txn = db.begin_read()?;
+            let table = read_txn.open_table(ACCOUNT_HISTORY_TABLE)?;
+            let latest_change = table
+                .range((hashed_address.0, 0)..=(hashed_address.0, block_number))?
+                .next_back()
+                .transpose()?
+                .map(|(key, changes)| (key.value().1, changes.value().to()));
+            Ok(latest_change)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_storage_changes(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+        block_number: BlockNumber,
+    ) -> Result<Option<(BlockNumber, StorageChanges)>, StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let read_txn = db.begin_read()?;
+            let table = read_txn.open_table(STORAGE_HISTORY_TABLE)?;
+            let latest_change = table
+                .range(
+                    (hashed_address.0, hashed_key.0, 0)
+                        ..=(hashed_address.0, hashed_key.0, block_number),
+                )?
+                .next_back()
+                .transpose()?
+                .map(|(key, changes)| (key.value().2, changes.value().to()));
+            Ok(latest_change)
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn update_state_history_start(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA_TABLE,
+            ChainDataIndex::StateHistoryStart,
+            block_number.encode_to_vec(),
+        )
+        .await
+    }
+
+    async fn get_state_history_start(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self
+            .read(CHAIN_DATA_TABLE, ChainDataIndex::StateHistoryStart)
+            .await?
+        {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn write_flat_state(
+        &self,
+        changes: StateChangeSet,
+        block: Option<(BlockNumber, H256)>,
+    ) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut accounts_table = write_txn.open_table(FLAT_ACCOUNTS_TABLE)?;
+            for (hashed_address, account_state) in changes.accounts {
+                let key = <H256 as Into<AccountHashRLP>>::into(hashed_address);
+                match account_state {
+                    Some(account_state) => {
+                        accounts_table.insert(
+                            key,
+                            <AccountState as Into<AccountStateRLP>>::into(account_state),
+                        )?;
+                    }
+                    None => {
+                        accounts_table.remove(key)?;
+                    }
+                }
+            }
+            let mut storage_table = write_txn.open_table(FLAT_STORAGE_TABLE)?;
+            for (hashed_address, hashed_key, value) in changes.storage {
+                let key = (hashed_address.0, hashed_key.0);
+                if value.is_zero() {
+                    storage_table.remove(key)?;
+                } else {
+                    storage_table.insert(key, value.to_big_endian())?;
+                }
+            }
+            if let Some(block) = block {
+                write_txn
+                    .open_table(CHAIN_DATA_TABLE)?
+                    .insert(ChainDataIndex::FlatStateBlock, block.encode_to_vec())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    async fn clear_flat_state(&self) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let write_txn = db.begin_write()?;
+            write_txn.delete_table(FLAT_ACCOUNTS_TABLE)?;
+            write_txn.delete_table(FLAT_STORAGE_TABLE)?;
+            // Recreate the tables so they can be read right away
+            write_txn.open_table(FLAT_ACCOUNTS_TABLE)?;
+            write_txn.open_table(FLAT_STORAGE_TABLE)?;
+            write_txn
+                .open_table(CHAIN_DATA_TABLE)?
+                .remove(ChainDataIndex::FlatStateBlock)?;
+            write_txn.commit()?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    fn get_flat_state_block(&self) -> Result<Option<(BlockNumber, H256)>, StoreError> {
+        self.read_sync(CHAIN_DATA_TABLE, ChainDataIndex::FlatStateBlock)?
+            .map(|rlp| RLPDecode::decode(&rlp.value()))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn get_flat_account(&self, hashed_address: H256) -> Result<Option<AccountState>, StoreError> {
+        Ok(self
+            .read_sync(
+                FLAT_ACCOUNTS_TABLE,
+                <H256 as Into<AccountHashRLP>>::into(hashed_address),
+            )?
+            .map(|account_state| account_state.value().to()))
+    }
+
+    fn get_flat_storage(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<U256>, StoreError> {
+        Ok(self
+            .read_sync(FLAT_STORAGE_TABLE, (hashed_address.0, hashed_key.0))?
+            .map(|value| U256::from_big_endian(&value.value())))
+    }
+
+    async fn add_snapshot_diff(
+        &self,
+        block_number: BlockNumber,
+        diff: SnapshotDiff,
+    ) -> Result<(), StoreError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let write_txn = db.begin_write()?;
+            {
+                let mut table = write_txn.open_table(SNAPSHOT_DIFFS_TABLE)?;
+                let mut diffs = table
+                    .get(block_number)?
+                    .map(|diffs| diffs.value().to())
+                    .unwrap_or_default();
+                diffs.retain(|stored: &SnapshotDiff| stored.state_root != diff.state_root);
+                diffs.push(diff);
+                table.insert(
+                    block_number,
+                    <Vec<SnapshotDiff> as Into<SnapshotDiffsRLP>>::into(diffs),
+                )?;
+            }
+            write_txn.commit()?;
+
+            Ok(())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
+    }
+
+    async fn get_snapshot_diffs(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<SnapshotDiff>, StoreError> {
+        Ok(self
+            .read(SNAPSHOT_DIFFS_TABLE, block_number)
+            .await?
+            .map(|diffs| diffs.value().to())
+            .unwrap_or_default())
+    }
+
+    async fn remove_snapshot_diffs(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.delete(SNAPSHOT_DIFFS_TABLE, block_number)
+    }
 }
 
 impl redb::Value for ChainDataIndex {
@@ -1352,6 +1804,13 @@ pub fn init_db() -> Result<Database, StoreError> {
     table_creation_txn.open_table(SNAP_STATE_TABLE)?;
     table_creation_txn.open_table(STATE_SNAPSHOT_TABLE)?;
     table_creation_txn.open_multimap_table(STORAGE_SNAPSHOT_TABLE)?;
+    table_creation_txn.open_table(STATE_JOURNALS_TABLE)?;
+    table_creation_txn.open_table(BLOOM_BITS_TABLE)?;
+    table_creation_txn.open_table(ACCOUNT_HISTORY_TABLE)?;
+    table_creation_txn.open_table(STORAGE_HISTORY_TABLE)?;
+    table_creation_txn.open_table(FLAT_ACCOUNTS_TABLE)?;
+    table_creation_txn.open_table(FLAT_STORAGE_TABLE)?;
+    table_creation_txn.open_table(SNAPSHOT_DIFFS_TABLE)?;
     table_creation_txn.commit()?;
 
     Ok(db)
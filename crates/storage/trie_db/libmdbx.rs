@@ -1,7 +1,7 @@
 use ethrex_trie::error::TrieError;
 use libmdbx::orm::{Database, Table};
 use std::{marker::PhantomData, sync::Arc};
-/// Libmdbx implementation for the TrieDB trait, with get and put operations.
+/// Libmdbx implementation for the TrieDB trait, with get, put and remove operations.
 pub struct LibmdbxTrieDB<T: Table> {
     db: Arc<Database>,
     phantom: PhantomData<T>,
@@ -43,6 +43,13 @@ where
         }
         txn.commit().map_err(TrieError::DbError)
     }
+    fn remove_batch(&self, keys: Vec<Vec<u8>>) -> Result<(), TrieError> {
+        let txn = self.db.begin_readwrite().map_err(TrieError::DbError)?;
+        for key in keys {
+            txn.delete::<T>(key, None).map_err(TrieError::DbError)?;
+        }
+        txn.commit().map_err(TrieError::DbError)
+    }
 }
 
 #[cfg(test)]
@@ -64,6 +64,14 @@ where
         }
         txn.commit().map_err(TrieError::DbError)
     }
+    fn remove_batch(&self, keys: Vec<Vec<u8>>) -> Result<(), TrieError> {
+        let txn = self.db.begin_readwrite().map_err(TrieError::DbError)?;
+        for key in keys {
+            txn.delete::<T>((self.fixed_key.clone(), node_hash_to_fixed_size(key)), None)
+                .map_err(TrieError::DbError)?;
+        }
+        txn.commit().map_err(TrieError::DbError)
+    }
 }
 
 #[cfg(test)]
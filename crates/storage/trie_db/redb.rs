@@ -71,4 +71,26 @@ impl TrieDB for RedBTrie {
 
         Ok(())
     }
+
+    fn remove_batch(&self, keys: Vec<Vec<u8>>) -> Result<(), TrieError> {
+        let write_txn = self
+            .db
+            .begin_write()
+            .map_err(|e| TrieError::DbError(e.into()))?;
+        {
+            let mut table = write_txn
+                .open_table(TABLE)
+                .map_err(|e| TrieError::DbError(e.into()))?;
+            for key in keys {
+                table
+                    .remove(&*key)
+                    .map_err(|e| TrieError::DbError(e.into()))?;
+            }
+        }
+        write_txn
+            .commit()
+            .map_err(|e| TrieError::DbError(e.into()))?;
+
+        Ok(())
+    }
 }
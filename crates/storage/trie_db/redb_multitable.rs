@@ -97,4 +97,26 @@ impl TrieDB for RedBMultiTableTrieDB {
 
         Ok(())
     }
+
+    fn remove_batch(&self, keys: Vec<Vec<u8>>) -> Result<(), TrieError> {
+        let write_txn = self
+            .db
+            .begin_write()
+            .map_err(|e| TrieError::DbError(e.into()))?;
+        {
+            let mut table = write_txn
+                .open_multimap_table(STORAGE_TRIE_NODES_TABLE)
+                .map_err(|e| TrieError::DbError(e.into()))?;
+            for key in keys {
+                table
+                    .remove_all((self.fixed_key, node_hash_to_fixed_size(key)))
+                    .map_err(|e| TrieError::DbError(e.into()))?;
+            }
+        }
+        write_txn
+            .commit()
+            .map_err(|e| TrieError::DbError(e.into()))?;
+
+        Ok(())
+    }
 }
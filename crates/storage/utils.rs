@@ -9,6 +9,10 @@ pub enum ChainDataIndex {
     LatestBlockNumber = 4,
     PendingBlockNumber = 5,
     IsSynced = 6,
+    EarliestStateBlockNumber = 7,
+    BloomIndexedSections = 8,
+    StateHistoryStart = 9,
+    FlatStateBlock = 10,
 }
 
 impl From<u8> for ChainDataIndex {
@@ -27,6 +31,14 @@ impl From<u8> for ChainDataIndex {
                 ChainDataIndex::PendingBlockNumber
             }
             x if x == ChainDataIndex::IsSynced as u8 => ChainDataIndex::IsSynced,
+            x if x == ChainDataIndex::EarliestStateBlockNumber as u8 => {
+                ChainDataIndex::EarliestStateBlockNumber
+            }
+            x if x == ChainDataIndex::BloomIndexedSections as u8 => {
+                ChainDataIndex::BloomIndexedSections
+            }
+            x if x == ChainDataIndex::StateHistoryStart as u8 => ChainDataIndex::StateHistoryStart,
+            x if x == ChainDataIndex::FlatStateBlock as u8 => ChainDataIndex::FlatStateBlock,
             _ => panic!("Invalid value when casting to ChainDataIndex: {}", value),
         }
     }
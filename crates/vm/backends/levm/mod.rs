@@ -1,4 +1,6 @@
 pub mod db;
+mod overrides;
+mod tracing;
 
 use super::revm::db::get_potential_child_nodes;
 use super::BlockExecutionResult;
@@ -12,14 +14,15 @@ use bytes::Bytes;
 use ethrex_common::{
     types::{
         code_hash, requests::Requests, AccessList, AccountInfo, AuthorizationTuple, Block,
-        BlockHeader, EIP1559Transaction, EIP7702Transaction, Fork, GenericTransaction, Receipt,
-        Transaction, TxKind, Withdrawal, GWEI_TO_WEI, INITIAL_BASE_FEE,
+        BlockHeader, EIP1559Transaction, EIP7702Transaction, Fork, GenericTransaction, L1FeeParams,
+        Receipt, Transaction, TxKind, Withdrawal, GWEI_TO_WEI, INITIAL_BASE_FEE,
     },
     Address, H256, U256,
 };
 use ethrex_levm::db::gen_db::GeneralizedDatabase;
 use ethrex_levm::{
     errors::{ExecutionReport, TxResult, VMError},
+    hooks::l2_hook::L1FeeConfig,
     vm::{EVMConfig, Substate, VM},
     Account, Environment,
 };
@@ -32,6 +35,7 @@ use std::sync::Arc;
 
 // Export needed types
 pub use ethrex_levm::db::CacheDB;
+pub use overrides::{AccountOverride, StateOverride};
 /// The struct implements the following functions:
 /// [LEVM::execute_block]
 /// [LEVM::execute_tx]
@@ -45,21 +49,7 @@ impl LEVM {
         block: &Block,
         db: &mut GeneralizedDatabase,
     ) -> Result<BlockExecutionResult, EvmError> {
-        cfg_if::cfg_if! {
-            if #[cfg(not(feature = "l2"))] {
-                let chain_config = db.store.get_chain_config();
-                let block_header = &block.header;
-                let fork = chain_config.fork(block_header.timestamp);
-                if block_header.parent_beacon_block_root.is_some() && fork >= Fork::Cancun {
-                    Self::beacon_root_contract_call(block_header, db)?;
-                }
-
-                if fork >= Fork::Prague {
-                    //eip 2935: stores parent block hash in system contract
-                    Self::process_block_hash_history(block_header, db)?;
-                }
-            }
-        }
+        Self::apply_system_calls(&block.header, db)?;
 
         let mut receipts = Vec::new();
         let mut cumulative_gas_used = 0;
@@ -103,41 +93,37 @@ impl LEVM {
         block_header: &BlockHeader,
         db: &mut GeneralizedDatabase,
     ) -> Result<ExecutionReport, EvmError> {
-        let chain_config = db.store.get_chain_config();
-        let gas_price: U256 = tx
-            .effective_gas_price(block_header.base_fee_per_gas)
-            .ok_or(VMError::InvalidTransaction)?
-            .into();
-
-        let config = EVMConfig::new_from_chain_config(&chain_config, block_header);
-        let env = Environment {
-            origin: tx_sender,
-            refunded_gas: 0,
-            gas_limit: tx.gas_limit(),
-            config,
-            block_number: block_header.number.into(),
-            coinbase: block_header.coinbase,
-            timestamp: block_header.timestamp.into(),
-            prev_randao: Some(block_header.prev_randao),
-            chain_id: chain_config.chain_id.into(),
-            base_fee_per_gas: block_header.base_fee_per_gas.unwrap_or_default().into(),
-            gas_price,
-            block_excess_blob_gas: block_header.excess_blob_gas.map(U256::from),
-            block_blob_gas_used: block_header.blob_gas_used.map(U256::from),
-            tx_blob_hashes: tx.blob_versioned_hashes(),
-            tx_max_priority_fee_per_gas: tx.max_priority_fee().map(U256::from),
-            tx_max_fee_per_gas: tx.max_fee_per_gas().map(U256::from),
-            tx_max_fee_per_blob_gas: tx.max_fee_per_blob_gas().map(U256::from),
-            tx_nonce: tx.nonce(),
-            block_gas_limit: block_header.gas_limit,
-            transient_storage: HashMap::new(),
-            difficulty: block_header.difficulty,
-        };
+        let env = env_from_tx(tx, tx_sender, block_header, db)?;
 
         let mut vm = VM::new(env, db, tx)?;
 
         vm.execute().map_err(VMError::into)
     }
+
+    /// Runs the system contract calls that precede the transactions of a block.
+    pub fn apply_system_calls(
+        block_header: &BlockHeader,
+        db: &mut GeneralizedDatabase,
+    ) -> Result<(), EvmError> {
+        cfg_if::cfg_if! {
+            if #[cfg(not(feature = "l2"))] {
+                let chain_config = db.store.get_chain_config();
+                let fork = chain_config.fork(block_header.timestamp);
+                if block_header.parent_beacon_block_root.is_some() && fork >= Fork::Cancun {
+                    Self::beacon_root_contract_call(block_header, db)?;
+                }
+
+                if fork >= Fork::Prague {
+                    //eip 2935: stores parent block hash in system contract
+                    Self::process_block_hash_history(block_header, db)?;
+                }
+            } else {
+                let _ = (block_header, db);
+            }
+        }
+        Ok(())
+    }
+
     pub fn simulate_tx_from_generic(
         // The transaction to execute.
         tx: &GenericTransaction,
@@ -369,23 +355,33 @@ impl LEVM {
         Ok((report.into(), access_list))
     }
 
+    /// Builds the [ExecutionDB] needed to execute `blocks` sequentially, starting from the
+    /// state of the first block's parent and ending in the state of the last block.
     pub async fn to_execution_db(
-        block: &Block,
+        blocks: &[Block],
         store: &Store,
     ) -> Result<ExecutionDB, ExecutionDBError> {
-        let parent_hash = block.header.parent_hash;
+        let (Some(first_block), Some(last_block)) = (blocks.first(), blocks.last()) else {
+            return Err(ExecutionDBError::Custom(
+                "Can't create an ExecutionDB for an empty list of blocks".to_string(),
+            ));
+        };
+        let parent_hash = first_block.header.parent_hash;
+        let last_block_hash = last_block.hash();
         let chain_config = store.get_chain_config()?;
-        let fork = chain_config.fork(block.header.timestamp);
+        let fork = chain_config.fork(first_block.header.timestamp);
 
         let logger = Arc::new(DatabaseLogger::new(Arc::new(StoreWrapper {
             store: store.clone(),
-            block_hash: block.header.parent_hash,
+            block_hash: parent_hash,
         })));
         let logger_ref = Arc::clone(&logger);
         let mut db = GeneralizedDatabase::new(logger, CacheDB::new());
 
         // pre-execute and get all state changes
-        let _ = Self::execute_block(block, &mut db);
+        for block in blocks {
+            let _ = Self::execute_block(block, &mut db);
+        }
         let execution_updates = Self::get_state_transitions(&mut db, fork).map_err(Box::new)?;
 
         // index accessed account addresses and storage keys
@@ -469,7 +465,7 @@ impl LEVM {
 
         // get account proofs
         let state_trie = store
-            .state_trie(block.hash())?
+            .state_trie(last_block_hash)?
             .ok_or(ExecutionDBError::NewMissingStateTrie(parent_hash))?;
         let parent_state_trie = store
             .state_trie(parent_hash)?
@@ -499,8 +495,8 @@ impl LEVM {
                 // way the storage trie was initially empty so there aren't any proofs to add.
                 continue;
             };
-            let storage_trie = store.storage_trie(block.hash(), address)?.ok_or(
-                ExecutionDBError::NewMissingStorageTrie(block.hash(), address),
+            let storage_trie = store.storage_trie(last_block_hash, address)?.ok_or(
+                ExecutionDBError::NewMissingStorageTrie(last_block_hash, address),
             )?;
             let paths = storage_keys.iter().map(hash_key).collect::<Vec<_>>();
 
@@ -679,6 +675,48 @@ pub fn build_access_list(substate: &Substate) -> AccessList {
     access_list
 }
 
+fn env_from_tx(
+    tx: &Transaction,
+    tx_sender: Address,
+    block_header: &BlockHeader,
+    db: &GeneralizedDatabase,
+) -> Result<Environment, VMError> {
+    let chain_config = db.store.get_chain_config();
+    let gas_price: U256 = tx
+        .effective_gas_price(block_header.base_fee_per_gas)
+        .ok_or(VMError::InvalidTransaction)?
+        .into();
+
+    let config = EVMConfig::new_from_chain_config(&chain_config, block_header);
+    Ok(Environment {
+        origin: tx_sender,
+        refunded_gas: 0,
+        gas_limit: tx.gas_limit(),
+        config,
+        block_number: block_header.number.into(),
+        coinbase: block_header.coinbase,
+        timestamp: block_header.timestamp.into(),
+        prev_randao: Some(block_header.prev_randao),
+        chain_id: chain_config.chain_id.into(),
+        base_fee_per_gas: block_header.base_fee_per_gas.unwrap_or_default().into(),
+        gas_price,
+        block_excess_blob_gas: block_header.excess_blob_gas.map(U256::from),
+        block_blob_gas_used: block_header.blob_gas_used.map(U256::from),
+        tx_blob_hashes: tx.blob_versioned_hashes(),
+        tx_max_priority_fee_per_gas: tx.max_priority_fee().map(U256::from),
+        tx_max_fee_per_gas: tx.max_fee_per_gas().map(U256::from),
+        tx_max_fee_per_blob_gas: tx.max_fee_per_blob_gas().map(U256::from),
+        tx_nonce: tx.nonce(),
+        block_gas_limit: block_header.gas_limit,
+        transient_storage: HashMap::new(),
+        difficulty: block_header.difficulty,
+        l1_fee_config: chain_config.l1_fee_vault.map(|fee_vault| L1FeeConfig {
+            fee_vault,
+            params: L1FeeParams::from_extra_data(&block_header.extra_data),
+        }),
+    })
+}
+
 fn env_from_generic(
     tx: &GenericTransaction,
     header: &BlockHeader,
@@ -709,6 +747,8 @@ fn env_from_generic(
         block_gas_limit: header.gas_limit,
         transient_storage: HashMap::new(),
         difficulty: header.difficulty,
+        // The L1 fee isn't paid with gas, so simulations don't charge it
+        l1_fee_config: None,
     })
 }
 
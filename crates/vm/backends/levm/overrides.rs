@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use ethrex_common::{types::ChainConfig, Address, H256, U256};
+use ethrex_levm::{
+    db::{cache, error::DatabaseError, gen_db::GeneralizedDatabase, Database as LevmDatabase},
+    AccountInfo, StorageSlot,
+};
+use serde::Deserialize;
+
+use super::LEVM;
+use crate::EvmError;
+
+/// Overrides of the state of accounts, applied before simulating a transaction.
+/// Follows the format of geth's `stateOverride` parameter.
+pub type StateOverride = HashMap<Address, AccountOverride>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    #[serde(default)]
+    pub balance: Option<U256>,
+    #[serde(default, with = "ethrex_common::serde_utils::u64::hex_str_opt")]
+    pub nonce: Option<u64>,
+    #[serde(default, with = "ethrex_common::serde_utils::bytes::opt")]
+    pub code: Option<Bytes>,
+    /// Replaces the whole storage of the account, slots not included are left empty
+    #[serde(default)]
+    pub state: Option<HashMap<H256, H256>>,
+    /// Replaces the given storage slots, leaving the rest as they are
+    #[serde(default)]
+    pub state_diff: Option<HashMap<H256, H256>>,
+}
+
+impl LEVM {
+    /// Applies the overrides to the accounts in the cache, so that the following
+    /// executions see the overridden balance, nonce, code and storage
+    pub fn apply_state_override(
+        db: &mut GeneralizedDatabase,
+        state_override: &StateOverride,
+    ) -> Result<(), EvmError> {
+        let mut replaced_storages = HashSet::new();
+        for (address, account_override) in state_override {
+            let mut account = db.get_account(*address)?;
+            if let Some(balance) = account_override.balance {
+                account.info.balance = balance;
+            }
+            if let Some(nonce) = account_override.nonce {
+                account.info.nonce = nonce;
+            }
+            if let Some(code) = &account_override.code {
+                account.info.bytecode = code.clone();
+            }
+            let storage = match (&account_override.state, &account_override.state_diff) {
+                (Some(_), Some(_)) => {
+                    return Err(EvmError::Custom(format!(
+                        "account {address:#x} has both 'state' and 'stateDiff'"
+                    )))
+                }
+                (Some(state), None) => {
+                    replaced_storages.insert(*address);
+                    account.storage.clear();
+                    Some(state)
+                }
+                (None, Some(state_diff)) => Some(state_diff),
+                (None, None) => None,
+            };
+            for (key, value) in storage.into_iter().flatten() {
+                let value = U256::from_big_endian(value.as_bytes());
+                account.storage.insert(
+                    *key,
+                    StorageSlot {
+                        original_value: value,
+                        current_value: value,
+                    },
+                );
+            }
+            cache::insert_account(&mut db.cache, *address, account);
+        }
+
+        if !replaced_storages.is_empty() {
+            db.store = Arc::new(ReplacedStorageDatabase {
+                store: db.store.clone(),
+                accounts: replaced_storages,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Database wrapper that reads every storage slot of the accounts whose storage was
+/// replaced by a state override as empty, the overridden slots are read from the cache
+struct ReplacedStorageDatabase {
+    store: Arc<dyn LevmDatabase>,
+    accounts: HashSet<Address>,
+}
+
+impl LevmDatabase for ReplacedStorageDatabase {
+    fn get_account_info(&self, address: Address) -> Result<AccountInfo, DatabaseError> {
+        self.store.get_account_info(address)
+    }
+
+    fn get_storage_slot(&self, address: Address, key: H256) -> Result<U256, DatabaseError> {
+        if self.accounts.contains(&address) {
+            return Ok(U256::zero());
+        }
+        self.store.get_storage_slot(address, key)
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> Result<Option<H256>, DatabaseError> {
+        self.store.get_block_hash(block_number)
+    }
+
+    fn account_exists(&self, address: Address) -> bool {
+        self.store.account_exists(address)
+    }
+
+    fn get_chain_config(&self) -> ChainConfig {
+        self.store.get_chain_config()
+    }
+
+    fn get_account_code(&self, code_hash: H256) -> Result<Option<Bytes>, DatabaseError> {
+        self.store.get_account_code(code_hash)
+    }
+}
@@ -0,0 +1,85 @@
+use super::{adjust_disabled_base_fee, env_from_generic, env_from_tx, vm_from_generic, LEVM};
+use crate::{EvmError, ExecutionResult};
+use ethrex_common::{
+    types::{Block, BlockHeader, GenericTransaction, Transaction},
+    Address, H256,
+};
+use ethrex_levm::{
+    db::gen_db::GeneralizedDatabase,
+    errors::{ExecutionReport, VMError},
+    tracers::tracer::Tracer,
+    vm::VM,
+};
+
+impl LEVM {
+    /// Re-executes the transactions of `block` on top of its parent state, attaching a new
+    /// tracer to each of them.
+    /// If `tx_index` is given, only that transaction is traced and execution stops after it.
+    /// Returns the hash of every traced transaction along with its tracer.
+    pub fn trace_block<T: Tracer>(
+        block: &Block,
+        db: &mut GeneralizedDatabase,
+        tx_index: Option<usize>,
+        mut new_tracer: impl FnMut() -> T,
+    ) -> Result<Vec<(H256, T)>, EvmError> {
+        Self::apply_system_calls(&block.header, db)?;
+
+        let mut traces = Vec::new();
+        for (index, (tx, tx_sender)) in block
+            .body
+            .get_transactions_with_sender()
+            .into_iter()
+            .enumerate()
+        {
+            match tx_index {
+                Some(tx_index) if index < tx_index => {
+                    Self::execute_tx(tx, tx_sender, &block.header, db)?;
+                }
+                Some(tx_index) if index > tx_index => break,
+                _ => {
+                    let mut tracer = new_tracer();
+                    Self::trace_tx(tx, tx_sender, &block.header, db, &mut tracer)?;
+                    traces.push((tx.compute_hash(), tracer));
+                }
+            }
+        }
+
+        Ok(traces)
+    }
+
+    /// Executes a transaction with `tracer` attached to the VM.
+    pub fn trace_tx(
+        tx: &Transaction,
+        tx_sender: Address,
+        block_header: &BlockHeader,
+        db: &mut GeneralizedDatabase,
+        tracer: &mut dyn Tracer,
+    ) -> Result<ExecutionReport, EvmError> {
+        let env = env_from_tx(tx, tx_sender, block_header, db)?;
+        let mut vm = VM::new(env, db, tx)?;
+        vm.tracer = Some(tracer);
+
+        vm.execute().map_err(VMError::into)
+    }
+
+    /// Same as [LEVM::simulate_tx_from_generic] but with `tracer` attached to the VM.
+    pub fn trace_tx_from_generic(
+        tx: &GenericTransaction,
+        block_header: &BlockHeader,
+        db: &mut GeneralizedDatabase,
+        tracer: &mut dyn Tracer,
+    ) -> Result<ExecutionResult, EvmError> {
+        let mut env = env_from_generic(tx, block_header, db)?;
+
+        env.block_gas_limit = u64::MAX; // disable block gas limit
+
+        adjust_disabled_base_fee(&mut env);
+
+        let mut vm = vm_from_generic(tx, env, db)?;
+        vm.tracer = Some(tracer);
+
+        vm.execute()
+            .map(|value| value.into())
+            .map_err(VMError::into)
+    }
+}
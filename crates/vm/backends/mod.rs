@@ -13,9 +13,10 @@ use ethrex_common::types::{
 use ethrex_common::{Address, H256};
 use ethrex_levm::db::gen_db::GeneralizedDatabase;
 use ethrex_levm::db::CacheDB;
+use ethrex_levm::tracers::tracer::Tracer;
 use ethrex_storage::Store;
 use ethrex_storage::{error::StoreError, AccountUpdate};
-use levm::LEVM;
+use levm::{StateOverride, LEVM};
 use revm::db::EvmState;
 use revm::REVM;
 use std::sync::Arc;
@@ -84,9 +85,9 @@ impl Evm {
 
     pub async fn to_execution_db(
         store: &Store,
-        block: &Block,
+        blocks: &[Block],
     ) -> Result<ExecutionDB, ExecutionDBError> {
-        LEVM::to_execution_db(block, store).await
+        LEVM::to_execution_db(blocks, store).await
     }
 
     pub fn default(store: Store, parent_hash: H256) -> Self {
@@ -234,6 +235,53 @@ impl Evm {
         }
     }
 
+    /// Wraps [LEVM::apply_state_override], state overrides are not supported by [REVM].
+    pub fn apply_state_override(&mut self, state_override: &StateOverride) -> Result<(), EvmError> {
+        match self {
+            Evm::REVM { .. } => Err(EvmError::Custom(
+                "State overrides are only supported by LEVM".to_string(),
+            )),
+            Evm::LEVM { db } => LEVM::apply_state_override(db, state_override),
+        }
+    }
+
+    /// Returns the nonce of an account, including the changes made by the transactions
+    /// simulated so far. Only supported by LEVM, as [REVM] doesn't keep those changes.
+    pub fn get_nonce(&mut self, address: Address) -> Result<u64, EvmError> {
+        match self {
+            Evm::REVM { .. } => Err(EvmError::Custom(
+                "Simulated nonces are only supported by LEVM".to_string(),
+            )),
+            Evm::LEVM { db } => Ok(db.get_account(address)?.info.nonce),
+        }
+    }
+
+    /// Wraps [LEVM::trace_block], tracing is not supported by [REVM].
+    pub fn trace_block<T: Tracer>(
+        &mut self,
+        block: &Block,
+        tx_index: Option<usize>,
+        new_tracer: impl FnMut() -> T,
+    ) -> Result<Vec<(H256, T)>, EvmError> {
+        match self {
+            Evm::REVM { .. } => Err(tracing_unsupported()),
+            Evm::LEVM { db } => LEVM::trace_block(block, db, tx_index, new_tracer),
+        }
+    }
+
+    /// Wraps [LEVM::trace_tx_from_generic], tracing is not supported by [REVM].
+    pub fn trace_tx_from_generic(
+        &mut self,
+        tx: &GenericTransaction,
+        header: &BlockHeader,
+        tracer: &mut dyn Tracer,
+    ) -> Result<ExecutionResult, EvmError> {
+        match self {
+            Evm::REVM { .. } => Err(tracing_unsupported()),
+            Evm::LEVM { db } => LEVM::trace_tx_from_generic(tx, header, db, tracer),
+        }
+    }
+
     pub fn create_access_list(
         &mut self,
         tx: &GenericTransaction,
@@ -276,6 +324,10 @@ impl Evm {
     }
 }
 
+fn tracing_unsupported() -> EvmError {
+    EvmError::Custom("Transaction tracing is only supported by LEVM".to_string())
+}
+
 #[derive(Clone, Debug)]
 pub struct BlockExecutionResult {
     pub receipts: Vec<Receipt>,
@@ -1,6 +1,6 @@
 use ethrex_common::{Address, H256, U256};
 
-use crate::vm::EVMConfig;
+use crate::{hooks::l2_hook::L1FeeConfig, vm::EVMConfig};
 
 use std::collections::HashMap;
 /// [EIP-1153]: https://eips.ethereum.org/EIPS/eip-1153#reference-implementation
@@ -31,4 +31,6 @@ pub struct Environment {
     pub tx_nonce: u64,
     pub block_gas_limit: u64,
     pub transient_storage: TransientStorage,
+    /// L2 only, set if the transaction has to pay an L1 fee.
+    pub l1_fee_config: Option<L1FeeConfig>,
 }
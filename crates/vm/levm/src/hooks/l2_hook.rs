@@ -1,6 +1,9 @@
 use std::cmp::max;
 
-use ethrex_common::{types::Fork, Address, U256};
+use ethrex_common::{
+    types::{Fork, L1FeeParams},
+    Address, U256,
+};
 
 use crate::{
     constants::{INIT_CODE_MAX_SIZE, TX_BASE_COST, VALID_BLOB_PREFIXES},
@@ -270,3 +273,45 @@ impl Hook for L2Hook {
         Ok(())
     }
 }
+
+/// L1 fee charged to the L2 transactions, credited to `fee_vault`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct L1FeeConfig {
+    pub fee_vault: Address,
+    pub params: L1FeeParams,
+}
+
+/// Charges non privileged L2 transactions for the L1 resources they consume.
+/// It runs before the [DefaultHook](super::default_hook::DefaultHook), so the sender has to be
+/// able to pay for the L1 fee on top of the up front cost of the transaction.
+pub struct L1FeeHook {
+    pub fee_vault: Address,
+    /// Fee of the transaction being executed, see [L1FeeParams::l1_fee]
+    pub l1_fee: U256,
+}
+
+impl Hook for L1FeeHook {
+    fn prepare_execution(&self, vm: &mut crate::vm::VM<'_>) -> Result<(), crate::errors::VMError> {
+        let l1_fee = self.l1_fee;
+        let sender_address = vm.env.origin;
+        if vm.db.get_account(sender_address)?.info.balance < l1_fee {
+            return Err(VMError::TxValidation(
+                TxValidationError::InsufficientAccountFunds,
+            ));
+        }
+
+        // The fee is charged even if the transaction reverts, as it is posted to L1 anyway
+        vm.decrease_account_balance(sender_address, l1_fee)?;
+        vm.increase_account_balance(self.fee_vault, l1_fee)?;
+
+        Ok(())
+    }
+
+    fn finalize_execution(
+        &self,
+        _vm: &mut crate::vm::VM<'_>,
+        _report: &mut crate::errors::ExecutionReport,
+    ) -> Result<(), crate::errors::VMError> {
+        Ok(())
+    }
+}
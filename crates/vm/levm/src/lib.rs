@@ -12,6 +12,7 @@ pub mod opcode_handlers;
 pub mod opcodes;
 pub mod operations;
 pub mod precompiles;
+pub mod tracers;
 pub mod utils;
 pub mod vm;
 pub use account::*;
@@ -6,6 +6,7 @@ use crate::{
     gas_cost::{self, max_message_call_gas, SELFDESTRUCT_REFUND},
     memory::{self, calculate_memory_size},
     precompiles::is_precompile,
+    tracers::tracer::CallType,
     utils::{address_to_word, word_to_address, *},
     vm::{RetData, StateBackup, VM},
     Account,
@@ -636,6 +637,24 @@ impl<'a> VM<'a> {
                 .insert(target_address);
         }
 
+        if self.tracer.is_some() {
+            self.trace_enter(
+                CallType::SelfDestruct,
+                to,
+                target_address,
+                balance_to_transfer,
+                0,
+                &Bytes::new(),
+            );
+            self.trace_exit(&ExecutionReport {
+                result: TxResult::Success,
+                gas_used: 0,
+                gas_refunded: 0,
+                output: Bytes::new(),
+                logs: vec![],
+            });
+        }
+
         Ok(OpcodeResult::Halt)
     }
 
@@ -751,6 +770,20 @@ impl<'a> VM<'a> {
         // 3. Decrease sender's balance.
         self.decrease_account_balance(deployer_address, value_in_wei_to_send)?;
 
+        let call_type = if salt.is_some() {
+            CallType::Create2
+        } else {
+            CallType::Create
+        };
+        self.trace_enter(
+            call_type,
+            deployer_address,
+            new_address,
+            value_in_wei_to_send,
+            max_message_call_gas,
+            &code,
+        );
+
         let new_call_frame = CallFrame::new(
             deployer_address,
             new_address,
@@ -868,6 +901,20 @@ impl<'a> VM<'a> {
             self.increase_account_balance(to, value)?;
         }
 
+        if self.tracer.is_some() {
+            let current_call_frame = self.current_call_frame()?;
+            let call_type =
+                CallType::from_opcode(&current_call_frame.next_opcode()).unwrap_or(CallType::Call);
+            // Delegated calls are reported as made to the address whose code is executed.
+            let callee = match call_type {
+                CallType::DelegateCall | CallType::CallCode => code_address,
+                _ => to,
+            };
+            let caller = current_call_frame.to;
+            let input = Bytes::from(calldata.clone());
+            self.trace_enter(call_type, caller, callee, value, gas_limit, &input);
+        }
+
         self.return_data.push(RetData {
             is_create: false,
             ret_offset,
@@ -911,6 +958,7 @@ impl<'a> VM<'a> {
         call_frame: &CallFrame,
         tx_report: &ExecutionReport,
     ) -> Result<bool, VMError> {
+        self.trace_exit(tx_report);
         if call_frame.depth == 0 {
             self.call_frames.push(call_frame.clone());
             return Ok(false);
@@ -0,0 +1,171 @@
+use crate::{
+    errors::{ExecutionReport, TxResult, VMError},
+    tracers::tracer::{error_message, CallType, Tracer},
+    vm::VM,
+};
+use bytes::Bytes;
+use ethrex_common::{serde_utils, types::Log, Address, H256, U256};
+use serde::Serialize;
+
+/// Options of the `callTracer`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CallTracerConfig {
+    /// Only trace the top-level call, ignoring sub-calls.
+    pub only_top_call: bool,
+    /// Include the logs emitted by each call.
+    pub with_log: bool,
+}
+
+/// A log emitted inside a traced call.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallLog {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    #[serde(with = "serde_utils::bytes")]
+    pub data: Bytes,
+    /// Number of sub-calls made before the log was emitted.
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub position: u64,
+}
+
+/// A call frame, in the format returned by geth's `callTracer`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallTrace {
+    #[serde(rename = "type")]
+    pub call_type: CallType,
+    pub from: Address,
+    pub to: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub gas: u64,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub gas_used: u64,
+    #[serde(with = "serde_utils::bytes")]
+    pub input: Bytes,
+    #[serde(with = "serde_utils::bytes", skip_serializing_if = "Bytes::is_empty")]
+    pub output: Bytes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallTrace>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<CallLog>,
+}
+
+impl CallTrace {
+    /// Logs of reverted calls are discarded, along with the ones of their sub-calls.
+    fn clear_logs(&mut self) {
+        self.logs.clear();
+        for call in self.calls.iter_mut() {
+            call.clear_logs();
+        }
+    }
+}
+
+/// Builds the tree of calls made by a transaction.
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    config: CallTracerConfig,
+    /// Calls that have been entered but haven't returned yet.
+    stack: Vec<CallTrace>,
+    /// Number of nested calls ignored because of `only_top_call`.
+    ignored_depth: usize,
+    result: Option<CallTrace>,
+}
+
+impl CallTracer {
+    pub fn new(config: CallTracerConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the top-level call, `None` if the transaction didn't execute.
+    pub fn into_result(self) -> Option<CallTrace> {
+        self.result
+    }
+}
+
+impl Tracer for CallTracer {
+    fn tx_end(&mut self, _vm: &VM<'_>, report: &ExecutionReport) {
+        // The top-level call reports the gas used by the whole transaction.
+        if let Some(result) = self.result.as_mut() {
+            result.gas_used = report.gas_used;
+            if let TxResult::Revert(error) = &report.result {
+                result.error = Some(error_message(error));
+            }
+        }
+    }
+
+    fn enter(
+        &mut self,
+        call_type: CallType,
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: u64,
+        input: &Bytes,
+    ) {
+        if self.config.only_top_call && !self.stack.is_empty() {
+            self.ignored_depth = self.ignored_depth.saturating_add(1);
+            return;
+        }
+        let value = match call_type {
+            CallType::DelegateCall | CallType::StaticCall => None,
+            _ => Some(value),
+        };
+        self.stack.push(CallTrace {
+            call_type,
+            from,
+            to,
+            value,
+            gas,
+            gas_used: 0,
+            input: input.clone(),
+            output: Bytes::new(),
+            error: None,
+            calls: Vec::new(),
+            logs: Vec::new(),
+        });
+    }
+
+    fn exit(&mut self, gas_used: u64, output: &Bytes, error: Option<&VMError>) {
+        if self.ignored_depth > 0 {
+            self.ignored_depth = self.ignored_depth.saturating_sub(1);
+            return;
+        }
+        let Some(mut call) = self.stack.pop() else {
+            return;
+        };
+        call.gas_used = gas_used;
+        // Output is only meaningful for calls that returned or reverted.
+        if error.is_none() || error == Some(&VMError::RevertOpcode) {
+            call.output = output.clone();
+        }
+        if let Some(error) = error {
+            call.error = Some(error_message(error));
+            call.clear_logs();
+        }
+        match self.stack.last_mut() {
+            Some(parent) => parent.calls.push(call),
+            None => self.result = Some(call),
+        }
+    }
+
+    fn log(&mut self, log: &Log) {
+        if !self.config.with_log || self.ignored_depth > 0 {
+            return;
+        }
+        if let Some(call) = self.stack.last_mut() {
+            call.logs.push(CallLog {
+                address: log.address,
+                topics: log.topics.clone(),
+                data: log.data.clone(),
+                position: u64::try_from(call.calls.len()).unwrap_or(u64::MAX),
+            });
+        }
+    }
+}
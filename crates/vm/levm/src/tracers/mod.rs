@@ -0,0 +1,5 @@
+pub mod call_tracer;
+pub mod prestate_tracer;
+pub mod struct_logger;
+pub mod tracer;
+pub mod transfer_log_tracer;
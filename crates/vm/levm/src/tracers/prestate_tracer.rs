@@ -0,0 +1,99 @@
+use crate::{
+    db::gen_db::GeneralizedDatabase, errors::ExecutionReport, precompiles::is_precompile,
+    tracers::tracer::Tracer, vm::VM,
+};
+use bytes::Bytes;
+use ethrex_common::{serde_utils, Address, H256, U256};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// State of an account before the traced transaction was executed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrestateAccount {
+    pub balance: U256,
+    pub nonce: u64,
+    #[serde(with = "serde_utils::bytes", skip_serializing_if = "Bytes::is_empty")]
+    pub code: Bytes,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<H256, H256>,
+}
+
+pub type Prestate = BTreeMap<Address, PrestateAccount>;
+
+/// Collects the accounts and storage slots accessed by a transaction, with
+/// the values they had before it was executed.
+#[derive(Default)]
+pub struct PrestateTracer {
+    /// Copy of the database taken before the transaction modified it.
+    initial_db: Option<GeneralizedDatabase>,
+    result: Prestate,
+}
+
+impl PrestateTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_result(self) -> Prestate {
+        self.result
+    }
+
+    fn storage_value(db: &GeneralizedDatabase, address: Address, key: H256) -> U256 {
+        db.cache
+            .get(&address)
+            .and_then(|account| account.storage.get(&key))
+            .map(|slot| slot.current_value)
+            .or_else(|| db.store.get_storage_slot(address, key).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl Tracer for PrestateTracer {
+    fn tx_start(&mut self, vm: &VM<'_>) {
+        self.initial_db = Some(vm.db.clone());
+    }
+
+    fn tx_end(&mut self, vm: &VM<'_>, _report: &ExecutionReport) {
+        let Some(initial_db) = self.initial_db.take() else {
+            return;
+        };
+        let fork = vm.env.config.fork;
+        let substate = &vm.accrued_substate;
+        let addresses = substate
+            .touched_accounts
+            .iter()
+            .chain(substate.touched_storage_slots.keys())
+            .filter(|address| !is_precompile(address, fork));
+
+        for address in addresses {
+            let Ok(account) = initial_db.get_account_no_push_cache(*address) else {
+                continue;
+            };
+            // Accounts created by the transaction didn't exist beforehand.
+            if substate.created_accounts.contains(address) && account.is_empty() {
+                continue;
+            }
+            let storage = substate
+                .touched_storage_slots
+                .get(address)
+                .map(|keys| {
+                    keys.iter()
+                        .map(|key| {
+                            let value = Self::storage_value(&initial_db, *address, *key);
+                            (*key, H256::from(value.to_big_endian()))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            self.result.insert(
+                *address,
+                PrestateAccount {
+                    balance: account.info.balance,
+                    nonce: account.info.nonce,
+                    code: account.info.bytecode,
+                    storage,
+                },
+            );
+        }
+    }
+}
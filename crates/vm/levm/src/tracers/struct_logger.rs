@@ -0,0 +1,196 @@
+use crate::{
+    call_frame::CallFrame,
+    errors::{ExecutionReport, VMError},
+    opcodes::Opcode,
+    tracers::tracer::{error_message, Tracer},
+    vm::VM,
+};
+use bytes::Bytes;
+use ethrex_common::{serde_utils, Address, U256};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+fn serialize_return_data<S>(value: &Option<Bytes>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match value {
+        Some(bytes) => serde_utils::bytes::serialize(bytes, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Options of the default (opcode level) tracer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StructLoggerConfig {
+    pub disable_stack: bool,
+    pub disable_storage: bool,
+    pub enable_memory: bool,
+    pub enable_return_data: bool,
+}
+
+/// A single executed opcode, in the format returned by geth's struct logger.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: usize,
+    pub op: String,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<U256>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_return_data"
+    )]
+    pub return_data: Option<Bytes>,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub refund: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of tracing a transaction with the struct logger.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLoggerResult {
+    pub gas: u64,
+    pub failed: bool,
+    #[serde(with = "serde_utils::bytes")]
+    pub return_value: Bytes,
+    pub struct_logs: Vec<StructLog>,
+}
+
+#[derive(Debug)]
+struct PendingStep {
+    opcode: Opcode,
+    log: StructLog,
+    /// The two topmost stack items before execution, used to track storage accesses.
+    stack_top: (Option<U256>, Option<U256>),
+}
+
+/// Records every executed opcode along with the machine state before its execution.
+#[derive(Debug, Default)]
+pub struct StructLogger {
+    config: StructLoggerConfig,
+    logs: Vec<StructLog>,
+    /// Opcode being executed, it's completed in `step_end`.
+    pending: Option<PendingStep>,
+    /// Storage slots accessed so far, by contract.
+    storage: HashMap<Address, BTreeMap<String, String>>,
+    result: StructLoggerResult,
+}
+
+impl StructLogger {
+    pub fn new(config: StructLoggerConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn into_result(self) -> StructLoggerResult {
+        self.result
+    }
+
+    fn record_storage(
+        &mut self,
+        address: Address,
+        key: U256,
+        value: U256,
+    ) -> BTreeMap<String, String> {
+        let storage = self.storage.entry(address).or_default();
+        storage.insert(
+            hex::encode(key.to_big_endian()),
+            hex::encode(value.to_big_endian()),
+        );
+        storage.clone()
+    }
+}
+
+impl Tracer for StructLogger {
+    fn tx_end(&mut self, _vm: &VM<'_>, report: &ExecutionReport) {
+        self.result = StructLoggerResult {
+            gas: report.gas_used,
+            failed: !report.is_success(),
+            return_value: report.output.clone(),
+            struct_logs: std::mem::take(&mut self.logs),
+        };
+    }
+
+    fn step_start(&mut self, vm: &VM<'_>) {
+        let Ok(call_frame) = vm.current_call_frame() else {
+            return;
+        };
+        let opcode = call_frame.next_opcode();
+        let mut stack_items = call_frame.stack.stack.iter().rev().copied();
+        let stack_top = (stack_items.next(), stack_items.next());
+        let log = StructLog {
+            pc: call_frame.pc(),
+            op: format!("{opcode:?}"),
+            gas: call_frame.gas_limit.saturating_sub(call_frame.gas_used),
+            gas_cost: 0,
+            depth: call_frame.depth.saturating_add(1),
+            stack: (!self.config.disable_stack).then(|| call_frame.stack.stack.clone()),
+            memory: self
+                .config
+                .enable_memory
+                .then(|| call_frame.memory.chunks(32).map(hex::encode).collect()),
+            storage: None,
+            return_data: self
+                .config
+                .enable_return_data
+                .then(|| call_frame.sub_return_data.clone()),
+            refund: vm.env.refunded_gas,
+            error: None,
+        };
+        self.pending = Some(PendingStep {
+            opcode,
+            log,
+            stack_top,
+        });
+    }
+
+    fn step_end(&mut self, call_frame: &CallFrame, gas_cost: u64, error: Option<&VMError>) {
+        let Some(PendingStep {
+            opcode,
+            mut log,
+            stack_top,
+        }) = self.pending.take()
+        else {
+            return;
+        };
+        log.gas_cost = gas_cost;
+        log.error = error.map(error_message);
+
+        if !self.config.disable_storage && error.is_none() {
+            match opcode {
+                // The loaded value is left on top of the stack.
+                Opcode::SLOAD => {
+                    if let (Some(key), Some(value)) =
+                        (stack_top.0, call_frame.stack.stack.last().copied())
+                    {
+                        log.storage = Some(self.record_storage(call_frame.to, key, value));
+                    }
+                }
+                Opcode::SSTORE => {
+                    if let (Some(key), Some(value)) = stack_top {
+                        log.storage = Some(self.record_storage(call_frame.to, key, value));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.logs.push(log);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    call_frame::CallFrame,
+    errors::{ExecutionReport, TxResult, VMError},
+    opcodes::Opcode,
+    vm::VM,
+};
+use bytes::Bytes;
+use ethrex_common::{types::Log, Address, U256};
+use serde::Serialize;
+
+/// Kind of message call a traced frame was created by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CallType {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+    Create,
+    Create2,
+    SelfDestruct,
+}
+
+impl CallType {
+    pub fn from_opcode(opcode: &Opcode) -> Option<Self> {
+        match opcode {
+            Opcode::CALL => Some(CallType::Call),
+            Opcode::CALLCODE => Some(CallType::CallCode),
+            Opcode::DELEGATECALL => Some(CallType::DelegateCall),
+            Opcode::STATICCALL => Some(CallType::StaticCall),
+            Opcode::CREATE => Some(CallType::Create),
+            Opcode::CREATE2 => Some(CallType::Create2),
+            Opcode::SELFDESTRUCT => Some(CallType::SelfDestruct),
+            _ => None,
+        }
+    }
+}
+
+/// Instrumentation hooks invoked by the VM while executing a transaction.
+///
+/// Every method has an empty default implementation so tracers only need to
+/// implement the events they care about. A tracer is attached to a VM by
+/// setting `VM::tracer` before calling `VM::execute`.
+pub trait Tracer {
+    /// Called once before the transaction is prepared, with the state still untouched.
+    fn tx_start(&mut self, _vm: &VM<'_>) {}
+
+    /// Called once after the transaction has been finalized.
+    fn tx_end(&mut self, _vm: &VM<'_>, _report: &ExecutionReport) {}
+
+    /// Called when a new call frame is entered, including the top-level one.
+    fn enter(
+        &mut self,
+        _call_type: CallType,
+        _from: Address,
+        _to: Address,
+        _value: U256,
+        _gas: u64,
+        _input: &Bytes,
+    ) {
+    }
+
+    /// Called when the innermost entered call frame returns.
+    fn exit(&mut self, _gas_used: u64, _output: &Bytes, _error: Option<&VMError>) {}
+
+    /// Called before executing every opcode.
+    fn step_start(&mut self, _vm: &VM<'_>) {}
+
+    /// Called after executing every opcode with the frame that executed it.
+    fn step_end(&mut self, _call_frame: &CallFrame, _gas_cost: u64, _error: Option<&VMError>) {}
+
+    /// Called whenever a LOG opcode emits a log.
+    fn log(&mut self, _log: &Log) {}
+}
+
+/// Returns the error message geth uses for a failed call frame.
+pub fn error_message(error: &VMError) -> String {
+    match error {
+        VMError::RevertOpcode => "execution reverted".to_string(),
+        VMError::OutOfGas(_) => "out of gas".to_string(),
+        VMError::InvalidOpcode | VMError::OpcodeNotFound => "invalid opcode".to_string(),
+        VMError::InvalidJump => "invalid jump destination".to_string(),
+        VMError::StackUnderflow => "stack underflow".to_string(),
+        VMError::StackOverflow => "stack limit reached".to_string(),
+        VMError::OpcodeNotAllowedInStaticContext => "write protection".to_string(),
+        VMError::AddressAlreadyOccupied => "contract address collision".to_string(),
+        VMError::ContractOutputTooBig => "max code size exceeded".to_string(),
+        VMError::InvalidContractPrefix => "invalid code: must not begin with 0xef".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl<'a> VM<'a> {
+    /// Runs `f` with the attached tracer, if any, giving it read access to the VM.
+    fn with_tracer(&mut self, f: impl FnOnce(&mut dyn Tracer, &VM<'a>)) {
+        if let Some(tracer) = self.tracer.take() {
+            f(&mut *tracer, self);
+            self.tracer = Some(tracer);
+        }
+    }
+
+    pub fn trace_tx_start(&mut self) {
+        self.with_tracer(|tracer, vm| tracer.tx_start(vm));
+    }
+
+    pub fn trace_tx_end(&mut self, report: &ExecutionReport) {
+        self.with_tracer(|tracer, vm| tracer.tx_end(vm, report));
+    }
+
+    pub fn trace_enter(
+        &mut self,
+        call_type: CallType,
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: u64,
+        input: &Bytes,
+    ) {
+        if let Some(tracer) = self.tracer.as_deref_mut() {
+            tracer.enter(call_type, from, to, value, gas, input);
+        }
+    }
+
+    pub fn trace_exit(&mut self, report: &ExecutionReport) {
+        if let Some(tracer) = self.tracer.as_deref_mut() {
+            let error = match &report.result {
+                TxResult::Success => None,
+                TxResult::Revert(error) => Some(error),
+            };
+            tracer.exit(report.gas_used, &report.output, error);
+        }
+    }
+
+    pub fn trace_log(&mut self, log: &Log) {
+        if let Some(tracer) = self.tracer.as_deref_mut() {
+            tracer.log(log);
+        }
+    }
+
+    /// Notifies the tracer that the current opcode is about to be executed.
+    /// Returns the index of the executing frame and the gas it used so far,
+    /// which `trace_step_end` needs to compute the cost of the opcode.
+    pub fn trace_step_start(&mut self) -> Option<(usize, u64)> {
+        self.tracer.as_ref()?;
+        let frame_index = self.call_frames.len().checked_sub(1)?;
+        let gas_used = self.call_frames.get(frame_index)?.gas_used;
+        self.with_tracer(|tracer, vm| tracer.step_start(vm));
+        Some((frame_index, gas_used))
+    }
+
+    pub fn trace_step_end(&mut self, step: (usize, u64), error: Option<&VMError>) {
+        let (frame_index, gas_used_before) = step;
+        if let (Some(tracer), Some(call_frame)) = (
+            self.tracer.as_deref_mut(),
+            self.call_frames.get(frame_index),
+        ) {
+            let gas_cost = call_frame.gas_used.saturating_sub(gas_used_before);
+            tracer.step_end(call_frame, gas_cost, error);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{
+    errors::VMError,
+    tracers::tracer::{CallType, Tracer},
+};
+use bytes::Bytes;
+use ethrex_common::{types::Log, Address, H160, H256, U256};
+
+/// Address geth uses as the emitter of the pseudo-logs of ETH transfers.
+pub const TRANSFER_LOG_ADDRESS: Address = H160([0xee; 20]);
+
+/// `keccak256("Transfer(address,address,uint256)")`, the topic of ERC-20 transfers,
+/// reused by the pseudo-logs of ETH transfers.
+pub const TRANSFER_TOPIC: H256 = H256([
+    0xdd, 0xf2, 0x52, 0xad, 0x1b, 0xe2, 0xc8, 0x9b, 0x69, 0xc2, 0xb0, 0x68, 0xfc, 0x37, 0x8d, 0xaa,
+    0x95, 0x2b, 0xa7, 0xf1, 0x63, 0xc4, 0xa1, 0x16, 0x28, 0xf5, 0x5a, 0x4d, 0xf5, 0x23, 0xb3, 0xef,
+]);
+
+/// Collects the logs of a transaction along with a pseudo-log for every ETH transfer,
+/// in the order they happened. Used by `eth_simulateV1` when `traceTransfers` is set.
+///
+/// Transfers are logged as ERC-20 `Transfer` events emitted by [TRANSFER_LOG_ADDRESS].
+/// Like the regular logs, the transfers of reverted calls are discarded.
+#[derive(Debug, Default)]
+pub struct TransferLogTracer {
+    /// Logs of the calls that have been entered but haven't returned yet.
+    stack: Vec<Vec<Log>>,
+    logs: Vec<Log>,
+}
+
+impl TransferLogTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the logs of the transaction, empty if it reverted.
+    pub fn into_logs(self) -> Vec<Log> {
+        self.logs
+    }
+}
+
+impl Tracer for TransferLogTracer {
+    fn enter(
+        &mut self,
+        call_type: CallType,
+        from: Address,
+        to: Address,
+        value: U256,
+        _gas: u64,
+        _input: &Bytes,
+    ) {
+        let mut logs = Vec::new();
+        // Delegate calls keep the value of their caller, they don't transfer it again.
+        if call_type != CallType::DelegateCall && !value.is_zero() {
+            logs.push(Log {
+                address: TRANSFER_LOG_ADDRESS,
+                topics: vec![TRANSFER_TOPIC, from.into(), to.into()],
+                data: Bytes::copy_from_slice(&value.to_big_endian()),
+            });
+        }
+        self.stack.push(logs);
+    }
+
+    fn exit(&mut self, _gas_used: u64, _output: &Bytes, error: Option<&VMError>) {
+        let Some(mut logs) = self.stack.pop() else {
+            return;
+        };
+        if error.is_some() {
+            logs.clear();
+        }
+        match self.stack.last_mut() {
+            Some(parent) => parent.append(&mut logs),
+            None => self.logs = logs,
+        }
+    }
+
+    fn log(&mut self, log: &Log) {
+        if let Some(logs) = self.stack.last_mut() {
+            logs.push(log.clone());
+        }
+    }
+}
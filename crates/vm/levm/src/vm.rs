@@ -4,11 +4,16 @@ use crate::{
     db::{cache, gen_db::GeneralizedDatabase},
     environment::Environment,
     errors::{ExecutionReport, InternalError, OpcodeResult, TxResult, VMError},
-    hooks::{default_hook::DefaultHook, hook::Hook, l2_hook::L2Hook},
+    hooks::{
+        default_hook::DefaultHook,
+        hook::Hook,
+        l2_hook::{L1FeeHook, L2Hook},
+    },
     precompiles::{
         execute_precompile, is_precompile, SIZE_PRECOMPILES_CANCUN, SIZE_PRECOMPILES_PRAGUE,
         SIZE_PRECOMPILES_PRE_CANCUN,
     },
+    tracers::tracer::{CallType, Tracer},
     utils::*,
     TransientStorage,
 };
@@ -167,6 +172,8 @@ pub struct VM<'a> {
     pub hooks: Vec<Arc<dyn Hook>>,
     pub return_data: Vec<RetData>,
     pub backups: Vec<StateBackup>,
+    /// Optional instrumentation used for `debug_trace*` requests.
+    pub tracer: Option<&'a mut dyn Tracer>,
 }
 
 pub struct RetData {
@@ -224,10 +231,17 @@ impl<'a> VM<'a> {
             }
         }
 
-        let hooks: Vec<Arc<dyn Hook>> = match tx {
-            Transaction::PrivilegedL2Transaction(privileged_tx) => vec![Arc::new(L2Hook {
+        let hooks: Vec<Arc<dyn Hook>> = match (tx, env.l1_fee_config) {
+            (Transaction::PrivilegedL2Transaction(privileged_tx), _) => vec![Arc::new(L2Hook {
                 recipient: privileged_tx.recipient,
             })],
+            (_, Some(config)) => vec![
+                Arc::new(L1FeeHook {
+                    fee_vault: config.fee_vault,
+                    l1_fee: config.params.l1_fee(tx),
+                }),
+                Arc::new(DefaultHook),
+            ],
             _ => vec![Arc::new(DefaultHook)],
         };
 
@@ -270,6 +284,7 @@ impl<'a> VM<'a> {
                     hooks,
                     return_data: vec![],
                     backups: vec![],
+                    tracer: None,
                 })
             }
             TxKind::Create => {
@@ -311,6 +326,7 @@ impl<'a> VM<'a> {
                     hooks,
                     return_data: vec![],
                     backups: vec![],
+                    tracer: None,
                 })
             }
         }
@@ -339,7 +355,11 @@ impl<'a> VM<'a> {
         loop {
             let opcode = self.current_call_frame()?.next_opcode();
 
+            let step = self.trace_step_start();
             let op_result = self.handle_current_opcode(opcode);
+            if let Some(step) = step {
+                self.trace_step_end(step, op_result.as_ref().err());
+            }
 
             match op_result {
                 Ok(OpcodeResult::Continue { pc_increment }) => self
@@ -400,6 +420,8 @@ impl<'a> VM<'a> {
 
     /// Main function for executing an external transaction
     pub fn execute(&mut self) -> Result<ExecutionReport, VMError> {
+        self.trace_tx_start();
+
         if let Err(e) = self.prepare_execution() {
             // We need to do a cleanup of the cache so that it doesn't interfere with next transaction's execution
             self.restore_cache_state(self.current_call_frame()?.cache_backup.clone())?;
@@ -412,6 +434,22 @@ impl<'a> VM<'a> {
         // These are: Incrementing sender nonce, transferring value to a delegate account, decreasing sender account balance
         self.current_call_frame_mut()?.cache_backup = HashMap::new();
 
+        if self.tracer.is_some() {
+            let call_frame = self.current_call_frame()?;
+            let (call_type, input) = if self.is_create() {
+                (CallType::Create, call_frame.bytecode.clone())
+            } else {
+                (CallType::Call, call_frame.calldata.clone())
+            };
+            let (from, to, value, gas) = (
+                call_frame.msg_sender,
+                call_frame.to,
+                call_frame.msg_value,
+                call_frame.gas_limit,
+            );
+            self.trace_enter(call_type, from, to, value, gas, &input);
+        }
+
         // In CREATE type transactions:
         //  Add created contract to cache, reverting transaction if the address is already occupied
         if self.is_create() {
@@ -444,6 +482,7 @@ impl<'a> VM<'a> {
         let mut report = self.run_execution()?;
 
         self.finalize_execution(&mut report)?;
+        self.trace_tx_end(&report);
         Ok(report)
     }
 
@@ -467,8 +506,10 @@ impl<'a> VM<'a> {
             logs: vec![],
             output: Bytes::new(),
         };
+        self.trace_exit(&report);
 
         self.finalize_execution(&mut report)?;
+        self.trace_tx_end(&report);
 
         Ok(report)
     }
@@ -0,0 +1,184 @@
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::arithmetic_side_effects)]
+
+use bytes::Bytes;
+use ethrex_common::{
+    types::{
+        ChainConfig, EIP1559Transaction, L1FeeParams, PrivilegedL2Transaction, Transaction, TxKind,
+    },
+    Address, H256, U256,
+};
+use ethrex_levm::{
+    db::{error::DatabaseError, gen_db::GeneralizedDatabase, CacheDB, Database},
+    errors::{TxResult, TxValidationError, VMError},
+    hooks::l2_hook::L1FeeConfig,
+    vm::VM,
+    Account, AccountInfo, Environment,
+};
+use std::{collections::HashMap, sync::Arc};
+
+const SENDER: u64 = 0x5e4d;
+const CONTRACT: u64 = 0xc0de;
+const FEE_VAULT: u64 = 0xfee;
+const GAS_LIMIT: u64 = 100_000;
+
+/// The accounts under test live in the cache, every other account is empty
+struct EmptyDatabase;
+
+impl Database for EmptyDatabase {
+    fn get_account_info(&self, _address: Address) -> Result<AccountInfo, DatabaseError> {
+        Ok(AccountInfo::default())
+    }
+
+    fn get_storage_slot(&self, _address: Address, _key: H256) -> Result<U256, DatabaseError> {
+        Ok(U256::zero())
+    }
+
+    fn get_block_hash(&self, _block_number: u64) -> Result<Option<H256>, DatabaseError> {
+        Ok(None)
+    }
+
+    fn account_exists(&self, _address: Address) -> bool {
+        false
+    }
+
+    fn get_chain_config(&self) -> ChainConfig {
+        ChainConfig::default()
+    }
+
+    fn get_account_code(&self, _code_hash: H256) -> Result<Option<Bytes>, DatabaseError> {
+        Ok(None)
+    }
+}
+
+fn l1_fee_config() -> L1FeeConfig {
+    L1FeeConfig {
+        fee_vault: Address::from_low_u64_be(FEE_VAULT),
+        params: L1FeeParams {
+            l1_base_fee_per_gas: 7,
+            l1_blob_base_fee_per_gas: 3,
+        },
+    }
+}
+
+/// Creates a database with a funded sender and a contract with the given code
+fn db_with_sender(sender_balance: U256, contract_code: Bytes) -> GeneralizedDatabase {
+    let mut cache = CacheDB::new();
+    cache.insert(
+        Address::from_low_u64_be(SENDER),
+        Account::new(sender_balance, Bytes::new(), 0, HashMap::new()),
+    );
+    cache.insert(
+        Address::from_low_u64_be(CONTRACT),
+        Account::new(U256::zero(), contract_code, 1, HashMap::new()),
+    );
+    GeneralizedDatabase::new(Arc::new(EmptyDatabase), cache)
+}
+
+fn env() -> Environment {
+    Environment {
+        origin: Address::from_low_u64_be(SENDER),
+        gas_limit: GAS_LIMIT,
+        block_gas_limit: GAS_LIMIT,
+        gas_price: U256::one(),
+        base_fee_per_gas: U256::one(),
+        tx_max_fee_per_gas: Some(U256::one()),
+        tx_max_priority_fee_per_gas: Some(U256::zero()),
+        l1_fee_config: Some(l1_fee_config()),
+        ..Default::default()
+    }
+}
+
+fn tx(calldata: Bytes) -> Transaction {
+    Transaction::EIP1559Transaction(EIP1559Transaction {
+        to: TxKind::Call(Address::from_low_u64_be(CONTRACT)),
+        data: calldata,
+        gas_limit: GAS_LIMIT,
+        max_fee_per_gas: 1,
+        ..Default::default()
+    })
+}
+
+fn balance(db: &mut GeneralizedDatabase, address: u64) -> U256 {
+    db.get_account(Address::from_low_u64_be(address))
+        .unwrap()
+        .info
+        .balance
+}
+
+#[test]
+fn l1_fee_is_credited_to_the_vault() {
+    let initial_balance = U256::from(10_000_000);
+    let mut db = db_with_sender(initial_balance, Bytes::new());
+    let tx = tx(Bytes::from(vec![1, 2, 3]));
+    let l1_fee = l1_fee_config().params.l1_fee(&tx);
+
+    let report = VM::new(env(), &mut db, &tx).unwrap().execute().unwrap();
+    assert!(report.is_success());
+
+    assert_eq!(
+        balance(&mut db, SENDER),
+        initial_balance - l1_fee - U256::from(report.gas_used)
+    );
+    assert_eq!(balance(&mut db, FEE_VAULT), l1_fee);
+}
+
+#[test]
+fn l1_fee_is_charged_on_revert() {
+    let initial_balance = U256::from(10_000_000);
+    // PUSH1 0 PUSH1 0 REVERT
+    let mut db = db_with_sender(
+        initial_balance,
+        Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xfd]),
+    );
+    let tx = tx(Bytes::new());
+    let l1_fee = l1_fee_config().params.l1_fee(&tx);
+
+    let report = VM::new(env(), &mut db, &tx).unwrap().execute().unwrap();
+    assert!(matches!(report.result, TxResult::Revert(_)));
+
+    assert_eq!(
+        balance(&mut db, SENDER),
+        initial_balance - l1_fee - U256::from(report.gas_used)
+    );
+    assert_eq!(balance(&mut db, FEE_VAULT), l1_fee);
+}
+
+#[test]
+fn sender_that_cant_pay_the_l1_fee_is_rejected() {
+    let tx = tx(Bytes::new());
+    let l1_fee = l1_fee_config().params.l1_fee(&tx);
+    // Enough for the gas, but not for the gas and the L1 fee
+    let initial_balance = U256::from(GAS_LIMIT) + l1_fee - 1;
+    let mut db = db_with_sender(initial_balance, Bytes::new());
+
+    let result = VM::new(env(), &mut db, &tx).unwrap().execute();
+    assert!(matches!(
+        result,
+        Err(VMError::TxValidation(
+            TxValidationError::InsufficientAccountFunds
+        ))
+    ));
+
+    // Nothing charged by the hooks is kept
+    assert_eq!(balance(&mut db, SENDER), initial_balance);
+    assert_eq!(balance(&mut db, FEE_VAULT), U256::zero());
+}
+
+#[test]
+fn privileged_transactions_dont_pay_the_l1_fee() {
+    let mut db = db_with_sender(U256::zero(), Bytes::new());
+    let tx = Transaction::PrivilegedL2Transaction(PrivilegedL2Transaction {
+        to: TxKind::Call(Address::from_low_u64_be(CONTRACT)),
+        recipient: Address::from_low_u64_be(SENDER),
+        data: Bytes::from(vec![1, 2, 3]),
+        gas_limit: GAS_LIMIT,
+        max_fee_per_gas: 1,
+        ..Default::default()
+    });
+
+    let report = VM::new(env(), &mut db, &tx).unwrap().execute().unwrap();
+    assert!(report.is_success());
+
+    assert_eq!(balance(&mut db, FEE_VAULT), U256::zero());
+}
@@ -1 +1,2 @@
+mod l1_fee_hook;
 mod tests;
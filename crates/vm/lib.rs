@@ -6,8 +6,12 @@ mod helpers;
 
 pub mod backends;
 
-pub use backends::{BlockExecutionResult, Evm, EvmEngine};
+pub use backends::{
+    levm::{AccountOverride, StateOverride},
+    BlockExecutionResult, Evm, EvmEngine,
+};
 pub use db::{ExecutionDB, StoreWrapper};
 pub use errors::{EvmError, ExecutionDBError};
+pub use ethrex_levm::tracers;
 pub use execution_result::ExecutionResult;
 pub use helpers::{create_contract_address, fork_to_spec_id, SpecId};